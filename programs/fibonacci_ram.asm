@@ -0,0 +1,28 @@
+; Fibonacci sequence for the Intel 4004
+; Stores the first eight Fibonacci numbers (0, 1, 1, 2, 3, 5, 8, 13) in
+; RAM chip 0, register 0, characters 0-7, then loops at DONE.
+;
+; Assemble with: cargo run -- --assemble programs/fibonacci_ram.asm
+
+COUNT   EQU 8                   ; Numbers to store
+
+        ORG 0x000
+START:  FIM P0, 0x00            ; R0R1: RAM chip 0, register 0, character 0
+        LDM 0
+        XCH R2                  ; R2 = F(n)
+        LDM 1
+        XCH R3                  ; R3 = F(n+1)
+        LDM 16 - COUNT
+        XCH R4                  ; R4 counts up to 16
+
+LOOP:   SRC P0                  ; Select the character in R1
+        LD R2
+        WRM                     ; Store F(n)
+        INC R1                  ; Next character
+        CLC
+        ADD R3                  ; ACC = F(n) + F(n+1)
+        XCH R3                  ; R3 = F(n+2), ACC = F(n+1)
+        XCH R2                  ; R2 = F(n+1)
+        ISZ R4, LOOP
+
+DONE:   JUN DONE
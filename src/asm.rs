@@ -0,0 +1,1004 @@
+//! # MCS-4 Assembler
+//!
+//! Two-pass assembler for Intel 4004 (and 4040) programs.
+//!
+//! The output is a flat ROM image starting at address 0, the same raw binary format
+//! `ConfigurableSystem::load_program_data` and the `--file` option consume: byte N of
+//! the image is loaded at program address N, so page N lives in ROM chip N.
+//!
+//! ## Source Format
+//!
+//! ```text
+//! ; Comments run from ';' to the end of the line
+//! COUNT   EQU 16 - 8          ; Equates: NAME EQU expr, or NAME = expr
+//!         ORG 0x000           ; Set the assembly address
+//! START:  FIM P0, 0x00        ; Labels end with ':'
+//!         LDM COUNT
+//! LOOP:   ISZ R4, LOOP        ; JCN/ISZ reach the current page only
+//!         JUN START           ; JUN/JMS reach the whole 12-bit space
+//! TABLE:  DB 1, 2, "AB", $ & 0xFF
+//! ```
+//!
+//! - Mnemonics, directives, register and pair names are case-insensitive; labels are not
+//! - Registers are `R0`-`R15`, pairs `P0`-`P7` (or Intel's `0P`-`7P`); any expression works too
+//! - JCN conditions are numbers 0-15 or `TZ`, `TN`, `C0`, `C1`, `AZ`, `AN`
+//!   (test pin zero/non-zero, carry clear/set, accumulator zero/non-zero)
+//! - Numbers are decimal, `0x1F`, `$1F`, `1FH`, `0b101` or `'A'`; `$` alone is the current address
+//! - Expressions support `+ - * / % & | ^ << >> ~` and parentheses with C precedence
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+/// Size of the MCS-4 program address space
+pub const PROGRAM_SPACE: usize = 4096;
+
+/// Result of a successful assembly
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assembly {
+    pub binary: Vec<u8>, // ROM image from address 0, gaps filled with NOP (0x00)
+    pub symbols: BTreeMap<String, u16>, // Label addresses; equates are not included
+    pub warnings: Vec<String>, // Non-fatal diagnostics, prefixed with the line number
+}
+
+impl Assembly {
+    /// Render the labels as a symbol file
+    /// One `NAME EQU 0xADR` line per label in address order, so the file is valid source too
+    /// Returns: Symbol file text
+    pub fn symbol_file(&self) -> String {
+        let mut labels: Vec<(&String, &u16)> = self.symbols.iter().collect();
+        labels.sort_by_key(|(name, address)| (**address, (*name).clone()));
+        labels
+            .iter()
+            .map(|(name, address)| format!("{} EQU 0x{:03X}\n", name, address))
+            .collect()
+    }
+}
+
+/// Operand layout of an instruction, with the opcode it starts from
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Form {
+    Implied(u8),      // One byte, no operands
+    Register(u8),     // Index register in the low nibble
+    Immediate(u8),    // 4-bit data in the low nibble
+    Pair(u8),         // Register pair in bits 1-3
+    PairData(u8),     // FIM: pair, then an 8-bit data byte
+    ShortJump(u8),    // JCN: condition, then an address in the current page
+    RegisterJump(u8), // ISZ: register, then an address in the current page
+    LongJump(u8),     // JUN/JMS: 12-bit address split across both bytes
+}
+
+impl Form {
+    /// Get the encoded size of an instruction
+    /// Returns: 1 or 2 bytes
+    fn size(&self) -> usize {
+        match self {
+            Form::Implied(_) | Form::Register(_) | Form::Immediate(_) | Form::Pair(_) => 1,
+            _ => 2,
+        }
+    }
+}
+
+/// Look up a mnemonic
+/// Parameters: mnemonic - Upper-case mnemonic
+/// Returns: Some(Form) for MCS-4 mnemonics including the 4040 additions, None otherwise
+fn lookup_mnemonic(mnemonic: &str) -> Option<Form> {
+    let form = match mnemonic {
+        "NOP" => Form::Implied(0x00),
+        // Intel 4040 additions
+        "HLT" => Form::Implied(0x01),
+        "BBS" => Form::Implied(0x02),
+        "LCR" => Form::Implied(0x03),
+        "OR4" => Form::Implied(0x04),
+        "OR5" => Form::Implied(0x05),
+        "AN6" => Form::Implied(0x06),
+        "AN7" => Form::Implied(0x07),
+        "DB0" => Form::Implied(0x08),
+        "DB1" => Form::Implied(0x09),
+        "SB0" => Form::Implied(0x0A),
+        "SB1" => Form::Implied(0x0B),
+        "EIN" => Form::Implied(0x0C),
+        "DIN" => Form::Implied(0x0D),
+        "RPM" => Form::Implied(0x0E),
+        // Two-byte and register instructions
+        "JCN" => Form::ShortJump(0x10),
+        "FIM" => Form::PairData(0x20),
+        "SRC" => Form::Pair(0x21),
+        "FIN" => Form::Pair(0x30),
+        "JIN" => Form::Pair(0x31),
+        "JUN" => Form::LongJump(0x40),
+        "JMS" => Form::LongJump(0x50),
+        "INC" => Form::Register(0x60),
+        "ISZ" => Form::RegisterJump(0x70),
+        "ADD" => Form::Register(0x80),
+        "SUB" => Form::Register(0x90),
+        "LD" => Form::Register(0xA0),
+        "XCH" => Form::Register(0xB0),
+        "BBL" => Form::Immediate(0xC0),
+        "LDM" => Form::Immediate(0xD0),
+        // I/O and RAM instructions
+        "WRM" => Form::Implied(0xE0),
+        "WMP" => Form::Implied(0xE1),
+        "WRR" => Form::Implied(0xE2),
+        "WPM" => Form::Implied(0xE3),
+        "WR0" => Form::Implied(0xE4),
+        "WR1" => Form::Implied(0xE5),
+        "WR2" => Form::Implied(0xE6),
+        "WR3" => Form::Implied(0xE7),
+        "SBM" => Form::Implied(0xE8),
+        "RDM" => Form::Implied(0xE9),
+        "RDR" => Form::Implied(0xEA),
+        "ADM" => Form::Implied(0xEB),
+        "RD0" => Form::Implied(0xEC),
+        "RD1" => Form::Implied(0xED),
+        "RD2" => Form::Implied(0xEE),
+        "RD3" => Form::Implied(0xEF),
+        // Accumulator group
+        "CLB" => Form::Implied(0xF0),
+        "CLC" => Form::Implied(0xF1),
+        "IAC" => Form::Implied(0xF2),
+        "CMC" => Form::Implied(0xF3),
+        "CMA" => Form::Implied(0xF4),
+        "RAL" => Form::Implied(0xF5),
+        "RAR" => Form::Implied(0xF6),
+        "TCC" => Form::Implied(0xF7),
+        "DAC" => Form::Implied(0xF8),
+        "TCS" => Form::Implied(0xF9),
+        "STC" => Form::Implied(0xFA),
+        "DAA" => Form::Implied(0xFB),
+        "KBP" => Form::Implied(0xFC),
+        "DCL" => Form::Implied(0xFD),
+        _ => return None,
+    };
+    Some(form)
+}
+
+/// One parsed source statement
+#[derive(Debug, Clone)]
+enum Statement {
+    Empty,
+    Instruction(String, Form, Vec<String>), // Mnemonic, form, operand expressions
+    Org(String),
+    Db(Vec<String>),
+    Equ(String, String), // Name, expression
+}
+
+/// A source line after parsing
+#[derive(Debug, Clone)]
+struct SourceLine {
+    number: usize,         // 1-based line number for diagnostics
+    label: Option<String>, // Label defined at the start of the line
+    statement: Statement,
+}
+
+/// Two-pass MCS-4 assembler
+/// Pass one assigns addresses and defines labels, pass two encodes the instructions.
+/// ORG and EQU expressions are evaluated in pass one, so they may only use symbols
+/// defined above them; instruction and DB operands may refer forward.
+pub struct Assembler {
+    symbols: HashMap<String, i64>, // Labels and equates
+    labels: BTreeMap<String, u16>, // Labels only, reported in the Assembly
+    image: Vec<Option<u8>>,        // Program space, None where nothing was assembled
+    warnings: Vec<String>,         // Collected warnings
+}
+
+impl Default for Assembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Assembler {
+    /// Create a new assembler with an empty symbol table
+    /// Returns: New Assembler instance
+    pub fn new() -> Self {
+        Assembler {
+            symbols: HashMap::new(),
+            labels: BTreeMap::new(),
+            image: vec![None; PROGRAM_SPACE],
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Assemble a complete source text
+    /// Parameters: source - Assembly source
+    /// Returns: Ok(Assembly) on success, Err(String) with the first error and its line number
+    pub fn assemble(mut self, source: &str) -> Result<Assembly, String> {
+        let lines = source
+            .lines()
+            .enumerate()
+            .map(|(index, text)| parse_line(text).map_err(|e| format!("Line {}: {}", index + 1, e)))
+            .collect::<Result<Vec<_>, String>>()?;
+        let lines: Vec<SourceLine> = lines
+            .into_iter()
+            .enumerate()
+            .map(|(index, (label, statement))| SourceLine {
+                number: index + 1,
+                label,
+                statement,
+            })
+            .collect();
+
+        // Pass one: addresses and symbols
+        let mut addresses = Vec::with_capacity(lines.len());
+        let mut address = 0usize;
+        for line in &lines {
+            self.first_pass_line(line, &mut address)
+                .map_err(|e| format!("Line {}: {}", line.number, e))?;
+            addresses.push(address);
+            address += self
+                .statement_size(&line.statement)
+                .map_err(|e| format!("Line {}: {}", line.number, e))?;
+        }
+
+        // Pass two: encoding
+        for (line, &address) in lines.iter().zip(&addresses) {
+            let bytes = self
+                .encode(&line.statement, address, line.number)
+                .map_err(|e| format!("Line {}: {}", line.number, e))?;
+            self.emit(address, &bytes)
+                .map_err(|e| format!("Line {}: {}", line.number, e))?;
+        }
+
+        let end = self
+            .image
+            .iter()
+            .rposition(|byte| byte.is_some())
+            .map_or(0, |last| last + 1);
+        Ok(Assembly {
+            binary: self.image[..end]
+                .iter()
+                .map(|b| b.unwrap_or(0x00))
+                .collect(),
+            symbols: self.labels,
+            warnings: self.warnings,
+        })
+    }
+
+    /// Define labels and evaluate ORG/EQU for one line
+    /// Parameters: line - Parsed line, address - Current address, updated by ORG
+    /// Returns: Ok(()) on success, Err(String) on a duplicate symbol or bad expression
+    fn first_pass_line(&mut self, line: &SourceLine, address: &mut usize) -> Result<(), String> {
+        if let Statement::Org(expr) = &line.statement {
+            let target = self.evaluate(expr, *address)?;
+            if !(0..PROGRAM_SPACE as i64).contains(&target) {
+                return Err(format!(
+                    "ORG 0x{:X} is outside the 4 KiB program space",
+                    target
+                ));
+            }
+            *address = target as usize;
+        }
+
+        if let Some(label) = &line.label {
+            self.define(label, *address as i64)?;
+            self.labels.insert(label.clone(), *address as u16);
+        }
+
+        if let Statement::Equ(name, expr) = &line.statement {
+            let value = self.evaluate(expr, *address)?;
+            self.define(name, value)?;
+        }
+        Ok(())
+    }
+
+    /// Add a symbol to the table
+    /// Parameters: name - Symbol name, value - Symbol value
+    /// Returns: Ok(()) on success, Err(String) if already defined
+    fn define(&mut self, name: &str, value: i64) -> Result<(), String> {
+        if self.symbols.insert(name.to_string(), value).is_some() {
+            return Err(format!("Symbol '{}' is already defined", name));
+        }
+        Ok(())
+    }
+
+    /// Get the number of bytes a statement occupies
+    /// Parameters: statement - Parsed statement
+    /// Returns: Ok(size) in bytes, Err(String) if a DB string is malformed
+    fn statement_size(&self, statement: &Statement) -> Result<usize, String> {
+        Ok(match statement {
+            Statement::Instruction(_, form, _) => form.size(),
+            Statement::Db(items) => items
+                .iter()
+                .map(|item| string_literal(item).map_or(Ok(1), |s| s.map(|s| s.len())))
+                .sum::<Result<usize, String>>()?,
+            _ => 0,
+        })
+    }
+
+    /// Encode one statement
+    /// Parameters: statement - Parsed statement, address - Address of its first byte,
+    ///             line - Line number for warnings
+    /// Returns: Ok(bytes), Err(String) if an operand is missing, undefined or out of range
+    fn encode(
+        &mut self,
+        statement: &Statement,
+        address: usize,
+        line: usize,
+    ) -> Result<Vec<u8>, String> {
+        match statement {
+            Statement::Instruction(mnemonic, form, operands) => {
+                self.encode_instruction(mnemonic, *form, operands, address, line)
+            }
+            Statement::Db(items) => {
+                let mut bytes = Vec::new();
+                for item in items {
+                    match string_literal(item) {
+                        Some(text) => bytes.extend(text?.bytes()),
+                        None => {
+                            let value = self.evaluate(item, address + bytes.len())?;
+                            bytes.push(check_range(value, -128, 255, "DB value")? as u8);
+                        }
+                    }
+                }
+                Ok(bytes)
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Encode an instruction
+    /// Parameters: mnemonic - Upper-case mnemonic, form - Operand layout, operands - Operand
+    ///             expressions, address - Address of the opcode, line - Line number for warnings
+    /// Returns: Ok(bytes), Err(String) if the operands do not fit the instruction
+    fn encode_instruction(
+        &mut self,
+        mnemonic: &str,
+        form: Form,
+        operands: &[String],
+        address: usize,
+        line: usize,
+    ) -> Result<Vec<u8>, String> {
+        let expected = match form {
+            Form::Implied(_) => 0,
+            Form::PairData(_) | Form::ShortJump(_) | Form::RegisterJump(_) => 2,
+            _ => 1,
+        };
+        if operands.len() != expected {
+            return Err(format!(
+                "{} takes {} operand(s), found {}",
+                mnemonic,
+                expected,
+                operands.len()
+            ));
+        }
+
+        // JCN and ISZ take their page from the word after their second byte, FIN and JIN
+        // from the next opcode
+        let next_word = match form {
+            Form::ShortJump(_) | Form::RegisterJump(_) => Some(address + 2),
+            _ if matches!(mnemonic, "FIN" | "JIN") => Some(address + 1),
+            _ => None,
+        };
+        if let Some(next_word) = next_word.filter(|next| next & 0xF00 != address & 0xF00) {
+            self.warnings.push(format!(
+                "Line {}: {} at 0x{:03X} ends its page and operates on page 0x{:03X}",
+                line,
+                mnemonic,
+                address,
+                next_word & 0xF00
+            ));
+        }
+
+        Ok(match form {
+            Form::Implied(opcode) => vec![opcode],
+            Form::Register(opcode) => vec![opcode | self.register(&operands[0], address)?],
+            Form::Immediate(opcode) => {
+                let data = self.evaluate(&operands[0], address)?;
+                vec![opcode | check_range(data, 0, 15, "4-bit data")? as u8]
+            }
+            Form::Pair(opcode) => vec![opcode | (self.pair(&operands[0], address)? << 1)],
+            Form::PairData(opcode) => {
+                let data = self.evaluate(&operands[1], address)?;
+                vec![
+                    opcode | (self.pair(&operands[0], address)? << 1),
+                    check_range(data, 0, 255, "8-bit data")? as u8,
+                ]
+            }
+            Form::ShortJump(opcode) => vec![
+                opcode | self.condition(&operands[0], address)?,
+                self.page_address(mnemonic, &operands[1], address)?,
+            ],
+            Form::RegisterJump(opcode) => vec![
+                opcode | self.register(&operands[0], address)?,
+                self.page_address(mnemonic, &operands[1], address)?,
+            ],
+            Form::LongJump(opcode) => {
+                let target = self.evaluate(&operands[0], address)?;
+                let target = check_range(target, 0, 0xFFF, "Jump target")? as u16;
+                vec![opcode | (target >> 8) as u8, target as u8]
+            }
+        })
+    }
+
+    /// Resolve the target of a JCN or ISZ
+    /// The target lies in the page of the word after the second byte, as the CPU sees it
+    /// Parameters: mnemonic - Instruction, operand - Target expression, address - Opcode address
+    /// Returns: Ok(low byte of the target), Err(String) if the target is outside the reachable page
+    fn page_address(&self, mnemonic: &str, operand: &str, address: usize) -> Result<u8, String> {
+        let target = self.evaluate(operand, address)?;
+        let page = (address + 2) & 0xF00;
+        if !(page as i64..page as i64 + 0x100).contains(&target) {
+            return Err(format!(
+                "{} target 0x{:03X} is outside page 0x{:03X}; use JUN to leave the page",
+                mnemonic, target, page
+            ));
+        }
+        Ok(target as u8)
+    }
+
+    /// Resolve an index register operand
+    /// Parameters: operand - `Rn` or an expression, address - Current address
+    /// Returns: Ok(register 0-15), Err(String) otherwise
+    fn register(&self, operand: &str, address: usize) -> Result<u8, String> {
+        let upper = operand.to_ascii_uppercase();
+        let value = match upper.strip_prefix('R').map(str::parse::<i64>) {
+            Some(Ok(index)) => index,
+            _ => self.evaluate(operand, address)?,
+        };
+        Ok(check_range(value, 0, 15, "Register")? as u8)
+    }
+
+    /// Resolve a register pair operand
+    /// Parameters: operand - `Pn`, `nP` or an expression, address - Current address
+    /// Returns: Ok(pair 0-7), Err(String) otherwise
+    fn pair(&self, operand: &str, address: usize) -> Result<u8, String> {
+        let upper = operand.to_ascii_uppercase();
+        let named = upper
+            .strip_prefix('P')
+            .or_else(|| upper.strip_suffix('P'))
+            .and_then(|index| index.parse::<i64>().ok());
+        let value = match named {
+            Some(index) => index,
+            None => self.evaluate(operand, address)?,
+        };
+        Ok(check_range(value, 0, 7, "Register pair")? as u8)
+    }
+
+    /// Resolve a JCN condition
+    /// Parameters: operand - Condition name or an expression, address - Current address
+    /// Returns: Ok(condition 0-15), Err(String) otherwise
+    fn condition(&self, operand: &str, address: usize) -> Result<u8, String> {
+        let value = match operand.to_ascii_uppercase().as_str() {
+            "TZ" => 0x1,
+            "C1" => 0x2,
+            "AZ" => 0x4,
+            "TN" => 0x9,
+            "C0" => 0xA,
+            "AN" => 0xC,
+            _ => self.evaluate(operand, address)?,
+        };
+        Ok(check_range(value, 0, 15, "Condition")? as u8)
+    }
+
+    /// Place bytes into the program image
+    /// Parameters: address - First address, bytes - Bytes to place
+    /// Returns: Ok(()) on success, Err(String) past the end of the program space or on overlap
+    fn emit(&mut self, address: usize, bytes: &[u8]) -> Result<(), String> {
+        if address + bytes.len() > PROGRAM_SPACE {
+            return Err(format!(
+                "Code at 0x{:03X} runs past the end of the 4 KiB program space",
+                address
+            ));
+        }
+        for (offset, &byte) in bytes.iter().enumerate() {
+            let slot = &mut self.image[address + offset];
+            if slot.is_some() {
+                return Err(format!(
+                    "Address 0x{:03X} is assembled twice",
+                    address + offset
+                ));
+            }
+            *slot = Some(byte);
+        }
+        Ok(())
+    }
+
+    /// Evaluate an expression
+    /// Parameters: expr - Expression text, address - Value of `$`
+    /// Returns: Ok(value), Err(String) on a syntax error or undefined symbol
+    fn evaluate(&self, expr: &str, address: usize) -> Result<i64, String> {
+        let tokens = tokenize(expr)?;
+        let mut parser = ExprParser {
+            tokens: &tokens,
+            position: 0,
+            symbols: &self.symbols,
+            address: address as i64,
+        };
+        let value = parser.binary(0)?;
+        if parser.position != tokens.len() {
+            return Err(format!("Unexpected text in expression '{}'", expr));
+        }
+        Ok(value)
+    }
+}
+
+/// Assemble a source text with a fresh assembler
+/// Parameters: source - Assembly source
+/// Returns: Ok(Assembly) on success, Err(String) with the first error and its line number
+pub fn assemble(source: &str) -> Result<Assembly, String> {
+    Assembler::new().assemble(source)
+}
+
+/// Assemble a source file
+/// Parameters: path - Path of the source file
+/// Returns: Ok(Assembly) on success, Err(String) if the file cannot be read or has errors
+pub fn assemble_file(path: &str) -> Result<Assembly, String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    assemble(&source).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Check that a value fits an operand
+/// Parameters: value - Evaluated value, min/max - Inclusive range, what - Operand description
+/// Returns: Ok(value), Err(String) if out of range
+fn check_range(value: i64, min: i64, max: i64, what: &str) -> Result<i64, String> {
+    if value < min || value > max {
+        return Err(format!(
+            "{} {} is out of range {}..{}",
+            what, value, min, max
+        ));
+    }
+    Ok(value)
+}
+
+/// Check whether an identifier character is allowed
+fn is_symbol_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.'
+}
+
+/// Split off a leading identifier
+/// Parameters: text - Text starting with the identifier
+/// Returns: Some((identifier, rest)) if text starts with a letter, '_' or '.'
+fn leading_identifier(text: &str) -> Option<(&str, &str)> {
+    let first = text.chars().next()?;
+    if !(first.is_ascii_alphabetic() || first == '_' || first == '.') {
+        return None;
+    }
+    let end = text
+        .find(|c: char| !is_symbol_char(c))
+        .unwrap_or(text.len());
+    Some((&text[..end], &text[end..]))
+}
+
+/// Parse a double-quoted DB string
+/// Parameters: item - DB operand
+/// Returns: None if the operand is not a string, Some(Ok(text)) or Some(Err) if unterminated
+fn string_literal(item: &str) -> Option<Result<String, String>> {
+    let rest = item.strip_prefix('"')?;
+    Some(
+        rest.strip_suffix('"')
+            .map(str::to_string)
+            .ok_or_else(|| format!("Unterminated string {}", item)),
+    )
+}
+
+/// Remove a comment, ignoring ';' inside quotes
+/// Parameters: text - Source line
+/// Returns: Code part of the line
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, ';') => return &text[..index],
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Split operands at top-level commas
+/// Parameters: text - Operand field
+/// Returns: Trimmed operands; empty if the field is blank
+fn split_operands(text: &str) -> Vec<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Vec::new();
+    }
+    let mut operands = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut depth = 0;
+    for c in text.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                operands.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    operands.push(current.trim().to_string());
+    operands
+}
+
+/// Parse one source line
+/// Parameters: text - Source line
+/// Returns: Ok((label, statement)), Err(String) on an unknown mnemonic or malformed label
+fn parse_line(text: &str) -> Result<(Option<String>, Statement), String> {
+    let mut code = strip_comment(text).trim();
+    let mut label = None;
+
+    // A leading identifier followed by ':' is a label
+    if let Some((name, rest)) = leading_identifier(code) {
+        if let Some(rest) = rest.strip_prefix(':') {
+            label = Some(name.to_string());
+            code = rest.trim();
+        }
+    }
+    if code.is_empty() {
+        return Ok((label, Statement::Empty));
+    }
+
+    let (word, rest) = leading_identifier(code)
+        .ok_or_else(|| format!("Expected a mnemonic or directive, found '{}'", code))?;
+    let rest = rest.trim();
+
+    // NAME EQU expr or NAME = expr
+    let equate = rest
+        .strip_prefix('=')
+        .or_else(|| {
+            leading_identifier(rest)
+                .filter(|(keyword, _)| keyword.eq_ignore_ascii_case("EQU"))
+                .map(|(_, value)| value)
+        })
+        .map(str::trim);
+    if let Some(value) = equate {
+        if label.is_some() {
+            return Err("An equate cannot also carry a label".to_string());
+        }
+        return Ok((None, Statement::Equ(word.to_string(), value.to_string())));
+    }
+
+    let keyword = word.to_ascii_uppercase();
+    let operands = split_operands(rest);
+    let statement = match keyword.as_str() {
+        "ORG" => match operands.as_slice() {
+            [expr] => Statement::Org(expr.clone()),
+            _ => return Err("ORG takes one address".to_string()),
+        },
+        "DB" if !operands.is_empty() => Statement::Db(operands),
+        "DB" => return Err("DB needs at least one value".to_string()),
+        _ => {
+            let form =
+                lookup_mnemonic(&keyword).ok_or_else(|| format!("Unknown mnemonic '{}'", word))?;
+            Statement::Instruction(keyword, form, operands)
+        }
+    };
+    Ok((label, statement))
+}
+
+/// Expression token
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Symbol(String),
+    Here,             // `$`, the current address
+    Operator(String), // Arithmetic, bitwise and shift operators
+    Open,
+    Close,
+}
+
+/// Split an expression into tokens
+/// Parameters: expr - Expression text
+/// Returns: Ok(tokens), Err(String) on a malformed number or unknown character
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' || c == ')' {
+            tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            i += 1;
+        } else if c == '\'' {
+            // Character literal
+            match (chars.get(i + 1), chars.get(i + 2)) {
+                (Some(&ch), Some('\'')) => tokens.push(Token::Number(ch as i64)),
+                _ => return Err(format!("Malformed character literal in '{}'", expr)),
+            }
+            i += 3;
+        } else if c == '$' {
+            let end = (i + 1..chars.len())
+                .find(|&j| !chars[j].is_ascii_hexdigit())
+                .unwrap_or(chars.len());
+            if end == i + 1 {
+                tokens.push(Token::Here);
+            } else {
+                let digits: String = chars[i + 1..end].iter().collect();
+                tokens.push(Token::Number(parse_number(&digits, 16, expr)?));
+            }
+            i = end;
+        } else if c.is_ascii_digit() {
+            let end = (i..chars.len())
+                .find(|&j| !chars[j].is_ascii_alphanumeric())
+                .unwrap_or(chars.len());
+            let word: String = chars[i..end].iter().collect();
+            let lower = word.to_ascii_lowercase();
+            let value = if let Some(hex) = lower.strip_prefix("0x") {
+                parse_number(hex, 16, expr)?
+            } else if let Some(hex) = lower.strip_suffix('h') {
+                parse_number(hex, 16, expr)?
+            } else if let Some(binary) = lower.strip_prefix("0b") {
+                parse_number(binary, 2, expr)?
+            } else {
+                parse_number(&lower, 10, expr)?
+            };
+            tokens.push(Token::Number(value));
+            i = end;
+        } else if c.is_ascii_alphabetic() || c == '_' || c == '.' {
+            let end = (i..chars.len())
+                .find(|&j| !is_symbol_char(chars[j]))
+                .unwrap_or(chars.len());
+            tokens.push(Token::Symbol(chars[i..end].iter().collect()));
+            i = end;
+        } else {
+            let pair: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            if pair == "<<" || pair == ">>" {
+                tokens.push(Token::Operator(pair));
+                i += 2;
+            } else if "+-*/%&|^~".contains(c) {
+                tokens.push(Token::Operator(c.to_string()));
+                i += 1;
+            } else {
+                return Err(format!("Unexpected character '{}' in '{}'", c, expr));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parse a number in a given radix
+/// Parameters: digits - Digits without prefix or suffix, radix - Number base, expr - Whole
+///             expression for the error message
+/// Returns: Ok(value), Err(String) if the digits are invalid
+fn parse_number(digits: &str, radix: u32, expr: &str) -> Result<i64, String> {
+    i64::from_str_radix(digits, radix).map_err(|_| format!("Invalid number in '{}'", expr))
+}
+
+/// Precedence-climbing expression parser
+struct ExprParser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    symbols: &'a HashMap<String, i64>,
+    address: i64,
+}
+
+impl ExprParser<'_> {
+    /// Get the binding strength of a binary operator
+    /// Parameters: operator - Operator text
+    /// Returns: Some(precedence), higher binds tighter, None if not binary
+    fn precedence(operator: &str) -> Option<u8> {
+        match operator {
+            "|" => Some(1),
+            "^" => Some(2),
+            "&" => Some(3),
+            "<<" | ">>" => Some(4),
+            "+" | "-" => Some(5),
+            "*" | "/" | "%" => Some(6),
+            _ => None,
+        }
+    }
+
+    /// Parse binary operators binding tighter than a minimum precedence
+    /// Parameters: min_precedence - Lowest precedence to accept
+    /// Returns: Ok(value), Err(String) on a syntax error
+    fn binary(&mut self, min_precedence: u8) -> Result<i64, String> {
+        let mut left = self.unary()?;
+        while let Some(Token::Operator(operator)) = self.tokens.get(self.position) {
+            let Some(precedence) = Self::precedence(operator) else {
+                break;
+            };
+            if precedence < min_precedence {
+                break;
+            }
+            let operator = operator.clone();
+            self.position += 1;
+            let right = self.binary(precedence + 1)?;
+            left = match operator.as_str() {
+                "|" => left | right,
+                "^" => left ^ right,
+                "&" => left & right,
+                "<<" => left.checked_shl(right as u32).unwrap_or(0),
+                ">>" => left.checked_shr(right as u32).unwrap_or(0),
+                "+" => left.wrapping_add(right),
+                "-" => left.wrapping_sub(right),
+                "*" => left.wrapping_mul(right),
+                _ if right == 0 => return Err("Division by zero".to_string()),
+                "/" => left / right,
+                _ => left % right,
+            };
+        }
+        Ok(left)
+    }
+
+    /// Parse a unary expression, number, symbol or parenthesised expression
+    /// Returns: Ok(value), Err(String) on a syntax error or undefined symbol
+    fn unary(&mut self) -> Result<i64, String> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| "Missing operand in expression".to_string())?;
+        self.position += 1;
+        match token {
+            Token::Number(value) => Ok(value),
+            Token::Here => Ok(self.address),
+            Token::Symbol(name) => self
+                .symbols
+                .get(&name)
+                .copied()
+                .ok_or_else(|| format!("Undefined symbol '{}'", name)),
+            Token::Operator(op) if op == "-" => Ok(-self.unary()?),
+            Token::Operator(op) if op == "+" => self.unary(),
+            Token::Operator(op) if op == "~" => Ok(!self.unary()?),
+            Token::Open => {
+                let value = self.binary(0)?;
+                if self.tokens.get(self.position) != Some(&Token::Close) {
+                    return Err("Missing ')' in expression".to_string());
+                }
+                self.position += 1;
+                Ok(value)
+            }
+            other => Err(format!("Unexpected {:?} in expression", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asm_encodes_every_operand_form() {
+        let source = "
+            NOP
+            LDM 5
+            XCH R10
+            INC r3
+            FIM P2, 0xA5
+            SRC 1P
+            FIN P0
+            JIN P7
+            JUN 0x123
+            JMS 0xFFF
+            JCN AN, 0x00
+            JCN 6, $
+            ISZ R4, 2
+            BBL 15
+            WR2
+            DCL
+            HLT
+        ";
+        let assembly = assemble(source).unwrap();
+        assert_eq!(
+            assembly.binary,
+            vec![
+                0x00,
+                0xD5,
+                0xBA,
+                0x63,
+                0x24,
+                0xA5,
+                0x23,
+                0x30,
+                0x3F,
+                0x41,
+                0x23,
+                0x5F,
+                0xFF,
+                0x1C,
+                0x00,
+                0x16,
+                0x0F,
+                0x74,
+                0x02,
+                0xC0 | 15,
+                0xE6,
+                0xFD,
+                0x01,
+            ]
+        );
+        assert!(assembly.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_asm_labels_equates_and_expressions() {
+        let source = "
+COUNT   EQU 16 - 8
+BASE    = (COUNT << 4) | 1       ; 0x81
+START:  FIM P0, BASE
+LOOP:   LDM COUNT & 0x7
+        ISZ R1, LOOP
+        JUN END
+        DB 'A', \"Hi;\", -1, $ & 0xFF, 1FH, 0b101, $20
+END:    JUN START
+";
+        let assembly = assemble(source).unwrap();
+        assert_eq!(
+            assembly.binary,
+            vec![
+                0x20, 0x81, 0xD0, 0x71, 0x02, 0x40, 0x10, 0x41, 0x48, 0x69, 0x3B, 0xFF, 0x0C, 0x1F,
+                0x05, 0x20, 0x40, 0x00,
+            ]
+        );
+        assert_eq!(assembly.symbols["START"], 0x000);
+        assert_eq!(assembly.symbols["LOOP"], 0x002);
+        assert_eq!(assembly.symbols["END"], 0x010);
+        assert!(!assembly.symbols.contains_key("COUNT"));
+        assert_eq!(
+            assembly.symbol_file(),
+            "START EQU 0x000\nLOOP EQU 0x002\nEND EQU 0x010\n"
+        );
+    }
+
+    #[test]
+    fn test_asm_org_fills_gaps_with_nop() {
+        let assembly = assemble("ORG 0x100\nMAIN: JUN MAIN\nORG 4\nDB 7").unwrap();
+        assert_eq!(assembly.binary.len(), 0x102);
+        assert_eq!(&assembly.binary[..6], &[0x00, 0x00, 0x00, 0x00, 0x07, 0x00]);
+        assert_eq!(&assembly.binary[0x100..], &[0x41, 0x00]);
+        assert!(assemble("").unwrap().binary.is_empty());
+    }
+
+    #[test]
+    fn test_asm_page_rules() {
+        // A JCN in the last word of a page reaches the next page, with a warning
+        let assembly = assemble("ORG 0xFF\nJCN C1, NEXT\nNEXT: FIN P1").unwrap();
+        assert_eq!(&assembly.binary[0xFF..], &[0x12, 0x01, 0x32]);
+        assert_eq!(assembly.warnings.len(), 1);
+        assert!(assembly.warnings[0].starts_with("Line 2: JCN at 0x0FF"));
+
+        let assembly = assemble("ORG 0x1FF\nJIN P0").unwrap();
+        assert!(assembly.warnings[0].contains("page 0x200"));
+
+        // One word earlier the second byte still ends the page
+        let assembly = assemble("ORG 0xFE\nJCN TZ, 0x110").unwrap();
+        assert_eq!(&assembly.binary[0xFE..], &[0x11, 0x10]);
+        assert!(assembly.warnings[0].starts_with("Line 2: JCN at 0x0FE"));
+        let error = assemble("ORG 0xFE\nISZ R0, 0x10").unwrap_err();
+        assert!(error.starts_with("Line 2: ISZ target 0x010 is outside page 0x100"));
+
+        let error = assemble("ORG 0xFD\nISZ R0, 0x100").unwrap_err();
+        assert!(error.starts_with("Line 2: ISZ target 0x100 is outside page 0x000"));
+        assert!(assemble("ORG 0xFD\nISZ R0, 0x10")
+            .unwrap()
+            .warnings
+            .is_empty());
+    }
+
+    #[test]
+    fn test_asm_reports_errors_with_line_numbers() {
+        assert_eq!(
+            assemble("NOP\nFOO 1").unwrap_err(),
+            "Line 2: Unknown mnemonic 'FOO'"
+        );
+        assert_eq!(
+            assemble("LDM 16").unwrap_err(),
+            "Line 1: 4-bit data 16 is out of range 0..15"
+        );
+        assert_eq!(
+            assemble("JUN NOWHERE").unwrap_err(),
+            "Line 1: Undefined symbol 'NOWHERE'"
+        );
+        assert_eq!(
+            assemble("A: NOP\nA: NOP").unwrap_err(),
+            "Line 2: Symbol 'A' is already defined"
+        );
+        assert!(assemble("FIM P8, 0").is_err());
+        assert!(assemble("ADD").is_err());
+        assert!(assemble("ORG 0xFFF\nJUN 0").is_err());
+        assert!(assemble("NOP\nORG 0\nNOP")
+            .unwrap_err()
+            .contains("assembled twice"));
+        assert!(assemble("ORG FWD\nFWD: NOP").is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use std::any::Any;
 use std::collections::HashMap;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
@@ -6,7 +7,11 @@ use std::sync::{
 use std::thread;
 use std::time::Duration;
 
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
 use crate::pin::Pin;
+use crate::sim_time::TimeBase;
 
 /// Core trait for all hardware components in the emulator
 /// Provides the fundamental interface that all components must implement
@@ -29,6 +34,10 @@ pub trait Component: Send + Sync {
     /// This method is called repeatedly during component execution
     fn update(&mut self);
 
+    /// Mark the component as running without entering its own execution loop
+    /// Used by schedulers that drive `update()` externally instead of spawning threads
+    fn start(&mut self);
+
     /// Run the component in a continuous loop until stopped
     /// This method blocks until the component is stopped
     fn run(&mut self);
@@ -40,7 +49,52 @@ pub trait Component: Send + Sync {
     /// Check if the component is currently running
     /// Returns: true if component is running, false otherwise
     fn is_running(&self) -> bool;
+
+    /// Attach the shared simulation time base used to measure latencies
+    /// Components that do not track time can rely on the default no-op implementation
+    /// Parameters: time_base - Time base shared by all components of a system
+    fn attach_time_base(&mut self, _time_base: TimeBase) {}
+
+    /// Capture the component's internal state for a machine snapshot
+    /// Pin levels are captured by the system, so this covers registers, memory and latches
+    /// Returns: State as JSON, or None for components without internal state
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Restore internal state captured by `snapshot_state`
+    /// Parameters: state - State from a snapshot of a component of the same type
+    /// Returns: Ok(()) on success, Err(String) if the state does not fit this component
+    fn restore_state(&mut self, _state: &serde_json::Value) -> Result<(), String> {
+        Err(format!("Component {} has no state to restore", self.name()))
+    }
+
+    /// Get this component as `Any` for downcasting to its concrete type
+    /// Returns: Reference to the component as a `dyn Any`
+    fn as_any(&self) -> &dyn Any;
+
+    /// Get this component as mutable `Any` for downcasting to its concrete type
+    /// Returns: Mutable reference to the component as a `dyn Any`
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// Convert a component state into the JSON returned by `Component::snapshot_state`
+/// Parameters: state - Serializable state of a component
+/// Returns: State as JSON, or None if it cannot be serialized
+pub fn state_to_json<T: Serialize>(state: &T) -> Option<serde_json::Value> {
+    serde_json::to_value(state).ok()
 }
+
+/// Convert the JSON given to `Component::restore_state` back into a component state
+/// Parameters: name - Component name for error messages, state - State as JSON
+/// Returns: Ok(state), Err(String) if the JSON does not describe this kind of component
+pub fn state_from_json<T: DeserializeOwned>(
+    name: &str,
+    state: &serde_json::Value,
+) -> Result<T, String> {
+    T::deserialize(state).map_err(|e| format!("Invalid state for component {}: {}", name, e))
+}
+
 /// Extended trait for components that can be run in their own threads
 /// Provides automatic thread spawning functionality for components
 pub trait RunnableComponent: Component + Send + 'static {
@@ -138,10 +192,14 @@ impl Component for BaseComponent {
         // Base implementation does nothing
     }
 
+    fn start(&mut self) {
+        self.set_running(true);
+    }
+
     /// Run the component in a continuous loop until stopped
     /// Provides a default time-sliced execution model with 10 microsecond delays
     fn run(&mut self) {
-        self.set_running(true);
+        self.start();
         while self.is_running() {
             self.update();
             thread::sleep(Duration::from_micros(10));
@@ -157,6 +215,14 @@ impl Component for BaseComponent {
     fn is_running(&self) -> bool {
         self.running.load(Ordering::SeqCst)
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
 }
 
 impl RunnableComponent for BaseComponent {}
@@ -1,10 +1,13 @@
+use std::any::Any;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
-use crate::component::{BaseComponent, Component};
+use crate::component::{self, BaseComponent, Component};
 use crate::pin::{Pin, PinValue};
+use crate::sim_time::TimeBase;
+use serde::{Deserialize, Serialize};
 
 pub struct GenericClock {
     base: BaseComponent,
@@ -15,6 +18,15 @@ pub struct GenericClock {
     high_time: Duration,
     low_time: Duration,
     enabled: bool,
+    time_base: TimeBase, // Simulation time base advanced by every clock edge
+}
+
+/// Snapshot of the clock output phase
+/// Frequency and duty cycle are configuration and not included
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenericClockState {
+    current_state: PinValue,
+    enabled: bool,
 }
 
 impl GenericClock {
@@ -31,6 +43,7 @@ impl GenericClock {
             high_time: Duration::from_secs_f64(0.5 / frequency), // Will be recalculated in set_duty_cycle
             low_time: Duration::from_secs_f64(0.5 / frequency), // Will be recalculated in set_duty_cycle
             enabled: true,
+            time_base: TimeBase::new(),
         };
 
         clock.set_duty_cycle(0.5); // Initialize timing
@@ -96,7 +109,29 @@ impl GenericClock {
         }
     }
 
+    /// Advance the clock by exactly one edge, independent of wall-clock time
+    /// Used by the lockstep scheduler to step the clock in simulated time
+    /// Returns: Duration of the half-period that ended at this edge in nanoseconds
+    pub fn tick(&mut self) -> u64 {
+        let elapsed = self.current_phase_time();
+        if self.enabled {
+            self.perform_transition();
+        }
+        elapsed.as_nanos() as u64
+    }
+
+    /// Get the length of the half-period the clock output is currently in
+    fn current_phase_time(&self) -> Duration {
+        match self.current_state {
+            PinValue::High => self.high_time,
+            _ => self.low_time,
+        }
+    }
+
     fn perform_transition(&mut self) {
+        // The edge ends the current half-period, so simulated time moves past it
+        self.time_base.advance(self.current_phase_time());
+
         let new_state = match self.current_state {
             PinValue::High => PinValue::Low,
             PinValue::Low => PinValue::High,
@@ -141,9 +176,13 @@ impl Component for GenericClock {
         }
     }
 
-    fn run(&mut self) {
+    fn start(&mut self) {
         self.base.set_running(true);
         self.enable(); // Ensure clock is enabled when running
+    }
+
+    fn run(&mut self) {
+        self.start();
 
         while self.is_running() {
             self.update();
@@ -171,10 +210,51 @@ impl Component for GenericClock {
     fn is_running(&self) -> bool {
         self.base.is_running()
     }
+
+    fn attach_time_base(&mut self, time_base: TimeBase) {
+        self.time_base = time_base;
+    }
+
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        component::state_to_json(&self.snapshot())
+    }
+
+    fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        let state = component::state_from_json(&self.base.name(), state)?;
+        self.restore(&state);
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
 }
 
 // Advanced clock features
-impl GenericClock {}
+impl GenericClock {
+    /// Capture the clock phase for a machine snapshot
+    /// Returns: Output level and enable state
+    pub fn snapshot(&self) -> GenericClockState {
+        GenericClockState {
+            current_state: self.current_state,
+            enabled: self.enabled,
+        }
+    }
+
+    /// Restore a clock phase captured by `snapshot` and drive CLK to match
+    /// The current half-period starts over in wall-clock time
+    /// Parameters: state - Clock state
+    pub fn restore(&mut self, state: &GenericClockState) {
+        self.current_state = state.current_state;
+        self.enabled = state.enabled;
+        self.last_transition = Instant::now();
+        self.set_clock_output(self.current_state);
+    }
+}
 
 #[cfg(test)]
 mod tests {
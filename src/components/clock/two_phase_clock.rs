@@ -1,10 +1,13 @@
+use std::any::Any;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
-use crate::component::{BaseComponent, Component};
+use crate::component::{self, BaseComponent, Component};
 use crate::pin::{Pin, PinValue};
+use crate::sim_time::TimeBase;
+use serde::{Deserialize, Serialize};
 
 pub struct TwoPhaseClock {
     base: BaseComponent,
@@ -13,6 +16,16 @@ pub struct TwoPhaseClock {
     last_transition: Instant,
     phase_time: Duration,
     enabled: bool,
+    time_base: TimeBase, // Simulation time base advanced by every clock edge
+}
+
+/// Snapshot of the clock phase
+/// The phase time is configuration and not included
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TwoPhaseClockState {
+    phi1_state: PinValue,
+    phi2_state: PinValue,
+    enabled: bool,
 }
 
 impl TwoPhaseClock {
@@ -33,6 +46,7 @@ impl TwoPhaseClock {
             last_transition: Instant::now(),
             phase_time,
             enabled: true,
+            time_base: TimeBase::new(),
         }
     }
 
@@ -90,6 +104,49 @@ impl TwoPhaseClock {
         }
     }
 
+    /// Advance the clock by exactly one phase edge, independent of wall-clock time
+    /// Used by the lockstep scheduler to step the clock in simulated time
+    /// Returns: Duration of the phase that the edge represents in nanoseconds
+    pub fn tick(&mut self) -> u64 {
+        if self.enabled {
+            self.perform_transition();
+        }
+        self.get_phase_time_ns()
+    }
+
+    /// Get the duration of a single clock phase
+    /// Returns: Phase duration in nanoseconds
+    pub fn get_phase_time_ns(&self) -> u64 {
+        self.phase_time.as_nanos() as u64
+    }
+
+    /// Get the current state of both clock phases
+    /// Returns: (PHI1 state, PHI2 state)
+    pub fn get_phase_states(&self) -> (PinValue, PinValue) {
+        (self.phi1_state, self.phi2_state)
+    }
+
+    /// Capture the clock phase for a machine snapshot
+    /// Returns: Φ1 and Φ2 levels and enable state
+    pub fn snapshot(&self) -> TwoPhaseClockState {
+        TwoPhaseClockState {
+            phi1_state: self.phi1_state,
+            phi2_state: self.phi2_state,
+            enabled: self.enabled,
+        }
+    }
+
+    /// Restore a clock phase captured by `snapshot` and drive Φ1 and Φ2 to match
+    /// The current phase starts over in wall-clock time
+    /// Parameters: state - Clock state
+    pub fn restore(&mut self, state: &TwoPhaseClockState) {
+        self.phi1_state = state.phi1_state;
+        self.phi2_state = state.phi2_state;
+        self.enabled = state.enabled;
+        self.last_transition = Instant::now();
+        self.update_outputs();
+    }
+
     fn should_transition(&self) -> bool {
         self.last_transition.elapsed() >= self.phase_time
     }
@@ -113,6 +170,9 @@ impl TwoPhaseClock {
             }
         }
 
+        // Each edge ends one phase, so simulated time moves forward by one phase
+        self.time_base.advance(self.phase_time);
+
         self.update_outputs();
         self.last_transition = Instant::now();
     }
@@ -155,9 +215,13 @@ impl Component for TwoPhaseClock {
         }
     }
 
-    fn run(&mut self) {
+    fn start(&mut self) {
         self.base.set_running(true);
         self.enable();
+    }
+
+    fn run(&mut self) {
+        self.start();
 
         while self.is_running() {
             self.update();
@@ -182,4 +246,26 @@ impl Component for TwoPhaseClock {
     fn is_running(&self) -> bool {
         self.base.is_running()
     }
+
+    fn attach_time_base(&mut self, time_base: TimeBase) {
+        self.time_base = time_base;
+    }
+
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        component::state_to_json(&self.snapshot())
+    }
+
+    fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        let state = component::state_from_json(&self.base.name(), state)?;
+        self.restore(&state);
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
 }
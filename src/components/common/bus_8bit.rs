@@ -0,0 +1,102 @@
+//! Common functionality for byte-wide memories on an 8-bit microprocessor bus
+//!
+//! Shared by `GenericRam` and `Rom8Bit` on a 6502-style bus: a full 16-bit address bus,
+//! an 8-bit data bus, an RW direction line and an active-high chip select.
+
+use crate::component::{BaseComponent, Component};
+use crate::pin::PinValue;
+
+/// Pin names shared by the 8-bit bus memories: A0-A15, D0-D7, RW and CS
+pub fn bus_pin_names() -> Vec<&'static str> {
+    vec![
+        "A0", "A1", "A2", "A3", "A4", "A5", "A6", "A7", "A8", "A9", "A10", "A11", "A12", "A13",
+        "A14", "A15", // 16 address lines
+        "D0", "D1", "D2", "D3", "D4", "D5", "D6", "D7", // 8 data lines
+        "RW", // Read/Write
+        "CS", // Chip select
+    ]
+}
+
+/// Check that a memory fits the 16-bit address space
+/// Parameters: size - Size in bytes, base_address - First bus address
+/// Returns: Ok(()) if size is 1-65536 and base+size does not pass $FFFF, Err(String) otherwise
+pub fn check_address_window(size: usize, base_address: u16) -> Result<(), String> {
+    if size == 0 || base_address as usize + size > 0x10000 {
+        return Err(format!(
+            "{} bytes at 0x{:04X} do not fit the 64 KB address space",
+            size, base_address
+        ));
+    }
+    Ok(())
+}
+
+/// Copy a block into a memory array
+/// Parameters: memory - Memory contents, data - Bytes to load, offset - Index of the first byte
+/// Returns: Ok(()) on success, Err(String) if the block does not fit
+pub fn load_block(memory: &mut [u8], data: &[u8], offset: usize) -> Result<(), String> {
+    let end = offset + data.len();
+    if end > memory.len() {
+        return Err(format!(
+            "Data ({} bytes at offset 0x{:04X}) exceeds memory size {}",
+            data.len(),
+            offset,
+            memory.len()
+        ));
+    }
+    memory[offset..end].copy_from_slice(data);
+    Ok(())
+}
+
+/// Bus side of the 8-bit memories
+pub trait Bus8BitMemory {
+    fn get_base(&self) -> &BaseComponent;
+
+    /// Read a pin of the bus
+    /// Parameters: name - Pin name
+    /// Returns: Pin level, HighZ if the pin does not exist or cannot be read
+    fn read_pin(&self, name: &str) -> PinValue {
+        self.get_base()
+            .get_pin(name)
+            .ok()
+            .and_then(|pin| pin.lock().ok().map(|guard| guard.read()))
+            .unwrap_or(PinValue::HighZ)
+    }
+
+    /// Read a group of lines such as A0-A15 or D0-D7
+    /// Parameters: prefix - Pin name prefix, width - Number of lines
+    /// Returns: Value on the lines, undriven lines read as 0
+    fn read_lines(&self, prefix: &str, width: usize) -> usize {
+        (0..width)
+            .filter(|line| self.read_pin(&format!("{}{}", prefix, line)) == PinValue::High)
+            .fold(0, |value, line| value | (1 << line))
+    }
+
+    /// Find the byte the bus addresses
+    /// Parameters: base_address - First bus address, size - Memory size in bytes
+    /// Returns: Some(offset) while selected and the address is in the window, None otherwise
+    fn selected_offset(&self, base_address: u16, size: usize) -> Option<usize> {
+        if self.read_pin("CS") == PinValue::Low {
+            return None;
+        }
+        let offset = self
+            .read_lines("A", 16)
+            .checked_sub(base_address as usize)?;
+        (offset < size).then_some(offset)
+    }
+
+    /// Drive the data lines, or release them with None
+    /// Parameters: data - Byte to drive, None to tri-state D0-D7
+    fn drive_data_bus(&self, data: Option<u8>) {
+        let base = self.get_base();
+        for line in 0..8 {
+            if let Ok(pin) = base.get_pin(&format!("D{}", line)) {
+                if let Ok(mut pin_guard) = pin.lock() {
+                    let value = data.map_or(PinValue::HighZ, |d| {
+                        PinValue::from_bool((d >> line) & 1 == 1)
+                    });
+                    pin_guard.set_driver(Some(format!("{}_DATA", base.name())), value);
+                }
+            }
+        }
+    }
+}
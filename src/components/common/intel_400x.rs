@@ -5,11 +5,13 @@
 
 use crate::component::{BaseComponent, Component};
 use crate::pin::{Pin, PinValue};
+use crate::sim_time::SimTime;
+use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 // Memory operation state machine states (shared across all 400x chips)
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum MemoryState {
     Idle,         // No memory operation in progress
     AddressPhase, // Currently latching address nibbles
@@ -17,7 +19,70 @@ pub enum MemoryState {
     DriveData,    // Latency elapsed, driving data on bus
 }
 
+/// Subcycles of the 8-clock MCS-4 instruction cycle
+/// The 4004 asserts SYNC during A1; every other chip on the bus follows the cycle by
+/// counting Φ1 edges from there, so all chips agree on what D0-D3 carries at any time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BusSubcycle {
+    A1, // CPU drives address bits 0-3
+    A2, // CPU drives address bits 4-7
+    A3, // CPU drives address bits 8-11 with CM-ROM active (ROM chip select)
+    M1, // ROM drives the opcode high nibble (OPR)
+    M2, // ROM drives the opcode low nibble (OPA), CM active for I/O instructions
+    X1, // Execution
+    X2, // SRC chip select nibble with CM active, or I/O data transfer
+    X3, // SRC character address nibble
+}
+
+impl BusSubcycle {
+    /// All subcycles in bus order, starting at A1
+    pub const ALL: [BusSubcycle; 8] = [
+        BusSubcycle::A1,
+        BusSubcycle::A2,
+        BusSubcycle::A3,
+        BusSubcycle::M1,
+        BusSubcycle::M2,
+        BusSubcycle::X1,
+        BusSubcycle::X2,
+        BusSubcycle::X3,
+    ];
+
+    /// Get the subcycle that follows this one
+    /// Returns: Next subcycle, wrapping from X3 back to A1
+    pub fn next(self) -> Self {
+        Self::ALL[(self.index() + 1) % 8]
+    }
+
+    /// Get the position of this subcycle within the instruction cycle
+    /// Returns: 0 for A1 through 7 for X3
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Check whether the CPU drives the fetch address during this subcycle
+    pub fn is_address(self) -> bool {
+        matches!(self, BusSubcycle::A1 | BusSubcycle::A2 | BusSubcycle::A3)
+    }
+
+    /// Check whether program memory drives the opcode during this subcycle
+    pub fn is_memory(self) -> bool {
+        matches!(self, BusSubcycle::M1 | BusSubcycle::M2)
+    }
+
+    /// Check whether this subcycle belongs to the execution phase
+    pub fn is_execute(self) -> bool {
+        matches!(self, BusSubcycle::X1 | BusSubcycle::X2 | BusSubcycle::X3)
+    }
+}
+
+impl std::fmt::Display for BusSubcycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
 /// Common timing constants for Intel 400x series
+/// All values are measured against the simulation time base, not wall-clock time
 pub struct TimingConstants;
 
 impl TimingConstants {
@@ -171,8 +236,8 @@ pub trait Intel400xAddressHandling {
         address_high_nibble: &mut Option<u8>,
         address_low_nibble: &mut Option<u8>,
         full_address_ready: &mut bool,
-        address_latch_time: &mut Option<Instant>,
-        _access_time: Duration,
+        address_latch_time: &mut Option<SimTime>,
+        now: SimTime,
     ) {
         if address_high_nibble.is_none() {
             // First cycle: latch high nibble (bits 7-4)
@@ -186,7 +251,7 @@ pub trait Intel400xAddressHandling {
                 self.assemble_full_address(*address_high_nibble, *address_low_nibble)
             {
                 *full_address_ready = true;
-                *address_latch_time = Some(Instant::now());
+                *address_latch_time = Some(now);
 
                 // Clear nibble storage for next address
                 *address_high_nibble = None;
@@ -196,14 +261,17 @@ pub trait Intel400xAddressHandling {
     }
 
     /// Handle latency timing during wait state
+    /// Parameters: address_latch_time - Simulated time the address was latched,
+    /// access_time - Required access latency, now - Current simulated time
     /// Returns: true if latency has elapsed
     fn handle_latency_wait(
         &self,
-        address_latch_time: &Option<Instant>,
+        address_latch_time: &Option<SimTime>,
         access_time: Duration,
+        now: SimTime,
     ) -> bool {
         if let Some(latch_time) = address_latch_time {
-            latch_time.elapsed() >= access_time
+            now.saturating_duration_since(*latch_time) >= access_time
         } else {
             false
         }
@@ -266,6 +334,21 @@ pub trait Intel400xControlPins {
     }
 }
 
+/// Common instruction cycle tracking for chips that follow the 4004 on the shared bus
+pub trait Intel400xBusCycle: Intel400xControlPins {
+    /// Work out which subcycle starts at this Φ1 rising edge
+    /// Hardware: SYNC marks A1; otherwise the chip counts forward from the previous subcycle
+    /// Parameters: current - Subcycle that just ended
+    /// Returns: Subcycle starting now
+    fn next_subcycle(&self, current: BusSubcycle) -> BusSubcycle {
+        if self.read_sync_pin() {
+            BusSubcycle::A1
+        } else {
+            current.next()
+        }
+    }
+}
+
 /// Common reset handling functionality
 pub trait Intel400xResetHandling {
     fn get_base(&self) -> &BaseComponent;
@@ -327,7 +410,7 @@ impl From<TimingState> for MemoryState {
 }
 
 // Forward declaration for RamState enum (defined in individual chip modules)
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum RamState {
     Idle,
     AddressPhase,
@@ -384,8 +467,8 @@ impl TimingState {
 pub trait Intel400xTimingState {
     fn get_timing_state(&self) -> TimingState;
     fn set_timing_state(&mut self, state: TimingState);
-    fn get_address_latch_time(&self) -> Option<Instant>;
-    fn set_address_latch_time(&mut self, time: Option<Instant>);
+    fn get_address_latch_time(&self) -> Option<SimTime>;
+    fn set_address_latch_time(&mut self, time: Option<SimTime>);
     fn get_full_address_ready(&self) -> bool;
     fn set_full_address_ready(&mut self, ready: bool);
     fn get_address_high_nibble(&self) -> Option<u8>;
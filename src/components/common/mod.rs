@@ -1,5 +1,6 @@
-//! Common functionality for Intel 400x series chips
+//! Common functionality shared by the chips of a component family
 
+pub mod bus_8bit;
 pub mod intel_400x;
 
 // Re-export commonly used items for convenience
@@ -1,89 +1,108 @@
-use std::collections::HashMap;
+use std::any::Any;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
-
-use crate::component::{BaseComponent, Component, RunnableComponent};
+use std::time::Duration;
+
+use crate::component::{self, BaseComponent, Component, RunnableComponent};
+use crate::components::common::intel_400x::BusSubcycle;
+use crate::components::interface::intel_4008::Intel4008;
+use crate::components::interface::intel_4009::Intel4009;
+use crate::components::memory::generic_ram::GenericRam;
+use crate::components::memory::intel_4001::Intel4001;
+use crate::components::memory::intel_4002::Intel4002;
 use crate::pin::{Pin, PinValue};
 use crate::types::U12;
+use serde::{Deserialize, Serialize};
+
+/// Program memory attached for functional execution, with the ROM bank (CM-ROM line) it
+/// answers: an Intel 4001, or the 4008, 4009 and RAM of a standard memory system
+pub type ProgramMemoryChip = (u8, Arc<Mutex<Box<dyn Component>>>);
 
-/// Represents the current phase of instruction execution
-/// The 4004 CPU processes instructions in distinct phases synchronized with the clock
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum InstructionPhase {
-    Fetch,   // Fetching instruction from memory
-    Execute, // Executing the instruction
+/// An Intel 4002 attached for functional execution, with the CM-RAM line (0-3) it answers
+pub type DataMemoryChip = (u8, Arc<Mutex<Box<dyn Component>>>);
+
+/// Execution strategy used by the CPU
+/// Both modes share the same decoder and instruction implementations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionMode {
+    PinLevel,   // Bus-accurate: fetch over D0-D3 in step with the two-phase clock
+    Functional, // Instruction-level: fetch directly from attached program memory
 }
 
-/// Memory operation state machine states
-/// Tracks the current phase of memory access operations
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum MemoryState {
-    Idle,         // No memory operation in progress
-    AddressPhase, // Currently latching address nibbles
-    WaitLatency,  // Address latched, waiting for access time
-    DriveData,    // Latency elapsed, driving data on bus
+/// MCS-4 CPU variant
+/// The Intel 4040 runs every 4004 program and adds interrupts, halt and stop, a second
+/// index register bank, a 7-level stack and a second ROM bank
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuVariant {
+    Intel4004, // 16 index registers, 3-level stack, 46 instructions
+    Intel4040, // 24 index registers, 7-level stack, 60 instructions
 }
 
 /// Intel 4004 instruction set enumeration
-/// Complete set of 46 instructions for the Intel 4004 microprocessor
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Complete set of 46 instructions for the Intel 4004 microprocessor, named as in the
+/// MCS-4 datasheet. Register operands are 0-15, register pair operands are 0-7.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 enum Instruction {
-    // Data Transfer Instructions (8)
-    Ldm(u8), // Load accumulator immediate (LDM #)
-    Ld(u8),  // Load accumulator from register (LD R)
-    Xch(u8), // Exchange accumulator with register (XCH R)
-    Add(u8), // Add register to accumulator (ADD R)
-    Sub(u8), // Subtract register from accumulator (SUB R)
-    Inc(u8), // Increment register (INC R)
-    Bbl(u8), // Branch back and load (BBL #)
-
-    // Arithmetic Instructions (4)
-    AddC(u8), // Add register with carry (ADC R)
-    SubC(u8), // Subtract register with carry (SBC R)
-    Dad(u8),  // Decimal add register (DAD R)
-    Daa,      // Decimal adjust accumulator (DAA)
-
-    // Logic Instructions (4)
-    Ral, // Rotate left (RAL)
-    Rar, // Rotate right (RAR)
-    Tcc, // Transmit carry clear (TCC)
-    Tcs, // Transmit carry set (TCS)
-
-    // Control Transfer Instructions (8)
-    Jcn(u8, u16),   // Jump conditional (JCN condition, addr)
-    Jms(u16),       // Jump to subroutine (JMS addr)
-    JmsHigh(u8),    // Jump to subroutine high nibble (two-instruction format)
-    JmsLow(u8),     // Jump to subroutine low nibble (two-instruction format)
-    Jun(u16),       // Jump unconditional (JUN addr)
-    JunHigh(u8),    // Jump unconditional high nibble (two-instruction format)
-    JunLow(u8),     // Jump unconditional low nibble (two-instruction format)
-    Jnt(u16),       // Jump on test (JNT addr)
-    JntInvert(u16), // Jump on test inverted (JNT addr) - wait instruction
-
-    // Machine Instruction (1)
-    Src(u8), // Send register control (SRC R)
-
-    // Input/Output and RAM Instructions (8)
-    Wrm, // Write accumulator to RAM (WRM)
-    Wmp, // Write memory pointer (WMP)
-    Wrr, // Write ROM port and register (WRR)
-    Wpm, // Write program memory (WPM)
-    Adm, // Add from memory (ADM)
-    Sbm, // Subtract from memory (SBM)
-    Rdm, // Read memory (RDM)
-    Rdr, // Read ROM port and register (RDR)
-
-    // Accumulator Group Instructions (8)
+    // Machine Instructions (0x00-0xDF)
+    Nop,          // No operation (NOP)
+    Jcn(u8, u16), // Jump conditional (JCN condition, addr)
+    Fim(u8, u8),  // Fetch immediate into register pair (FIM P, data)
+    Src(u8),      // Send register control (SRC P)
+    Fin(u8, u8),  // Fetch indirect from ROM into register pair (FIN P), with fetched data
+    Jin(u8),      // Jump indirect through register pair (JIN P)
+    Jun(u16),     // Jump unconditional (JUN addr)
+    Jms(u16),     // Jump to subroutine (JMS addr)
+    Inc(u8),      // Increment register (INC R)
+    Isz(u8, u16), // Increment register, jump if not zero (ISZ R, addr)
+    Add(u8),      // Add register to accumulator with carry (ADD R)
+    Sub(u8),      // Subtract register from accumulator with borrow (SUB R)
+    Ld(u8),       // Load accumulator from register (LD R)
+    Xch(u8),      // Exchange accumulator with register (XCH R)
+    Bbl(u8),      // Branch back and load (BBL #)
+    Ldm(u8),      // Load accumulator immediate (LDM #)
+
+    // Input/Output and RAM Instructions (0xE0-0xEF)
+    Wrm,    // Write accumulator to RAM main character (WRM)
+    Wmp,    // Write accumulator to RAM output port (WMP)
+    Wrr,    // Write accumulator to ROM output port (WRR)
+    Wpm,    // Write program RAM (WPM)
+    Wr(u8), // Write accumulator to RAM status character 0-3 (WR0-WR3)
+    Sbm,    // Subtract RAM main character with borrow (SBM)
+    Rdm,    // Read RAM main character (RDM)
+    Rdr,    // Read ROM input port (RDR)
+    Adm,    // Add RAM main character with carry (ADM)
+    Rd(u8), // Read RAM status character 0-3 (RD0-RD3)
+
+    // Accumulator Group Instructions (0xF0-0xFD)
     Clb, // Clear both (CLB)
     Clc, // Clear carry (CLC)
+    Iac, // Increment accumulator (IAC)
     Cmc, // Complement carry (CMC)
-    Stc, // Set carry (STC)
     Cma, // Complement accumulator (CMA)
-    Iac, // Increment accumulator (IAC)
-    // Note: CMC and RAL are already defined above
-
-    // Invalid instruction
+    Ral, // Rotate left through carry (RAL)
+    Rar, // Rotate right through carry (RAR)
+    Tcc, // Transmit carry and clear (TCC)
+    Dac, // Decrement accumulator (DAC)
+    Tcs, // Transfer carry subtract (TCS)
+    Stc, // Set carry (STC)
+    Daa, // Decimal adjust accumulator (DAA)
+    Kbp, // Keyboard process (KBP)
+    Dcl, // Designate command line (DCL)
+
+    // Intel 4040 Instructions (0x01-0x0E)
+    Hlt,    // Halt until an interrupt or STP (HLT)
+    Bbs,    // Branch back from interrupt and restore SRC (BBS)
+    Lcr,    // Load command register into accumulator (LCR)
+    Or(u8), // OR register 4 or 5 into accumulator (OR4, OR5)
+    An(u8), // AND register 6 or 7 into accumulator (AN6, AN7)
+    Db(u8), // Designate ROM bank 0 or 1 (DB0, DB1)
+    Sb(u8), // Select index register bank 0 or 1 (SB0, SB1)
+    Ein,    // Enable interrupt (EIN)
+    Din,    // Disable interrupt (DIN)
+    Rpm,    // Read program RAM (RPM)
+
+    // Unassigned opcodes (0x01-0x0F on the 4004, 0x0F on the 4040, 0xFE, 0xFF)
     Invalid,
 }
 
@@ -98,43 +117,93 @@ enum Instruction {
 /// - 16 4-bit index registers
 /// - 4-bit accumulator with carry flag
 /// - Proper timing and state machine behavior
+/// - Functional mode that fetches straight from attached ROMs for fast regression runs
+/// - Optional Intel 4040 extensions, see `CpuVariant` and the `Intel4040` component
 pub struct Intel4004 {
     base: BaseComponent,
-    accumulator: u8,                     // Main accumulator register (4-bit)
-    carry: bool,                         // Carry flag for arithmetic operations
-    index_registers: [u8; 16],           // 16 4-bit index registers (R0-R15)
-    pub(crate) program_counter: U12,     // 12-bit program counter
-    stack: [U12; 3],                     // 3-level 12-bit address stack
-    stack_pointer: u8,                   // Stack pointer (0-2)
-    cycle_count: u64,                    // Total number of clock cycles executed
-    instruction_phase: InstructionPhase, // Current instruction execution phase
-    current_instruction: u8,             // Currently executing instruction
-    address_latch: u8,                   // Latched address for memory operations
-    data_latch: u8,                      // Latched data for memory operations
-    clock_speed: f64,                    // Target clock speed in Hz
-    rom_port: u8,                        // Currently selected ROM port (0-15)
-    ram_bank: u8,                        // Currently selected RAM bank (0-7)
+    accumulator: u8,                 // Main accumulator register (4-bit)
+    carry: bool,                     // Carry flag for arithmetic operations
+    index_registers: [u8; 24],       // R0-R15, then the 4040's second bank of R0-R7
+    pub(crate) program_counter: U12, // 12-bit program counter
+    stack: [U12; 7],                 // 12-bit address stack, 3 levels used on the 4004
+    stack_pointer: u8,               // Stack pointer (0-2, 0-6 on the 4040)
+    cycle_count: u64,                // Total number of clock cycles executed
+    current_instruction: u8,         // Currently executing instruction
+    address_latch: u8,               // Latched address for memory operations
+    clock_speed: f64,                // Target clock speed in Hz
+    rom_port: u8,                    // Currently selected ROM port (0-15)
+    ram_bank: u8,                    // Currently selected RAM bank (0-7)
 
     // Two-phase clock state tracking
     prev_phi1: PinValue, // Previous Φ1 clock state for edge detection
     prev_phi2: PinValue, // Previous Φ2 clock state for edge detection
 
-    // Memory operation state machine
-    memory_state: MemoryState,       // Current state of memory operation
-    address_high_nibble: Option<u8>, // High nibble of 8-bit address
-    address_low_nibble: Option<u8>,  // Low nibble of 8-bit address
-    full_address_ready: bool,        // Whether complete address is assembled
+    // Bus cycle state
+    subcycle: BusSubcycle,  // Current subcycle of the 8-clock instruction cycle
+    opr_latch: u8,          // Opcode high nibble sampled during M1
+    io_data: u8,            // Data read from the bus during X2 of an I/O read instruction
+    instruction_count: u64, // Total number of instructions executed
+
+    // Functional execution
+    execution_mode: ExecutionMode, // Pin-level or functional execution
+    program_memory: Vec<ProgramMemoryChip>, // Program memory chips with their ROM bank
+    data_memory: Vec<DataMemoryChip>, // RAM chips with their CM-RAM line
 
     // Instruction execution state
     current_op: Instruction, // Currently decoded instruction
 
-    // Two-instruction format support
-    pending_operand: Option<u8>, // High nibble of operand for two-instruction format
-    operand_assembled: bool,     // Whether operand has been fully assembled
+    second_cycle: bool, // Whether the next instruction cycle completes a two-cycle instruction
+    instruction_latched: bool, // Whether M1/M2 of this cycle delivered an instruction to execute
+
+    // Intel 4040 extensions
+    variant: CpuVariant,     // Instruction set, register file and pinout
+    register_bank: u8,       // Index register bank selected by SB0/SB1
+    rom_bank: u8,            // ROM bank selected by DB0/DB1 (CM-ROM0 or CM-ROM1)
+    interrupt_enabled: bool, // Set by EIN, cleared by DIN
+    interrupt_active: bool,  // Interrupt acknowledged and not yet ended by BBS
+    saved_src: u8,           // SRC address at the time the interrupt was taken
+    halted: bool,            // HLT executed, waiting for an interrupt or STP
+    stopped: bool,           // STP was high at the last instruction boundary
+    idle_cycle: bool,        // Halted or stopped: this instruction cycle fetches nothing
+
+    // Debugger support
+    breakpoints: BTreeSet<u16>, // Program addresses that stop execution when reached
+    breakpoint_hit: Option<u16>, // Breakpoint reached and not yet taken by the debugger
+}
 
-    // Timing and synchronization
-    address_latch_time: Option<Instant>, // Timestamp when address was latched
-    access_time: Duration,               // Memory access time (typical 500ns)
+/// Snapshot of the CPU state behind the pins
+/// Configuration (variant, clock speed, execution mode, attached program memory) and
+/// debugger breakpoints belong to the system and are not part of a snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Intel4004State {
+    accumulator: u8,
+    carry: bool,
+    index_registers: Vec<u8>, // R0-R15, then the 4040's second bank of R0-R7
+    program_counter: u16,
+    stack: Vec<u16>,
+    stack_pointer: u8,
+    cycle_count: u64,
+    instruction_count: u64,
+    current_instruction: u8,
+    address_latch: u8,
+    rom_port: u8,
+    ram_bank: u8,
+    prev_phi1: PinValue,
+    prev_phi2: PinValue,
+    subcycle: BusSubcycle,
+    opr_latch: u8,
+    io_data: u8,
+    current_op: Instruction,
+    second_cycle: bool,
+    instruction_latched: bool,
+    register_bank: u8,
+    rom_bank: u8,
+    interrupt_enabled: bool,
+    interrupt_active: bool,
+    saved_src: u8,
+    halted: bool,
+    stopped: bool,
+    idle_cycle: bool,
 }
 
 impl Intel4004 {
@@ -142,15 +211,37 @@ impl Intel4004 {
     /// Parameters: name - Component identifier, clock_speed - Target clock frequency in Hz
     /// Returns: New Intel4004 instance with initialized state
     pub fn new(name: String, clock_speed: f64) -> Self {
-        let pin_names = vec![
-            "D0", "D1", "D2", "D3",    // Data bus pins
-            "SYNC",  // Sync signal
-            "CM",    // Chip select for memory
-            "TEST",  // Test pin
-            "RESET", // Reset
-            "PHI1",  // Clock phase 1
-            "PHI2",  // Clock phase 2
+        Self::new_with_variant(name, clock_speed, CpuVariant::Intel4004)
+    }
+
+    /// Create a new MCS-4 CPU of the given variant
+    /// The 4040 adds the CM_ROM1, INT, STP, STPA and INTA pins
+    /// Parameters: name - Component identifier, clock_speed - Target clock frequency in Hz,
+    /// variant - Intel 4004 or Intel 4040
+    /// Returns: New Intel4004 instance with initialized state
+    pub fn new_with_variant(name: String, clock_speed: f64, variant: CpuVariant) -> Self {
+        let mut pin_names = vec![
+            "D0", "D1", "D2", "D3",      // Data bus pins
+            "SYNC",    // Sync signal
+            "CM_ROM",  // Command line for the 4001 ROMs
+            "CM_RAM0", // Command line for RAM bank 0
+            "CM_RAM1", // Command line for RAM bank 1
+            "CM_RAM2", // Command line for RAM bank 2
+            "CM_RAM3", // Command line for RAM bank 3
+            "TEST",    // Test pin
+            "RESET",   // Reset
+            "PHI1",    // Clock phase 1
+            "PHI2",    // Clock phase 2
         ];
+        if variant == CpuVariant::Intel4040 {
+            pin_names.extend([
+                "CM_ROM1", // Command line for ROM bank 1
+                "INT",     // Interrupt request
+                "STP",     // Stop request
+                "STPA",    // Stop acknowledge
+                "INTA",    // Interrupt acknowledge
+            ]);
+        }
 
         let pins = BaseComponent::create_pin_map(&pin_names, &name);
 
@@ -158,15 +249,13 @@ impl Intel4004 {
             base: BaseComponent::new(name, pins),
             accumulator: 0,
             carry: false,
-            index_registers: [0u8; 16],
+            index_registers: [0u8; 24],
             program_counter: U12::new(0),
-            stack: [U12::new(0); 3],
+            stack: [U12::new(0); 7],
             stack_pointer: 0,
             cycle_count: 0,
-            instruction_phase: InstructionPhase::Fetch,
             current_instruction: 0,
             address_latch: 0,
-            data_latch: 0,
             clock_speed,
             rom_port: 0,
             ram_bank: 0,
@@ -175,22 +264,37 @@ impl Intel4004 {
             prev_phi1: PinValue::Low,
             prev_phi2: PinValue::Low,
 
-            // Memory operation state machine
-            memory_state: MemoryState::Idle,
-            address_high_nibble: None,
-            address_low_nibble: None,
-            full_address_ready: false,
+            // Bus cycle state - the first Φ1 edge after reset starts A1
+            subcycle: BusSubcycle::X3,
+            opr_latch: 0,
+            io_data: 0,
+            instruction_count: 0,
+
+            // Functional execution
+            execution_mode: ExecutionMode::PinLevel,
+            program_memory: Vec::new(),
+            data_memory: Vec::new(),
 
             // Instruction execution state
             current_op: Instruction::Invalid,
 
-            // Two-instruction format support
-            pending_operand: None,
-            operand_assembled: false,
-
-            // Timing and synchronization
-            address_latch_time: None,
-            access_time: Duration::from_nanos(500), // 500ns typical access time
+            second_cycle: false,
+            instruction_latched: false,
+
+            // Intel 4040 extensions
+            variant,
+            register_bank: 0,
+            rom_bank: 0,
+            interrupt_enabled: false,
+            interrupt_active: false,
+            saved_src: 0,
+            halted: false,
+            stopped: false,
+            idle_cycle: false,
+
+            // Debugger support
+            breakpoints: BTreeSet::new(),
+            breakpoint_hit: None,
         }
     }
 
@@ -207,23 +311,25 @@ impl Intel4004 {
     pub fn reset(&mut self) {
         self.accumulator = 0;
         self.carry = false;
-        self.index_registers = [0u8; 16];
+        self.index_registers = [0u8; 24];
         self.program_counter = U12::new(0);
-        self.stack = [U12::new(0); 3];
+        self.stack = [U12::new(0); 7];
         self.stack_pointer = 0;
-        self.instruction_phase = InstructionPhase::Fetch;
         self.rom_port = 0;
         self.ram_bank = 0;
+        self.reset_4040_state();
 
-        // Reset memory operation state
-        self.memory_state = MemoryState::Idle;
-        self.address_latch_time = None;
-        self.address_high_nibble = None;
-        self.address_low_nibble = None;
-        self.full_address_ready = false;
+        // Restart the bus cycle and drop any partially fetched instruction
+        self.subcycle = BusSubcycle::X3;
+        self.opr_latch = 0;
+        self.io_data = 0;
+        self.current_op = Instruction::Invalid;
+        self.second_cycle = false;
+        self.instruction_latched = false;
+        self.breakpoint_hit = None;
 
         self.set_sync(false);
-        self.set_cm(false);
+        self.set_cm_lines(false, false);
         self.tri_state_data_bus();
     }
 
@@ -289,17 +395,51 @@ impl Intel4004 {
         }
     }
 
-    /// Set the CM (Chip Select) pin state
-    /// Parameters: high - true for high voltage, false for low voltage
-    fn set_cm(&self, high: bool) {
-        if let Ok(pin) = self.base.get_pin("CM") {
-            if let Ok(mut pin_guard) = pin.lock() {
-                let value = if high { PinValue::High } else { PinValue::Low };
-                pin_guard.set_driver(Some(self.base.get_name().parse().unwrap()), value);
+    /// Set the command lines CM-ROM and CM-RAM0..3
+    /// Hardware: The CM-RAM lines asserted are the ones selected by the last DCL. On the
+    /// 4040 the CM-ROM line is CM_ROM or CM_ROM1, as selected by DB0/DB1.
+    /// Parameters: rom - Assert CM-ROM, ram - Assert the selected CM-RAM lines
+    fn set_cm_lines(&self, rom: bool, ram: bool) {
+        let ram_lines = self.get_cm_ram_lines();
+        let lines = [
+            ("CM_ROM", rom && self.rom_bank == 0),
+            ("CM_ROM1", rom && self.rom_bank == 1),
+            ("CM_RAM0", ram && ram_lines[0]),
+            ("CM_RAM1", ram && ram_lines[1]),
+            ("CM_RAM2", ram && ram_lines[2]),
+            ("CM_RAM3", ram && ram_lines[3]),
+        ];
+
+        for (name, high) in lines {
+            if let Ok(pin) = self.base.get_pin(name) {
+                if let Ok(mut pin_guard) = pin.lock() {
+                    let value = if high { PinValue::High } else { PinValue::Low };
+                    pin_guard.set_driver(Some(self.base.get_name().parse().unwrap()), value);
+                }
             }
         }
     }
 
+    /// Get the CM-RAM lines selected by DCL
+    /// Hardware: Bank 0 uses CM-RAM0 alone; otherwise bits 0-2 of the bank drive
+    /// CM-RAM1-CM-RAM3, so banks 3 and 5-7 assert several lines at once
+    /// Returns: Active state of CM-RAM0..CM-RAM3
+    pub fn get_cm_ram_lines(&self) -> [bool; 4] {
+        let bank = self.ram_bank & 0x07;
+        [
+            bank == 0,
+            bank & 0x01 != 0,
+            bank & 0x02 != 0,
+            bank & 0x04 != 0,
+        ]
+    }
+
+    /// Get the RAM bank selected by DCL
+    /// Returns: Bank number (0-7)
+    pub fn get_ram_bank(&self) -> u8 {
+        self.ram_bank
+    }
+
     /// Read the state of all control pins
     /// Returns: (sync, cm, test) - State of control signals
     fn read_control_pins(&self) -> (bool, bool, bool) {
@@ -313,7 +453,7 @@ impl Intel4004 {
             false
         };
 
-        let cm = if let Ok(pin) = self.base.get_pin("CM") {
+        let cm = if let Ok(pin) = self.base.get_pin("CM_ROM") {
             if let Ok(pin_guard) = pin.lock() {
                 pin_guard.read() == PinValue::High
             } else {
@@ -363,43 +503,116 @@ impl Intel4004 {
         (phi1, phi2)
     }
 
-    /// Handle Φ1 rising edge - Address and control phase
-    /// Hardware: Φ1 high = CPU drives bus with address/control information
-    /// Memory operations start when SYNC goes high during Φ1 rising edge
+    /// Handle Φ1 rising edge - Start of the next bus subcycle
+    /// Hardware: SYNC marks A1, the CPU drives the fetch address during A1-A3 and
+    /// releases the bus to memory during M1-M2. During X2-X3 it sends the SRC address
+    /// or the accumulator of an I/O write instruction.
     fn handle_phi1_rising(&mut self) {
         // Handle system reset first (highest priority)
         self.handle_reset();
 
-        // Check for memory operation start on Φ1 rising edge with SYNC high
-        let (sync, cm, _) = self.read_control_pins();
-        if sync && cm {
-            // Start memory address phase on Φ1 rising edge
-            println!(
-                "DEBUG: [CPU] Starting memory operation - SYNC: {}, CM: {}",
-                sync, cm
-            );
-            self.start_memory_address_phase();
+        self.subcycle = self.subcycle.next();
+        self.set_sync(self.subcycle == BusSubcycle::A1);
+        if self.subcycle == BusSubcycle::A1 && !self.second_cycle {
+            self.idle_cycle = !self.begin_instruction();
         }
 
-        // Handle memory address phase operations during Φ1
-        self.handle_memory_address_operations();
-    }
-
-    /// Handle Φ1 falling edge - End of address phase
-    fn handle_phi1_falling(&mut self) {
-        // Currently no specific operations needed on Φ1 falling
+        let address = self.fetch_address();
+        match self.subcycle {
+            BusSubcycle::A1 => self.write_data_bus((address & 0x0F) as u8),
+            BusSubcycle::A2 => self.write_data_bus(((address >> 4) & 0x0F) as u8),
+            BusSubcycle::A3 => {
+                // CM-ROM selects the ROM chip named by the page nibble
+                self.write_data_bus(((address >> 8) & 0x0F) as u8);
+                self.set_cm_lines(true, false);
+            }
+            BusSubcycle::M2 => {
+                // CM during M2 tells memory that OPA selects an I/O operation
+                let io = !self.second_cycle && !self.idle_cycle && self.opr_latch == 0xE;
+                self.set_cm_lines(io, io);
+                self.tri_state_data_bus();
+            }
+            BusSubcycle::X2 => match self.current_op {
+                _ if self.second_cycle || self.idle_cycle => {
+                    self.set_cm_lines(false, false);
+                    self.tri_state_data_bus();
+                }
+                Instruction::Src(pair) => {
+                    // Chip select and register nibble, with CM so the chips latch it
+                    self.write_data_bus(self.read_register_pair(pair) >> 4);
+                    self.set_cm_lines(true, true);
+                }
+                Instruction::Bbs => {
+                    // BBS resends the SRC address saved when the interrupt was taken
+                    self.write_data_bus(self.saved_src >> 4);
+                    self.set_cm_lines(true, true);
+                }
+                op if Self::is_io_write(op) => {
+                    self.write_data_bus(self.accumulator);
+                    self.set_cm_lines(false, false);
+                }
+                _ => {
+                    self.set_cm_lines(false, false);
+                    self.tri_state_data_bus();
+                }
+            },
+            BusSubcycle::X3 => match self.current_op {
+                Instruction::Src(pair) if !self.second_cycle && !self.idle_cycle => {
+                    // Character address nibble
+                    self.write_data_bus(self.read_register_pair(pair) & 0x0F);
+                    self.set_cm_lines(false, false);
+                }
+                Instruction::Bbs if !self.idle_cycle => {
+                    self.write_data_bus(self.saved_src & 0x0F);
+                    self.set_cm_lines(false, false);
+                }
+                _ => {
+                    self.set_cm_lines(false, false);
+                    self.tri_state_data_bus();
+                }
+            },
+            BusSubcycle::X1 => {
+                // RPM's OPR is 0, so it can only be flagged with CM once OPA is in
+                let rpm =
+                    !self.second_cycle && !self.idle_cycle && self.current_op == Instruction::Rpm;
+                self.set_cm_lines(rpm, false);
+                self.tri_state_data_bus();
+            }
+            BusSubcycle::M1 => {
+                // Release the bus so memory can drive it
+                self.set_cm_lines(false, false);
+                self.tri_state_data_bus();
+            }
+        }
     }
 
-    /// Handle Φ2 rising edge - Data phase
+    /// Handle Φ2 rising edge - Sample the bus and execute
+    /// Hardware: Program memory drives OPR during M1 and OPA during M2, and the selected
+    /// RAM or ROM port drives the operand of an I/O read instruction during X2
     fn handle_phi2_rising(&mut self) {
-        // Handle memory data phase operations during Φ2
-        self.handle_memory_data_operations();
-    }
-
-    /// Handle Φ2 falling edge - End of data phase
-    fn handle_phi2_falling(&mut self) {
-        // Handle memory cleanup operations when Φ2 falls
-        self.handle_memory_cleanup_operations();
+        match self.subcycle {
+            BusSubcycle::M1 => {
+                self.opr_latch = self.read_data_bus();
+            }
+            // A halted or stopped CPU ignores what memory sends
+            BusSubcycle::M2 if !self.idle_cycle => {
+                let byte = (self.opr_latch << 4) | self.read_data_bus();
+                self.accept_instruction_byte(byte);
+                self.instruction_latched = true;
+            }
+            BusSubcycle::X2
+                if !self.second_cycle && !self.idle_cycle && Self::is_io_read(self.current_op) =>
+            {
+                self.io_data = self.read_data_bus();
+            }
+            // Two-word instructions execute at the end of their second cycle. A Φ2 edge
+            // seen right after reset, before any instruction was fetched, executes nothing.
+            BusSubcycle::X3 if !self.second_cycle && self.instruction_latched => {
+                self.instruction_latched = false;
+                self.execute_single_instruction();
+            }
+            _ => {}
+        }
     }
 
     /// Handle system reset signal
@@ -419,674 +632,634 @@ impl Intel4004 {
             // RESET is high - clear all internal state
             self.accumulator = 0;
             self.carry = false;
-            self.index_registers = [0u8; 16];
+            self.index_registers = [0u8; 24];
             self.program_counter = U12::new(0);
-            self.stack = [U12::new(0); 3];
+            self.stack = [U12::new(0); 7];
             self.stack_pointer = 0;
-            self.instruction_phase = InstructionPhase::Fetch;
             self.rom_port = 0;
             self.ram_bank = 0;
+            self.reset_4040_state();
 
-            // Reset memory operation state
-            self.memory_state = MemoryState::Idle;
-            self.address_latch_time = None;
-            self.address_high_nibble = None;
-            self.address_low_nibble = None;
-            self.full_address_ready = false;
+            // Restart the bus cycle
+            self.subcycle = BusSubcycle::X3;
+            self.opr_latch = 0;
+            self.io_data = 0;
+            self.current_op = Instruction::Invalid;
 
-            // Reset two-instruction format state
-            self.pending_operand = None;
-            self.operand_assembled = false;
+            self.second_cycle = false;
 
             // Tri-state data bus
             self.tri_state_data_bus();
         }
     }
 
-    /// Start memory address phase
-    fn start_memory_address_phase(&mut self) {
-        self.memory_state = MemoryState::AddressPhase;
-        self.full_address_ready = false;
+    /// Clear the Intel 4040 bank, interrupt, halt and stop state
+    /// Hardware: RESET selects register bank 0 and ROM bank 0 and disables interrupts
+    fn reset_4040_state(&mut self) {
+        self.register_bank = 0;
+        self.rom_bank = 0;
+        self.interrupt_enabled = false;
+        self.interrupt_active = false;
+        self.saved_src = 0;
+        self.halted = false;
+        self.stopped = false;
+        self.idle_cycle = false;
+        self.set_output_pin("STPA", false);
+        self.set_output_pin("INTA", false);
     }
 
-    /// Handle memory address-related operations during Φ1
-    fn handle_memory_address_operations(&mut self) {
-        match self.memory_state {
-            MemoryState::Idle => {
-                // In idle state, ensure bus is tri-stated
-                self.tri_state_data_bus();
-            }
-
-            MemoryState::AddressPhase => {
-                // Currently latching address nibbles during Φ1
-                self.handle_address_latching();
-            }
-
-            MemoryState::WaitLatency => {
-                // Address latched, waiting for access latency
-                self.handle_latency_wait();
-            }
+    /// Read an input pin of the 4040
+    /// Parameters: name - Pin name
+    /// Returns: true if the pin exists and reads high
+    fn read_input_pin(&self, name: &str) -> bool {
+        self.base
+            .get_pin(name)
+            .ok()
+            .and_then(|pin| pin.lock().ok().map(|guard| guard.read() == PinValue::High))
+            .unwrap_or(false)
+    }
 
-            MemoryState::DriveData => {
-                // Data phase should be handled by Φ2, not Φ1
-                self.tri_state_data_bus();
+    /// Drive an output pin of the 4040; pins the 4004 lacks are skipped
+    /// Parameters: name - Pin name, high - true for high voltage, false for low voltage
+    fn set_output_pin(&self, name: &str, high: bool) {
+        if let Ok(pin) = self.base.get_pin(name) {
+            if let Ok(mut pin_guard) = pin.lock() {
+                let value = if high { PinValue::High } else { PinValue::Low };
+                pin_guard.set_driver(Some(self.base.get_name().parse().unwrap()), value);
             }
         }
     }
 
-    /// Handle memory data-related operations during Φ2
-    fn handle_memory_data_operations(&mut self) {
-        match self.memory_state {
-            MemoryState::Idle => {
-                self.tri_state_data_bus();
-            }
+    /// Run the 4040 stop, halt and interrupt logic at an instruction boundary
+    /// A CPU waiting at a breakpoint idles like a stopped 4040 until the debugger takes the hit.
+    /// Hardware: STP high stops the CPU and raises STPA until STP is released; it also
+    /// ends a halt. An enabled INT request ends a halt, raises INTA, saves the SRC address
+    /// for BBS and calls location 0x003 on the current ROM bank. The 4004 always fetches.
+    /// Returns: true if this instruction cycle fetches an instruction
+    fn begin_instruction(&mut self) -> bool {
+        if self.breakpoint_hit.is_some() {
+            return false;
+        }
+        if self.variant != CpuVariant::Intel4040 {
+            return true;
+        }
 
-            MemoryState::AddressPhase => {
-                self.tri_state_data_bus();
-            }
+        self.stopped = self.read_input_pin("STP");
+        self.set_output_pin("STPA", self.stopped);
+        if self.stopped {
+            self.halted = false;
+            return false;
+        }
 
-            MemoryState::WaitLatency => {
-                self.handle_latency_wait();
-                if self.memory_state == MemoryState::DriveData {
-                    self.handle_data_driving();
-                }
-            }
+        if self.interrupt_enabled && !self.interrupt_active && self.read_input_pin("INT") {
+            self.halted = false;
+            self.interrupt_active = true;
+            self.saved_src = self.address_latch;
+            self.push_stack(self.program_counter);
+            self.program_counter.set(0x003);
+            self.set_output_pin("INTA", true);
+        }
+
+        !self.halted
+    }
 
-            MemoryState::DriveData => {
-                self.handle_data_driving();
+    /// Get the address the next instruction cycle fetches from
+    /// FIN's second cycle reads the byte addressed by register pair 0 on the current page;
+    /// every other cycle fetches at the program counter
+    /// Returns: 12-bit fetch address
+    fn fetch_address(&self) -> u16 {
+        match self.current_op {
+            Instruction::Fin(..) if self.second_cycle => {
+                (self.program_counter.value() & 0xF00) | self.read_register_pair(0) as u16
             }
+            _ => self.program_counter.value(),
         }
     }
 
-    /// Handle memory cleanup operations on Φ2 falling edge
-    fn handle_memory_cleanup_operations(&mut self) {
-        match self.memory_state {
-            MemoryState::DriveData => {
-                // End of data phase - tri-state bus and return to idle
-                self.tri_state_data_bus();
-                self.return_to_idle();
-            }
+    /// Take one byte fetched from program memory
+    /// Shared by the pin-level and functional fetch paths. The first cycle decodes an opcode;
+    /// the second cycle of a two-cycle instruction supplies its address or data byte.
+    /// Parameters: byte - Fetched byte
+    fn accept_instruction_byte(&mut self, byte: u8) {
+        if !self.second_cycle {
+            self.program_counter.inc();
+            self.current_instruction = byte;
+            self.current_op = self.decode_instruction(byte);
+            self.second_cycle = Self::is_two_cycle(self.current_op);
+            return;
+        }
 
-            MemoryState::Idle | MemoryState::AddressPhase | MemoryState::WaitLatency => {
-                self.tri_state_data_bus();
-            }
+        self.second_cycle = false;
+        if let Instruction::Fin(pair, _) = self.current_op {
+            // FIN's data fetch does not advance the program counter
+            self.current_op = Instruction::Fin(pair, byte);
+            return;
         }
+
+        self.program_counter.inc();
+
+        // 8-bit targets refer to the page holding the second word, so an instruction
+        // occupying the last word of a page jumps into the next page
+        let target = (self.program_counter.value() & 0xF00) | byte as u16;
+        self.current_op = match self.current_op {
+            Instruction::Jcn(condition, _) => Instruction::Jcn(condition, target),
+            Instruction::Isz(reg, _) => Instruction::Isz(reg, target),
+            Instruction::Fim(pair, _) => Instruction::Fim(pair, byte),
+            Instruction::Jun(addr_high) => Instruction::Jun(addr_high | byte as u16),
+            Instruction::Jms(addr_high) => Instruction::Jms(addr_high | byte as u16),
+            other => other,
+        };
     }
 
-    /// Handle address nibble latching during address phase
-    fn handle_address_latching(&mut self) {
-        let nibble = self.read_data_bus();
+    /// Check whether an instruction sends the accumulator to a RAM or ROM chip during X2
+    /// Parameters: instruction - Decoded instruction
+    /// Returns: true for WRM, WMP, WRR, WPM and WR0-WR3
+    fn is_io_write(instruction: Instruction) -> bool {
+        matches!(
+            instruction,
+            Instruction::Wrm
+                | Instruction::Wmp
+                | Instruction::Wrr
+                | Instruction::Wpm
+                | Instruction::Wr(_)
+        )
+    }
 
-        if self.address_high_nibble.is_none() {
-            // First cycle: latch high nibble (bits 7-4)
-            self.address_high_nibble = Some(nibble);
-        } else if self.address_low_nibble.is_none() {
-            // Second cycle: latch low nibble (bits 3-0) and transition to latency wait
-            self.address_low_nibble = Some(nibble);
-            self.assemble_full_address();
-            self.start_latency_wait();
-        }
+    /// Check whether an instruction reads its operand from a RAM or ROM chip during X2
+    /// Parameters: instruction - Decoded instruction
+    /// Returns: true for SBM, RDM, RDR, ADM, RD0-RD3 and the 4040's RPM
+    fn is_io_read(instruction: Instruction) -> bool {
+        matches!(
+            instruction,
+            Instruction::Sbm
+                | Instruction::Rdm
+                | Instruction::Rdr
+                | Instruction::Adm
+                | Instruction::Rd(_)
+                | Instruction::Rpm
+        )
     }
 
-    /// Assemble complete 8-bit address from high and low nibbles
-    fn assemble_full_address(&mut self) {
-        if let (Some(high), Some(low)) = (self.address_high_nibble, self.address_low_nibble) {
-            // Assemble 8-bit address: (high << 4) | low
-            self.address_latch = (high << 4) | low;
-            self.full_address_ready = true;
-            self.address_latch_time = Some(Instant::now());
+    /// Check whether an instruction occupies two instruction cycles
+    /// Parameters: instruction - Instruction decoded from the first word
+    /// Returns: true for JCN, FIM, FIN, JUN, JMS and ISZ
+    fn is_two_cycle(instruction: Instruction) -> bool {
+        matches!(
+            instruction,
+            Instruction::Jcn(..)
+                | Instruction::Fim(..)
+                | Instruction::Fin(..)
+                | Instruction::Jun(_)
+                | Instruction::Jms(_)
+                | Instruction::Isz(..)
+        )
+    }
 
-            // Clear nibble storage for next address
-            self.address_high_nibble = None;
-            self.address_low_nibble = None;
-        }
+    /// Get the attached program memory answering the ROM bank selected by DB0/DB1
+    /// Returns: Iterator over the chips on the current CM-ROM line
+    fn program_memory_bank(&self) -> impl Iterator<Item = &Arc<Mutex<Box<dyn Component>>>> {
+        self.program_memory
+            .iter()
+            .filter(|(bank, _)| *bank == self.rom_bank)
+            .map(|(_, chip)| chip)
     }
 
-    /// Transition to latency wait state
-    fn start_latency_wait(&mut self) {
-        self.memory_state = MemoryState::WaitLatency;
-        self.address_latch_time = Some(Instant::now());
+    /// Run a closure on the first chip of a given type in the selected ROM bank
+    /// Parameters: f - Closure given mutable access to the chip
+    /// Returns: Some(result of f), or None if the bank has no such chip
+    fn with_program_memory<T: 'static, R>(&self, mut f: impl FnMut(&mut T) -> R) -> Option<R> {
+        self.program_memory_bank().find_map(|chip| {
+            let mut chip = chip.lock().ok()?;
+            chip.as_any_mut().downcast_mut::<T>().map(&mut f)
+        })
     }
 
-    /// Handle latency timing during wait state
-    fn handle_latency_wait(&mut self) {
-        if let Some(latch_time) = self.address_latch_time {
-            if latch_time.elapsed() >= self.access_time {
-                // Latency elapsed, transition to data driving
-                self.start_data_driving();
-            }
+    /// Read a program byte directly from the attached program memory
+    /// Within the ROM bank selected by DB0/DB1, the ROM whose chip number matches the page
+    /// answers, as it would on the bus; a standard memory system reads its RAM.
+    /// Unpopulated pages read as 0, like a floating bus
+    /// Parameters: address - 12-bit program address
+    /// Returns: Instruction byte at the address
+    fn fetch_program_byte(&self, address: u16) -> u8 {
+        let page = (address >> 8) as u8;
+        self.program_memory_bank()
+            .find_map(|chip| {
+                let chip = chip.lock().ok()?;
+                if let Some(ram) = chip.as_any().downcast_ref::<GenericRam>() {
+                    return ram.read_byte(address as usize);
+                }
+                let rom = chip.as_any().downcast_ref::<Intel4001>()?;
+                if rom.get_chip_id() == page {
+                    rom.read_rom((address & 0xFF) as u8)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(0)
+    }
+
+    /// Decode an instruction byte into an Instruction enum
+    /// Follows the MCS-4 opcode map: OPR (high nibble) selects the group, OPA the operand
+    /// Parameters: opcode - 8-bit instruction opcode
+    /// Returns: Decoded instruction
+    fn decode_instruction(&self, opcode: u8) -> Instruction {
+        let opa = opcode & 0x0F;
+        let pair = opa >> 1;
+
+        match opcode >> 4 {
+            0x0 if opa == 0 => Instruction::Nop,
+            0x0 if self.variant == CpuVariant::Intel4040 => match opa {
+                0x1 => Instruction::Hlt,
+                0x2 => Instruction::Bbs,
+                0x3 => Instruction::Lcr,
+                0x4 | 0x5 => Instruction::Or(opa),
+                0x6 | 0x7 => Instruction::An(opa),
+                0x8 | 0x9 => Instruction::Db(opa - 0x8),
+                0xA | 0xB => Instruction::Sb(opa - 0xA),
+                0xC => Instruction::Ein,
+                0xD => Instruction::Din,
+                0xE => Instruction::Rpm,
+                _ => Instruction::Invalid,
+            },
+            0x0 => Instruction::Invalid,
+            // Two-cycle instructions: the second cycle fills in the address or data byte
+            0x1 => Instruction::Jcn(opa, 0),
+            0x2 if opa & 1 == 0 => Instruction::Fim(pair, 0),
+            0x2 => Instruction::Src(pair),
+            0x3 if opa & 1 == 0 => Instruction::Fin(pair, 0),
+            0x3 => Instruction::Jin(pair),
+            0x4 => Instruction::Jun((opa as u16) << 8),
+            0x5 => Instruction::Jms((opa as u16) << 8),
+            0x6 => Instruction::Inc(opa),
+            0x7 => Instruction::Isz(opa, 0),
+            0x8 => Instruction::Add(opa),
+            0x9 => Instruction::Sub(opa),
+            0xA => Instruction::Ld(opa),
+            0xB => Instruction::Xch(opa),
+            0xC => Instruction::Bbl(opa),
+            0xD => Instruction::Ldm(opa),
+            0xE => match opa {
+                0x0 => Instruction::Wrm,
+                0x1 => Instruction::Wmp,
+                0x2 => Instruction::Wrr,
+                0x3 => Instruction::Wpm,
+                0x4..=0x7 => Instruction::Wr(opa - 0x4),
+                0x8 => Instruction::Sbm,
+                0x9 => Instruction::Rdm,
+                0xA => Instruction::Rdr,
+                0xB => Instruction::Adm,
+                _ => Instruction::Rd(opa - 0xC),
+            },
+            _ => match opa {
+                0x0 => Instruction::Clb,
+                0x1 => Instruction::Clc,
+                0x2 => Instruction::Iac,
+                0x3 => Instruction::Cmc,
+                0x4 => Instruction::Cma,
+                0x5 => Instruction::Ral,
+                0x6 => Instruction::Rar,
+                0x7 => Instruction::Tcc,
+                0x8 => Instruction::Dac,
+                0x9 => Instruction::Tcs,
+                0xA => Instruction::Stc,
+                0xB => Instruction::Daa,
+                0xC => Instruction::Kbp,
+                0xD => Instruction::Dcl,
+                _ => Instruction::Invalid,
+            },
         }
     }
 
-    /// Transition to data driving state
-    fn start_data_driving(&mut self) {
-        self.memory_state = MemoryState::DriveData;
+    /// Map an index register number to its storage slot
+    /// On the 4040, bank 1 replaces R0-R7 with eight more registers; R8-R15 are shared
+    /// Parameters: index - Register number (0-15)
+    /// Returns: Index into the register file
+    fn register_slot(&self, index: u8) -> usize {
+        let index = (index & 0x0F) as usize;
+        if self.register_bank == 1 && index < 8 {
+            16 + index
+        } else {
+            index
+        }
     }
 
-    /// Handle data driving during DriveData state
-    fn handle_data_driving(&mut self) {
-        let (sync, cm, _) = self.read_control_pins();
+    /// Read an index register of the selected bank
+    /// Parameters: index - Register number (0-15)
+    /// Returns: 4-bit register value
+    fn read_register(&self, index: u8) -> u8 {
+        self.index_registers[self.register_slot(index)]
+    }
 
-        // Memory read: SYNC=1, CM=1, valid address
-        if sync && cm && self.full_address_ready {
-            // All conditions met: drive data on bus
-            let data = self.data_latch;
-            self.write_data_bus(data);
+    /// Write an index register of the selected bank
+    /// Parameters: index - Register number (0-15), value - New 4-bit value
+    fn write_register(&mut self, index: u8, value: u8) {
+        let slot = self.register_slot(index);
+        self.index_registers[slot] = value & 0x0F;
+    }
 
-            if self.cycle_count % 1000 == 0 {
-                // Log every 1000 cycles
-                println!("DEBUG: [{}] CPU State | PC: 0x{:03X} | Cycles: {} | ACC: 0x{:X} | SYNC: {} | CM: {} | Ready: {}",
-                         self.base.name(), self.program_counter.value(), self.cycle_count, self.accumulator, sync, cm, self.full_address_ready);
-            }
-        } else {
-            // Conditions not met, tri-state
-            self.tri_state_data_bus();
+    /// Read a register pair as an 8-bit value
+    /// Parameters: pair - Register pair index (0-7), even register holds the high nibble
+    /// Returns: (R2P << 4) | R2P+1
+    fn read_register_pair(&self, pair: u8) -> u8 {
+        let even = (pair & 0x07) * 2;
+        (self.read_register(even) << 4) | self.read_register(even + 1)
+    }
+
+    /// Write an 8-bit value into a register pair
+    /// Parameters: pair - Register pair index (0-7), value - High nibble goes to the even register
+    fn write_register_pair(&mut self, pair: u8, value: u8) {
+        let even = (pair & 0x07) * 2;
+        self.write_register(even, value >> 4);
+        self.write_register(even + 1, value & 0x0F);
+    }
+
+    /// Get the number of levels of the address stack
+    /// Returns: 3 on the 4004, 7 on the 4040
+    fn stack_depth(&self) -> u8 {
+        match self.variant {
+            CpuVariant::Intel4004 => 3,
+            CpuVariant::Intel4040 => 7,
         }
     }
 
-    /// Reset memory state machine to idle
-    fn return_to_idle(&mut self) {
-        self.memory_state = MemoryState::Idle;
-        self.address_latch_time = None;
-        self.address_high_nibble = None;
-        self.address_low_nibble = None;
-        self.full_address_ready = false;
+    /// Add a nibble and the carry to the accumulator
+    /// Parameters: value - 4-bit operand
+    fn add_with_carry(&mut self, value: u8) {
+        let result = self.accumulator + (value & 0x0F) + self.carry as u8;
+        self.carry = result > 0x0F;
+        self.accumulator = result & 0x0F;
     }
 
-    /// Decode an instruction byte into an Instruction enum
-    /// Parameters: opcode - 8-bit instruction opcode
-    /// Returns: Decoded instruction
-    fn decode_instruction(&self, opcode: u8) -> Instruction {
-        match opcode {
-            // Data Transfer Instructions (0x00-0x0F)
-            0x00..=0x0F => {
-                let reg = opcode & 0x0F;
-                if opcode < 0x08 {
-                    Instruction::Ld(reg) // LD R
-                } else {
-                    Instruction::Xch(reg) // XCH R
-                }
+    /// Subtract a nibble from the accumulator
+    /// Carry acts as an inverted borrow: set means no borrow occurred
+    /// Parameters: value - 4-bit operand
+    fn subtract_with_borrow(&mut self, value: u8) {
+        let result = self.accumulator + (!value & 0x0F) + (!self.carry) as u8;
+        self.carry = result > 0x0F;
+        self.accumulator = result & 0x0F;
+    }
+
+    /// Push a return address onto the 3-level (4040: 7-level) stack
+    /// The pointer wraps like the real chip, so a 4th nested call overwrites the oldest entry
+    /// Parameters: address - 12-bit return address
+    fn push_stack(&mut self, address: U12) {
+        self.stack[self.stack_pointer as usize] = address;
+        self.stack_pointer = (self.stack_pointer + 1) % self.stack_depth();
+    }
+
+    /// Pop a return address from the 3-level (4040: 7-level) stack
+    /// There is no underflow detection on the real chip: popping an empty stack
+    /// wraps the pointer and returns whatever stale address is in that level
+    /// Returns: 12-bit return address
+    fn pop_stack(&mut self) -> U12 {
+        let depth = self.stack_depth();
+        self.stack_pointer = (self.stack_pointer + depth - 1) % depth;
+        self.stack[self.stack_pointer as usize]
+    }
+
+    /// Replace the low 8 bits of the program counter, staying on the current page
+    /// Parameters: address - 8-bit address within the page
+    fn jump_within_page(&mut self, address: u8) {
+        let page = self.program_counter.value() & 0xF00;
+        self.program_counter.set(page | address as u16);
+    }
+
+    /// Execute the current instruction
+    /// The program counter already points past the instruction, since it advances on fetch
+    fn execute_instruction(&mut self) {
+        match self.current_op {
+            Instruction::Nop | Instruction::Invalid => {
+                // No operation - unassigned opcodes behave as NOP
             }
 
-            // Arithmetic Instructions (0x10-0x1F)
-            0x10..=0x1F => {
-                let reg = opcode & 0x0F;
-                if opcode < 0x18 {
-                    Instruction::Add(reg) // ADD R
-                } else {
-                    Instruction::Sub(reg) // SUB R
+            // Machine Instructions
+            Instruction::Jcn(condition, addr) => {
+                // C1 inverts, C2 tests ACC == 0, C3 tests carry, C4 tests TEST pin low
+                let (_, _, test) = self.read_control_pins();
+                let selected = (condition & 0x4 != 0 && self.accumulator == 0)
+                    || (condition & 0x2 != 0 && self.carry)
+                    || (condition & 0x1 != 0 && !test);
+                if selected != (condition & 0x8 != 0) {
+                    self.program_counter.set(addr);
                 }
             }
 
-            // Arithmetic with Carry Instructions (0x20-0x2F)
-            0x20..=0x2F => {
-                let reg = opcode & 0x0F;
-                if opcode < 0x28 {
-                    Instruction::AddC(reg) // ADC R
-                } else {
-                    Instruction::SubC(reg) // SBC R
-                }
+            Instruction::Fim(pair, data) => {
+                self.write_register_pair(pair, data);
             }
 
-            // Jump Conditional Instructions (0x30-0x3F)
-            0x30..=0x3F => {
-                let condition = opcode & 0x0F;
-                Instruction::Jcn(condition, 0) // JCN condition (operand follows)
+            Instruction::Src(pair) => {
+                // Send register control - address the ROM/RAM chip and character for I/O
+                self.address_latch = self.read_register_pair(pair);
+                self.rom_port = self.address_latch >> 4;
             }
 
-            // Load Data to Accumulator (0x40-0x4F)
-            0x40..=0x4F => {
-                let imm = opcode & 0x0F;
-                Instruction::Ldm(imm) // LDM #
+            Instruction::Fin(pair, data) => {
+                // Fetch indirect - data was read during the second cycle
+                self.write_register_pair(pair, data);
             }
 
-            // I/O and RAM Instructions (0x50-0x5F)
-            0x50..=0x5F => {
-                match opcode {
-                    0x50..=0x57 => Instruction::Wrm, // WRM
-                    0x58..=0x5F => Instruction::Wmp, // WMP
-                    _ => Instruction::Invalid,
-                }
+            Instruction::Jin(pair) => {
+                let address = self.read_register_pair(pair);
+                self.jump_within_page(address);
             }
 
-            // Register I/O Instructions (0x60-0x6F)
-            0x60..=0x6F => {
-                match opcode {
-                    0x60..=0x67 => Instruction::Wrr, // WRR
-                    0x68..=0x6F => Instruction::Wpm, // WPM
-                    _ => Instruction::Invalid,
-                }
+            Instruction::Jun(addr) => {
+                self.program_counter.set(addr);
             }
 
-            // Accumulator Group Instructions (0x70-0x7F)
-            0x70..=0x7F => {
-                match opcode {
-                    0x70 => Instruction::Adm, // ADM
-                    0x71 => Instruction::Sbm, // SBM
-                    0x72 => Instruction::Clb, // CLB
-                    0x73 => Instruction::Clc, // CLC
-                    0x74 => Instruction::Cmc, // CMC
-                    0x75 => Instruction::Stc, // STC
-                    0x76 => Instruction::Cma, // CMA
-                    0x77 => Instruction::Iac, // IAC
-                    0x78 => Instruction::Rdm, // RDM
-                    0x79 => Instruction::Rdr, // RDR
-                    0x7A => Instruction::Ral, // RAL
-                    0x7B => Instruction::Rar, // RAR
-                    0x7C => Instruction::Tcc, // TCC
-                    0x7D => Instruction::Tcs, // TCS
-                    0x7E => Instruction::Daa, // DAA
-                    0x7F => Instruction::Tcs, // TCS (duplicate in some docs)
-                    _ => Instruction::Invalid,
-                }
+            Instruction::Jms(addr) => {
+                // Jump to subroutine - push the return address to the stack
+                self.push_stack(self.program_counter);
+                self.program_counter.set(addr);
+            }
+
+            Instruction::Inc(reg) => {
+                self.write_register(reg, self.read_register(reg) + 1);
             }
 
-            // Jump Unconditional High Nibble (0x80-0x8F)
-            0x80..=0x8F => {
-                let addr_high = opcode & 0x0F;
-                Instruction::JunHigh(addr_high) // JUN high nibble
-            }
-
-            // Jump Unconditional Low Nibble (0x90-0x9F)
-            0x90..=0x9F => {
-                let addr_low = opcode & 0x0F;
-                Instruction::JunLow(addr_low) // JUN low nibble
-            }
-
-            // Jump to Subroutine High Nibble (0xA0-0xAF)
-            0xA0..=0xAF => {
-                let addr_high = opcode & 0x0F;
-                Instruction::JmsHigh(addr_high) // JMS high nibble
-            }
-
-            // Jump to Subroutine Low Nibble (0xB0-0xBF)
-            0xB0..=0xBF => {
-                let addr_low = opcode & 0x0F;
-                Instruction::JmsLow(addr_low) // JMS low nibble
-            }
-
-            // Increment Register Instructions (0xC0-0xEF)
-            0xC0..=0xEF => {
-                let reg = opcode & 0x0F;
-                Instruction::Inc(reg) // INC R
-            }
-
-            // Accumulator Group Instructions (0xF0-0xFF)
-            0xF0..=0xFF => {
-                match opcode {
-                    0xF0 => Instruction::Clb, // CLB
-                    0xF1 => Instruction::Clc, // CLC
-                    0xF2 => Instruction::Iac, // IAC
-                    0xF3 => Instruction::Cmc, // CMC
-                    0xF4 => Instruction::Cma, // CMA
-                    0xF5 => Instruction::Ral, // RAL
-                    0xF6 => Instruction::Rar, // RAR
-                    0xF7 => Instruction::Rar, // RAR (duplicate)
-                    0xF8 => Instruction::Daa, // DAA
-                    0xF9 => Instruction::Daa, // DAA (duplicate)
-                    0xFA => Instruction::Stc, // STC
-                    0xFB => Instruction::Stc, // STC (duplicate)
-                    0xFC => Instruction::Tcc, // TCC
-                    0xFD => Instruction::Tcs, // TCS
-                    0xFE => Instruction::Invalid,
-                    0xFF => Instruction::Invalid,
-                    _ => Instruction::Invalid,
+            Instruction::Isz(reg, addr) => {
+                let value = (self.read_register(reg) + 1) & 0x0F;
+                self.write_register(reg, value);
+                if value != 0 {
+                    self.program_counter.set(addr);
                 }
             }
-        }
-    }
 
-    /// Execute the current instruction
-    /// Hardware-accurate instruction execution with proper timing
-    fn execute_instruction(&mut self) {
-        match self.current_op {
-            Instruction::Invalid => {
-                // Invalid instruction - do nothing
-                self.program_counter.inc();
+            Instruction::Add(reg) => {
+                self.add_with_carry(self.read_register(reg));
             }
 
-            // Data Transfer Instructions
-            Instruction::Ldm(imm) => {
-                self.accumulator = imm & 0x0F;
-                self.program_counter.inc();
+            Instruction::Sub(reg) => {
+                self.subtract_with_borrow(self.read_register(reg));
             }
 
             Instruction::Ld(reg) => {
-                if reg < 16 {
-                    self.accumulator = self.index_registers[reg as usize];
-                }
-                self.program_counter.inc();
+                self.accumulator = self.read_register(reg);
             }
 
             Instruction::Xch(reg) => {
-                if reg < 16 {
-                    let temp = self.accumulator;
-                    self.accumulator = self.index_registers[reg as usize];
-                    self.index_registers[reg as usize] = temp;
-                }
-                self.program_counter.inc();
+                let slot = self.register_slot(reg);
+                std::mem::swap(&mut self.accumulator, &mut self.index_registers[slot]);
             }
 
-            Instruction::Add(reg) => {
-                if reg < 16 {
-                    let result = self.accumulator + self.index_registers[reg as usize];
-                    self.carry = result > 0x0F;
-                    self.accumulator = result & 0x0F;
-                }
-                self.program_counter.inc();
-            }
-
-            Instruction::Sub(reg) => {
-                if reg < 16 {
-                    let result = self
-                        .accumulator
-                        .wrapping_sub(self.index_registers[reg as usize]);
-                    self.carry = self.accumulator < self.index_registers[reg as usize];
-                    self.accumulator = result & 0x0F;
-                }
-                self.program_counter.inc();
+            Instruction::Bbl(imm) => {
+                // Branch back and load - pop from stack and load accumulator
+                self.program_counter = self.pop_stack();
+                self.accumulator = imm & 0x0F;
             }
 
-            // Arithmetic with Carry Instructions
-            Instruction::AddC(reg) => {
-                if reg < 16 {
-                    let carry_val = if self.carry { 1 } else { 0 };
-                    let result = self.accumulator + self.index_registers[reg as usize] + carry_val;
-                    self.carry = result > 0x0F;
-                    self.accumulator = result & 0x0F;
-                }
-                self.program_counter.inc();
-            }
-
-            Instruction::SubC(reg) => {
-                if reg < 16 {
-                    let carry_val = if self.carry { 1 } else { 0 };
-                    let result = self
-                        .accumulator
-                        .wrapping_sub(self.index_registers[reg as usize])
-                        .wrapping_sub(carry_val);
-                    self.carry =
-                        self.accumulator < (self.index_registers[reg as usize] + carry_val);
-                    self.accumulator = result & 0x0F;
-                }
-                self.program_counter.inc();
+            Instruction::Ldm(imm) => {
+                self.accumulator = imm & 0x0F;
             }
 
-            // Logic Instructions
-            Instruction::Ral => {
-                let new_carry = (self.accumulator & 0x08) != 0;
-                self.accumulator =
-                    ((self.accumulator << 1) | (if self.carry { 1 } else { 0 })) & 0x0F;
-                self.carry = new_carry;
-                self.program_counter.inc();
-            }
+            // I/O and RAM Instructions
+            // Write instructions send the accumulator to the selected chip during X2,
+            // so there is nothing left to do once the cycle reaches execution
+            Instruction::Wrm
+            | Instruction::Wmp
+            | Instruction::Wrr
+            | Instruction::Wpm
+            | Instruction::Wr(_) => {}
 
-            Instruction::Rar => {
-                let new_carry = (self.accumulator & 0x01) != 0;
-                self.accumulator =
-                    ((self.accumulator >> 1) | (if self.carry { 0x08 } else { 0 })) & 0x0F;
-                self.carry = new_carry;
-                self.program_counter.inc();
+            Instruction::Sbm => {
+                self.subtract_with_borrow(self.io_data);
             }
 
-            Instruction::Tcc => {
-                self.accumulator = 0;
-                self.carry = false;
-                self.program_counter.inc();
+            Instruction::Rdm | Instruction::Rdr | Instruction::Rd(_) | Instruction::Rpm => {
+                self.accumulator = self.io_data & 0x0F;
             }
 
-            Instruction::Tcs => {
-                self.accumulator = 0x0F;
-                self.carry = true;
-                self.program_counter.inc();
+            Instruction::Adm => {
+                self.add_with_carry(self.io_data);
             }
 
             // Accumulator Group Instructions
             Instruction::Clb => {
                 self.accumulator = 0;
                 self.carry = false;
-                self.program_counter.inc();
             }
 
             Instruction::Clc => {
                 self.carry = false;
-                self.program_counter.inc();
             }
 
-            Instruction::Cmc => {
-                self.carry = !self.carry;
-                self.program_counter.inc();
+            Instruction::Iac => {
+                let result = self.accumulator + 1;
+                self.carry = result > 0x0F;
+                self.accumulator = result & 0x0F;
             }
 
-            Instruction::Stc => {
-                self.carry = true;
-                self.program_counter.inc();
+            Instruction::Cmc => {
+                self.carry = !self.carry;
             }
 
             Instruction::Cma => {
                 self.accumulator = (!self.accumulator) & 0x0F;
-                self.program_counter.inc();
             }
 
-            Instruction::Iac => {
-                let result = self.accumulator + 1;
-                self.carry = result > 0x0F;
-                self.accumulator = result & 0x0F;
-                self.program_counter.inc();
+            Instruction::Ral => {
+                let new_carry = (self.accumulator & 0x08) != 0;
+                self.accumulator = ((self.accumulator << 1) | self.carry as u8) & 0x0F;
+                self.carry = new_carry;
             }
 
-            Instruction::Daa => {
-                // Decimal adjust accumulator
-                if self.accumulator > 9 || self.carry {
-                    self.accumulator += 6;
-                    if self.accumulator > 0x0F {
-                        self.carry = true;
-                        self.accumulator &= 0x0F;
-                    }
-                }
-                self.program_counter.inc();
+            Instruction::Rar => {
+                let new_carry = (self.accumulator & 0x01) != 0;
+                self.accumulator =
+                    ((self.accumulator >> 1) | (if self.carry { 0x08 } else { 0 })) & 0x0F;
+                self.carry = new_carry;
             }
 
-            // Jump Instructions - Two-instruction format
-            Instruction::JunHigh(addr_high) => {
-                self.pending_operand = Some(addr_high);
-                // Don't increment PC - wait for low nibble
+            Instruction::Tcc => {
+                self.accumulator = self.carry as u8;
+                self.carry = false;
             }
 
-            Instruction::JunLow(addr_low) => {
-                if let Some(addr_high) = self.pending_operand {
-                    let addr = ((addr_high as u16) << 4) | (addr_low as u16);
-                    self.program_counter.set(addr);
-                    self.pending_operand = None;
-                }
+            Instruction::Dac => {
+                // Carry is set when no borrow occurs
+                self.carry = self.accumulator != 0;
+                self.accumulator = self.accumulator.wrapping_sub(1) & 0x0F;
             }
 
-            Instruction::Jun(addr) => {
-                self.program_counter.set(addr);
+            Instruction::Tcs => {
+                self.accumulator = if self.carry { 10 } else { 9 };
+                self.carry = false;
             }
 
-            Instruction::Jcn(condition, addr) => {
-                // Decode condition bits properly
-                let should_jump = match condition & 0x0F {
-                    0x0 => !self.carry && self.accumulator != 0, // JNT (Jump if no carry and ACC != 0)
-                    0x1 => self.carry,                           // JC (Jump if carry)
-                    0x2 => self.accumulator == 0,                // JZ (Jump if zero)
-                    0x3 => self.accumulator != 0,                // JNZ (Jump if not zero)
-                    0x4 => true,                                 // JUN (Jump unconditional)
-                    0x5 => false,                                // Always false
-                    0x6 => true,                                 // Always true
-                    0x7 => false,                                // Always false
-                    0x8 => true,                                 // Always true
-                    0x9 => false,                                // Always false
-                    0xA => true,                                 // Always true
-                    0xB => false,                                // Always false
-                    0xC => true,                                 // Always true
-                    0xD => false,                                // Always false
-                    0xE => true,                                 // Always true
-                    0xF => false,                                // Always false
-                    _ => false,
-                };
+            Instruction::Stc => {
+                self.carry = true;
+            }
 
-                if should_jump {
-                    self.program_counter.set(addr);
-                } else {
-                    self.program_counter.inc();
+            Instruction::Daa => {
+                // Decimal adjust accumulator - carry is only ever set, never cleared
+                if self.accumulator > 9 || self.carry {
+                    let result = self.accumulator + 6;
+                    if result > 0x0F {
+                        self.carry = true;
+                    }
+                    self.accumulator = result & 0x0F;
                 }
             }
 
-            Instruction::JmsHigh(addr_high) => {
-                self.pending_operand = Some(addr_high);
-                // Don't increment PC - wait for low nibble
+            Instruction::Kbp => {
+                // Keyboard process - convert a one-hot key line to its position
+                self.accumulator = match self.accumulator {
+                    0x0 => 0x0,
+                    0x1 => 0x1,
+                    0x2 => 0x2,
+                    0x4 => 0x3,
+                    0x8 => 0x4,
+                    _ => 0xF,
+                };
             }
 
-            Instruction::JmsLow(addr_low) => {
-                if let Some(addr_high) = self.pending_operand {
-                    let addr = ((addr_high as u16) << 4) | (addr_low as u16);
-                    // Jump to subroutine - push current PC to stack
-                    if self.stack_pointer < 3 {
-                        self.stack[self.stack_pointer as usize] = self.program_counter;
-                        self.stack_pointer += 1;
-                        self.program_counter.set(addr);
-                    }
-                    self.pending_operand = None;
-                }
+            Instruction::Dcl => {
+                // Designate command line - select the CM-RAM lines from the low 3 bits
+                self.ram_bank = self.accumulator & 0x07;
             }
 
-            Instruction::Jms(addr) => {
-                // Jump to subroutine - push current PC to stack
-                if self.stack_pointer < 3 {
-                    self.stack[self.stack_pointer as usize] = self.program_counter;
-                    self.stack_pointer += 1;
-                    self.program_counter.set(addr);
-                }
+            // Intel 4040 Instructions
+            Instruction::Hlt => {
+                self.halted = true;
             }
 
-            Instruction::Bbl(imm) => {
-                // Branch back and load - pop from stack and load accumulator
-                if self.stack_pointer > 0 {
-                    self.stack_pointer -= 1;
-                    self.program_counter = self.stack[self.stack_pointer as usize];
-                }
-                self.accumulator = imm & 0x0F;
+            Instruction::Bbs => {
+                // Return from the interrupt routine; the saved SRC was resent during X2-X3
+                self.program_counter = self.pop_stack();
+                self.address_latch = self.saved_src;
+                self.rom_port = self.saved_src >> 4;
+                self.interrupt_active = false;
+                self.set_output_pin("INTA", false);
             }
 
-            // I/O and RAM Instructions
-            Instruction::Wrm => {
-                // Write accumulator to RAM at current RAM address
-                // This would interface with RAM chips - for now, just log
-                println!(
-                    "DEBUG: [CPU] WRM - Write ACC 0x{:X} to RAM address 0x{:02X}",
-                    self.accumulator, self.address_latch
-                );
-                self.program_counter.inc();
+            Instruction::Lcr => {
+                // Load command register - the bank selected by the last DCL
+                self.accumulator = self.ram_bank;
             }
 
-            Instruction::Wmp => {
-                // Write memory pointer - set RAM address from accumulator
-                self.address_latch = self.accumulator;
-                println!(
-                    "DEBUG: [CPU] WMP - Set RAM address to 0x{:02X}",
-                    self.address_latch
-                );
-                self.program_counter.inc();
+            Instruction::Or(reg) => {
+                self.accumulator |= self.read_register(reg);
             }
 
-            Instruction::Wrr => {
-                // Write ROM port and register - handled by memory interface
-                println!("DEBUG: [CPU] WRR - Write to ROM port");
-                self.program_counter.inc();
+            Instruction::An(reg) => {
+                self.accumulator &= self.read_register(reg);
             }
 
-            Instruction::Wpm => {
-                // Write program memory - handled by memory interface
-                println!("DEBUG: [CPU] WPM - Write to program memory");
-                self.program_counter.inc();
+            Instruction::Db(bank) => {
+                self.rom_bank = bank;
             }
 
-            Instruction::Adm => {
-                // Add from memory - add RAM data to accumulator
-                // This would read from RAM and add to accumulator
-                println!(
-                    "DEBUG: [CPU] ADM - Add from RAM address 0x{:02X}",
-                    self.address_latch
-                );
-                self.program_counter.inc();
+            Instruction::Sb(bank) => {
+                self.register_bank = bank;
             }
 
-            Instruction::Sbm => {
-                // Subtract from memory - subtract RAM data from accumulator
-                println!(
-                    "DEBUG: [CPU] SBM - Subtract from RAM address 0x{:02X}",
-                    self.address_latch
-                );
-                self.program_counter.inc();
+            Instruction::Ein => {
+                self.interrupt_enabled = true;
             }
 
-            Instruction::Rdm => {
-                // Read memory - read RAM data to accumulator
-                println!(
-                    "DEBUG: [CPU] RDM - Read from RAM address 0x{:02X}",
-                    self.address_latch
-                );
-                self.program_counter.inc();
-            }
-
-            Instruction::Rdr => {
-                // Read ROM port and register - handled by memory interface
-                println!("DEBUG: [CPU] RDR - Read from ROM port");
-                self.program_counter.inc();
-            }
-
-            // Register Control Instructions
-            Instruction::Src(reg) => {
-                // Send register control - select ROM/RAM port
-                self.rom_port = reg & 0x0F;
-                self.program_counter.inc();
-            }
-
-            // Increment Register Instructions
-            Instruction::Inc(reg) => {
-                if reg < 16 {
-                    self.index_registers[reg as usize] =
-                        (self.index_registers[reg as usize] + 1) & 0x0F;
-                }
-                self.program_counter.inc();
-            }
-
-            // Decimal Add Instructions
-            Instruction::Dad(reg) => {
-                if reg < 16 {
-                    let acc = self.accumulator;
-                    let reg_val = self.index_registers[reg as usize];
-                    let result = acc + reg_val + (if self.carry { 1 } else { 0 });
-
-                    // Decimal adjustment
-                    let adjusted_result = if result > 9 { result + 6 } else { result };
-                    self.accumulator = adjusted_result & 0x0F;
-                    self.carry = adjusted_result > 0x0F;
-                }
-                self.program_counter.inc();
-            }
-
-            // Jump on Test Instructions
-            Instruction::Jnt(addr) => {
-                // Jump if test pin is high
-                let (_, _, test) = self.read_control_pins();
-                if test {
-                    self.program_counter.set(addr);
-                } else {
-                    self.program_counter.inc();
-                }
-            }
-
-            Instruction::JntInvert(addr) => {
-                // Jump if test pin is low (inverted)
-                let (_, _, test) = self.read_control_pins();
-                if !test {
-                    self.program_counter.set(addr);
-                } else {
-                    self.program_counter.inc();
-                }
+            Instruction::Din => {
+                self.interrupt_enabled = false;
             }
         }
     }
@@ -1121,12 +1294,30 @@ impl Intel4004 {
         self.carry
     }
 
+    /// Set the carry flag
+    /// Parameters: carry - New carry flag state
+    pub fn set_carry(&mut self, carry: bool) {
+        self.carry = carry;
+    }
+
     /// Get the current stack pointer value
-    /// Returns: Stack pointer (0-2 for the 3-level stack)
+    /// Returns: Stack pointer (0-2 for the 4004's 3-level stack, 0-6 on the 4040)
     pub fn get_stack_pointer(&self) -> u8 {
         self.stack_pointer
     }
 
+    /// Get the contents of the address stack
+    /// Entries are ordered from the most recently pushed one, so index 0 is where the
+    /// next BBL returns to; levels that were never written hold their reset value
+    /// Returns: The 12-bit stack entries, newest first (3 on the 4004, 7 on the 4040)
+    pub fn get_stack(&self) -> Vec<u16> {
+        let depth = self.stack_depth() as usize;
+        let sp = self.stack_pointer as usize;
+        (1..=depth)
+            .map(|age| self.stack[(sp + depth - age) % depth].value())
+            .collect()
+    }
+
     /// Get the total number of clock cycles executed
     /// Returns: Total cycle count since reset
     pub fn get_cycle_count(&self) -> u64 {
@@ -1140,11 +1331,11 @@ impl Intel4004 {
     }
 
     /// Set an index register to a specific value
-    /// Parameters: index - Register index (0-15), value - New 4-bit register value
+    /// Parameters: index - Register index (0-15) in the selected bank, value - New 4-bit register value
     /// Returns: Ok(()) if successful, Err(String) if index out of range
     pub fn set_register(&mut self, index: u8, value: u8) -> Result<(), String> {
         if index < 16 {
-            self.index_registers[index as usize] = value & 0x0F;
+            self.write_register(index, value);
             Ok(())
         } else {
             Err("Register index out of range".to_string())
@@ -1152,34 +1343,395 @@ impl Intel4004 {
     }
 
     /// Get the value of an index register
-    /// Parameters: index - Register index (0-15)
+    /// Parameters: index - Register index (0-15) in the selected bank
     /// Returns: Some(register_value) if index valid, None if out of range
     pub fn get_register(&self, index: u8) -> Option<u8> {
         if index < 16 {
-            Some(self.index_registers[index as usize])
+            Some(self.read_register(index))
         } else {
             None
         }
     }
 
-    /// Test helper: Execute a single instruction for testing
-    /// This bypasses the normal clock synchronization for testing purposes
+    /// Get the current subcycle of the instruction cycle
+    /// Returns: Subcycle the bus is in, X3 after reset so the next Φ1 starts A1
+    pub fn get_subcycle(&self) -> BusSubcycle {
+        self.subcycle
+    }
+
+    /// Get the CPU variant
+    /// Returns: Intel4004 or Intel4040
+    pub fn get_variant(&self) -> CpuVariant {
+        self.variant
+    }
+
+    /// Get the index register bank selected by SB0/SB1
+    /// Returns: 0 or 1, always 0 on the 4004
+    pub fn get_register_bank(&self) -> u8 {
+        self.register_bank
+    }
+
+    /// Get the ROM bank selected by DB0/DB1
+    /// Returns: 0 (CM_ROM) or 1 (CM_ROM1), always 0 on the 4004
+    pub fn get_rom_bank(&self) -> u8 {
+        self.rom_bank
+    }
+
+    /// Check whether interrupts are enabled (EIN) on the 4040
+    /// Returns: true after EIN until DIN or reset
+    pub fn is_interrupt_enabled(&self) -> bool {
+        self.interrupt_enabled
+    }
+
+    /// Check whether the 4040 is inside an interrupt routine
+    /// Returns: true from interrupt acknowledge until BBS, while INTA is high
+    pub fn is_interrupt_active(&self) -> bool {
+        self.interrupt_active
+    }
+
+    /// Check whether the 4040 is halted by HLT
+    /// Returns: true until an interrupt or STP resumes execution
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Check whether the 4040 is stopped by STP
+    /// Returns: true while STPA is high
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Get the total number of instructions executed
+    /// Returns: Instruction count since creation, counted identically in both execution modes
+    pub fn get_instruction_count(&self) -> u64 {
+        self.instruction_count
+    }
+
+    /// Select pin-level or functional execution
+    /// Parameters: mode - Execution mode to use from the next update on
+    pub fn set_execution_mode(&mut self, mode: ExecutionMode) {
+        self.execution_mode = mode;
+        if mode == ExecutionMode::Functional {
+            // The bus is not used in functional mode
+            self.set_sync(false);
+            self.set_cm_lines(false, false);
+            self.tri_state_data_bus();
+        }
+    }
+
+    /// Get the current execution mode
+    /// Returns: Active ExecutionMode
+    pub fn get_execution_mode(&self) -> ExecutionMode {
+        self.execution_mode
+    }
+
+    /// Attach program memory for functional execution
+    /// Parameters: chips - Intel 4001 ROMs, each serving the page of its chip number, or a
+    ///             4008/4009 pair with its RAM, each with the ROM bank (0-1) it answers
+    pub fn attach_program_memory(&mut self, chips: Vec<ProgramMemoryChip>) {
+        self.program_memory = chips;
+    }
+
+    /// Attach data memory for functional execution
+    /// Parameters: chips - Intel 4002 RAM components, each with the CM-RAM line (0-3) it answers
+    pub fn attach_data_memory(&mut self, chips: Vec<DataMemoryChip>) {
+        self.data_memory = chips;
+    }
+
+    /// Execute one complete instruction in functional mode
+    /// Fetches directly from the attached program memory without driving D0-D3, SYNC or CM.
+    /// SRC and the I/O group reach the attached RAMs and ROM ports as they would over the bus,
+    /// and WPM/RPM the program RAM behind a 4008/4009 pair.
+    /// Returns: Ok(()) on success, Err(String) if no program memory is attached
+    pub fn step_functional(&mut self) -> Result<(), String> {
+        if self.program_memory.is_empty() {
+            return Err("No program memory attached for functional execution".to_string());
+        }
+
+        if !self.begin_instruction() {
+            // Halted or stopped: the CPU idles through one instruction cycle
+            self.cycle_count += 8;
+            return Ok(());
+        }
+
+        loop {
+            let byte = self.fetch_program_byte(self.fetch_address());
+            self.accept_instruction_byte(byte);
+            self.cycle_count += 8; // One 8-clock instruction cycle per word
+            if !self.second_cycle {
+                break;
+            }
+        }
+
+        match self.current_op {
+            Instruction::Src(pair) => self.send_src_functional(self.read_register_pair(pair)),
+            Instruction::Bbs => self.send_src_functional(self.saved_src),
+            Instruction::Rpm => self.io_data = self.read_program_ram_functional(),
+            op if self.current_instruction >> 4 == 0xE => {
+                let data = self.execute_io_functional(self.current_instruction & 0x0F);
+                if op == Instruction::Wpm {
+                    self.write_program_ram_functional();
+                }
+                if Self::is_io_read(op) {
+                    self.io_data = data;
+                }
+            }
+            _ => {}
+        }
+
+        self.execute_single_instruction();
+        Ok(())
+    }
+
+    /// Send an SRC address to the attached memory without a bus cycle
+    /// Hardware: Every ROM and 4008 on the selected ROM bank latches the address; RAMs only
+    /// see it on the CM-RAM lines selected by DCL
+    /// Parameters: address - Register pair sent by SRC
+    fn send_src_functional(&self, address: u8) {
+        for chip in self.program_memory_bank() {
+            if let Ok(mut chip) = chip.lock() {
+                if let Some(rom) = chip.as_any_mut().downcast_mut::<Intel4001>() {
+                    rom.select_io_port(address >> 4);
+                } else if let Some(latch) = chip.as_any_mut().downcast_mut::<Intel4008>() {
+                    latch.select_src_address(address);
+                }
+            }
+        }
+
+        let lines = self.get_cm_ram_lines();
+        for (line, chip) in &self.data_memory {
+            if !lines[(*line & 0x03) as usize] {
+                continue;
+            }
+            if let Ok(mut chip) = chip.lock() {
+                if let Some(ram) = chip.as_any_mut().downcast_mut::<Intel4002>() {
+                    ram.select_character(address);
+                }
+            }
+        }
+    }
+
+    /// Execute an I/O group instruction on the attached memory without a bus cycle
+    /// Hardware: Chips answering a read drive the bus together; a low level wins
+    /// Parameters: opa - Low nibble of the I/O instruction
+    /// Returns: Operand of a read instruction, 0 if no chip answers
+    fn execute_io_functional(&self, opa: u8) -> u8 {
+        let mut answers = Vec::new();
+        for chip in self.program_memory_bank() {
+            if let Ok(mut chip) = chip.lock() {
+                if let Some(rom) = chip.as_any_mut().downcast_mut::<Intel4001>() {
+                    answers.extend(rom.execute_io(opa, self.accumulator));
+                }
+            }
+        }
+
+        let lines = self.get_cm_ram_lines();
+        for (line, chip) in &self.data_memory {
+            if !lines[(*line & 0x03) as usize] {
+                continue;
+            }
+            if let Ok(mut chip) = chip.lock() {
+                if let Some(ram) = chip.as_any_mut().downcast_mut::<Intel4002>() {
+                    answers.extend(ram.execute_io(opa, self.accumulator));
+                }
+            }
+        }
+
+        answers.into_iter().reduce(|a, b| a & b).unwrap_or(0)
+    }
+
+    /// Write the accumulator to program RAM through a 4008/4009 pair without a bus cycle
+    /// Hardware: The 4008 addresses the location named by the last SRC and the 4009 pairs
+    /// the nibbles of two WPMs into the byte it writes
+    fn write_program_ram_functional(&self) {
+        let Some(address) = self.with_program_memory(Intel4008::select_program_ram_address) else {
+            return;
+        };
+        let byte = self
+            .with_program_memory(|converter: &mut Intel4009| {
+                converter.write_program_nibble(self.accumulator)
+            })
+            .flatten();
+        if let Some(byte) = byte {
+            // Writes outside the RAM are dropped, as the bus would
+            let _ = self
+                .with_program_memory(|ram: &mut GenericRam| ram.write_byte(address as usize, byte));
+        }
+    }
+
+    /// Read a program RAM nibble through a 4008/4009 pair without a bus cycle
+    /// Hardware: The 4008 addresses the location named by the last SRC and the 4009 returns
+    /// its high nibble on the first RPM of a pair, the low nibble on the second
+    /// Returns: Nibble for the accumulator, 0 if no 4008/4009 pair answers
+    fn read_program_ram_functional(&self) -> u8 {
+        let Some(address) = self.with_program_memory(Intel4008::select_program_ram_address) else {
+            return 0;
+        };
+        let byte = self
+            .with_program_memory(|ram: &mut GenericRam| ram.read_byte(address as usize))
+            .flatten()
+            .unwrap_or(0);
+        self.with_program_memory(|converter: &mut Intel4009| converter.read_program_nibble(byte))
+            .unwrap_or(0)
+    }
+
+    /// Execute the decoded instruction and run the breakpoint hook
+    /// Both execution modes finish every instruction here. When the new program counter
+    /// is a breakpoint, the hit is recorded and the CPU idles at that instruction boundary
+    /// until `take_breakpoint_hit`, so the instruction at a breakpoint has not run yet.
     pub fn execute_single_instruction(&mut self) {
-        // Force instruction phase to execute if we're in fetch phase
-        if self.instruction_phase == InstructionPhase::Fetch {
-            self.instruction_phase = InstructionPhase::Execute;
+        self.execute_instruction();
+        self.instruction_count += 1;
+
+        let address = self.program_counter.value();
+        if self.breakpoints.contains(&address) {
+            self.breakpoint_hit = Some(address);
         }
+    }
 
-        if self.instruction_phase == InstructionPhase::Execute {
-            let old_pc = self.program_counter.value();
-            self.execute_instruction();
-            let new_pc = self.program_counter.value();
+    /// Set a breakpoint
+    /// Parameters: address - 12-bit program address
+    /// Returns: true if the breakpoint is new, false if it was already set
+    pub fn add_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.insert(address & 0x0FFF)
+    }
 
-            println!(
-                "DEBUG: [TEST] Single Execute | PC: 0x{:03X} -> 0x{:03X} | ACC: 0x{:X}",
-                old_pc, new_pc, self.accumulator
-            );
+    /// Remove a breakpoint
+    /// Parameters: address - 12-bit program address
+    /// Returns: true if a breakpoint was removed, false if none was set there
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.remove(&(address & 0x0FFF))
+    }
+
+    /// Remove every breakpoint
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Get the breakpoints
+    /// Returns: Breakpoint addresses in ascending order
+    pub fn get_breakpoints(&self) -> Vec<u16> {
+        self.breakpoints.iter().copied().collect()
+    }
+
+    /// Take the breakpoint the CPU stopped at and let it continue
+    /// Returns: Some(address) if a breakpoint was hit since the last call, None otherwise
+    pub fn take_breakpoint_hit(&mut self) -> Option<u16> {
+        self.breakpoint_hit.take()
+    }
+
+    /// Capture the CPU state for a machine snapshot
+    /// Returns: Registers, stack, program counter and bus cycle state
+    pub fn snapshot(&self) -> Intel4004State {
+        Intel4004State {
+            accumulator: self.accumulator,
+            carry: self.carry,
+            index_registers: self.index_registers.to_vec(),
+            program_counter: self.program_counter.value(),
+            stack: self.stack.iter().map(U12::value).collect(),
+            stack_pointer: self.stack_pointer,
+            cycle_count: self.cycle_count,
+            instruction_count: self.instruction_count,
+            current_instruction: self.current_instruction,
+            address_latch: self.address_latch,
+            rom_port: self.rom_port,
+            ram_bank: self.ram_bank,
+            prev_phi1: self.prev_phi1,
+            prev_phi2: self.prev_phi2,
+            subcycle: self.subcycle,
+            opr_latch: self.opr_latch,
+            io_data: self.io_data,
+            current_op: self.current_op,
+            second_cycle: self.second_cycle,
+            instruction_latched: self.instruction_latched,
+            register_bank: self.register_bank,
+            rom_bank: self.rom_bank,
+            interrupt_enabled: self.interrupt_enabled,
+            interrupt_active: self.interrupt_active,
+            saved_src: self.saved_src,
+            halted: self.halted,
+            stopped: self.stopped,
+            idle_cycle: self.idle_cycle,
+        }
+    }
+
+    /// Restore a CPU state captured by `snapshot`
+    /// A pending breakpoint hit is dropped; the breakpoints themselves are kept
+    /// The state is checked as a whole first, so a rejected state leaves the CPU unchanged
+    /// Parameters: state - CPU state
+    /// Returns: Ok(()) on success, Err(String) if the register file or stack size is wrong,
+    ///          the stack pointer is beyond this CPU's stack or a register is out of range
+    pub fn restore(&mut self, state: &Intel4004State) -> Result<(), String> {
+        let index_registers: [u8; 24] = state
+            .index_registers
+            .as_slice()
+            .try_into()
+            .map_err(|_| "CPU state needs 24 index registers".to_string())?;
+        let stack: [u16; 7] = state
+            .stack
+            .as_slice()
+            .try_into()
+            .map_err(|_| "CPU state needs 7 stack levels".to_string())?;
+        if state.stack_pointer >= self.stack_depth() {
+            return Err(format!(
+                "CPU state stack pointer {} is beyond the {}-level stack",
+                state.stack_pointer,
+                self.stack_depth()
+            ));
+        }
+        if let Some(register) = index_registers.iter().position(|&value| value > 0x0F) {
+            return Err(format!(
+                "CPU state index register {} holds 0x{:02X}, which is not a 4-bit value",
+                register, index_registers[register]
+            ));
         }
+        let limits = [
+            ("accumulator", state.accumulator, 0x0F),
+            ("ROM port", state.rom_port, 0x0F),
+            ("OPR latch", state.opr_latch, 0x0F),
+            ("I/O data", state.io_data, 0x0F),
+            ("RAM bank", state.ram_bank, 0x07),
+            ("register bank", state.register_bank, 0x01),
+            ("ROM bank", state.rom_bank, 0x01),
+        ];
+        if let Some((field, value, _)) = limits.iter().find(|(_, value, max)| value > max) {
+            return Err(format!(
+                "CPU state {} 0x{:02X} is out of range",
+                field, value
+            ));
+        }
+
+        self.index_registers = index_registers;
+        self.stack = stack.map(U12::new);
+
+        self.accumulator = state.accumulator;
+        self.carry = state.carry;
+        self.program_counter = U12::new(state.program_counter);
+        self.stack_pointer = state.stack_pointer;
+        self.cycle_count = state.cycle_count;
+        self.instruction_count = state.instruction_count;
+        self.current_instruction = state.current_instruction;
+        self.address_latch = state.address_latch;
+        self.rom_port = state.rom_port;
+        self.ram_bank = state.ram_bank;
+        self.prev_phi1 = state.prev_phi1;
+        self.prev_phi2 = state.prev_phi2;
+        self.subcycle = state.subcycle;
+        self.opr_latch = state.opr_latch;
+        self.io_data = state.io_data;
+        self.current_op = state.current_op;
+        self.second_cycle = state.second_cycle;
+        self.instruction_latched = state.instruction_latched;
+        self.register_bank = state.register_bank;
+        self.rom_bank = state.rom_bank;
+        self.interrupt_enabled = state.interrupt_enabled;
+        self.interrupt_active = state.interrupt_active;
+        self.saved_src = state.saved_src;
+        self.halted = state.halted;
+        self.stopped = state.stopped;
+        self.idle_cycle = state.idle_cycle;
+        self.breakpoint_hit = None;
+        Ok(())
     }
 
     /// Test helper: Load a test program into the CPU
@@ -1257,7 +1809,7 @@ mod tests {
         // Set up register 5 with value 3 for ADD test
         cpu.set_register(5, 3).unwrap();
 
-        // Test ADD - 0x25 = ADD 5 (5 + 3 = 8)
+        // Test ADD - 0x85 = ADD 5 (5 + 3 = 8)
         cpu.current_op = Instruction::Add(5);
         cpu.execute_instruction();
         assert_eq!(cpu.get_accumulator(), 8);
@@ -1275,12 +1827,12 @@ mod tests {
         cpu.set_register(0, 0x0A).unwrap();
         cpu.set_register(1, 0x05).unwrap();
 
-        // Test LD (load from register 0 into accumulator) - 0x00 = LD 0
+        // Test LD (load from register 0 into accumulator) - 0xA0 = LD 0
         cpu.current_op = Instruction::Ld(0);
         cpu.execute_instruction();
         assert_eq!(cpu.get_accumulator(), 0x0A);
 
-        // Test XCH (exchange with register 1) - 0x11 = XCH 1
+        // Test XCH (exchange with register 1) - 0xB1 = XCH 1
         cpu.current_op = Instruction::Xch(1);
         cpu.execute_instruction();
         assert_eq!(cpu.get_accumulator(), 0x05); // ACC gets R1's value
@@ -1296,7 +1848,7 @@ mod tests {
 
         cpu.reset();
 
-        // Test BBL (Branch Back and Load) - 0x40-0x4F
+        // Test BBL (Branch Back and Load) - 0xC0-0xCF
         // Set up stack with return address
         cpu.program_counter = U12::new(0x100);
         cpu.stack[0] = U12::new(0x200);
@@ -1318,19 +1870,22 @@ mod tests {
 
         cpu.reset();
 
-        // Test DAD (Decimal Add) - requires testing with registers
+        // Test ADD + DAA (5 + 3 = 8, no decimal adjustment needed)
         cpu.set_accumulator(5);
         cpu.set_register(0, 3).unwrap();
-
-        // Test DAD 0 (5 + 3 = 8, no decimal adjustment needed)
-        cpu.current_op = Instruction::Dad(0);
+        cpu.current_op = Instruction::Add(0);
+        cpu.execute_instruction();
+        cpu.current_op = Instruction::Daa;
         cpu.execute_instruction();
         assert_eq!(cpu.get_accumulator(), 8);
+        assert_eq!(cpu.get_carry(), false);
 
         // Test decimal adjustment case (accumulator + register > 9)
         cpu.set_accumulator(7);
         cpu.set_register(1, 5).unwrap();
-        cpu.current_op = Instruction::Dad(1);
+        cpu.current_op = Instruction::Add(1);
+        cpu.execute_instruction();
+        cpu.current_op = Instruction::Daa;
         cpu.execute_instruction();
         assert_eq!(cpu.get_accumulator(), 2); // 7 + 5 = 12 -> 12 + 6 = 18 -> 18 & 0x0F = 2 with carry
         assert_eq!(cpu.get_carry(), true); // Carry should be set due to decimal overflow
@@ -1343,19 +1898,26 @@ mod tests {
         let mut cpu = Intel4004::new("TEST_CPU".to_string(), 750000.0);
 
         cpu.reset();
-        cpu.set_program_counter(0x100);
 
-        // Test JNT (Jump on Test) - requires TEST pin setup
-        // For testing, we'll simulate the TEST pin behavior
+        // JCN 1 jumps while the TEST pin is low
+        cpu.set_program_counter(0x100);
+        cpu.current_op = Instruction::Jcn(0x1, 0x120);
+        cpu.execute_instruction();
+        assert_eq!(cpu.get_program_counter(), 0x120);
 
-        // Test with TEST pin high (should jump)
-        cpu.current_op = Instruction::Jnt(0x200);
-        // Note: In real implementation, this would check the TEST pin
-        // For unit testing, we verify the instruction is recognized
+        // JCN 9 (inverted) does not jump while TEST is low
+        cpu.current_op = Instruction::Jcn(0x9, 0x140);
+        cpu.execute_instruction();
+        assert_eq!(cpu.get_program_counter(), 0x120);
 
-        // Test JNTINVERT (Jump on Test Inverted)
-        cpu.current_op = Instruction::JntInvert(0x300);
-        // Note: In real implementation, this would check inverted TEST pin
+        // Drive TEST high and both conditions flip
+        drive_pin(&cpu, "TEST", "TEST_DRIVER", PinValue::High);
+        cpu.current_op = Instruction::Jcn(0x1, 0x160);
+        cpu.execute_instruction();
+        assert_eq!(cpu.get_program_counter(), 0x120);
+        cpu.current_op = Instruction::Jcn(0x9, 0x180);
+        cpu.execute_instruction();
+        assert_eq!(cpu.get_program_counter(), 0x180);
 
         println!("DEBUG: Test pin instructions test completed successfully");
     }
@@ -1366,12 +1928,15 @@ mod tests {
 
         cpu.reset();
 
-        // Test SRC (Send Register Control) - select ROM port
-        cpu.current_op = Instruction::Src(0x05); // Select ROM port 5
+        // Test SRC (Send Register Control) - send pair 2 (R4R5) as the I/O address
+        cpu.set_register(4, 0x05).unwrap();
+        cpu.set_register(5, 0x0C).unwrap();
+        cpu.current_op = Instruction::Src(2);
         cpu.execute_instruction();
-        // Note: SRC sets rom_port field - would need getter to verify
+        assert_eq!(cpu.address_latch, 0x5C);
+        assert_eq!(cpu.rom_port, 0x05); // High nibble selects the ROM chip
 
-        // Test INC (Increment Register) - 0xC0-0xEF range
+        // Test INC (Increment Register) - 0x60-0x6F range
         cpu.set_register(0, 0x0A).unwrap();
         cpu.current_op = Instruction::Inc(0);
         cpu.execute_instruction();
@@ -1379,6 +1944,643 @@ mod tests {
 
         println!("DEBUG: Register control test completed successfully");
     }
+
+    /// Architectural state compared between execution modes
+    type CpuSnapshot = (u16, u8, bool, [u8; 24], u8, [U12; 7]);
+
+    fn snapshot(cpu: &Intel4004) -> CpuSnapshot {
+        (
+            cpu.get_program_counter(),
+            cpu.accumulator,
+            cpu.carry,
+            cpu.index_registers,
+            cpu.stack_pointer,
+            cpu.stack,
+        )
+    }
+
+    fn rom_with_program(chip_id: u8, program: &[u8]) -> Arc<Mutex<Box<dyn Component>>> {
+        let mut rom = Intel4001::new("TEST_ROM".to_string());
+        rom.set_chip_id(chip_id).unwrap();
+        rom.load_rom_data(program.to_vec(), 0).unwrap();
+        Arc::new(Mutex::new(Box::new(rom)))
+    }
+
+    fn drive_pin(cpu: &Intel4004, pin: &str, driver: &str, value: PinValue) {
+        let pin = cpu.get_pin(pin).unwrap();
+        pin.lock()
+            .unwrap()
+            .set_driver(Some(driver.to_string()), value);
+    }
+
+    /// Clock a pin-level CPU and answer its fetches like a ROM on the data bus
+    /// The responder follows SYNC, latches the address during A1-A3 and drives OPR/OPA during M1-M2
+    fn run_pin_level(
+        cpu: &mut Intel4004,
+        rom: &Arc<Mutex<Box<dyn Component>>>,
+        instructions: u64,
+    ) -> Vec<CpuSnapshot> {
+        let mut trace = Vec::new();
+        let mut subcycle = 0;
+        let mut address = 0u16;
+        let mut byte = 0u8;
+
+        while cpu.get_instruction_count() < instructions {
+            // Φ1: the CPU starts the next subcycle
+            drive_pin(cpu, "PHI1", "TEST_CLOCK", PinValue::High);
+            drive_pin(cpu, "PHI2", "TEST_CLOCK", PinValue::Low);
+            cpu.update();
+
+            let sync = cpu.get_pin("SYNC").unwrap().lock().unwrap().read() == PinValue::High;
+            subcycle = if sync { 0 } else { subcycle + 1 };
+
+            let drive = match subcycle {
+                0..=2 => {
+                    let nibble = cpu.read_data_bus() as u16;
+                    address = if subcycle == 0 {
+                        nibble
+                    } else {
+                        address | (nibble << (4 * subcycle))
+                    };
+                    None
+                }
+                3 => {
+                    let chip = rom.lock().unwrap();
+                    let rom = chip.as_any().downcast_ref::<Intel4001>().unwrap();
+                    byte = if address < 0x100 {
+                        rom.read_rom(address as u8).unwrap()
+                    } else {
+                        0
+                    };
+                    Some(byte >> 4)
+                }
+                4 => Some(byte & 0x0F),
+                _ => None,
+            };
+            for i in 0..4 {
+                let value = drive.map_or(PinValue::HighZ, |nibble| {
+                    PinValue::from_bool((nibble >> i) & 1 == 1)
+                });
+                drive_pin(cpu, &format!("D{}", i), "TEST_ROM", value);
+            }
+
+            // Φ2: the CPU samples the bus and executes at X3
+            let executed = cpu.get_instruction_count();
+            drive_pin(cpu, "PHI1", "TEST_CLOCK", PinValue::Low);
+            drive_pin(cpu, "PHI2", "TEST_CLOCK", PinValue::High);
+            cpu.update();
+
+            if cpu.get_instruction_count() > executed {
+                trace.push(snapshot(cpu));
+            }
+        }
+
+        trace
+    }
+
+    #[test]
+    fn test_4004_functional_mode_matches_pin_level() {
+        let mut program = [0u8; 0x40];
+        let mut place = |address: usize, bytes: &[u8]| {
+            program[address..address + bytes.len()].copy_from_slice(bytes);
+        };
+        place(0x000, &[0xD5]); // LDM 5
+        place(0x001, &[0xB8]); // XCH R8
+        place(0x002, &[0x20, 0x30]); // FIM P0, 0x30
+        place(0x004, &[0x32]); // FIN P1
+        place(0x005, &[0x7A, 0x05]); // ISZ R10, 005
+        place(0x007, &[0x61]); // INC R1
+        place(0x008, &[0xA1]); // LD R1
+        place(0x009, &[0xF2]); // IAC
+        place(0x00A, &[0x12, 0x14]); // JCN C, 014
+        place(0x00C, &[0x50, 0x20]); // JMS 020
+        place(0x00E, &[0x98]); // SUB R8
+        place(0x00F, &[0x40, 0x07]); // JUN 007
+        place(0x014, &[0xF3]); // CMC
+        place(0x015, &[0x40, 0x07]); // JUN 007
+        place(0x020, &[0x62]); // INC R2
+        place(0x021, &[0xF5]); // RAL
+        place(0x022, &[0xFB]); // DAA
+        place(0x023, &[0xF8]); // DAC
+        place(0x024, &[0xC3]); // BBL 3
+        place(0x030, &[0xA5]); // FIN data
+        let rom = rom_with_program(0, &program);
+        let instructions = 200;
+
+        let mut pin_cpu = Intel4004::new("PIN_CPU".to_string(), 750000.0);
+        drive_pin(&pin_cpu, "PHI1", "TEST_CLOCK", PinValue::Low);
+        drive_pin(&pin_cpu, "PHI2", "TEST_CLOCK", PinValue::Low);
+        pin_cpu.start();
+        let pin_trace = run_pin_level(&mut pin_cpu, &rom, instructions);
+
+        let mut functional_cpu = Intel4004::new("FUNCTIONAL_CPU".to_string(), 750000.0);
+        functional_cpu.attach_program_memory(vec![(0, rom.clone())]);
+        functional_cpu.set_execution_mode(ExecutionMode::Functional);
+        functional_cpu.start();
+        let mut functional_trace = Vec::new();
+        for _ in 0..instructions {
+            functional_cpu.step_functional().unwrap();
+            functional_trace.push(snapshot(&functional_cpu));
+        }
+
+        assert_eq!(pin_trace.len(), instructions as usize);
+        assert_eq!(pin_trace, functional_trace);
+
+        // Functional mode never touches the bus
+        assert_eq!(
+            functional_cpu
+                .get_pin("SYNC")
+                .unwrap()
+                .lock()
+                .unwrap()
+                .read(),
+            PinValue::Low
+        );
+    }
+
+    #[test]
+    fn test_4004_functional_mode_requires_program_memory() {
+        let mut cpu = Intel4004::new("TEST_CPU".to_string(), 750000.0);
+        cpu.set_execution_mode(ExecutionMode::Functional);
+        cpu.start();
+
+        assert!(cpu.step_functional().is_err());
+
+        // Pages without an attached ROM read as zero
+        cpu.attach_program_memory(vec![(0, rom_with_program(0, &[0xF2]))]);
+        cpu.set_program_counter(0x100);
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_program_counter(), 0x101);
+        assert_eq!(cpu.get_instruction_count(), 1);
+    }
+
+    #[test]
+    fn test_4004_functional_mode_reaches_ram_and_rom_ports() {
+        let program = [
+            0x20, 0x40, // FIM P0, 40: chip 1, register 0, character 0
+            0x21, // SRC P0
+            0xD7, // LDM 7
+            0xE0, // WRM
+            0xE2, // WRR
+            0xD0, // LDM 0
+            0xE9, // RDM
+            0xB2, // XCH R2
+            0xEA, // RDR
+            0xD1, // LDM 1
+            0xFD, // DCL: bank 1 leaves the chip on CM-RAM0 unselected
+            0xE9, // RDM
+        ];
+        let mut ram = Intel4002::new("TEST_RAM".to_string());
+        ram.set_chip_id(1).unwrap();
+        let ram: Arc<Mutex<Box<dyn Component>>> = Arc::new(Mutex::new(Box::new(ram)));
+
+        let mut cpu = Intel4004::new("TEST_CPU".to_string(), 750000.0);
+        cpu.attach_program_memory(vec![
+            (0, rom_with_program(4, &[])),
+            (0, rom_with_program(0, &program)),
+        ]);
+        cpu.attach_data_memory(vec![(0, ram.clone())]);
+        cpu.set_execution_mode(ExecutionMode::Functional);
+        cpu.start();
+
+        for _ in 0..8 {
+            cpu.step_functional().unwrap();
+        }
+        assert_eq!(cpu.get_register(2), Some(0x7)); // RDM
+        {
+            let chip = ram.lock().unwrap();
+            let chip = chip.as_any().downcast_ref::<Intel4002>().unwrap();
+            assert_eq!(chip.read_ram(0), Some(0x7));
+        }
+
+        // WRR went to the ROM named by the SRC chip number
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_accumulator(), 0x7);
+
+        for _ in 0..3 {
+            cpu.step_functional().unwrap();
+        }
+        assert_eq!(cpu.get_accumulator(), 0x0);
+    }
+
+    #[test]
+    fn test_4004_breakpoint_stops_before_instruction() {
+        let mut cpu = functional_cpu(&[vec![0xD5, 0xF2, 0xF2, 0x40, 0x00]]); // LDM 5, IAC, IAC, JUN 000
+        assert!(cpu.add_breakpoint(0x002));
+        assert!(!cpu.add_breakpoint(0x002));
+
+        cpu.step_functional().unwrap();
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_program_counter(), 0x002);
+        assert_eq!(cpu.get_accumulator(), 6);
+
+        // The CPU idles at the breakpoint until the hit is taken
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_instruction_count(), 2);
+        assert_eq!(cpu.take_breakpoint_hit(), Some(0x002));
+        assert_eq!(cpu.take_breakpoint_hit(), None);
+
+        // Resuming executes the instruction at the breakpoint
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_accumulator(), 7);
+        assert!(cpu.remove_breakpoint(0x002));
+        assert!(cpu.get_breakpoints().is_empty());
+    }
+
+    /// Run a program from a set of 256-byte ROM pages in functional mode
+    fn functional_cpu(pages: &[Vec<u8>]) -> Intel4004 {
+        let mut cpu = Intel4004::new("TEST_CPU".to_string(), 750000.0);
+        cpu.attach_program_memory(
+            pages
+                .iter()
+                .enumerate()
+                .map(|(chip, page)| (0, rom_with_program(chip as u8, page)))
+                .collect(),
+        );
+        cpu.set_execution_mode(ExecutionMode::Functional);
+        cpu.start();
+        cpu
+    }
+
+    #[test]
+    fn test_4004_two_word_jumps_use_12_bit_targets() {
+        let mut page0 = vec![0u8; 256];
+        let mut page1 = vec![0u8; 256];
+        page0[0x00..0x02].copy_from_slice(&[0x51, 0x10]); // JMS 110
+        page0[0x02] = 0xB0; // XCH R0
+        page0[0x03..0x05].copy_from_slice(&[0x41, 0x20]); // JUN 120
+        page1[0x10] = 0xC7; // BBL 7
+
+        let mut cpu = functional_cpu(&[page0, page1]);
+
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_program_counter(), 0x110);
+        assert_eq!(cpu.stack[0].value(), 0x002); // Return address follows the second word
+
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_program_counter(), 0x002);
+        assert_eq!(cpu.get_accumulator(), 7);
+
+        cpu.step_functional().unwrap();
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_register(0), Some(7));
+        assert_eq!(cpu.get_program_counter(), 0x120);
+
+        // Each two-word instruction spends two 8-clock instruction cycles
+        assert_eq!(cpu.get_instruction_count(), 4);
+        assert_eq!(cpu.get_cycle_count(), 8 * 6);
+    }
+
+    #[test]
+    fn test_4004_last_word_of_page_quirk() {
+        let mut pages = vec![vec![0u8; 256]; 4];
+        pages[0][0xFE..].copy_from_slice(&[0x18, 0x10]); // 0FE: JCN (always), 10
+        pages[1][0x10..0x12].copy_from_slice(&[0x41, 0xFE]); // 110: JUN 1FE
+        pages[1][0xFE..].copy_from_slice(&[0x70, 0x20]); // 1FE: ISZ R0, 20
+        pages[2][0x20..0x22].copy_from_slice(&[0x20, 0x05]); // 220: FIM P0, 05
+        pages[2][0x22..0x24].copy_from_slice(&[0x42, 0xFF]); // 222: JUN 2FF
+        pages[2][0xFF] = 0x32; // 2FF: FIN P1
+        pages[3][0x05] = 0xA5; // 305: FIN data
+
+        let mut cpu = functional_cpu(&pages);
+        cpu.set_program_counter(0x0FE);
+
+        // JCN's second word sits in the next page, so its target does too
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_program_counter(), 0x110);
+
+        cpu.step_functional().unwrap();
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_program_counter(), 0x220);
+
+        cpu.step_functional().unwrap();
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_program_counter(), 0x2FF);
+
+        // FIN in the last word of a page reads from the next page
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_register(2), Some(0x0A));
+        assert_eq!(cpu.get_register(3), Some(0x05));
+        assert_eq!(cpu.get_program_counter(), 0x300);
+    }
+
+    #[test]
+    fn test_4004_decoder_opcode_table() {
+        let cpu = Intel4004::new("TEST_CPU".to_string(), 750000.0);
+
+        // Groups whose OPA is a register, condition or immediate operand
+        let groups: [(u8, fn(u8) -> Instruction); 11] = [
+            (0x1, |n| Instruction::Jcn(n, 0)),
+            (0x4, |n| Instruction::Jun((n as u16) << 8)),
+            (0x5, |n| Instruction::Jms((n as u16) << 8)),
+            (0x6, Instruction::Inc),
+            (0x7, |n| Instruction::Isz(n, 0)),
+            (0x8, Instruction::Add),
+            (0x9, Instruction::Sub),
+            (0xA, Instruction::Ld),
+            (0xB, Instruction::Xch),
+            (0xC, Instruction::Bbl),
+            (0xD, Instruction::Ldm),
+        ];
+        for (opr, instruction) in groups {
+            for opa in 0..16 {
+                let opcode = (opr << 4) | opa;
+                assert_eq!(
+                    cpu.decode_instruction(opcode),
+                    instruction(opa),
+                    "opcode 0x{:02X}",
+                    opcode
+                );
+            }
+        }
+
+        // Register pair groups: even OPA and odd OPA select different instructions
+        for pair in 0..8 {
+            let opa = pair << 1;
+            assert_eq!(
+                cpu.decode_instruction(0x20 | opa),
+                Instruction::Fim(pair, 0)
+            );
+            assert_eq!(cpu.decode_instruction(0x21 | opa), Instruction::Src(pair));
+            assert_eq!(
+                cpu.decode_instruction(0x30 | opa),
+                Instruction::Fin(pair, 0)
+            );
+            assert_eq!(cpu.decode_instruction(0x31 | opa), Instruction::Jin(pair));
+        }
+
+        // Single-opcode instructions and unassigned opcodes
+        let table = [
+            (0x00, Instruction::Nop),
+            (0xE0, Instruction::Wrm),
+            (0xE1, Instruction::Wmp),
+            (0xE2, Instruction::Wrr),
+            (0xE3, Instruction::Wpm),
+            (0xE4, Instruction::Wr(0)),
+            (0xE5, Instruction::Wr(1)),
+            (0xE6, Instruction::Wr(2)),
+            (0xE7, Instruction::Wr(3)),
+            (0xE8, Instruction::Sbm),
+            (0xE9, Instruction::Rdm),
+            (0xEA, Instruction::Rdr),
+            (0xEB, Instruction::Adm),
+            (0xEC, Instruction::Rd(0)),
+            (0xED, Instruction::Rd(1)),
+            (0xEE, Instruction::Rd(2)),
+            (0xEF, Instruction::Rd(3)),
+            (0xF0, Instruction::Clb),
+            (0xF1, Instruction::Clc),
+            (0xF2, Instruction::Iac),
+            (0xF3, Instruction::Cmc),
+            (0xF4, Instruction::Cma),
+            (0xF5, Instruction::Ral),
+            (0xF6, Instruction::Rar),
+            (0xF7, Instruction::Tcc),
+            (0xF8, Instruction::Dac),
+            (0xF9, Instruction::Tcs),
+            (0xFA, Instruction::Stc),
+            (0xFB, Instruction::Daa),
+            (0xFC, Instruction::Kbp),
+            (0xFD, Instruction::Dcl),
+            (0xFE, Instruction::Invalid),
+            (0xFF, Instruction::Invalid),
+        ];
+        for (opcode, instruction) in table {
+            assert_eq!(
+                cpu.decode_instruction(opcode),
+                instruction,
+                "opcode 0x{:02X}",
+                opcode
+            );
+        }
+        for opcode in 0x01..=0x0F {
+            assert_eq!(cpu.decode_instruction(opcode), Instruction::Invalid);
+        }
+    }
+
+    #[test]
+    fn test_4004_carry_semantics() {
+        let mut cpu = Intel4004::new("TEST_CPU".to_string(), 750000.0);
+        cpu.reset();
+
+        // ADD includes the carry: 9 + 8 + 1 = 18 -> 2 with carry
+        cpu.set_accumulator(9);
+        cpu.set_register(3, 8).unwrap();
+        cpu.carry = true;
+        cpu.current_op = Instruction::Add(3);
+        cpu.execute_instruction();
+        assert_eq!((cpu.get_accumulator(), cpu.get_carry()), (2, true));
+
+        // SUB with carry clear: 5 - 3 = 2, carry set means no borrow
+        cpu.set_accumulator(5);
+        cpu.carry = false;
+        cpu.set_register(4, 3).unwrap();
+        cpu.current_op = Instruction::Sub(4);
+        cpu.execute_instruction();
+        assert_eq!((cpu.get_accumulator(), cpu.get_carry()), (2, true));
+
+        // SUB with borrow: 3 - 5 = -2 -> 14, carry clear
+        cpu.set_accumulator(3);
+        cpu.carry = false;
+        cpu.set_register(4, 5).unwrap();
+        cpu.current_op = Instruction::Sub(4);
+        cpu.execute_instruction();
+        assert_eq!((cpu.get_accumulator(), cpu.get_carry()), (14, false));
+
+        // TCC / TCS / DAC
+        cpu.carry = true;
+        cpu.current_op = Instruction::Tcc;
+        cpu.execute_instruction();
+        assert_eq!((cpu.get_accumulator(), cpu.get_carry()), (1, false));
+
+        cpu.carry = true;
+        cpu.current_op = Instruction::Tcs;
+        cpu.execute_instruction();
+        assert_eq!((cpu.get_accumulator(), cpu.get_carry()), (10, false));
+        cpu.current_op = Instruction::Tcs;
+        cpu.execute_instruction();
+        assert_eq!(cpu.get_accumulator(), 9);
+
+        cpu.set_accumulator(0);
+        cpu.current_op = Instruction::Dac;
+        cpu.execute_instruction();
+        assert_eq!((cpu.get_accumulator(), cpu.get_carry()), (15, false));
+        cpu.current_op = Instruction::Dac;
+        cpu.execute_instruction();
+        assert_eq!((cpu.get_accumulator(), cpu.get_carry()), (14, true));
+    }
+
+    #[test]
+    fn test_4004_kbp_and_dcl() {
+        let mut cpu = Intel4004::new("TEST_CPU".to_string(), 750000.0);
+        cpu.reset();
+
+        let expected = [0, 1, 2, 15, 3, 15, 15, 15, 4, 15, 15, 15, 15, 15, 15, 15];
+        for (input, output) in expected.iter().enumerate() {
+            cpu.set_accumulator(input as u8);
+            cpu.current_op = Instruction::Kbp;
+            cpu.execute_instruction();
+            assert_eq!(cpu.get_accumulator(), *output, "KBP of {}", input);
+        }
+
+        cpu.set_accumulator(0x0B);
+        cpu.current_op = Instruction::Dcl;
+        cpu.execute_instruction();
+        assert_eq!(cpu.get_ram_bank(), 0x03);
+        assert_eq!(cpu.get_cm_ram_lines(), [false, true, true, false]);
+
+        cpu.set_accumulator(0x00);
+        cpu.execute_instruction();
+        assert_eq!(cpu.get_cm_ram_lines(), [true, false, false, false]);
+    }
+
+    #[test]
+    fn test_4004_register_pair_instructions() {
+        let mut cpu = Intel4004::new("TEST_CPU".to_string(), 750000.0);
+        cpu.reset();
+
+        // FIM loads a pair, high nibble into the even register
+        cpu.current_op = Instruction::Fim(3, 0xA7);
+        cpu.execute_instruction();
+        assert_eq!(cpu.get_register(6), Some(0x0A));
+        assert_eq!(cpu.get_register(7), Some(0x07));
+
+        // JIN jumps within the current page
+        cpu.set_program_counter(0x312);
+        cpu.current_op = Instruction::Jin(3);
+        cpu.execute_instruction();
+        assert_eq!(cpu.get_program_counter(), 0x3A7);
+
+        // ISZ jumps until the register wraps to zero
+        cpu.set_register(2, 0x0E).unwrap();
+        cpu.current_op = Instruction::Isz(2, 0x340);
+        cpu.execute_instruction();
+        assert_eq!(cpu.get_program_counter(), 0x340);
+        cpu.set_program_counter(0x350);
+        cpu.execute_instruction();
+        assert_eq!(cpu.get_register(2), Some(0));
+        assert_eq!(cpu.get_program_counter(), 0x350);
+    }
+
+    #[test]
+    fn test_4004_stack_wraps_on_overflow_and_underflow() {
+        let mut page0 = vec![0u8; 256];
+        page0[0x00..0x02].copy_from_slice(&[0x50, 0x10]); // 000: JMS 010
+        page0[0x10..0x12].copy_from_slice(&[0x50, 0x20]); // 010: JMS 020
+        page0[0x12] = 0xC4; // 012: BBL 4
+        page0[0x20..0x22].copy_from_slice(&[0x50, 0x30]); // 020: JMS 030
+        page0[0x22] = 0xC3; // 022: BBL 3
+        page0[0x30..0x32].copy_from_slice(&[0x50, 0x40]); // 030: JMS 040
+        page0[0x32] = 0xC2; // 032: BBL 2
+        page0[0x40] = 0xC1; // 040: BBL 1
+
+        let mut cpu = functional_cpu(&[page0]);
+
+        for _ in 0..3 {
+            cpu.step_functional().unwrap();
+        }
+        assert_eq!(cpu.get_stack(), [0x022, 0x012, 0x002]);
+        assert_eq!(cpu.get_stack_pointer(), 0);
+
+        // The 4th nested call overwrites the oldest return address
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_program_counter(), 0x040);
+        assert_eq!(cpu.get_stack(), [0x032, 0x022, 0x012]);
+        assert_eq!(cpu.get_stack_pointer(), 1);
+
+        let mut returns = Vec::new();
+        for _ in 0..5 {
+            cpu.step_functional().unwrap();
+            returns.push((cpu.get_program_counter(), cpu.get_accumulator()));
+        }
+
+        // The outermost BBL lands on the overwritten level instead of 0x002,
+        // and popping the now-empty stack keeps cycling through stale entries
+        assert_eq!(
+            returns,
+            vec![(0x032, 1), (0x022, 2), (0x012, 3), (0x032, 4), (0x022, 2)]
+        );
+        assert_eq!(cpu.get_stack_pointer(), 2);
+    }
+
+    #[test]
+    fn test_4004_bbl_on_empty_stack_still_pops() {
+        let mut cpu = Intel4004::new("TEST_CPU".to_string(), 750000.0);
+        cpu.reset();
+        cpu.set_program_counter(0x123);
+
+        cpu.current_op = Instruction::Bbl(0x5);
+        cpu.execute_instruction();
+
+        // The pointer wraps to the top level, which still holds its reset value
+        assert_eq!(cpu.get_program_counter(), 0x000);
+        assert_eq!(cpu.get_accumulator(), 0x5);
+        assert_eq!(cpu.get_stack_pointer(), 2);
+    }
+
+    #[test]
+    fn test_4004_snapshot_round_trip() {
+        let mut cpu = Intel4004::new("TEST_CPU".to_string(), 750000.0);
+        cpu.reset();
+        cpu.set_accumulator(0xA);
+        cpu.set_carry(true);
+        cpu.set_register(3, 0x7).unwrap();
+        cpu.push_stack(U12::new(0x2B4));
+        cpu.set_program_counter(0x0F0);
+        cpu.add_breakpoint(0x100);
+
+        let state = cpu.snapshot_state().unwrap();
+        let mut restored = Intel4004::new("TEST_CPU".to_string(), 750000.0);
+        restored.restore_state(&state).unwrap();
+
+        assert_eq!(restored.snapshot(), cpu.snapshot());
+        assert_eq!(restored.get_accumulator(), 0xA);
+        assert!(restored.get_carry());
+        assert_eq!(restored.get_register(3), Some(0x7));
+        assert_eq!(restored.get_program_counter(), 0x0F0);
+        assert_eq!(restored.get_stack()[0], 0x2B4);
+        // Breakpoints belong to the debugger session, not the machine
+        assert!(restored.get_breakpoints().is_empty());
+
+        // State of another component type is rejected
+        assert!(restored
+            .restore_state(&serde_json::json!({"memory": []}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_4004_restore_rejects_out_of_range_state() {
+        let mut cpu = Intel4004::new("TEST_CPU".to_string(), 750000.0);
+        cpu.reset();
+        cpu.set_accumulator(0x3);
+        let before = cpu.snapshot();
+
+        // A 4040 may be three levels deep, a 4004 wraps at three
+        let mut state = before.clone();
+        state.stack_pointer = 3;
+        assert!(cpu.restore(&state).is_err());
+        let mut cpu_4040 =
+            Intel4004::new_with_variant("TEST_CPU".to_string(), 750000.0, CpuVariant::Intel4040);
+        assert!(cpu_4040.restore(&state).is_ok());
+        state.stack_pointer = 7;
+        assert!(cpu_4040.restore(&state).is_err());
+
+        let mut state = before.clone();
+        state.accumulator = 0x1F;
+        state.index_registers[0] = 0x5;
+        assert!(cpu.restore(&state).is_err());
+
+        let mut state = before.clone();
+        state.index_registers[23] = 0x10;
+        assert!(cpu.restore(&state).is_err());
+
+        let mut state = before.clone();
+        state.ram_bank = 8;
+        assert!(cpu.restore(&state).is_err());
+
+        // Nothing of a rejected state is committed
+        assert_eq!(cpu.snapshot(), before);
+    }
 }
 
 impl Component for Intel4004 {
@@ -1395,208 +2597,75 @@ impl Component for Intel4004 {
     }
 
     /// Update the CPU state for one simulation cycle
-    /// Processes clock cycles and executes instructions when running
+    /// Pin-level mode follows the clock edges; functional mode executes one whole instruction
     fn update(&mut self) {
         if !self.is_running() {
             return;
         }
 
-        // Handle both rising and falling edges for proper two-phase operation
+        if self.execution_mode == ExecutionMode::Functional {
+            if let Err(e) = self.step_functional() {
+                println!(
+                    "DEBUG: [{}] Functional execution stopped: {}",
+                    self.base.name(),
+                    e
+                );
+                self.base.set_running(false);
+            }
+            return;
+        }
+
         let (phi1, phi2) = self.read_clock_pins();
         let phi1_rising = phi1 == PinValue::High && self.prev_phi1 == PinValue::Low;
-        let phi1_falling = phi1 == PinValue::Low && self.prev_phi1 == PinValue::High;
         let phi2_rising = phi2 == PinValue::High && self.prev_phi2 == PinValue::Low;
-        let phi2_falling = phi2 == PinValue::Low && self.prev_phi2 == PinValue::High;
 
         // Update clock states for next edge detection
         self.prev_phi1 = phi1;
         self.prev_phi2 = phi2;
 
         if phi1_rising {
-            // Reduced debug output - only show occasional cycles
-            static mut CYCLE_COUNT: u32 = 0;
-            unsafe {
-                CYCLE_COUNT += 1;
-                if CYCLE_COUNT % 1000 == 0 {
-                    let (sync, cm, _) = self.read_control_pins();
-                    println!("DEBUG: [CPU] Φ1 Rising Edge - PC: 0x{:03X}, ACC: 0x{:X}, Phase: {:?}, SYNC: {}, CM: {} (cycle {})",
-                             self.program_counter.value(), self.accumulator, self.instruction_phase, sync, cm, CYCLE_COUNT);
-                }
-            }
+            // Φ1 Rising Edge: Next subcycle, CPU drives address nibbles during A1-A3
             self.handle_phi1_rising();
         }
 
-        if phi1_falling {
-            // Φ1 Falling Edge: End of address phase
-            // Reduced debug output - only show occasional cycles
-            static mut CYCLE_COUNT: u32 = 0;
-            unsafe {
-                CYCLE_COUNT += 1;
-                if CYCLE_COUNT % 1000 == 0 {
-                    println!("DEBUG: [CPU] Φ1 Falling Edge (cycle {})", CYCLE_COUNT);
-                }
-            }
-            self.handle_phi1_falling();
-        }
-
         if phi2_rising {
-            // Φ2 Rising Edge: Data phase (peripherals drive bus) - handle data operations
-            // Reduced debug output - only show occasional cycles
-            static mut CYCLE_COUNT: u32 = 0;
-            unsafe {
-                CYCLE_COUNT += 1;
-                if CYCLE_COUNT % 1000 == 0 {
-                    println!(
-                        "DEBUG: [CPU] Φ2 Rising Edge - Memory State: {:?} (cycle {})",
-                        self.memory_state, CYCLE_COUNT
-                    );
-                }
-            }
+            // Φ2 Rising Edge: CPU samples the opcode during M1-M2 and executes at X3
             self.handle_phi2_rising();
         }
 
-        if phi2_falling {
-            // Φ2 Falling Edge: End of data phase - tri-state bus and return to idle
-            // Reduced debug output - only show occasional cycles
-            static mut CYCLE_COUNT: u32 = 0;
-            unsafe {
-                CYCLE_COUNT += 1;
-                if CYCLE_COUNT % 1000 == 0 {
-                    println!("DEBUG: [CPU] Φ2 Falling Edge (cycle {})", CYCLE_COUNT);
-                }
-            }
-            self.handle_phi2_falling();
-        }
+        self.cycle_count += 1;
+    }
 
-        // Handle instruction execution during appropriate phases
-        // Set SYNC signal during fetch phase to initiate memory read
-        if self.instruction_phase == InstructionPhase::Fetch {
-            self.set_sync(true);
-            self.set_cm(true);
-            println!("DEBUG: [CPU] Setting signals - SYNC: HIGH, CM: HIGH");
-        } else {
-            self.set_sync(false);
-            println!("DEBUG: [CPU] Setting SYNC: LOW");
-        }
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        component::state_to_json(&self.snapshot())
+    }
 
-        println!(
-            "DEBUG: [CPU] Instruction phase: {:?}, PC: 0x{:03X}, State: {:?}",
-            self.instruction_phase,
-            self.program_counter.value(),
-            self.memory_state
-        );
-        match self.instruction_phase {
-            InstructionPhase::Fetch => {
-                // Check if we're waiting for an operand (two-instruction format)
-                if let Instruction::JunHigh(_) | Instruction::JmsHigh(_) = self.current_op {
-                    // Waiting for operand - fetch it
-                    let (sync, cm, _) = self.read_control_pins();
-                    if sync && cm {
-                        if self.memory_state == MemoryState::DriveData {
-                            let operand = self.read_data_bus();
-                            self.program_counter.inc(); // Advance PC after fetching operand
-
-                            // Complete the two-instruction format
-                            match self.current_op {
-                                Instruction::JunHigh(addr_high) => {
-                                    let addr = ((addr_high as u16) << 4) | (operand as u16);
-                                    self.current_op = Instruction::Jun(addr);
-                                    println!("DEBUG: [CPU] Fetched JUN operand 0x{:02X} -> complete address 0x{:03X}",
-                                             operand, addr);
-                                }
-                                Instruction::JmsHigh(addr_high) => {
-                                    let addr = ((addr_high as u16) << 4) | (operand as u16);
-                                    self.current_op = Instruction::Jms(addr);
-                                    println!("DEBUG: [CPU] Fetched JMS operand 0x{:02X} -> complete address 0x{:03X}",
-                                             operand, addr);
-                                }
-                                _ => {}
-                            }
-
-                            self.instruction_phase = InstructionPhase::Execute;
-                        }
-                    }
-                } else {
-                    // Normal instruction fetch
-                    let (sync, cm, _) = self.read_control_pins();
-                    println!(
-                        "DEBUG: [CPU] Normal fetch - SYNC: {}, CM: {}, State: {:?}",
-                        sync, cm, self.memory_state
-                    );
-                    if sync && cm {
-                        // Memory access - fetch instruction
-                        println!("DEBUG: [CPU] Fetching instruction from memory - PC: 0x{:03X}, State: {:?}", self.program_counter.value(), self.memory_state);
-                        if self.memory_state == MemoryState::DriveData {
-                            let instruction = self.read_data_bus();
-                            self.current_instruction = instruction;
-                            let decoded_op = self.decode_instruction(instruction);
-
-                            // Check if this is a two-instruction format that needs an operand
-                            match decoded_op {
-                                Instruction::JunHigh(_) | Instruction::JmsHigh(_) => {
-                                    // Two-instruction format - wait for operand
-                                    self.current_op = decoded_op;
-                                    // Don't advance PC yet - wait for operand
-                                    println!("DEBUG: [CPU] Fetched two-instruction opcode 0x{:02X} from PC 0x{:03X}",
-                                             instruction, self.program_counter.value());
-                                }
-                                _ => {
-                                    // Single instruction - execute immediately
-                                    self.current_op = decoded_op;
-                                    self.instruction_phase = InstructionPhase::Execute;
-                                    self.program_counter.inc();
-
-                                    println!("DEBUG: [CPU] Fetched single instruction 0x{:02X} from PC 0x{:03X} | ACC: 0x{:X}",
-                                             instruction, self.program_counter.value(), self.accumulator);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        let state = component::state_from_json(&self.base.name(), state)?;
+        self.restore(&state)
+    }
 
-            InstructionPhase::Execute => {
-                // Check if we need to fetch an operand for two-instruction format
-                match self.current_op {
-                    Instruction::JunHigh(_) | Instruction::JmsHigh(_) => {
-                        // Need to fetch operand - stay in execute phase
-                        // The operand will be fetched in the next cycle
-                    }
-                    _ => {
-                        // Execute the current instruction
-                        let old_pc = self.program_counter.value();
-                        self.execute_instruction();
-                        let new_pc = self.program_counter.value();
-
-                        // Debug: Show instruction execution details (reduced frequency)
-                        static mut EXEC_COUNT: u32 = 0;
-                        unsafe {
-                            EXEC_COUNT += 1;
-                            if EXEC_COUNT % 10 == 0 {
-                                println!("DEBUG: [CPU] Executed {:?} | PC: 0x{:03X} -> 0x{:03X} | ACC: 0x{:X} | RAM_Ready: {}",
-                                         self.current_op, old_pc, new_pc, self.accumulator, self.full_address_ready);
-                            }
-                        }
-
-                        self.instruction_phase = InstructionPhase::Fetch;
-                    }
-                }
-            }
-        }
+    /// Mark the CPU running for an external scheduler and reset it to a known state
+    fn start(&mut self) {
+        self.base.set_running(true);
+        self.reset();
 
-        self.cycle_count += 1;
+        // Sync clock edge state so the CPU counts subcycles in step with the memory chips
+        let (phi1, phi2) = self.read_clock_pins();
+        self.prev_phi1 = phi1;
+        self.prev_phi2 = phi2;
     }
 
     /// Run the CPU in a continuous loop until stopped
-    /// Provides a time-sliced execution model with 10 microsecond delays between cycles
+    /// Pin-level mode is time-sliced with 1 microsecond delays; functional mode runs unthrottled
     fn run(&mut self) {
-        // Time-slice model: run in a loop calling update() each cycle
-        self.base.set_running(true);
-        self.reset();
+        self.start();
 
         while self.is_running() {
             self.update();
-            thread::sleep(Duration::from_micros(1));
+            if self.execution_mode == ExecutionMode::PinLevel {
+                thread::sleep(Duration::from_micros(1));
+            }
         }
     }
 
@@ -1606,12 +2675,20 @@ impl Component for Intel4004 {
         self.base.set_running(false);
         self.tri_state_data_bus();
         self.set_sync(false);
-        self.set_cm(false);
+        self.set_cm_lines(false, false);
     }
 
     fn is_running(&self) -> bool {
         self.base.is_running()
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
 }
 
 impl RunnableComponent for Intel4004 {
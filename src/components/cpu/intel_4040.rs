@@ -0,0 +1,383 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+use crate::component::{Component, RunnableComponent};
+use crate::components::cpu::intel_4004::{CpuVariant, Intel4004};
+use crate::pin::Pin;
+
+/// Intel 4040 4-bit microprocessor implementation
+/// Successor of the 4004 that runs 4004 programs unchanged. The shared core lives in
+/// `Intel4004`; this component selects its 4040 variant and derefs to it, so every
+/// 4004 accessor works on a 4040 as well.
+///
+/// Hardware Architecture:
+/// - 24 index registers: SB0/SB1 switch R0-R7 between two banks, R8-R15 are shared
+/// - 7-level address stack
+/// - Two ROM banks, CM_ROM and CM_ROM1, selected by DB0/DB1
+/// - INT input with INTA acknowledge: an interrupt enabled by EIN saves the SRC
+///   address and calls 0x003; BBS returns and resends the saved SRC
+/// - HLT halts until an interrupt or STP; STP stops at the next instruction boundary
+///   and is acknowledged on STPA
+/// - Additional instructions LCR, OR4/OR5, AN6/AN7, DIN and RPM
+///
+/// Hardware Deviations:
+/// - The interrupt is taken between instructions instead of replacing a fetched one
+/// - Functional mode fetches from the attached ROMs whatever ROM bank is selected
+/// - Power-down operation (VDD2) is not modeled
+pub struct Intel4040 {
+    cpu: Intel4004, // 4004 core configured as a 4040
+}
+
+impl Intel4040 {
+    /// Create a new Intel 4040 CPU instance
+    /// Parameters: name - Component identifier, clock_speed - Target clock frequency in Hz
+    /// Returns: New Intel4040 instance with initialized state
+    pub fn new(name: String, clock_speed: f64) -> Self {
+        Intel4040 {
+            cpu: Intel4004::new_with_variant(name, clock_speed, CpuVariant::Intel4040),
+        }
+    }
+}
+
+impl Deref for Intel4040 {
+    type Target = Intel4004;
+
+    fn deref(&self) -> &Intel4004 {
+        &self.cpu
+    }
+}
+
+impl DerefMut for Intel4040 {
+    fn deref_mut(&mut self) -> &mut Intel4004 {
+        &mut self.cpu
+    }
+}
+
+/// Get the MCS-4 CPU core of a component
+/// Parameters: component - Component to inspect
+/// Returns: Some(&Intel4004) for an Intel 4004 or the core of an Intel 4040, None otherwise
+pub fn mcs4_cpu(component: &dyn Component) -> Option<&Intel4004> {
+    let any = component.as_any();
+    any.downcast_ref::<Intel4004>()
+        .or_else(|| any.downcast_ref::<Intel4040>().map(|cpu| &cpu.cpu))
+}
+
+/// Get the MCS-4 CPU core of a component for modification
+/// Parameters: component - Component to inspect
+/// Returns: Some(&mut Intel4004) for an Intel 4004 or the core of an Intel 4040, None otherwise
+pub fn mcs4_cpu_mut(component: &mut dyn Component) -> Option<&mut Intel4004> {
+    if component.as_any().is::<Intel4004>() {
+        return component.as_any_mut().downcast_mut::<Intel4004>();
+    }
+    component
+        .as_any_mut()
+        .downcast_mut::<Intel4040>()
+        .map(|cpu| &mut cpu.cpu)
+}
+
+impl Component for Intel4040 {
+    fn name(&self) -> String {
+        self.cpu.name()
+    }
+
+    fn pins(&self) -> HashMap<String, Arc<Mutex<Pin>>> {
+        self.cpu.pins()
+    }
+
+    fn get_pin(&self, name: &str) -> Result<Arc<Mutex<Pin>>, String> {
+        self.cpu.get_pin(name)
+    }
+
+    fn update(&mut self) {
+        self.cpu.update();
+    }
+
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        self.cpu.snapshot_state()
+    }
+
+    fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        self.cpu.restore_state(state)
+    }
+
+    fn start(&mut self) {
+        self.cpu.start();
+    }
+
+    fn run(&mut self) {
+        self.cpu.run();
+    }
+
+    fn stop(&mut self) {
+        self.cpu.stop();
+    }
+
+    fn is_running(&self) -> bool {
+        self.cpu.is_running()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl RunnableComponent for Intel4040 {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::cpu::intel_4004::{ExecutionMode, ProgramMemoryChip};
+    use crate::components::interface::intel_4008::Intel4008;
+    use crate::components::interface::intel_4009::Intel4009;
+    use crate::components::memory::generic_ram::GenericRam;
+    use crate::components::memory::intel_4001::Intel4001;
+    use crate::pin::PinValue;
+
+    fn shared(component: impl Component + 'static) -> Arc<Mutex<Box<dyn Component>>> {
+        Arc::new(Mutex::new(Box::new(component)))
+    }
+
+    fn rom_with_program(program: &[u8]) -> Arc<Mutex<Box<dyn Component>>> {
+        let mut rom = Intel4001::new("TEST_ROM".to_string());
+        rom.load_rom_data(program.to_vec(), 0).unwrap();
+        shared(rom)
+    }
+
+    fn functional_cpu_with_memory(chips: Vec<ProgramMemoryChip>) -> Intel4040 {
+        let mut cpu = Intel4040::new("TEST_CPU".to_string(), 740000.0);
+        cpu.attach_program_memory(chips);
+        cpu.set_execution_mode(ExecutionMode::Functional);
+        cpu.start();
+        cpu
+    }
+
+    fn functional_cpu(program: &[u8]) -> Intel4040 {
+        functional_cpu_with_memory(vec![(0, rom_with_program(program))])
+    }
+
+    fn drive_pin(cpu: &Intel4040, pin: &str, value: PinValue) {
+        cpu.get_pin(pin)
+            .unwrap()
+            .lock()
+            .unwrap()
+            .set_driver(Some("TEST".to_string()), value);
+    }
+
+    fn pin_is_high(cpu: &Intel4040, pin: &str) -> bool {
+        cpu.get_pin(pin).unwrap().lock().unwrap().read() == PinValue::High
+    }
+
+    #[test]
+    fn test_4040_pins_and_variant() {
+        let cpu = Intel4040::new("CPU_4040".to_string(), 740000.0);
+        assert_eq!(cpu.get_variant(), CpuVariant::Intel4040);
+        for pin in ["CM_ROM", "CM_ROM1", "INT", "STP", "STPA", "INTA"] {
+            assert!(cpu.get_pin(pin).is_ok(), "missing pin {}", pin);
+        }
+        assert!(Intel4004::new("CPU_4004".to_string(), 750000.0)
+            .get_pin("INT")
+            .is_err());
+        assert!(mcs4_cpu(&cpu).is_some());
+    }
+
+    #[test]
+    fn test_4040_register_banks() {
+        let mut cpu = functional_cpu(&[
+            0xD5, // LDM 5
+            0xB0, // XCH R0 (bank 0)
+            0xDA, // LDM A
+            0xB8, // XCH R8 (shared)
+            0x0B, // SB1
+            0xD7, // LDM 7
+            0xB0, // XCH R0 (bank 1)
+            0xA8, // LD R8
+            0x0A, // SB0
+        ]);
+        for _ in 0..8 {
+            cpu.step_functional().unwrap();
+        }
+        assert_eq!(cpu.get_register_bank(), 1);
+        assert_eq!(cpu.get_register(0), Some(0x7));
+        assert_eq!(cpu.get_accumulator(), 0xA);
+
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_register_bank(), 0);
+        assert_eq!(cpu.get_register(0), Some(0x5));
+        assert_eq!(cpu.get_register(8), Some(0xA));
+    }
+
+    #[test]
+    fn test_4040_seven_level_stack() {
+        // Seven nested calls, each routine at 0xN0 calls 0x(N+1)0 and returns from 0xN2
+        let mut program = vec![0u8; 0x80];
+        for level in 0..7 {
+            let routine = level * 0x10;
+            program[routine..routine + 2].copy_from_slice(&[0x50, (routine + 0x10) as u8]);
+            program[routine + 2] = 0xC0; // BBL 0
+        }
+        program[0x70] = 0xC0; // BBL 0
+        let mut cpu = functional_cpu(&program);
+
+        for _ in 0..7 {
+            cpu.step_functional().unwrap();
+        }
+        let stack = cpu.get_stack();
+        assert_eq!(stack.len(), 7);
+        assert_eq!(stack[0], 0x062);
+        assert_eq!(stack[6], 0x002);
+
+        for _ in 0..7 {
+            cpu.step_functional().unwrap();
+        }
+        assert_eq!(cpu.get_program_counter(), 0x002);
+    }
+
+    #[test]
+    fn test_4040_logic_and_command_register() {
+        let mut cpu = functional_cpu(&[
+            0x24, 0x9C, // FIM P2, 9C: R4 = 9, R5 = C
+            0x26, 0x6A, // FIM P3, 6A: R6 = 6, R7 = A
+            0xD1, // LDM 1
+            0x04, // OR4: 1 | 9 = 9
+            0x05, // OR5: 9 | C = D
+            0x07, // AN7: D & A = 8
+            0x06, // AN6: 8 & 6 = 0
+            0xD6, // LDM 6
+            0xFD, // DCL
+            0xD0, // LDM 0
+            0x03, // LCR
+        ]);
+        for _ in 0..4 {
+            cpu.step_functional().unwrap();
+        }
+        assert_eq!(cpu.get_accumulator(), 0x9);
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_accumulator(), 0xD);
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_accumulator(), 0x8);
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_accumulator(), 0x0);
+
+        for _ in 0..4 {
+            cpu.step_functional().unwrap();
+        }
+        assert_eq!(cpu.get_ram_bank(), 6);
+        assert_eq!(cpu.get_accumulator(), 6);
+    }
+
+    #[test]
+    fn test_4040_interrupt_and_bbs() {
+        let mut program = vec![0u8; 0x20];
+        program[0x00] = 0x0C; // EIN
+        program[0x01] = 0x23; // SRC P1
+        program[0x02] = 0x01; // HLT
+        program[0x03] = 0xD9; // 003: LDM 9 (interrupt routine)
+        program[0x04] = 0x02; // BBS
+        let mut cpu = functional_cpu(&program);
+        cpu.set_register(2, 0x4).unwrap();
+        cpu.set_register(3, 0x1).unwrap();
+
+        for _ in 0..3 {
+            cpu.step_functional().unwrap();
+        }
+        assert!(cpu.is_interrupt_enabled());
+        assert!(cpu.is_halted());
+
+        // Halted, the CPU fetches nothing
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_instruction_count(), 3);
+        assert_eq!(cpu.get_program_counter(), 0x003);
+
+        // INT ends the halt and calls 0x003
+        drive_pin(&cpu, "INT", PinValue::High);
+        cpu.step_functional().unwrap();
+        assert!(!cpu.is_halted());
+        assert!(cpu.is_interrupt_active());
+        assert!(pin_is_high(&cpu, "INTA"));
+        assert_eq!(cpu.get_accumulator(), 0x9);
+        assert_eq!(cpu.get_stack()[0], 0x003);
+
+        // Still held, INT is not taken again inside the routine
+        cpu.step_functional().unwrap();
+        assert!(!cpu.is_interrupt_active());
+        assert!(!pin_is_high(&cpu, "INTA"));
+        assert_eq!(cpu.get_program_counter(), 0x003);
+    }
+
+    #[test]
+    fn test_4040_stop_pin() {
+        let mut cpu = functional_cpu(&[0xF2, 0xF2, 0xF2]); // IAC x3
+
+        drive_pin(&cpu, "STP", PinValue::High);
+        cpu.step_functional().unwrap();
+        assert!(cpu.is_stopped());
+        assert!(pin_is_high(&cpu, "STPA"));
+        assert_eq!(cpu.get_accumulator(), 0);
+
+        drive_pin(&cpu, "STP", PinValue::Low);
+        cpu.step_functional().unwrap();
+        assert!(!cpu.is_stopped());
+        assert!(!pin_is_high(&cpu, "STPA"));
+        assert_eq!(cpu.get_accumulator(), 1);
+    }
+
+    #[test]
+    fn test_4040_rom_bank_selection() {
+        let mut cpu = functional_cpu_with_memory(vec![
+            (0, rom_with_program(&[0x09, 0xD3, 0xD3, 0xD3])), // DB1, LDM 3...
+            (1, rom_with_program(&[0x00, 0xD7, 0x08, 0xD9])), // NOP, LDM 7, DB0, LDM 9
+        ]);
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_rom_bank(), 1);
+
+        // Fetches follow the bank selected by DB0/DB1
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_accumulator(), 0x7);
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_rom_bank(), 0);
+        cpu.step_functional().unwrap();
+        assert_eq!(cpu.get_accumulator(), 0x3);
+    }
+
+    #[test]
+    fn test_4040_rpm_reads_back_wpm() {
+        let mut ram = GenericRam::new("PROGRAM_RAM".to_string(), 4096).unwrap();
+        ram.load_data(
+            &[
+                0x20, 0x80, // FIM P0, 80: program RAM address 0x80
+                0x21, // SRC P0
+                0xDA, // LDM A
+                0xE3, // WPM: high nibble
+                0xD5, // LDM 5
+                0xE3, // WPM: low nibble, writes the byte
+                0xD0, // LDM 0
+                0x0E, // RPM: high nibble
+                0xB2, // XCH R2
+                0x0E, // RPM: low nibble
+            ],
+            0,
+        )
+        .unwrap();
+        let ram = shared(ram);
+        let mut cpu = functional_cpu_with_memory(vec![
+            (0, shared(Intel4008::new("ADDR_4008".to_string()))),
+            (0, shared(Intel4009::new("DATA_4009".to_string()))),
+            (0, Arc::clone(&ram)),
+        ]);
+        for _ in 0..10 {
+            cpu.step_functional().unwrap();
+        }
+
+        let ram = ram.lock().unwrap();
+        let ram = ram.as_any().downcast_ref::<GenericRam>().unwrap();
+        assert_eq!(ram.read_byte(0x080), Some(0xA5));
+        assert_eq!(cpu.get_register(2), Some(0xA));
+        assert_eq!(cpu.get_accumulator(), 0x5);
+    }
+}
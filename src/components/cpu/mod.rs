@@ -1,9 +1,11 @@
 // CPU components module
 pub mod intel_4004;
+pub mod intel_4040;
 pub mod mos_6502;
 pub mod wdc_65c02;
 
 // Re-export the CPU types
 pub use intel_4004::Intel4004;
+pub use intel_4040::Intel4040;
 pub use mos_6502::MOS6502;
 pub use wdc_65c02::WDC65C02;
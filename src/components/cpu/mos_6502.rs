@@ -1,14 +1,263 @@
+use std::any::Any;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
-use crate::component::{BaseComponent, Component};
+use crate::component::{self, BaseComponent, Component};
 use crate::pin::{Pin, PinValue};
+use serde::{Deserialize, Serialize};
+
+// Processor status register bits
+pub const FLAG_CARRY: u8 = 0x01;
+pub const FLAG_ZERO: u8 = 0x02;
+pub const FLAG_INTERRUPT: u8 = 0x04;
+pub const FLAG_DECIMAL: u8 = 0x08;
+pub const FLAG_BREAK: u8 = 0x10;
+pub const FLAG_UNUSED: u8 = 0x20;
+pub const FLAG_OVERFLOW: u8 = 0x40;
+pub const FLAG_NEGATIVE: u8 = 0x80;
+
+/// Start address of Klaus Dormann's 6502 functional test
+pub const FUNCTIONAL_TEST_START: u16 = 0x0400;
+/// Address the standard build of the functional test loops on once every test passed
+pub const FUNCTIONAL_TEST_SUCCESS: u16 = 0x3469;
+
+/// Memory seen by the 6502 when it runs without its pins
+/// Used by test harnesses that step the core directly against a flat memory.
+pub trait Bus6502 {
+    /// Read a byte
+    /// Parameters: address - 16-bit bus address
+    /// Returns: Byte at the address
+    fn read(&mut self, address: u16) -> u8;
+
+    /// Write a byte
+    /// Parameters: address - 16-bit bus address, data - Byte to store
+    fn write(&mut self, address: u16, data: u8);
+}
+
+impl Bus6502 for Vec<u8> {
+    fn read(&mut self, address: u16) -> u8 {
+        self[address as usize % self.len()]
+    }
+
+    fn write(&mut self, address: u16, data: u8) {
+        let len = self.len();
+        self[address as usize % len] = data;
+    }
+}
+
+/// One bus cycle of the 6502
+/// Every clock cycle is exactly one read or one write, including the dummy accesses
+/// the NMOS part makes while it calculates addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BusAccess {
+    Read(u16),
+    Write(u16, u8),
+}
+
+/// Processor variants sharing the 6502 core
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mos6502Variant {
+    Nmos6502,        // Original NMOS 6502
+    Nmos6502Illegal, // NMOS 6502 that also executes the undocumented opcodes
+    Wdc65C02,        // WDC 65C02 CMOS version with the added instructions
+}
+
+/// Addressing modes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Mode {
+    Implied,
+    Accumulator,
+    Immediate,
+    ZeroPage,
+    ZeroPageX,
+    ZeroPageY,
+    Absolute,
+    AbsoluteX,
+    AbsoluteY,
+    IndirectX,
+    IndirectY,
+    Relative,
+    Indirect,
+    ZeroPageIndirect,        // (zp), 65C02
+    AbsoluteIndexedIndirect, // (abs,X) of JMP, 65C02
+    ZeroPageRelative,        // zp,rel of BBR/BBS, 65C02
+}
+
+/// Instruction mnemonics, the undocumented NMOS opcodes last
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Operation {
+    Adc,
+    And,
+    Asl,
+    Bbr,
+    Bbs,
+    Bcc,
+    Bcs,
+    Beq,
+    Bit,
+    Bmi,
+    Bne,
+    Bpl,
+    Bra,
+    Brk,
+    Bvc,
+    Bvs,
+    Clc,
+    Cld,
+    Cli,
+    Clv,
+    Cmp,
+    Cpx,
+    Cpy,
+    Dec,
+    Dex,
+    Dey,
+    Eor,
+    Inc,
+    Inx,
+    Iny,
+    Jmp,
+    Jsr,
+    Lda,
+    Ldx,
+    Ldy,
+    Lsr,
+    Nop,
+    Ora,
+    Pha,
+    Php,
+    Phx,
+    Phy,
+    Pla,
+    Plp,
+    Plx,
+    Ply,
+    Rmb,
+    Rol,
+    Ror,
+    Rti,
+    Rts,
+    Sbc,
+    Sec,
+    Sed,
+    Sei,
+    Smb,
+    Sta,
+    Stp,
+    Stx,
+    Sty,
+    Stz,
+    Tax,
+    Tay,
+    Trb,
+    Tsb,
+    Tsx,
+    Txa,
+    Txs,
+    Tya,
+    Wai,
+    // Undocumented NMOS opcodes
+    Alr,
+    Anc,
+    Ane,
+    Arr,
+    Dcp,
+    Isc,
+    Jam,
+    Las,
+    Lax,
+    Lxa,
+    Rla,
+    Rra,
+    Sax,
+    Sbx,
+    Sha,
+    Shx,
+    Shy,
+    Slo,
+    Sre,
+    Tas,
+}
+
+/// How an instruction with a memory operand uses the effective address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AccessKind {
+    Read,
+    Write,
+    Modify,
+}
+
+/// Cycle sequence the CPU is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Sequence {
+    Reset,       // RES: stack cycles are reads, vector $FFFC
+    Interrupt,   // IRQ or NMI: pushes PC and P, vector $FFFE or $FFFA
+    Instruction, // Opcode fetch and execution
+}
+
+/// Snapshot of the CPU registers and cycle sequencing state
+/// The variant is configuration and not included
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mos6502State {
+    accumulator: u8,
+    x_register: u8,
+    y_register: u8,
+    stack_pointer: u8,
+    program_counter: u16,
+    status_register: u8,
+    sequence: Sequence,
+    opcode: u8,
+    operation: Operation,
+    mode: Mode,
+    cycle: u8,
+    address: u16,
+    pointer: u8,
+    operand: u8,
+    address_ready_at: u8,
+    base_high: u8,
+    pending: BusAccess,
+    irq_line: bool,
+    nmi_line: bool,
+    nmi_pending: bool,
+    waiting: bool,
+    stopped: bool,
+    cycle_count: u64,
+    instruction_count: u64,
+    last_clock: PinValue,
+    is_reset: bool,
+}
 
 /// MOS Technology 6502 CPU - 8-bit microprocessor
+/// Cycle-stepped core: each clock cycle performs the one bus access the real part makes
+/// in that cycle, so cycle counts, dummy reads and read-modify-write double writes
+/// match the hardware.
+///
+/// Hardware Architecture:
+/// - A0-A15 address bus, D0-D7 bidirectional data bus, RW high for read
+/// - CLK (phase 0) input: a bus cycle completes on each falling edge, where the CPU
+///   latches D0-D7 for a read and presents the address of the next cycle
+/// - SYNC high while the opcode of an instruction is fetched
+/// - RES restarts the CPU with the 7-cycle reset sequence through the vector at $FFFC
+/// - IRQ (level, masked by I) and NMI (falling edge) push PC and P and continue through
+///   the vectors at $FFFE and $FFFA; an NMI during BRK or IRQ takes over its vector
+/// - RDY low inserts wait states: the NMOS part only stops in read cycles, the 65C02 in
+///   any cycle
+/// - All 151 documented opcodes, binary and decimal (BCD) arithmetic with NMOS flags
+/// - The Wdc65C02 variant adds the CMOS instructions and timing, see `WDC65C02`
+/// - The Nmos6502Illegal variant executes the undocumented opcodes (LAX, SAX, DCP, ISC,
+///   SLO, RLA, SRE, RRA, ANC, ALR, ARR, SBX, ...) with their real cycle counts; JAM
+///   locks the CPU until reset
+///
+/// Hardware Deviations:
+/// - Without the Nmos6502Illegal variant, undocumented opcodes execute as 2-cycle NOPs
+/// - ANE and LXA use 0xEE for the chip-dependent bits ORed into A
+/// - A taken branch that stays in its page does not delay a pending interrupt
+/// - The 65C02 leaves WAI without the extra cycle of the real part
 pub struct MOS6502 {
     base: BaseComponent,
+    variant: Mos6502Variant,
     // Registers
     accumulator: u8,
     x_register: u8,
@@ -17,14 +266,43 @@ pub struct MOS6502 {
     program_counter: u16,
     status_register: u8,
 
+    // Instruction sequencing
+    sequence: Sequence,   // Reset vector fetch or instruction execution
+    opcode: u8,           // Opcode of the current instruction
+    operation: Operation, // Decoded mnemonic
+    mode: Mode,           // Decoded addressing mode
+    cycle: u8,            // Cycles of the current instruction completed so far
+    address: u16,         // Effective address (or vector/target) being built
+    pointer: u8,          // Zero page pointer of indexed and indirect modes
+    operand: u8,          // Latched data byte
+    address_ready_at: u8, // Cycle in which the effective address was complete, 0 while not
+    base_high: u8,        // High byte of the unindexed base address
+    pending: BusAccess,   // Access presented on the bus for the current cycle
+
+    // Interrupt lines and low-power states
+    irq_line: bool,    // IRQ asserted
+    nmi_line: bool,    // NMI asserted
+    nmi_pending: bool, // NMI edge seen and not serviced yet
+    waiting: bool,     // 65C02 WAI: suspended until IRQ or NMI
+    stopped: bool,     // 65C02 STP: suspended until reset
+
     // Internal state
     cycle_count: u64,
+    instruction_count: u64,
+    last_clock: PinValue, // CLK level at the previous update, for edge detection
     is_reset: bool,
     is_running: bool,
 }
 
 impl MOS6502 {
     pub fn new(name: String) -> Self {
+        Self::new_with_variant(name, Mos6502Variant::Nmos6502)
+    }
+
+    /// Create a CPU of a given variant
+    /// Parameters: name - Component identifier, variant - NMOS 6502 or WDC 65C02
+    /// Returns: New MOS6502 instance waiting for reset
+    pub fn new_with_variant(name: String, variant: Mos6502Variant) -> Self {
         let pin_names = vec![
             "A0", "A1", "A2", "A3", "A4", "A5", "A6", "A7", "A8", "A9", "A10", "A11", "A12", "A13",
             "A14", "A15", // 16 address lines
@@ -42,31 +320,56 @@ impl MOS6502 {
 
         MOS6502 {
             base: BaseComponent::new(name, pins),
+            variant,
             accumulator: 0,
             x_register: 0,
             y_register: 0,
             stack_pointer: 0xFD,
             program_counter: 0xFFFC, // Reset vector location
             status_register: 0x20,   // Always set bit 5
+            sequence: Sequence::Reset,
+            opcode: 0,
+            operation: Operation::Nop,
+            mode: Mode::Implied,
+            cycle: 0,
+            address: 0,
+            pointer: 0,
+            operand: 0,
+            address_ready_at: 0,
+            base_high: 0,
+            pending: BusAccess::Read(0xFFFC),
+            irq_line: false,
+            nmi_line: false,
+            nmi_pending: false,
+            waiting: false,
+            stopped: false,
             cycle_count: 0,
+            instruction_count: 0,
+            last_clock: PinValue::Low,
             is_reset: false,
             is_running: false,
         }
     }
 
+    /// Reset the CPU
+    /// The next 7 cycles run the reset sequence: two dummy reads, three stack cycles that
+    /// only move S from $00 to $FD, and the vector fetch from $FFFC/$FFFD.
     pub fn reset(&mut self) {
         self.accumulator = 0;
         self.x_register = 0;
         self.y_register = 0;
-        self.stack_pointer = 0xFD;
+        self.stack_pointer = 0x00;
         self.program_counter = 0xFFFC;
-        self.status_register = 0x20;
+        self.status_register = FLAG_UNUSED | FLAG_INTERRUPT;
         self.is_reset = true;
+        self.nmi_pending = false;
+        self.waiting = false;
+        self.stopped = false;
 
-        // Set initial pin states
-        self.set_address_bus(0xFFFC);
-        self.set_data_bus(0xFF);
-        self.set_rw_pin(true); // Start in read mode
+        self.sequence = Sequence::Reset;
+        self.cycle = 0;
+        self.pending = BusAccess::Read(0xFFFC);
+        self.drive_bus();
     }
 
     fn set_address_bus(&self, address: u16) {
@@ -85,16 +388,14 @@ impl MOS6502 {
         }
     }
 
-    fn set_data_bus(&self, data: u8) {
+    /// Drive the data lines, or release them with None
+    /// Parameters: data - Byte to drive, None to tri-state D0-D7
+    fn set_data_bus(&self, data: Option<u8>) {
         for i in 0..8 {
             if let Ok(pin) = self.base.get_pin(&format!("D{}", i)) {
                 if let Ok(mut pin_guard) = pin.lock() {
-                    let bit_value = (data >> i) & 1;
-                    let pin_value = if bit_value == 1 {
-                        PinValue::High
-                    } else {
-                        PinValue::Low
-                    };
+                    let pin_value =
+                        data.map_or(PinValue::HighZ, |d| PinValue::from_bool((d >> i) & 1 == 1));
                     pin_guard.set_driver(Some(self.base.get_name().parse().unwrap()), pin_value);
                 }
             }
@@ -110,7 +411,18 @@ impl MOS6502 {
         }
     }
 
-    fn _read_data_bus(&self) -> u8 {
+    fn set_sync_pin(&self, sync: bool) {
+        if let Ok(pin) = self.base.get_pin("SYNC") {
+            if let Ok(mut pin_guard) = pin.lock() {
+                pin_guard.set_driver(
+                    Some(self.base.get_name().parse().unwrap()),
+                    PinValue::from_bool(sync),
+                );
+            }
+        }
+    }
+
+    fn read_data_bus(&self) -> u8 {
         let mut data = 0;
 
         for i in 0..8 {
@@ -126,6 +438,14 @@ impl MOS6502 {
         data
     }
 
+    fn read_clock_pin(&self) -> PinValue {
+        self.base
+            .get_pin("CLK")
+            .ok()
+            .and_then(|pin| pin.lock().ok().map(|guard| guard.read()))
+            .unwrap_or(PinValue::Low)
+    }
+
     pub(crate) fn read_control_pins(&self) -> (bool, bool, bool, bool) {
         let irq = if let Ok(pin) = self.base.get_pin("IRQ") {
             if let Ok(pin_guard) = pin.lock() {
@@ -159,7 +479,7 @@ impl MOS6502 {
 
         let rdy = if let Ok(pin) = self.base.get_pin("RDY") {
             if let Ok(pin_guard) = pin.lock() {
-                pin_guard.read() == PinValue::High // RDY is active high
+                pin_guard.read() != PinValue::Low // RDY is active high, pulled up when undriven
             } else {
                 true // Default to ready if pin doesn't exist
             }
@@ -170,136 +490,2166 @@ impl MOS6502 {
         (irq, nmi, reset, rdy)
     }
 
-    fn execute_instruction(&mut self) {
-        // Simplified instruction execution - just increment PC for compilation
-        self.program_counter = self.program_counter.wrapping_add(1);
+    /// Present the pending access on the pins
+    fn drive_bus(&self) {
+        match self.pending {
+            BusAccess::Read(address) => {
+                self.set_address_bus(address);
+                self.set_rw_pin(true);
+                self.set_data_bus(None);
+            }
+            BusAccess::Write(address, data) => {
+                self.set_address_bus(address);
+                self.set_rw_pin(false);
+                self.set_data_bus(Some(data));
+            }
+        }
+        // SYNC also marks the discarded opcode fetch that starts IRQ and NMI
+        self.set_sync_pin(self.cycle == 0 && self.sequence != Sequence::Reset);
+    }
+
+    // Cycle sequencing
+
+    /// Finish the pending bus cycle and set up the next one
+    /// Parameters: data - Byte read by the pending access (ignored for a write)
+    fn complete_cycle(&mut self, data: u8) {
+        // Interrupts are polled at the end of the second-to-last cycle, so a change of
+        // the I flag in the last cycle (CLI, SEI, PLP) only counts after the next instruction
+        let interrupt = self.interrupt_requested();
+
         self.cycle_count += 1;
+        self.cycle += 1;
 
-        // Minimal implementation to satisfy compilation
-        self.set_address_bus(self.program_counter);
-        self.set_rw_pin(true); // Always reading for now
+        let next = match self.sequence {
+            Sequence::Reset | Sequence::Interrupt => self.interrupt_cycle(data),
+            Sequence::Instruction => {
+                if self.cycle == 1 {
+                    self.decode_opcode(data);
+                }
+                self.execute_cycle(data)
+            }
+        };
+
+        self.pending = match next {
+            Some(access) => access,
+            None if self.sequence == Sequence::Instruction => {
+                self.instruction_count += 1;
+                if interrupt {
+                    self.begin_interrupt()
+                } else {
+                    self.begin_instruction()
+                }
+            }
+            None => self.begin_instruction(),
+        };
     }
-}
 
-impl Component for MOS6502 {
-    fn name(&self) -> String {
-        self.base.name()
+    /// Start fetching the next opcode
+    /// Returns: Opcode fetch access
+    fn begin_instruction(&mut self) -> BusAccess {
+        self.sequence = Sequence::Instruction;
+        self.cycle = 0;
+        self.address_ready_at = 0;
+        BusAccess::Read(self.program_counter)
     }
 
-    fn pins(&self) -> HashMap<String, Arc<Mutex<Pin>>> {
-        self.base.pins()
+    /// Check whether the next cycle fetches an opcode
+    /// Returns: true between instructions
+    pub fn is_at_instruction_boundary(&self) -> bool {
+        self.sequence == Sequence::Instruction && self.cycle == 0
     }
 
-    fn get_pin(&self, name: &str) -> Result<Arc<Mutex<Pin>>, String> {
-        self.base.get_pin(name)
+    /// Start the IRQ/NMI sequence in place of the next instruction
+    /// Returns: Discarded opcode fetch at PC
+    fn begin_interrupt(&mut self) -> BusAccess {
+        self.sequence = Sequence::Interrupt;
+        self.cycle = 0;
+        BusAccess::Read(self.program_counter)
     }
 
-    fn update(&mut self) {
-        if !self.is_running {
-            return;
+    /// Check whether an interrupt is to be taken at the next instruction boundary
+    /// Returns: true for a pending NMI, or IRQ asserted with I clear
+    fn interrupt_requested(&self) -> bool {
+        self.nmi_pending || (self.irq_line && self.status_register & FLAG_INTERRUPT == 0)
+    }
+
+    /// Choose the vector once the status is pushed
+    /// A pending NMI takes over the vector fetch of an IRQ, and of BRK on the NMOS part.
+    /// Returns: Address of the vector low byte
+    fn interrupt_vector(&mut self) -> u16 {
+        let brk = self.sequence == Sequence::Instruction;
+        if self.sequence == Sequence::Reset {
+            0xFFFC
+        } else if self.nmi_pending && !(brk && self.is_cmos()) {
+            self.nmi_pending = false;
+            0xFFFA
+        } else {
+            0xFFFE
         }
+    }
 
-        let (_irq, _nmi, reset, rdy) = self.read_control_pins();
+    /// Stack cycle of BRK, IRQ, NMI and RES
+    /// RES runs the pushes as reads, so it only moves the stack pointer.
+    /// Parameters: data - Byte to push
+    /// Returns: Stack access
+    fn interrupt_push(&mut self, data: u8) -> BusAccess {
+        if self.sequence == Sequence::Reset {
+            let address = self.stack_address();
+            self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+            BusAccess::Read(address)
+        } else {
+            self.push(data)
+        }
+    }
 
-        if reset && !self.is_reset {
-            self.reset();
-            return;
+    /// Cycle of BRK and of the RES, IRQ and NMI sequences, which share its 7 cycles
+    fn interrupt_cycle(&mut self, data: u8) -> Option<BusAccess> {
+        let brk = self.sequence == Sequence::Instruction;
+        match self.cycle {
+            // BRK skips a padding byte, so RTI returns to the instruction after it;
+            // the other sequences read the byte at PC without advancing
+            1 if brk => Some(self.fetch_operand()),
+            1 => Some(BusAccess::Read(self.program_counter)),
+            2 => Some(self.interrupt_push((self.program_counter >> 8) as u8)),
+            3 => Some(self.interrupt_push(self.program_counter as u8)),
+            4 => {
+                // B only exists in the status pushed by BRK and PHP
+                let status = if brk {
+                    self.status_register | FLAG_BREAK | FLAG_UNUSED
+                } else {
+                    (self.status_register | FLAG_UNUSED) & !FLAG_BREAK
+                };
+                self.address = self.interrupt_vector();
+                self.status_register |= FLAG_INTERRUPT;
+                if self.is_cmos() {
+                    self.status_register &= !FLAG_DECIMAL;
+                }
+                Some(self.interrupt_push(status))
+            }
+            5 => Some(BusAccess::Read(self.address)),
+            6 => {
+                self.operand = data;
+                Some(BusAccess::Read(self.address.wrapping_add(1)))
+            }
+            _ => {
+                self.program_counter = (data as u16) << 8 | self.operand as u16;
+                None
+            }
         }
+    }
 
-        self.is_reset = reset;
+    fn decode_opcode(&mut self, opcode: u8) {
+        self.opcode = opcode;
+        self.program_counter = self.program_counter.wrapping_add(1);
+        let (operation, mode) = self.decode(opcode);
+        self.operation = operation;
+        self.mode = mode;
+    }
 
-        if rdy {
-            self.execute_instruction();
+    /// Decode an opcode for the CPU variant
+    /// Parameters: opcode - Instruction byte
+    /// Returns: (operation, addressing mode)
+    fn decode(&self, opcode: u8) -> (Operation, Mode) {
+        match self.variant {
+            Mos6502Variant::Nmos6502 => Self::decode_nmos(opcode),
+            Mos6502Variant::Nmos6502Illegal => {
+                Self::decode_illegal(opcode).unwrap_or_else(|| Self::decode_nmos(opcode))
+            }
+            Mos6502Variant::Wdc65C02 => {
+                Self::decode_65c02(opcode).unwrap_or_else(|| Self::decode_nmos(opcode))
+            }
         }
-        // If RDY is low, the CPU waits
     }
 
-    fn run(&mut self) {
-        self.is_running = true;
-        self.reset();
+    /// Decode an opcode of the 65C02 that is new or differs from the NMOS part
+    /// Parameters: opcode - Instruction byte
+    /// Returns: Some((operation, addressing mode)), None if it decodes like the NMOS 6502
+    fn decode_65c02(opcode: u8) -> Option<(Operation, Mode)> {
+        use Mode::*;
+        use Operation::*;
 
-        while self.is_running {
-            self.update();
-            thread::sleep(Duration::from_micros(1));
+        let decoded = match opcode {
+            0x04 => (Tsb, ZeroPage),
+            0x0C => (Tsb, Absolute),
+            0x12 => (Ora, ZeroPageIndirect),
+            0x14 => (Trb, ZeroPage),
+            0x1A => (Inc, Accumulator),
+            0x1C => (Trb, Absolute),
+            0x32 => (And, ZeroPageIndirect),
+            0x34 => (Bit, ZeroPageX),
+            0x3A => (Dec, Accumulator),
+            0x3C => (Bit, AbsoluteX),
+            0x52 => (Eor, ZeroPageIndirect),
+            0x5A => (Phy, Implied),
+            0x64 => (Stz, ZeroPage),
+            0x72 => (Adc, ZeroPageIndirect),
+            0x74 => (Stz, ZeroPageX),
+            0x7A => (Ply, Implied),
+            0x7C => (Jmp, AbsoluteIndexedIndirect),
+            0x80 => (Bra, Relative),
+            0x89 => (Bit, Immediate),
+            0x92 => (Sta, ZeroPageIndirect),
+            0x9C => (Stz, Absolute),
+            0x9E => (Stz, AbsoluteX),
+            0xB2 => (Lda, ZeroPageIndirect),
+            0xCB => (Wai, Implied),
+            0xD2 => (Cmp, ZeroPageIndirect),
+            0xDA => (Phx, Implied),
+            0xDB => (Stp, Implied),
+            0xF2 => (Sbc, ZeroPageIndirect),
+            0xFA => (Plx, Implied),
+            // RMB0-7/SMB0-7 at x7, BBR0-7/BBS0-7 at xF; bits 4-6 select the bit
+            op if op & 0x0F == 0x07 => (if op & 0x80 == 0 { Rmb } else { Smb }, ZeroPage),
+            op if op & 0x0F == 0x0F => (if op & 0x80 == 0 { Bbr } else { Bbs }, ZeroPageRelative),
+            // Unused opcodes are NOPs that skip their operand bytes
+            0x02 | 0x22 | 0x42 | 0x62 | 0x82 | 0xC2 | 0xE2 => (Nop, Immediate),
+            0x44 => (Nop, ZeroPage),
+            0x54 | 0xD4 | 0xF4 => (Nop, ZeroPageX),
+            0x5C | 0xDC | 0xFC => (Nop, Absolute),
+            op if op & 0x03 == 0x03 => (Nop, Implied),
+            _ => return None,
+        };
+        Some(decoded)
+    }
+
+    /// Decode an undocumented opcode of the NMOS 6502
+    /// Parameters: opcode - Instruction byte
+    /// Returns: Some((operation, addressing mode)), None for documented opcodes
+    fn decode_illegal(opcode: u8) -> Option<(Operation, Mode)> {
+        use Mode::*;
+        use Operation::*;
+
+        // Opcodes xxxxxx11 combine the two neighbouring columns and take their address mode
+        let combined_mode = match opcode & 0x1F {
+            0x03 => IndirectX,
+            0x07 => ZeroPage,
+            0x0F => Absolute,
+            0x13 => IndirectY,
+            0x17 => ZeroPageX,
+            0x1B => AbsoluteY,
+            _ => AbsoluteX,
+        };
+
+        let decoded = match opcode {
+            0x02 | 0x12 | 0x22 | 0x32 | 0x42 | 0x52 | 0x62 | 0x72 | 0x92 | 0xB2 | 0xD2 | 0xF2 => {
+                (Jam, Implied)
+            }
+            0x0B | 0x2B => (Anc, Immediate),
+            0x4B => (Alr, Immediate),
+            0x6B => (Arr, Immediate),
+            0x8B => (Ane, Immediate),
+            0xAB => (Lxa, Immediate),
+            0xCB => (Sbx, Immediate),
+            0xEB => (Sbc, Immediate),
+            0x93 => (Sha, IndirectY),
+            0x9B => (Tas, AbsoluteY),
+            0x9C => (Shy, AbsoluteX),
+            0x9E => (Shx, AbsoluteY),
+            0x9F => (Sha, AbsoluteY),
+            0xBB => (Las, AbsoluteY),
+            // SAX and LAX index zero page and absolute addresses with Y
+            0x97 => (Sax, ZeroPageY),
+            0xB7 => (Lax, ZeroPageY),
+            0xBF => (Lax, AbsoluteY),
+            op if op & 0x03 == 0x03 => {
+                let operation = match op >> 5 {
+                    0 => Slo,
+                    1 => Rla,
+                    2 => Sre,
+                    3 => Rra,
+                    4 => Sax,
+                    5 => Lax,
+                    6 => Dcp,
+                    _ => Isc,
+                };
+                (operation, combined_mode)
+            }
+            // NOPs that still read their operand
+            0x1A | 0x3A | 0x5A | 0x7A | 0xDA | 0xFA => (Nop, Implied),
+            0x80 | 0x82 | 0x89 | 0xC2 | 0xE2 => (Nop, Immediate),
+            0x04 | 0x44 | 0x64 => (Nop, ZeroPage),
+            0x14 | 0x34 | 0x54 | 0x74 | 0xD4 | 0xF4 => (Nop, ZeroPageX),
+            0x0C => (Nop, Absolute),
+            0x1C | 0x3C | 0x5C | 0x7C | 0xDC | 0xFC => (Nop, AbsoluteX),
+            _ => return None,
+        };
+        Some(decoded)
+    }
+
+    /// Decode an opcode of the NMOS 6502
+    /// Parameters: opcode - Instruction byte
+    /// Returns: (operation, addressing mode); undocumented opcodes decode as implied NOP
+    fn decode_nmos(opcode: u8) -> (Operation, Mode) {
+        use Mode::*;
+        use Operation::*;
+
+        match opcode {
+            0x00 => (Brk, Implied),
+            0x01 => (Ora, IndirectX),
+            0x05 => (Ora, ZeroPage),
+            0x06 => (Asl, ZeroPage),
+            0x08 => (Php, Implied),
+            0x09 => (Ora, Immediate),
+            0x0A => (Asl, Accumulator),
+            0x0D => (Ora, Absolute),
+            0x0E => (Asl, Absolute),
+            0x10 => (Bpl, Relative),
+            0x11 => (Ora, IndirectY),
+            0x15 => (Ora, ZeroPageX),
+            0x16 => (Asl, ZeroPageX),
+            0x18 => (Clc, Implied),
+            0x19 => (Ora, AbsoluteY),
+            0x1D => (Ora, AbsoluteX),
+            0x1E => (Asl, AbsoluteX),
+            0x20 => (Jsr, Absolute),
+            0x21 => (And, IndirectX),
+            0x24 => (Bit, ZeroPage),
+            0x25 => (And, ZeroPage),
+            0x26 => (Rol, ZeroPage),
+            0x28 => (Plp, Implied),
+            0x29 => (And, Immediate),
+            0x2A => (Rol, Accumulator),
+            0x2C => (Bit, Absolute),
+            0x2D => (And, Absolute),
+            0x2E => (Rol, Absolute),
+            0x30 => (Bmi, Relative),
+            0x31 => (And, IndirectY),
+            0x35 => (And, ZeroPageX),
+            0x36 => (Rol, ZeroPageX),
+            0x38 => (Sec, Implied),
+            0x39 => (And, AbsoluteY),
+            0x3D => (And, AbsoluteX),
+            0x3E => (Rol, AbsoluteX),
+            0x40 => (Rti, Implied),
+            0x41 => (Eor, IndirectX),
+            0x45 => (Eor, ZeroPage),
+            0x46 => (Lsr, ZeroPage),
+            0x48 => (Pha, Implied),
+            0x49 => (Eor, Immediate),
+            0x4A => (Lsr, Accumulator),
+            0x4C => (Jmp, Absolute),
+            0x4D => (Eor, Absolute),
+            0x4E => (Lsr, Absolute),
+            0x50 => (Bvc, Relative),
+            0x51 => (Eor, IndirectY),
+            0x55 => (Eor, ZeroPageX),
+            0x56 => (Lsr, ZeroPageX),
+            0x58 => (Cli, Implied),
+            0x59 => (Eor, AbsoluteY),
+            0x5D => (Eor, AbsoluteX),
+            0x5E => (Lsr, AbsoluteX),
+            0x60 => (Rts, Implied),
+            0x61 => (Adc, IndirectX),
+            0x65 => (Adc, ZeroPage),
+            0x66 => (Ror, ZeroPage),
+            0x68 => (Pla, Implied),
+            0x69 => (Adc, Immediate),
+            0x6A => (Ror, Accumulator),
+            0x6C => (Jmp, Indirect),
+            0x6D => (Adc, Absolute),
+            0x6E => (Ror, Absolute),
+            0x70 => (Bvs, Relative),
+            0x71 => (Adc, IndirectY),
+            0x75 => (Adc, ZeroPageX),
+            0x76 => (Ror, ZeroPageX),
+            0x78 => (Sei, Implied),
+            0x79 => (Adc, AbsoluteY),
+            0x7D => (Adc, AbsoluteX),
+            0x7E => (Ror, AbsoluteX),
+            0x81 => (Sta, IndirectX),
+            0x84 => (Sty, ZeroPage),
+            0x85 => (Sta, ZeroPage),
+            0x86 => (Stx, ZeroPage),
+            0x88 => (Dey, Implied),
+            0x8A => (Txa, Implied),
+            0x8C => (Sty, Absolute),
+            0x8D => (Sta, Absolute),
+            0x8E => (Stx, Absolute),
+            0x90 => (Bcc, Relative),
+            0x91 => (Sta, IndirectY),
+            0x94 => (Sty, ZeroPageX),
+            0x95 => (Sta, ZeroPageX),
+            0x96 => (Stx, ZeroPageY),
+            0x98 => (Tya, Implied),
+            0x99 => (Sta, AbsoluteY),
+            0x9A => (Txs, Implied),
+            0x9D => (Sta, AbsoluteX),
+            0xA0 => (Ldy, Immediate),
+            0xA1 => (Lda, IndirectX),
+            0xA2 => (Ldx, Immediate),
+            0xA4 => (Ldy, ZeroPage),
+            0xA5 => (Lda, ZeroPage),
+            0xA6 => (Ldx, ZeroPage),
+            0xA8 => (Tay, Implied),
+            0xA9 => (Lda, Immediate),
+            0xAA => (Tax, Implied),
+            0xAC => (Ldy, Absolute),
+            0xAD => (Lda, Absolute),
+            0xAE => (Ldx, Absolute),
+            0xB0 => (Bcs, Relative),
+            0xB1 => (Lda, IndirectY),
+            0xB4 => (Ldy, ZeroPageX),
+            0xB5 => (Lda, ZeroPageX),
+            0xB6 => (Ldx, ZeroPageY),
+            0xB8 => (Clv, Implied),
+            0xB9 => (Lda, AbsoluteY),
+            0xBA => (Tsx, Implied),
+            0xBC => (Ldy, AbsoluteX),
+            0xBD => (Lda, AbsoluteX),
+            0xBE => (Ldx, AbsoluteY),
+            0xC0 => (Cpy, Immediate),
+            0xC1 => (Cmp, IndirectX),
+            0xC4 => (Cpy, ZeroPage),
+            0xC5 => (Cmp, ZeroPage),
+            0xC6 => (Dec, ZeroPage),
+            0xC8 => (Iny, Implied),
+            0xC9 => (Cmp, Immediate),
+            0xCA => (Dex, Implied),
+            0xCC => (Cpy, Absolute),
+            0xCD => (Cmp, Absolute),
+            0xCE => (Dec, Absolute),
+            0xD0 => (Bne, Relative),
+            0xD1 => (Cmp, IndirectY),
+            0xD5 => (Cmp, ZeroPageX),
+            0xD6 => (Dec, ZeroPageX),
+            0xD8 => (Cld, Implied),
+            0xD9 => (Cmp, AbsoluteY),
+            0xDD => (Cmp, AbsoluteX),
+            0xDE => (Dec, AbsoluteX),
+            0xE0 => (Cpx, Immediate),
+            0xE1 => (Sbc, IndirectX),
+            0xE4 => (Cpx, ZeroPage),
+            0xE5 => (Sbc, ZeroPage),
+            0xE6 => (Inc, ZeroPage),
+            0xE8 => (Inx, Implied),
+            0xE9 => (Sbc, Immediate),
+            0xEA => (Nop, Implied),
+            0xEC => (Cpx, Absolute),
+            0xED => (Sbc, Absolute),
+            0xEE => (Inc, Absolute),
+            0xF0 => (Beq, Relative),
+            0xF1 => (Sbc, IndirectY),
+            0xF5 => (Sbc, ZeroPageX),
+            0xF6 => (Inc, ZeroPageX),
+            0xF8 => (Sed, Implied),
+            0xF9 => (Sbc, AbsoluteY),
+            0xFD => (Sbc, AbsoluteX),
+            0xFE => (Inc, AbsoluteX),
+            _ => (Nop, Implied),
         }
     }
 
-    fn stop(&mut self) {
-        self.is_running = false;
+    fn access_kind(&self) -> AccessKind {
+        match self.operation {
+            Operation::Sta
+            | Operation::Stx
+            | Operation::Sty
+            | Operation::Stz
+            | Operation::Sax
+            | Operation::Sha
+            | Operation::Shx
+            | Operation::Shy
+            | Operation::Tas => AccessKind::Write,
+            Operation::Asl
+            | Operation::Lsr
+            | Operation::Rol
+            | Operation::Ror
+            | Operation::Inc
+            | Operation::Dec
+            | Operation::Trb
+            | Operation::Tsb
+            | Operation::Rmb
+            | Operation::Smb
+            | Operation::Slo
+            | Operation::Rla
+            | Operation::Sre
+            | Operation::Rra
+            | Operation::Dcp
+            | Operation::Isc => AccessKind::Modify,
+            _ => AccessKind::Read,
+        }
     }
 
-    fn is_running(&self) -> bool {
-        self.is_running
+    fn is_cmos(&self) -> bool {
+        self.variant == Mos6502Variant::Wdc65C02
     }
-}
 
-// 6502-specific methods
-impl MOS6502 {
-    pub fn get_program_counter(&self) -> u16 {
-        self.program_counter
+    /// Bit number of RMB, SMB, BBR and BBS, encoded in opcode bits 4-6
+    fn opcode_bit(&self) -> u8 {
+        1 << ((self.opcode >> 4) & 0x07)
     }
 
-    pub fn set_program_counter(&mut self, address: u16) {
-        self.program_counter = address;
+    /// Run one cycle of the current instruction
+    /// Parameters: data - Byte read in the cycle just completed
+    /// Returns: Some(access) for the next cycle, None when the instruction is finished
+    fn execute_cycle(&mut self, data: u8) -> Option<BusAccess> {
+        match (self.operation, self.mode) {
+            (Operation::Brk, _) => self.interrupt_cycle(data),
+            (Operation::Jsr, _) => self.jsr_cycle(data),
+            (Operation::Rts, _) => self.rts_cycle(data),
+            (Operation::Rti, _) => self.rti_cycle(data),
+            (Operation::Pha | Operation::Php | Operation::Phx | Operation::Phy, _) => {
+                self.push_cycle()
+            }
+            (Operation::Pla | Operation::Plp | Operation::Plx | Operation::Ply, _) => {
+                self.pull_cycle(data)
+            }
+            (Operation::Jmp, _) => self.jmp_cycle(data),
+            // JAM locks up with $FFFF on the bus until the next reset
+            (Operation::Jam, _) => {
+                self.stopped = true;
+                Some(BusAccess::Read(0xFFFF))
+            }
+            (_, Mode::Relative) => match self.cycle {
+                1 => Some(self.fetch_operand()),
+                step => self.branch_step(step, data),
+            },
+            (_, Mode::ZeroPageRelative) => self.bit_branch_cycle(data),
+            (Operation::Wai | Operation::Stp, _) => match self.cycle {
+                1 | 2 => Some(BusAccess::Read(self.program_counter)),
+                _ => {
+                    self.execute_implied();
+                    None
+                }
+            },
+            // The unused 65C02 opcodes x3 and xB finish with their fetch
+            (Operation::Nop, Mode::Implied) if self.is_cmos() && self.opcode & 0x03 == 0x03 => None,
+            (_, Mode::Implied | Mode::Accumulator) => match self.cycle {
+                1 => Some(BusAccess::Read(self.program_counter)),
+                _ => {
+                    self.execute_implied();
+                    None
+                }
+            },
+            (_, Mode::Immediate) => match self.cycle {
+                1 => Some(self.fetch_operand()),
+                2 => self.finish_read(data),
+                _ => None,
+            },
+            _ => self.memory_cycle(data),
+        }
     }
 
-    pub fn get_accumulator(&self) -> u8 {
-        self.accumulator
+    /// Read the next program byte
+    /// Returns: Read access at PC, with PC advanced past it
+    fn fetch_operand(&mut self) -> BusAccess {
+        let address = self.program_counter;
+        self.program_counter = address.wrapping_add(1);
+        BusAccess::Read(address)
     }
 
-    pub fn get_x_register(&self) -> u8 {
-        self.x_register
+    fn stack_address(&self) -> u16 {
+        0x0100 | self.stack_pointer as u16
     }
 
-    pub fn get_y_register(&self) -> u8 {
-        self.y_register
+    fn push(&mut self, data: u8) -> BusAccess {
+        let address = self.stack_address();
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        BusAccess::Write(address, data)
     }
 
-    pub fn get_stack_pointer(&self) -> u8 {
-        self.stack_pointer
+    fn pull(&mut self) -> BusAccess {
+        self.stack_pointer = self.stack_pointer.wrapping_add(1);
+        BusAccess::Read(self.stack_address())
     }
 
-    pub fn get_status_register(&self) -> u8 {
-        self.status_register
+    /// Cycle of an instruction with a memory operand
+    fn memory_cycle(&mut self, data: u8) -> Option<BusAccess> {
+        if self.address_ready_at == 0 {
+            if let Some(access) = self.address_cycle(data) {
+                return Some(access);
+            }
+            self.address_ready_at = self.cycle;
+        }
+
+        let address = self.address;
+        match (self.access_kind(), self.cycle - self.address_ready_at) {
+            (AccessKind::Read, 0) => Some(BusAccess::Read(address)),
+            (AccessKind::Read, 1) => self.finish_read(data),
+            (AccessKind::Read, _) => None,
+            (AccessKind::Write, 0) => match self.operation {
+                Operation::Sha | Operation::Shx | Operation::Shy | Operation::Tas => {
+                    Some(self.unstable_store())
+                }
+                _ => Some(BusAccess::Write(address, self.store_value())),
+            },
+            (AccessKind::Write, _) => None,
+            (AccessKind::Modify, 0) => Some(BusAccess::Read(address)),
+            (AccessKind::Modify, 1) => {
+                // The NMOS part writes the unmodified byte back before the result,
+                // the 65C02 reads it again instead
+                self.operand = data;
+                if self.is_cmos() {
+                    Some(BusAccess::Read(address))
+                } else {
+                    Some(BusAccess::Write(address, data))
+                }
+            }
+            (AccessKind::Modify, 2) => {
+                let result = self.execute_modify(self.operand);
+                Some(BusAccess::Write(address, result))
+            }
+            (AccessKind::Modify, _) => None,
+        }
     }
 
-    pub fn get_cycle_count(&self) -> u64 {
-        self.cycle_count
+    /// Addressing cycle of an instruction with a memory operand
+    /// Returns: Some(access) while the address is being built, None once it is complete
+    fn address_cycle(&mut self, data: u8) -> Option<BusAccess> {
+        let index = match self.mode {
+            Mode::ZeroPageX | Mode::AbsoluteX | Mode::IndirectX => self.x_register,
+            Mode::ZeroPageY | Mode::AbsoluteY | Mode::IndirectY => self.y_register,
+            _ => 0,
+        };
+
+        match (self.mode, self.cycle) {
+            (_, 1) => Some(self.fetch_operand()),
+            (Mode::ZeroPage, _) => {
+                self.address = data as u16;
+                None
+            }
+            (Mode::IndirectY | Mode::ZeroPageIndirect, 2) => {
+                // No dummy cycle: the pointer low byte is read right away
+                self.pointer = data.wrapping_add(1);
+                Some(BusAccess::Read(data as u16))
+            }
+            (Mode::ZeroPageX | Mode::ZeroPageY | Mode::IndirectX, 2) => {
+                // Dummy read of the unindexed zero page address
+                self.pointer = data;
+                Some(BusAccess::Read(data as u16))
+            }
+            (Mode::ZeroPageX | Mode::ZeroPageY, _) => {
+                self.address = self.pointer.wrapping_add(index) as u16;
+                None
+            }
+            (Mode::Absolute | Mode::AbsoluteX | Mode::AbsoluteY, 2) => {
+                self.address = data as u16;
+                Some(self.fetch_operand())
+            }
+            (Mode::Absolute, _) => {
+                self.address |= (data as u16) << 8;
+                None
+            }
+            (Mode::AbsoluteX | Mode::AbsoluteY, 3) => {
+                let base = (data as u16) << 8 | self.address;
+                self.index_address(base, index)
+            }
+            (Mode::IndirectX, 3) => {
+                self.pointer = self.pointer.wrapping_add(index);
+                Some(BusAccess::Read(self.pointer as u16))
+            }
+            (Mode::IndirectX, 4) => {
+                self.address = data as u16;
+                Some(BusAccess::Read(self.pointer.wrapping_add(1) as u16))
+            }
+            (Mode::IndirectX, _) => {
+                self.address |= (data as u16) << 8;
+                None
+            }
+            (Mode::IndirectY | Mode::ZeroPageIndirect, 3) => {
+                self.address = data as u16;
+                Some(BusAccess::Read(self.pointer as u16))
+            }
+            (Mode::ZeroPageIndirect, _) => {
+                self.address |= (data as u16) << 8;
+                None
+            }
+            (Mode::IndirectY, 4) => {
+                let base = (data as u16) << 8 | self.address;
+                self.index_address(base, index)
+            }
+            // Page fix-up read of an indexed mode done, the address is already set
+            _ => None,
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Add the index to a base address
+    /// The address bus first carries the base page with the indexed low byte; that read
+    /// is the operand read unless the page changed or the instruction writes. The 65C02
+    /// also skips the fix-up for shifts and rotates in the same page, and makes its dummy
+    /// read at the last operand byte instead of the unfinished address.
+    /// Returns: Some(dummy read) if an extra fix-up cycle is needed, None otherwise
+    fn index_address(&mut self, base: u16, index: u8) -> Option<BusAccess> {
+        self.address = base.wrapping_add(index as u16);
+        self.base_high = (base >> 8) as u8;
+        let partial = (base & 0xFF00) | (self.address & 0x00FF);
+        let same_page_shift = self.is_cmos()
+            && matches!(
+                self.operation,
+                Operation::Asl | Operation::Lsr | Operation::Rol | Operation::Ror
+            );
+        let fix_up = match self.access_kind() {
+            AccessKind::Read => partial != self.address,
+            AccessKind::Modify if same_page_shift => partial != self.address,
+            _ => true,
+        };
 
-    #[test]
-    fn test_6502_creation() {
-        let cpu = MOS6502::new("CPU_6502".to_string());
-        assert_eq!(cpu.name(), "CPU_6502");
-        assert!(!cpu.is_running());
+        if !fix_up {
+            None
+        } else if self.is_cmos() {
+            Some(BusAccess::Read(self.program_counter.wrapping_sub(1)))
+        } else {
+            Some(BusAccess::Read(partial))
+        }
     }
 
-    #[test]
-    fn test_6502_registers() {
-        let mut cpu = MOS6502::new("CPU_6502".to_string());
+    /// Branch cycles from the offset read on
+    /// Parameters: step - 2 for the cycle that read the offset, 3 and 4 for the taken
+    ///             branch and its page fix-up; data - Byte read in the cycle just completed
+    fn branch_step(&mut self, step: u8, data: u8) -> Option<BusAccess> {
+        match step {
+            2 => {
+                if !self.branch_taken() {
+                    return None;
+                }
+                self.address = self.program_counter.wrapping_add(data as i8 as i16 as u16);
+                Some(BusAccess::Read(self.program_counter))
+            }
+            3 => {
+                if self.address & 0xFF00 == self.program_counter & 0xFF00 {
+                    self.program_counter = self.address;
+                    return None;
+                }
+                // Crossing a page costs one more cycle on the uncorrected address
+                Some(BusAccess::Read(
+                    (self.program_counter & 0xFF00) | (self.address & 0x00FF),
+                ))
+            }
+            _ => {
+                self.program_counter = self.address;
+                None
+            }
+        }
+    }
 
-        cpu.set_program_counter(0x1234);
-        assert_eq!(cpu.get_program_counter(), 0x1234);
+    fn branch_taken(&self) -> bool {
+        let flag = |mask: u8| self.status_register & mask != 0;
+        match self.operation {
+            Operation::Bpl => !flag(FLAG_NEGATIVE),
+            Operation::Bmi => flag(FLAG_NEGATIVE),
+            Operation::Bvc => !flag(FLAG_OVERFLOW),
+            Operation::Bvs => flag(FLAG_OVERFLOW),
+            Operation::Bcc => !flag(FLAG_CARRY),
+            Operation::Bcs => flag(FLAG_CARRY),
+            Operation::Bne => !flag(FLAG_ZERO),
+            Operation::Beq => flag(FLAG_ZERO),
+            Operation::Bra => true,
+            Operation::Bbr => self.operand & self.opcode_bit() == 0,
+            Operation::Bbs => self.operand & self.opcode_bit() != 0,
+            _ => false,
+        }
+    }
 
-        assert_eq!(cpu.get_accumulator(), 0);
-        assert_eq!(cpu.get_x_register(), 0);
-        assert_eq!(cpu.get_y_register(), 0);
-        assert_eq!(cpu.get_stack_pointer(), 0xFD);
-        assert_eq!(cpu.get_status_register(), 0x20);
+    /// BBR/BBS: test a zero page bit, then branch like the other branches
+    fn bit_branch_cycle(&mut self, data: u8) -> Option<BusAccess> {
+        match self.cycle {
+            1 => Some(self.fetch_operand()),
+            2 => {
+                self.address = data as u16;
+                Some(BusAccess::Read(self.address))
+            }
+            3 => {
+                self.operand = data;
+                Some(BusAccess::Read(self.address))
+            }
+            4 => Some(self.fetch_operand()),
+            cycle => self.branch_step(cycle - 3, data),
+        }
     }
 
-    #[test]
-    fn test_6502_reset() {
-        let mut cpu = MOS6502::new("CPU_6502".to_string());
+    fn jmp_cycle(&mut self, data: u8) -> Option<BusAccess> {
+        match (self.cycle, self.is_cmos()) {
+            (1, _) => Some(self.fetch_operand()),
+            (2, _) => {
+                self.address = data as u16;
+                Some(self.fetch_operand())
+            }
+            (3, _) if self.mode == Mode::Absolute => {
+                self.program_counter = (data as u16) << 8 | self.address;
+                None
+            }
+            (3, false) => {
+                self.address |= (data as u16) << 8;
+                Some(BusAccess::Read(self.address))
+            }
+            (3, true) => {
+                // The 65C02 spends a cycle adding X (for (abs,X)) before reading the pointer
+                let index = match self.mode {
+                    Mode::AbsoluteIndexedIndirect => self.x_register as u16,
+                    _ => 0,
+                };
+                self.address = ((data as u16) << 8 | self.address).wrapping_add(index);
+                Some(BusAccess::Read(self.program_counter.wrapping_sub(1)))
+            }
+            (4, true) => Some(BusAccess::Read(self.address)),
+            (4, false) => {
+                // The NMOS part reads the pointer high byte without carrying into the page
+                self.operand = data;
+                Some(BusAccess::Read(
+                    (self.address & 0xFF00) | (self.address.wrapping_add(1) & 0x00FF),
+                ))
+            }
+            (5, true) => {
+                self.operand = data;
+                Some(BusAccess::Read(self.address.wrapping_add(1)))
+            }
+            _ => {
+                self.program_counter = (data as u16) << 8 | self.operand as u16;
+                None
+            }
+        }
+    }
 
-        cpu.set_program_counter(0x1000);
-        cpu.reset();
+    fn jsr_cycle(&mut self, data: u8) -> Option<BusAccess> {
+        match self.cycle {
+            1 => Some(self.fetch_operand()),
+            2 => {
+                self.address = data as u16;
+                Some(BusAccess::Read(self.stack_address()))
+            }
+            // PC points at the high byte of the target: RTS adds the missing one
+            3 => Some(self.push((self.program_counter >> 8) as u8)),
+            4 => Some(self.push(self.program_counter as u8)),
+            5 => Some(BusAccess::Read(self.program_counter)),
+            _ => {
+                self.program_counter = (data as u16) << 8 | self.address;
+                None
+            }
+        }
+    }
 
-        assert_eq!(cpu.get_program_counter(), 0xFFFC);
+    fn rts_cycle(&mut self, data: u8) -> Option<BusAccess> {
+        match self.cycle {
+            1 => Some(BusAccess::Read(self.program_counter)),
+            2 => Some(BusAccess::Read(self.stack_address())),
+            3 => Some(self.pull()),
+            4 => {
+                self.address = data as u16;
+                Some(self.pull())
+            }
+            5 => {
+                self.program_counter = (data as u16) << 8 | self.address;
+                Some(BusAccess::Read(self.program_counter))
+            }
+            _ => {
+                self.program_counter = self.program_counter.wrapping_add(1);
+                None
+            }
+        }
+    }
+
+    fn rti_cycle(&mut self, data: u8) -> Option<BusAccess> {
+        match self.cycle {
+            1 => Some(BusAccess::Read(self.program_counter)),
+            2 => Some(BusAccess::Read(self.stack_address())),
+            3 => Some(self.pull()),
+            4 => {
+                self.set_status_from_stack(data);
+                Some(self.pull())
+            }
+            5 => {
+                self.address = data as u16;
+                Some(self.pull())
+            }
+            _ => {
+                self.program_counter = (data as u16) << 8 | self.address;
+                None
+            }
+        }
+    }
+
+    fn push_cycle(&mut self) -> Option<BusAccess> {
+        match self.cycle {
+            1 => Some(BusAccess::Read(self.program_counter)),
+            2 => {
+                let value = match self.operation {
+                    Operation::Php => self.status_register | FLAG_BREAK | FLAG_UNUSED,
+                    Operation::Phx => self.x_register,
+                    Operation::Phy => self.y_register,
+                    _ => self.accumulator,
+                };
+                Some(self.push(value))
+            }
+            _ => None,
+        }
+    }
+
+    fn pull_cycle(&mut self, data: u8) -> Option<BusAccess> {
+        match self.cycle {
+            1 => Some(BusAccess::Read(self.program_counter)),
+            2 => Some(BusAccess::Read(self.stack_address())),
+            3 => Some(self.pull()),
+            _ => {
+                match self.operation {
+                    Operation::Plp => self.set_status_from_stack(data),
+                    Operation::Plx => self.x_register = data,
+                    Operation::Ply => self.y_register = data,
+                    _ => self.accumulator = data,
+                }
+                if self.operation != Operation::Plp {
+                    self.set_nz(data);
+                }
+                None
+            }
+        }
+    }
+
+    // Arithmetic and logic
+
+    fn set_flag(&mut self, mask: u8, value: bool) {
+        if value {
+            self.status_register |= mask;
+        } else {
+            self.status_register &= !mask;
+        }
+    }
+
+    fn set_nz(&mut self, value: u8) {
+        self.set_flag(FLAG_ZERO, value == 0);
+        self.set_flag(FLAG_NEGATIVE, value & 0x80 != 0);
+    }
+
+    /// Load P from a byte pulled off the stack; B and bit 5 do not exist in the register
+    fn set_status_from_stack(&mut self, data: u8) {
+        self.status_register = (data & !FLAG_BREAK) | FLAG_UNUSED;
+    }
+
+    fn carry(&self) -> u8 {
+        self.status_register & FLAG_CARRY
+    }
+
+    fn store_value(&self) -> u8 {
+        match self.operation {
+            Operation::Stx => self.x_register,
+            Operation::Sty => self.y_register,
+            Operation::Stz => 0,
+            Operation::Sax => self.accumulator & self.x_register,
+            _ => self.accumulator,
+        }
+    }
+
+    /// Store of SHA, SHX, SHY and TAS: the register value is ANDed with the base
+    /// address high byte plus one, and on a page crossing that value also replaces
+    /// the high byte of the address
+    /// Returns: Write access of the instruction
+    fn unstable_store(&mut self) -> BusAccess {
+        let register = match self.operation {
+            Operation::Shx => self.x_register,
+            Operation::Shy => self.y_register,
+            Operation::Tas => {
+                self.stack_pointer = self.accumulator & self.x_register;
+                self.stack_pointer
+            }
+            _ => self.accumulator & self.x_register,
+        };
+        let value = register & self.base_high.wrapping_add(1);
+        let mut address = self.address;
+        if (address >> 8) as u8 != self.base_high {
+            address = (value as u16) << 8 | (address & 0x00FF);
+        }
+        BusAccess::Write(address, value)
+    }
+
+    /// Execute a read instruction with its operand
+    /// The 65C02 takes one more cycle for ADC and SBC in decimal mode.
+    /// Returns: Some(dummy read) for that extra cycle, None when the instruction is done
+    fn finish_read(&mut self, data: u8) -> Option<BusAccess> {
+        self.execute_read(data);
+        let decimal = self.status_register & FLAG_DECIMAL != 0;
+        if self.is_cmos() && decimal && matches!(self.operation, Operation::Adc | Operation::Sbc) {
+            Some(BusAccess::Read(self.program_counter))
+        } else {
+            None
+        }
+    }
+
+    fn execute_read(&mut self, data: u8) {
+        match self.operation {
+            // Undocumented combinations of a read-modify-write and an ALU operation
+            Operation::Slo
+            | Operation::Rla
+            | Operation::Sre
+            | Operation::Rra
+            | Operation::Dcp
+            | Operation::Isc => {}
+            operation => self.read_operation(operation, data),
+        }
+    }
+
+    fn read_operation(&mut self, operation: Operation, data: u8) {
+        match operation {
+            Operation::Lda => {
+                self.accumulator = data;
+                self.set_nz(data);
+            }
+            Operation::Ldx => {
+                self.x_register = data;
+                self.set_nz(data);
+            }
+            Operation::Ldy => {
+                self.y_register = data;
+                self.set_nz(data);
+            }
+            Operation::Ora => {
+                self.accumulator |= data;
+                self.set_nz(self.accumulator);
+            }
+            Operation::And => {
+                self.accumulator &= data;
+                self.set_nz(self.accumulator);
+            }
+            Operation::Eor => {
+                self.accumulator ^= data;
+                self.set_nz(self.accumulator);
+            }
+            Operation::Adc => self.add_with_carry(data),
+            Operation::Sbc => self.subtract_with_borrow(data),
+            Operation::Cmp => self.compare(self.accumulator, data),
+            Operation::Cpx => self.compare(self.x_register, data),
+            Operation::Cpy => self.compare(self.y_register, data),
+            Operation::Bit => {
+                self.set_flag(FLAG_ZERO, self.accumulator & data == 0);
+                // BIT #imm of the 65C02 only sets Z
+                if self.mode != Mode::Immediate {
+                    self.set_flag(FLAG_NEGATIVE, data & 0x80 != 0);
+                    self.set_flag(FLAG_OVERFLOW, data & 0x40 != 0);
+                }
+            }
+            Operation::Lax => {
+                self.accumulator = data;
+                self.x_register = data;
+                self.set_nz(data);
+            }
+            Operation::Las => {
+                let value = data & self.stack_pointer;
+                self.accumulator = value;
+                self.x_register = value;
+                self.stack_pointer = value;
+                self.set_nz(value);
+            }
+            Operation::Anc => {
+                self.accumulator &= data;
+                self.set_nz(self.accumulator);
+                self.set_flag(FLAG_CARRY, self.accumulator & 0x80 != 0);
+            }
+            Operation::Alr => {
+                let value = self.accumulator & data;
+                self.accumulator = self.modify_operation(Operation::Lsr, value);
+            }
+            Operation::Arr => self.and_rotate_right(data),
+            Operation::Sbx => {
+                let value = self.accumulator & self.x_register;
+                self.set_flag(FLAG_CARRY, value >= data);
+                self.x_register = value.wrapping_sub(data);
+                self.set_nz(self.x_register);
+            }
+            // Unstable: the bits ORed into A vary between chips, 0xEE is the common value
+            Operation::Ane => {
+                self.accumulator = (self.accumulator | 0xEE) & self.x_register & data;
+                self.set_nz(self.accumulator);
+            }
+            Operation::Lxa => {
+                self.accumulator = (self.accumulator | 0xEE) & data;
+                self.x_register = self.accumulator;
+                self.set_nz(self.accumulator);
+            }
+            _ => {}
+        }
+    }
+
+    /// ARR: AND, then ROR A with flags taken from the adder; in decimal mode the
+    /// NMOS part also applies a BCD fix-up to each digit
+    fn and_rotate_right(&mut self, data: u8) {
+        let value = self.accumulator & data;
+        let mut result = value >> 1 | self.carry() << 7;
+        self.set_nz(result);
+
+        if self.status_register & FLAG_DECIMAL == 0 {
+            self.set_flag(FLAG_CARRY, result & 0x40 != 0);
+            self.set_flag(FLAG_OVERFLOW, ((result >> 6) ^ (result >> 5)) & 0x01 != 0);
+            self.accumulator = result;
+            return;
+        }
+
+        self.set_flag(FLAG_OVERFLOW, (value ^ result) & 0x40 != 0);
+        if (value & 0x0F) + (value & 0x01) > 0x05 {
+            result = (result & 0xF0) | (result.wrapping_add(0x06) & 0x0F);
+        }
+        let high_adjust = (value & 0xF0) as u16 + (value & 0x10) as u16 > 0x50;
+        if high_adjust {
+            result = result.wrapping_add(0x60);
+        }
+        self.set_flag(FLAG_CARRY, high_adjust);
+        self.accumulator = result;
+    }
+
+    /// Apply a read-modify-write operation
+    /// Parameters: value - Operand
+    /// Returns: Result, with the flags of the operation updated
+    fn execute_modify(&mut self, value: u8) -> u8 {
+        match self.operation {
+            Operation::Tsb | Operation::Trb => {
+                self.set_flag(FLAG_ZERO, self.accumulator & value == 0);
+                return match self.operation {
+                    Operation::Tsb => value | self.accumulator,
+                    _ => value & !self.accumulator,
+                };
+            }
+            Operation::Rmb => return value & !self.opcode_bit(),
+            Operation::Smb => return value | self.opcode_bit(),
+            _ => {}
+        }
+
+        // The undocumented combinations modify memory, then use the result like a read
+        let (modify, read) = match self.operation {
+            Operation::Slo => (Operation::Asl, Operation::Ora),
+            Operation::Rla => (Operation::Rol, Operation::And),
+            Operation::Sre => (Operation::Lsr, Operation::Eor),
+            Operation::Rra => (Operation::Ror, Operation::Adc),
+            Operation::Dcp => (Operation::Dec, Operation::Cmp),
+            Operation::Isc => (Operation::Inc, Operation::Sbc),
+            operation => return self.modify_operation(operation, value),
+        };
+        let result = self.modify_operation(modify, value);
+        self.read_operation(read, result);
+        result
+    }
+
+    /// Apply a shift, rotate, increment or decrement
+    /// Parameters: operation - Operation to apply, value - Operand
+    /// Returns: Result, with N, Z and (for shifts) C updated
+    fn modify_operation(&mut self, operation: Operation, value: u8) -> u8 {
+        let result = match operation {
+            Operation::Asl => {
+                self.set_flag(FLAG_CARRY, value & 0x80 != 0);
+                value << 1
+            }
+            Operation::Lsr => {
+                self.set_flag(FLAG_CARRY, value & 0x01 != 0);
+                value >> 1
+            }
+            Operation::Rol => {
+                let result = value << 1 | self.carry();
+                self.set_flag(FLAG_CARRY, value & 0x80 != 0);
+                result
+            }
+            Operation::Ror => {
+                let result = value >> 1 | self.carry() << 7;
+                self.set_flag(FLAG_CARRY, value & 0x01 != 0);
+                result
+            }
+            Operation::Inc => value.wrapping_add(1),
+            Operation::Dec => value.wrapping_sub(1),
+            _ => value,
+        };
+        self.set_nz(result);
+        result
+    }
+
+    fn execute_implied(&mut self) {
+        if self.mode == Mode::Accumulator {
+            self.accumulator = self.execute_modify(self.accumulator);
+            return;
+        }
+
+        match self.operation {
+            Operation::Clc => self.set_flag(FLAG_CARRY, false),
+            Operation::Sec => self.set_flag(FLAG_CARRY, true),
+            Operation::Cli => self.set_flag(FLAG_INTERRUPT, false),
+            Operation::Sei => self.set_flag(FLAG_INTERRUPT, true),
+            Operation::Clv => self.set_flag(FLAG_OVERFLOW, false),
+            Operation::Cld => self.set_flag(FLAG_DECIMAL, false),
+            Operation::Sed => self.set_flag(FLAG_DECIMAL, true),
+            Operation::Tax => {
+                self.x_register = self.accumulator;
+                self.set_nz(self.x_register);
+            }
+            Operation::Tay => {
+                self.y_register = self.accumulator;
+                self.set_nz(self.y_register);
+            }
+            Operation::Txa => {
+                self.accumulator = self.x_register;
+                self.set_nz(self.accumulator);
+            }
+            Operation::Tya => {
+                self.accumulator = self.y_register;
+                self.set_nz(self.accumulator);
+            }
+            Operation::Tsx => {
+                self.x_register = self.stack_pointer;
+                self.set_nz(self.x_register);
+            }
+            Operation::Txs => self.stack_pointer = self.x_register,
+            Operation::Inx => {
+                self.x_register = self.x_register.wrapping_add(1);
+                self.set_nz(self.x_register);
+            }
+            Operation::Iny => {
+                self.y_register = self.y_register.wrapping_add(1);
+                self.set_nz(self.y_register);
+            }
+            Operation::Dex => {
+                self.x_register = self.x_register.wrapping_sub(1);
+                self.set_nz(self.x_register);
+            }
+            Operation::Dey => {
+                self.y_register = self.y_register.wrapping_sub(1);
+                self.set_nz(self.y_register);
+            }
+            Operation::Wai => self.waiting = true,
+            Operation::Stp => self.stopped = true,
+            _ => {}
+        }
+    }
+
+    fn compare(&mut self, register: u8, data: u8) {
+        self.set_flag(FLAG_CARRY, register >= data);
+        self.set_nz(register.wrapping_sub(data));
+    }
+
+    /// ADC, in decimal mode with the NMOS flags: N and V come from the
+    /// intermediate result after the low digit adjust, Z from the binary sum.
+    /// The 65C02 sets N and Z from the decimal result.
+    fn add_with_carry(&mut self, data: u8) {
+        let a = self.accumulator as u16;
+        let m = data as u16;
+        let c = self.carry() as u16;
+        let binary = a + m + c;
+
+        if self.status_register & FLAG_DECIMAL == 0 {
+            self.set_flag(FLAG_CARRY, binary > 0xFF);
+            self.set_flag(FLAG_OVERFLOW, (!(a ^ m) & (a ^ binary) & 0x80) != 0);
+            self.accumulator = binary as u8;
+            self.set_nz(self.accumulator);
+            return;
+        }
+
+        let mut low = (a & 0x0F) + (m & 0x0F) + c;
+        if low > 0x09 {
+            low = ((low + 0x06) & 0x0F) + 0x10;
+        }
+        let mut result = (a & 0xF0) + (m & 0xF0) + low;
+        self.set_flag(FLAG_ZERO, binary & 0xFF == 0);
+        self.set_flag(FLAG_NEGATIVE, result & 0x80 != 0);
+        self.set_flag(FLAG_OVERFLOW, (!(a ^ m) & (a ^ result) & 0x80) != 0);
+        if result > 0x9F {
+            result += 0x60;
+        }
+        self.set_flag(FLAG_CARRY, result > 0xFF);
+        self.accumulator = result as u8;
+        if self.is_cmos() {
+            self.set_nz(self.accumulator);
+        }
+    }
+
+    /// SBC, in decimal mode with the NMOS flags: all flags come from the binary difference.
+    /// The 65C02 adjusts the difference differently and sets N and Z from the decimal result.
+    fn subtract_with_borrow(&mut self, data: u8) {
+        let a = self.accumulator as i16;
+        let m = data as i16;
+        let borrow = 1 - self.carry() as i16;
+        let binary = a - m - borrow;
+
+        self.set_flag(FLAG_CARRY, binary >= 0);
+        self.set_flag(FLAG_OVERFLOW, ((a ^ m) & (a ^ binary) & 0x80) != 0);
+        self.set_nz(binary as u8);
+
+        if self.status_register & FLAG_DECIMAL == 0 {
+            self.accumulator = binary as u8;
+            return;
+        }
+
+        let low = (a & 0x0F) - (m & 0x0F) - borrow;
+        if self.is_cmos() {
+            let mut result = binary;
+            if result < 0 {
+                result -= 0x60;
+            }
+            if low < 0 {
+                result -= 0x06;
+            }
+            self.accumulator = result as u8;
+            self.set_nz(self.accumulator);
+            return;
+        }
+
+        let low = if low < 0 {
+            ((low - 0x06) & 0x0F) - 0x10
+        } else {
+            low
+        };
+        let mut result = (a & 0xF0) - (m & 0xF0) + low;
+        if result < 0 {
+            result -= 0x60;
+        }
+        self.accumulator = result as u8;
+    }
+
+    /// Check whether a clock cycle passes without bus activity
+    /// WAI ends as soon as IRQ or NMI is asserted, even with interrupts disabled, and the
+    /// interrupt is serviced right away if it is taken; STP only ends with a reset.
+    /// Returns: true while the CPU is suspended by WAI or STP
+    fn idle_cycle(&mut self) -> bool {
+        if self.waiting && (self.irq_line || self.nmi_line) {
+            self.waiting = false;
+            if self.interrupt_requested() {
+                self.pending = self.begin_interrupt();
+            }
+        }
+        if self.waiting || self.stopped {
+            self.cycle_count += 1;
+            return true;
+        }
+        false
+    }
+
+    // Running without pins
+
+    /// Run one bus cycle against a flat memory instead of the pins
+    /// Parameters: bus - Memory the pending access goes to
+    pub fn clock(&mut self, bus: &mut dyn Bus6502) {
+        if self.idle_cycle() {
+            return;
+        }
+        let data = match self.pending {
+            BusAccess::Read(address) => bus.read(address),
+            BusAccess::Write(address, data) => {
+                bus.write(address, data);
+                data
+            }
+        };
+        self.complete_cycle(data);
+    }
+
+    /// Run up to the next instruction boundary against a flat memory
+    /// A CPU suspended by WAI or STP returns after a single idle cycle.
+    /// Parameters: bus - Memory to execute from
+    /// Returns: Number of cycles taken
+    pub fn step(&mut self, bus: &mut dyn Bus6502) -> u64 {
+        let start = self.cycle_count;
+        loop {
+            self.clock(bus);
+            if self.is_at_instruction_boundary() || self.waiting || self.stopped {
+                return self.cycle_count - start;
+            }
+        }
+    }
+
+    /// Run until the program traps in a jump or branch to itself
+    /// Parameters: bus - Memory to execute from, max_cycles - Cycle budget
+    /// Returns: Ok(address) of the trap, Err(String) if the budget runs out first
+    pub fn run_until_trap(
+        &mut self,
+        bus: &mut dyn Bus6502,
+        max_cycles: u64,
+    ) -> Result<u16, String> {
+        let limit = self.cycle_count + max_cycles;
+        while self.cycle_count < limit {
+            let address = self.program_counter;
+            self.step(bus);
+            if self.program_counter == address {
+                return Ok(address);
+            }
+        }
+        Err(format!(
+            "No trap within {} cycles, PC = 0x{:04X}",
+            max_cycles, self.program_counter
+        ))
+    }
+}
+
+/// Run Klaus Dormann's 6502 functional test from a local binary
+/// The binary is the 64 KB memory image of the default build (6502_functional_test.bin);
+/// execution starts at $0400 and ends in a jump or branch to itself, at
+/// FUNCTIONAL_TEST_SUCCESS when every test passed or at the failing check otherwise.
+/// Parameters: path - Path of the binary, max_cycles - Cycle budget
+/// Returns: Ok(cycles) when the success trap is reached, Err(String) with the trap address otherwise
+pub fn run_functional_test(path: &str, max_cycles: u64) -> Result<u64, String> {
+    let image = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if image.len() != 0x10000 {
+        return Err(format!(
+            "{} is {} bytes, expected a 64 KB memory image",
+            path,
+            image.len()
+        ));
+    }
+
+    let mut memory = image;
+    let mut cpu = MOS6502::new("CPU_6502".to_string());
+    cpu.set_program_counter(FUNCTIONAL_TEST_START);
+
+    let trap = cpu.run_until_trap(&mut memory, max_cycles)?;
+    if trap == FUNCTIONAL_TEST_SUCCESS {
+        Ok(cpu.get_cycle_count())
+    } else {
+        Err(format!(
+            "Functional test trapped at 0x{:04X} after {} cycles (A=0x{:02X} X=0x{:02X} Y=0x{:02X} P=0x{:02X})",
+            trap,
+            cpu.get_cycle_count(),
+            cpu.get_accumulator(),
+            cpu.get_x_register(),
+            cpu.get_y_register(),
+            cpu.get_status_register()
+        ))
+    }
+}
+
+impl Component for MOS6502 {
+    fn name(&self) -> String {
+        self.base.name()
+    }
+
+    fn pins(&self) -> HashMap<String, Arc<Mutex<Pin>>> {
+        self.base.pins()
+    }
+
+    fn get_pin(&self, name: &str) -> Result<Arc<Mutex<Pin>>, String> {
+        self.base.get_pin(name)
+    }
+
+    /// Main update cycle - completes a bus cycle on each falling CLK edge
+    fn update(&mut self) {
+        if !self.is_running {
+            return;
+        }
+
+        let (irq, nmi, reset, rdy) = self.read_control_pins();
+        self.set_interrupt_lines(irq, nmi);
+
+        // Held in reset while RES is low
+        if reset {
+            if !self.is_reset {
+                self.reset();
+            }
+            return;
+        }
+        self.is_reset = false;
+
+        let clock = self.read_clock_pin();
+        let falling_edge = self.last_clock == PinValue::High && clock == PinValue::Low;
+        self.last_clock = clock;
+
+        // RDY low holds the access on the bus as a wait state; the NMOS part finishes
+        // write cycles and stops at the next read
+        let write = matches!(self.pending, BusAccess::Write(..));
+        if falling_edge && !rdy && (self.is_cmos() || !write) {
+            self.cycle_count += 1;
+            return;
+        }
+
+        if falling_edge && !self.idle_cycle() {
+            let data = match self.pending {
+                BusAccess::Read(_) => self.read_data_bus(),
+                BusAccess::Write(_, data) => data,
+            };
+            self.complete_cycle(data);
+            self.drive_bus();
+        }
+    }
+
+    fn start(&mut self) {
+        self.is_running = true;
+        self.last_clock = self.read_clock_pin();
+        self.reset();
+    }
+
+    fn run(&mut self) {
+        self.start();
+
+        while self.is_running {
+            self.update();
+            thread::sleep(Duration::from_micros(1));
+        }
+    }
+
+    fn stop(&mut self) {
+        self.is_running = false;
+    }
+
+    fn is_running(&self) -> bool {
+        self.is_running
+    }
+
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        component::state_to_json(&self.snapshot())
+    }
+
+    fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        let state = component::state_from_json(&self.base.name(), state)?;
+        self.restore(&state);
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// 6502-specific methods
+impl MOS6502 {
+    /// Get the CPU variant
+    /// Returns: NMOS 6502 or WDC 65C02
+    pub fn get_variant(&self) -> Mos6502Variant {
+        self.variant
+    }
+
+    pub fn get_program_counter(&self) -> u16 {
+        self.program_counter
+    }
+
+    /// Set the program counter
+    /// Abandons the current instruction; the next cycle fetches the opcode at the address.
+    /// Parameters: address - New program counter
+    pub fn set_program_counter(&mut self, address: u16) {
+        self.program_counter = address;
+        self.pending = self.begin_instruction();
+    }
+
+    pub fn get_accumulator(&self) -> u8 {
+        self.accumulator
+    }
+
+    pub fn set_accumulator(&mut self, value: u8) {
+        self.accumulator = value;
+    }
+
+    pub fn get_x_register(&self) -> u8 {
+        self.x_register
+    }
+
+    pub fn set_x_register(&mut self, value: u8) {
+        self.x_register = value;
+    }
+
+    pub fn get_y_register(&self) -> u8 {
+        self.y_register
+    }
+
+    pub fn set_y_register(&mut self, value: u8) {
+        self.y_register = value;
+    }
+
+    pub fn get_stack_pointer(&self) -> u8 {
+        self.stack_pointer
+    }
+
+    pub fn set_stack_pointer(&mut self, value: u8) {
+        self.stack_pointer = value;
+    }
+
+    pub fn get_status_register(&self) -> u8 {
+        self.status_register
+    }
+
+    /// Set the processor status register
+    /// Parameters: value - New flags; bit 5 always reads as set and B does not exist in P
+    pub fn set_status_register(&mut self, value: u8) {
+        self.set_status_from_stack(value);
+    }
+
+    pub fn get_cycle_count(&self) -> u64 {
+        self.cycle_count
+    }
+
+    /// Get the number of completed instructions
+    /// Returns: Instructions executed since creation
+    pub fn get_instruction_count(&self) -> u64 {
+        self.instruction_count
+    }
+
+    /// Capture the CPU state for a machine snapshot
+    /// Returns: Registers, interrupt lines and the position within the current instruction
+    pub fn snapshot(&self) -> Mos6502State {
+        Mos6502State {
+            accumulator: self.accumulator,
+            x_register: self.x_register,
+            y_register: self.y_register,
+            stack_pointer: self.stack_pointer,
+            program_counter: self.program_counter,
+            status_register: self.status_register,
+            sequence: self.sequence,
+            opcode: self.opcode,
+            operation: self.operation,
+            mode: self.mode,
+            cycle: self.cycle,
+            address: self.address,
+            pointer: self.pointer,
+            operand: self.operand,
+            address_ready_at: self.address_ready_at,
+            base_high: self.base_high,
+            pending: self.pending,
+            irq_line: self.irq_line,
+            nmi_line: self.nmi_line,
+            nmi_pending: self.nmi_pending,
+            waiting: self.waiting,
+            stopped: self.stopped,
+            cycle_count: self.cycle_count,
+            instruction_count: self.instruction_count,
+            last_clock: self.last_clock,
+            is_reset: self.is_reset,
+        }
+    }
+
+    /// Restore a CPU state captured by `snapshot` and present its bus access again
+    /// Parameters: state - CPU state
+    pub fn restore(&mut self, state: &Mos6502State) {
+        self.accumulator = state.accumulator;
+        self.x_register = state.x_register;
+        self.y_register = state.y_register;
+        self.stack_pointer = state.stack_pointer;
+        self.program_counter = state.program_counter;
+        self.status_register = state.status_register;
+        self.sequence = state.sequence;
+        self.opcode = state.opcode;
+        self.operation = state.operation;
+        self.mode = state.mode;
+        self.cycle = state.cycle;
+        self.address = state.address;
+        self.pointer = state.pointer;
+        self.operand = state.operand;
+        self.address_ready_at = state.address_ready_at;
+        self.base_high = state.base_high;
+        self.pending = state.pending;
+        self.irq_line = state.irq_line;
+        self.nmi_line = state.nmi_line;
+        self.nmi_pending = state.nmi_pending;
+        self.waiting = state.waiting;
+        self.stopped = state.stopped;
+        self.cycle_count = state.cycle_count;
+        self.instruction_count = state.instruction_count;
+        self.last_clock = state.last_clock;
+        self.is_reset = state.is_reset;
+        self.drive_bus();
+    }
+
+    /// Drive the interrupt inputs when running without pins
+    /// IRQ is level sensitive; NMI is latched when it becomes asserted and is serviced
+    /// once per assertion.
+    /// Parameters: irq - IRQ asserted, nmi - NMI asserted
+    pub fn set_interrupt_lines(&mut self, irq: bool, nmi: bool) {
+        if nmi && !self.nmi_line {
+            self.nmi_pending = true;
+        }
+        self.irq_line = irq;
+        self.nmi_line = nmi;
+    }
+
+    /// Check whether an NMI is waiting to be serviced
+    /// Returns: true from the NMI edge until its vector is fetched
+    pub fn is_nmi_pending(&self) -> bool {
+        self.nmi_pending
+    }
+
+    /// Check whether WAI suspended the CPU
+    /// Returns: true until IRQ or NMI is asserted
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    /// Check whether STP stopped the CPU
+    /// Returns: true until the next reset
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Force the WAI/STP states
+    /// Parameters: waiting - Suspended until IRQ/NMI, stopped - Suspended until reset
+    pub fn set_low_power_state(&mut self, waiting: bool, stopped: bool) {
+        self.waiting = waiting;
+        self.stopped = stopped;
+    }
+
+    /// Get the opcode of the current or last instruction
+    /// Returns: Opcode byte
+    pub fn get_opcode(&self) -> u8 {
+        self.opcode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// CPU positioned at $0200 with the program loaded there in a 64 KB memory
+    fn cpu_with_program(program: &[u8]) -> (MOS6502, Vec<u8>) {
+        let mut memory = vec![0u8; 0x10000];
+        memory[0x0200..0x0200 + program.len()].copy_from_slice(program);
+        let mut cpu = MOS6502::new("CPU_6502".to_string());
+        cpu.set_program_counter(0x0200);
+        (cpu, memory)
+    }
+
+    #[test]
+    fn test_6502_creation() {
+        let cpu = MOS6502::new("CPU_6502".to_string());
+        assert_eq!(cpu.name(), "CPU_6502");
+        assert!(!cpu.is_running());
+    }
+
+    #[test]
+    fn test_6502_registers() {
+        let mut cpu = MOS6502::new("CPU_6502".to_string());
+
+        cpu.set_program_counter(0x1234);
+        assert_eq!(cpu.get_program_counter(), 0x1234);
+
+        assert_eq!(cpu.get_accumulator(), 0);
+        assert_eq!(cpu.get_x_register(), 0);
+        assert_eq!(cpu.get_y_register(), 0);
+        assert_eq!(cpu.get_stack_pointer(), 0xFD);
+        assert_eq!(cpu.get_status_register(), 0x20);
+    }
+
+    #[test]
+    fn test_6502_reset() {
+        let mut cpu = MOS6502::new("CPU_6502".to_string());
+
+        cpu.set_program_counter(0x1000);
+        cpu.reset();
+
+        assert_eq!(cpu.get_program_counter(), 0xFFFC);
+    }
+
+    #[test]
+    fn test_6502_reset_loads_vector() {
+        let mut memory = vec![0u8; 0x10000];
+        memory[0xFFFC] = 0x34;
+        memory[0xFFFD] = 0x12;
+        let mut cpu = MOS6502::new("CPU_6502".to_string());
+        cpu.reset();
+
+        // Two dummy reads, three stack reads, two vector reads
+        assert_eq!(cpu.step(&mut memory), 7);
+        assert_eq!(cpu.get_program_counter(), 0x1234);
+        assert_eq!(cpu.get_stack_pointer(), 0xFD);
+        assert!(cpu.is_at_instruction_boundary());
+        assert_eq!(cpu.get_status_register() & FLAG_INTERRUPT, FLAG_INTERRUPT);
+        assert_eq!(memory[0x0100], 0);
+    }
+
+    #[test]
+    fn test_6502_documented_cycle_counts() {
+        // Base cycle counts without page crossings or taken branches
+        #[rustfmt::skip]
+        let table: &[(u8, u64)] = &[
+            (0x00, 7), (0x01, 6), (0x05, 3), (0x06, 5), (0x08, 3), (0x09, 2), (0x0A, 2), (0x0D, 4), (0x0E, 6),
+            (0x11, 5), (0x15, 4), (0x16, 6), (0x18, 2), (0x19, 4), (0x1D, 4), (0x1E, 7),
+            (0x20, 6), (0x21, 6), (0x24, 3), (0x25, 3), (0x26, 5), (0x28, 4), (0x29, 2), (0x2A, 2), (0x2C, 4), (0x2D, 4), (0x2E, 6),
+            (0x31, 5), (0x35, 4), (0x36, 6), (0x38, 2), (0x39, 4), (0x3D, 4), (0x3E, 7),
+            (0x40, 6), (0x41, 6), (0x45, 3), (0x46, 5), (0x48, 3), (0x49, 2), (0x4A, 2), (0x4C, 3), (0x4D, 4), (0x4E, 6),
+            (0x51, 5), (0x55, 4), (0x56, 6), (0x58, 2), (0x59, 4), (0x5D, 4), (0x5E, 7),
+            (0x60, 6), (0x61, 6), (0x65, 3), (0x66, 5), (0x68, 4), (0x69, 2), (0x6A, 2), (0x6C, 5), (0x6D, 4), (0x6E, 6),
+            (0x71, 5), (0x75, 4), (0x76, 6), (0x78, 2), (0x79, 4), (0x7D, 4), (0x7E, 7),
+            (0x81, 6), (0x84, 3), (0x85, 3), (0x86, 3), (0x88, 2), (0x8A, 2), (0x8C, 4), (0x8D, 4), (0x8E, 4),
+            (0x91, 6), (0x94, 4), (0x95, 4), (0x96, 4), (0x98, 2), (0x99, 5), (0x9A, 2), (0x9D, 5),
+            (0xA0, 2), (0xA1, 6), (0xA2, 2), (0xA4, 3), (0xA5, 3), (0xA6, 3), (0xA8, 2), (0xA9, 2), (0xAA, 2), (0xAC, 4), (0xAD, 4), (0xAE, 4),
+            (0xB1, 5), (0xB4, 4), (0xB5, 4), (0xB6, 4), (0xB8, 2), (0xB9, 4), (0xBA, 2), (0xBC, 4), (0xBD, 4), (0xBE, 4),
+            (0xC0, 2), (0xC1, 6), (0xC4, 3), (0xC5, 3), (0xC6, 5), (0xC8, 2), (0xC9, 2), (0xCA, 2), (0xCC, 4), (0xCD, 4), (0xCE, 6),
+            (0xD1, 5), (0xD5, 4), (0xD6, 6), (0xD8, 2), (0xD9, 4), (0xDD, 4), (0xDE, 7),
+            (0xE0, 2), (0xE1, 6), (0xE4, 3), (0xE5, 3), (0xE6, 5), (0xE8, 2), (0xE9, 2), (0xEA, 2), (0xEC, 4), (0xED, 4), (0xEE, 6),
+            (0xF1, 5), (0xF5, 4), (0xF6, 6), (0xF8, 2), (0xF9, 4), (0xFD, 4), (0xFE, 7),
+        ];
+        // Plus the eight branches, the 143 entries above make all 151 documented opcodes
+        assert_eq!(table.len(), 143);
+
+        for &(opcode, cycles) in table {
+            let (mut cpu, mut memory) = cpu_with_program(&[opcode, 0x10, 0x00]);
+            assert_eq!(cpu.step(&mut memory), cycles, "opcode 0x{:02X}", opcode);
+        }
+    }
+
+    #[test]
+    fn test_6502_page_crossing_cycles() {
+        // LDA $02F0,X with X = 0x20 crosses into page 3
+        let (mut cpu, mut memory) = cpu_with_program(&[0xBD, 0xF0, 0x02]);
+        memory[0x0310] = 0x77;
+        cpu.set_x_register(0x20);
+        assert_eq!(cpu.step(&mut memory), 5);
+        assert_eq!(cpu.get_accumulator(), 0x77);
+
+        // LDA ($10),Y crossing a page
+        let (mut cpu, mut memory) = cpu_with_program(&[0xB1, 0x10]);
+        memory[0x10] = 0xFF;
+        memory[0x11] = 0x03;
+        memory[0x0401] = 0x55;
+        cpu.set_y_register(0x02);
+        assert_eq!(cpu.step(&mut memory), 6);
+        assert_eq!(cpu.get_accumulator(), 0x55);
+
+        // Zero page indexing wraps inside page zero
+        let (mut cpu, mut memory) = cpu_with_program(&[0xB5, 0xF0]);
+        memory[0x0010] = 0x99;
+        cpu.set_x_register(0x20);
+        assert_eq!(cpu.step(&mut memory), 4);
+        assert_eq!(cpu.get_accumulator(), 0x99);
+    }
+
+    #[test]
+    fn test_6502_branch_cycles() {
+        // Not taken: 2 cycles
+        let (mut cpu, mut memory) = cpu_with_program(&[0xF0, 0x10]); // BEQ
+        assert_eq!(cpu.step(&mut memory), 2);
+        assert_eq!(cpu.get_program_counter(), 0x0202);
+
+        // Taken in the same page: 3 cycles
+        let (mut cpu, mut memory) = cpu_with_program(&[0xD0, 0x10]); // BNE
+        assert_eq!(cpu.step(&mut memory), 3);
+        assert_eq!(cpu.get_program_counter(), 0x0212);
+
+        // Taken backwards into another page: 4 cycles
+        let (mut cpu, mut memory) = cpu_with_program(&[0xD0, 0xF0]); // BNE -16
+        assert_eq!(cpu.step(&mut memory), 4);
+        assert_eq!(cpu.get_program_counter(), 0x01F2);
+    }
+
+    #[test]
+    fn test_6502_read_modify_write_writes_twice() {
+        struct Recorder(Vec<u8>, Vec<(u16, u8)>);
+        impl Bus6502 for Recorder {
+            fn read(&mut self, address: u16) -> u8 {
+                self.0[address as usize]
+            }
+            fn write(&mut self, address: u16, data: u8) {
+                self.1.push((address, data));
+                self.0[address as usize] = data;
+            }
+        }
+
+        let (mut cpu, memory) = cpu_with_program(&[0xEE, 0x00, 0x03]); // INC $0300
+        let mut bus = Recorder(memory, Vec::new());
+        bus.0[0x0300] = 0x41;
+        cpu.step(&mut bus);
+        assert_eq!(bus.1, vec![(0x0300, 0x41), (0x0300, 0x42)]);
+    }
+
+    #[test]
+    fn test_6502_binary_arithmetic_flags() {
+        // 0x50 + 0x50 = 0xA0: signed overflow, no carry
+        let (mut cpu, mut memory) = cpu_with_program(&[0x69, 0x50]);
+        cpu.set_accumulator(0x50);
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_accumulator(), 0xA0);
+        let p = cpu.get_status_register();
+        assert_eq!(
+            p & (FLAG_OVERFLOW | FLAG_NEGATIVE | FLAG_CARRY),
+            FLAG_OVERFLOW | FLAG_NEGATIVE
+        );
+
+        // SEC; SBC #$01 from 0x00 borrows
+        let (mut cpu, mut memory) = cpu_with_program(&[0x38, 0xE9, 0x01]);
+        cpu.step(&mut memory);
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_accumulator(), 0xFF);
+        assert_eq!(cpu.get_status_register() & FLAG_CARRY, 0);
+
+        // CMP sets C for A >= M and Z on equality
+        let (mut cpu, mut memory) = cpu_with_program(&[0xC9, 0x40]);
+        cpu.set_accumulator(0x40);
+        cpu.step(&mut memory);
+        assert_eq!(
+            cpu.get_status_register() & (FLAG_CARRY | FLAG_ZERO),
+            FLAG_CARRY | FLAG_ZERO
+        );
+    }
+
+    #[test]
+    fn test_6502_decimal_mode() {
+        // SED; CLC; LDA #$58; ADC #$46 = $04 carry set
+        let (mut cpu, mut memory) = cpu_with_program(&[0xF8, 0x18, 0xA9, 0x58, 0x69, 0x46]);
+        for _ in 0..4 {
+            cpu.step(&mut memory);
+        }
+        assert_eq!(cpu.get_accumulator(), 0x04);
+        assert_eq!(cpu.get_status_register() & FLAG_CARRY, FLAG_CARRY);
+
+        // SED; SEC; LDA #$12; SBC #$21 = $91 borrow
+        let (mut cpu, mut memory) = cpu_with_program(&[0xF8, 0x38, 0xA9, 0x12, 0xE9, 0x21]);
+        for _ in 0..4 {
+            cpu.step(&mut memory);
+        }
+        assert_eq!(cpu.get_accumulator(), 0x91);
+        assert_eq!(cpu.get_status_register() & FLAG_CARRY, 0);
+
+        // NMOS: Z comes from the binary sum, $99 + $01 = $00 in BCD but Z clear
+        let (mut cpu, mut memory) = cpu_with_program(&[0xF8, 0x18, 0xA9, 0x99, 0x69, 0x01]);
+        for _ in 0..4 {
+            cpu.step(&mut memory);
+        }
+        assert_eq!(cpu.get_accumulator(), 0x00);
+        assert_eq!(
+            cpu.get_status_register() & (FLAG_CARRY | FLAG_ZERO),
+            FLAG_CARRY
+        );
+    }
+
+    #[test]
+    fn test_6502_subroutine_and_stack() {
+        // JSR $0210; (at $0210) PHA; PLA; RTS
+        let (mut cpu, mut memory) = cpu_with_program(&[0x20, 0x10, 0x02]);
+        memory[0x0210..0x0213].copy_from_slice(&[0x48, 0x68, 0x60]);
+        cpu.set_accumulator(0x80);
+
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_program_counter(), 0x0210);
+        assert_eq!(cpu.get_stack_pointer(), 0xFB);
+        assert_eq!(&memory[0x01FC..0x01FE], &[0x02, 0x02]);
+
+        cpu.step(&mut memory);
+        cpu.set_accumulator(0x00);
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_accumulator(), 0x80);
+        assert_ne!(cpu.get_status_register() & FLAG_NEGATIVE, 0);
+
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_program_counter(), 0x0203);
+        assert_eq!(cpu.get_stack_pointer(), 0xFD);
+    }
+
+    #[test]
+    fn test_6502_brk_and_rti() {
+        let (mut cpu, mut memory) = cpu_with_program(&[0x00, 0xFF, 0xEA]);
+        memory[0xFFFE] = 0x00;
+        memory[0xFFFF] = 0x03;
+        memory[0x0300] = 0x40; // RTI
+
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_program_counter(), 0x0300);
+        assert_eq!(memory[0x01FB] & FLAG_BREAK, FLAG_BREAK);
+        assert_ne!(cpu.get_status_register() & FLAG_INTERRUPT, 0);
+
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_program_counter(), 0x0202);
+        assert_eq!(cpu.get_status_register() & FLAG_INTERRUPT, 0);
+    }
+
+    #[test]
+    fn test_6502_irq_masked_by_i_flag() {
+        // SEI; CLI; NOP; NOP with the handler at $0300
+        let (mut cpu, mut memory) = cpu_with_program(&[0x78, 0x58, 0xEA, 0xEA]);
+        memory[0xFFFE] = 0x00;
+        memory[0xFFFF] = 0x03;
+        memory[0x0300] = 0x40; // RTI
+
+        cpu.step(&mut memory);
+        cpu.set_interrupt_lines(true, false);
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_program_counter(), 0x0202);
+
+        // CLI takes effect after the next instruction, then IRQ pushes PC and P
+        assert_eq!(cpu.step(&mut memory), 2 + 7);
+        assert_eq!(cpu.get_program_counter(), 0x0300);
+        assert_eq!(memory[0x01FD], 0x02);
+        assert_eq!(memory[0x01FC], 0x03);
+        assert_eq!(memory[0x01FB] & (FLAG_BREAK | FLAG_INTERRUPT), 0);
+        assert_ne!(cpu.get_status_register() & FLAG_INTERRUPT, 0);
+        assert_eq!(cpu.get_instruction_count(), 3);
+
+        // Inside the handler I masks the still asserted IRQ
+        cpu.set_interrupt_lines(false, false);
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_program_counter(), 0x0203);
+        assert_eq!(cpu.get_status_register() & FLAG_INTERRUPT, 0);
+    }
+
+    #[test]
+    fn test_6502_nmi_edge_triggered() {
+        // SEI; NOP; NOP; NOP with the NMI handler at $0300
+        let (mut cpu, mut memory) = cpu_with_program(&[0x78, 0xEA, 0xEA, 0xEA]);
+        memory[0xFFFA] = 0x00;
+        memory[0xFFFB] = 0x03;
+        memory[0x0300] = 0x40; // RTI
+
+        cpu.step(&mut memory);
+        cpu.set_interrupt_lines(false, true);
+        assert!(cpu.is_nmi_pending());
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_program_counter(), 0x0300);
+        assert!(!cpu.is_nmi_pending());
+
+        // NMI held low is not taken again
+        cpu.step(&mut memory);
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_program_counter(), 0x0203);
+
+        // A new falling edge is
+        cpu.set_interrupt_lines(false, false);
+        cpu.set_interrupt_lines(false, true);
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_program_counter(), 0x0300);
+    }
+
+    #[test]
+    fn test_6502_nmi_takes_over_brk_vector() {
+        let (mut cpu, mut memory) = cpu_with_program(&[0x00, 0xFF]);
+        memory[0xFFFA] = 0x00;
+        memory[0xFFFB] = 0x04;
+        memory[0xFFFE] = 0x00;
+        memory[0xFFFF] = 0x03;
+
+        // NMI arrives while BRK pushes PC: the vector fetch goes to $FFFA
+        cpu.clock(&mut memory);
+        cpu.clock(&mut memory);
+        cpu.set_interrupt_lines(false, true);
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_program_counter(), 0x0400);
+        assert_eq!(memory[0x01FB] & FLAG_BREAK, FLAG_BREAK);
+        assert!(!cpu.is_nmi_pending());
+    }
+
+    #[test]
+    fn test_6502_jmp_indirect_page_bug() {
+        let (mut cpu, mut memory) = cpu_with_program(&[0x6C, 0xFF, 0x03]);
+        memory[0x03FF] = 0x34;
+        memory[0x0300] = 0x12; // High byte comes from $0300, not $0400
+        memory[0x0400] = 0x56;
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_program_counter(), 0x1234);
+    }
+
+    #[test]
+    fn test_6502_run_until_trap() {
+        // LDX #$03; DEX; BNE -3; JMP $0205 (trap)
+        let (mut cpu, mut memory) =
+            cpu_with_program(&[0xA2, 0x03, 0xCA, 0xD0, 0xFD, 0x4C, 0x05, 0x02]);
+        assert_eq!(cpu.run_until_trap(&mut memory, 1000), Ok(0x0205));
+        assert_eq!(cpu.get_x_register(), 0);
+        assert_eq!(cpu.get_instruction_count(), 8);
+    }
+
+    /// NMOS CPU with the undocumented opcodes enabled, positioned like cpu_with_program
+    fn illegal_cpu_with_program(program: &[u8]) -> (MOS6502, Vec<u8>) {
+        let (_, memory) = cpu_with_program(program);
+        let mut cpu =
+            MOS6502::new_with_variant("CPU_6502".to_string(), Mos6502Variant::Nmos6502Illegal);
+        cpu.set_program_counter(0x0200);
+        (cpu, memory)
+    }
+
+    #[test]
+    fn test_6502_illegal_opcode_cycle_counts() {
+        #[rustfmt::skip]
+        let table: &[(u8, u64)] = &[
+            // SLO in every mode, the other read-modify-write combinations share its timing
+            (0x03, 8), (0x07, 5), (0x0F, 6), (0x13, 8), (0x17, 6), (0x1B, 7), (0x1F, 7),
+            (0xC3, 8), (0xE7, 5), (0x7B, 7),
+            (0x83, 6), (0x87, 3), (0x8F, 4), (0x97, 4), // SAX
+            (0xA3, 6), (0xA7, 3), (0xAF, 4), (0xB3, 5), (0xB7, 4), (0xBF, 4), // LAX
+            (0x0B, 2), (0x4B, 2), (0x6B, 2), (0xCB, 2), (0xEB, 2), (0x8B, 2), (0xAB, 2),
+            (0x93, 6), (0x9F, 5), (0x9E, 5), (0x9C, 5), (0x9B, 5), (0xBB, 4),
+            (0x1A, 2), (0x80, 2), (0x04, 3), (0x14, 4), (0x0C, 4), (0x1C, 4), // NOPs
+        ];
+        for &(opcode, cycles) in table {
+            let (mut cpu, mut memory) = illegal_cpu_with_program(&[opcode, 0x10, 0x00]);
+            assert_eq!(cpu.step(&mut memory), cycles, "opcode 0x{:02X}", opcode);
+        }
+
+        // Without the variant they stay 2-cycle NOPs
+        let (mut cpu, mut memory) = cpu_with_program(&[0xA7, 0x10]);
+        assert_eq!(cpu.step(&mut memory), 2);
+        assert_eq!(cpu.get_program_counter(), 0x0201);
+    }
+
+    #[test]
+    fn test_6502_illegal_load_store_and_combinations() {
+        let (mut cpu, mut memory) = illegal_cpu_with_program(&[
+            0xA7, 0x10, // LAX $10
+            0x87, 0x11, // SAX $11 (with X changed below)
+            0xC7, 0x12, // DCP $12
+            0xE7, 0x13, // ISC $13
+            0x07, 0x14, // SLO $14
+            0x67, 0x15, // RRA $15
+        ]);
+        memory[0x10] = 0xF0;
+        memory[0x12] = 0x41;
+        memory[0x13] = 0x0F;
+        memory[0x14] = 0x81;
+        memory[0x15] = 0x02;
+
+        cpu.step(&mut memory);
+        assert_eq!((cpu.get_accumulator(), cpu.get_x_register()), (0xF0, 0xF0));
+        cpu.set_x_register(0x3C);
+        cpu.step(&mut memory);
+        assert_eq!(memory[0x11], 0x30);
+
+        // DCP: $41 - 1 = $40, compared with A = $F0
+        cpu.step(&mut memory);
+        assert_eq!(memory[0x12], 0x40);
+        assert_eq!(cpu.get_status_register() & FLAG_CARRY, FLAG_CARRY);
+
+        // ISC: $0F + 1 = $10, A = $F0 - $10 = $E0
+        cpu.step(&mut memory);
+        assert_eq!(memory[0x13], 0x10);
+        assert_eq!(cpu.get_accumulator(), 0xE0);
+
+        // SLO: $81 << 1 = $02 with carry, A = $E0 | $02
+        cpu.step(&mut memory);
+        assert_eq!(memory[0x14], 0x02);
+        assert_eq!(cpu.get_accumulator(), 0xE2);
+
+        // RRA: $02 ror with carry in = $81, carry out 0, A = $E2 + $81 = $63 carry
+        cpu.step(&mut memory);
+        assert_eq!(memory[0x15], 0x81);
+        assert_eq!(cpu.get_accumulator(), 0x63);
+        assert_eq!(cpu.get_status_register() & FLAG_CARRY, FLAG_CARRY);
+    }
+
+    #[test]
+    fn test_6502_illegal_immediate_opcodes() {
+        let (mut cpu, mut memory) = illegal_cpu_with_program(&[
+            0x0B, 0x80, // ANC #$80
+            0x4B, 0x03, // ALR #$03
+            0x6B, 0xFF, // ARR #$FF
+            0xCB, 0x01, // SBX #$01
+        ]);
+        cpu.set_accumulator(0xC3);
+        cpu.set_x_register(0x0F);
+
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_accumulator(), 0x80);
+        assert_eq!(cpu.get_status_register() & FLAG_CARRY, FLAG_CARRY);
+
+        cpu.set_accumulator(0x07);
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_accumulator(), 0x01);
+        assert_eq!(cpu.get_status_register() & FLAG_CARRY, FLAG_CARRY);
+
+        // ARR: ($01 & $FF) ror with carry in = $80; C = bit 6, V = bit 6 ^ bit 5
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_accumulator(), 0x80);
+        assert_eq!(
+            cpu.get_status_register() & (FLAG_CARRY | FLAG_OVERFLOW | FLAG_NEGATIVE),
+            FLAG_NEGATIVE
+        );
+
+        // SBX: X = (A & X) - imm = ($80 & $0F) - 1 = $FF, borrow
+        cpu.step(&mut memory);
+        assert_eq!(cpu.get_x_register(), 0xFF);
+        assert_eq!(cpu.get_status_register() & FLAG_CARRY, 0);
+    }
+
+    #[test]
+    fn test_6502_jam_until_reset() {
+        let (mut cpu, mut memory) = illegal_cpu_with_program(&[0xE8, 0x02, 0xE8]); // INX; JAM
+        memory[0xFFFC] = 0x00;
+        memory[0xFFFD] = 0x02;
+
+        cpu.step(&mut memory);
+        cpu.step(&mut memory);
+        assert!(cpu.is_stopped());
+        for _ in 0..10 {
+            cpu.step(&mut memory);
+        }
+        assert_eq!(cpu.get_x_register(), 1);
+        assert_eq!(cpu.run_until_trap(&mut memory, 100), Ok(0x0202));
+
+        cpu.reset();
+        cpu.step(&mut memory);
+        assert!(!cpu.is_stopped());
+        assert_eq!(cpu.get_program_counter(), 0x0200);
     }
 }
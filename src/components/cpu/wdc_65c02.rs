@@ -1,65 +1,62 @@
+use std::any::Any;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
 use crate::component::Component;
+use crate::components::cpu::mos_6502::Mos6502Variant;
 use crate::components::cpu::MOS6502;
 use crate::pin::Pin;
 
 /// WDC 65C02 - CMOS version of 6502 with additional instructions
+/// The instruction set and timing live in the shared `MOS6502` core, which this
+/// component runs as its Wdc65C02 variant.
+///
+/// Hardware Architecture:
+/// - BRA, PHX/PHY/PLX/PLY, STZ, TRB/TSB, INC A/DEC A
+/// - (zp) addressing for the ALU group, BIT #imm/zp,X/abs,X, JMP (abs,X)
+/// - Rockwell/WDC bit instructions RMB/SMB and BBR/BBS
+/// - JMP (ind) reads its pointer across a page boundary correctly (one more cycle)
+/// - Decimal mode: N and Z reflect the BCD result, ADC/SBC take one more cycle;
+///   BRK, IRQ, NMI and RES clear D
+/// - Read-modify-write instructions read the operand twice instead of writing it twice
+/// - WAI suspends until IRQ or NMI, STP until RES; an NMI during BRK no longer
+///   takes over its vector
+/// - RDY low stops the CPU in write cycles as well
+/// - Unused opcodes are NOPs of 1 to 3 bytes
+///
+/// Hardware Deviations:
+/// - JMP-style NOP 0x5C takes 4 cycles instead of 8
+/// - WAI ends without the extra cycle of the real part
 pub struct WDC65C02 {
     pub(crate) base: MOS6502,
-    // 65C02-specific state
-    stop_mode: bool,
-    wait_mode: bool,
 }
 
 impl WDC65C02 {
     pub fn new(name: String) -> Self {
         WDC65C02 {
-            base: MOS6502::new(name),
-            stop_mode: false,
-            wait_mode: false,
+            base: MOS6502::new_with_variant(name, Mos6502Variant::Wdc65C02),
         }
     }
 
     // 65C02 specific methods
     pub fn enter_stop_mode(&mut self) {
-        self.stop_mode = true;
-        self.wait_mode = false;
+        self.base.set_low_power_state(false, true);
     }
 
     pub fn enter_wait_mode(&mut self) {
-        self.wait_mode = true;
-        self.stop_mode = false;
+        self.base.set_low_power_state(true, false);
     }
 
     pub fn exit_low_power_modes(&mut self) {
-        self.stop_mode = false;
-        self.wait_mode = false;
+        self.base.set_low_power_state(false, false);
     }
 
     pub fn is_in_stop_mode(&self) -> bool {
-        self.stop_mode
+        self.base.is_stopped()
     }
 
     pub fn is_in_wait_mode(&self) -> bool {
-        self.wait_mode
-    }
-
-    // 65C02 additional instructions would be implemented here
-    fn execute_65c02_instruction(&mut self) {
-        if self.stop_mode {
-            // CPU is stopped - no operation
-            return;
-        }
-
-        if self.wait_mode {
-            // CPU is waiting for interrupt - no operation
-            return;
-        }
-
-        // For compilation, just call the base 6502 implementation
-        // In a full implementation, this would handle 65C02-specific instructions
+        self.base.is_waiting()
     }
 }
 
@@ -76,24 +73,13 @@ impl Component for WDC65C02 {
         self.base.get_pin(name)
     }
 
+    /// Main update cycle - the core handles WAI and STP itself
     fn update(&mut self) {
-        if self.stop_mode || self.wait_mode {
-            // In low-power modes, only check for interrupts
-            let (irq, nmi, reset, _) = self.base.read_control_pins();
-
-            if nmi || reset || (irq && !self.base.get_status_register() & 0x04 == 0) {
-                // Exit low-power modes on interrupt or reset
-                self.exit_low_power_modes();
-            } else {
-                return; // Stay in low-power mode
-            }
-        }
-
-        // Delegate to base 6502 implementation
         self.base.update();
+    }
 
-        // 65C02-specific processing would go here
-        self.execute_65c02_instruction();
+    fn start(&mut self) {
+        self.base.start();
     }
 
     fn run(&mut self) {
@@ -107,6 +93,22 @@ impl Component for WDC65C02 {
     fn is_running(&self) -> bool {
         self.base.is_running()
     }
+
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        self.base.snapshot_state()
+    }
+
+    fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        self.base.restore_state(state)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
 }
 
 // 65C02-specific enhancements
@@ -118,17 +120,44 @@ impl WDC65C02 {
     pub fn get_base_cpu_mut(&mut self) -> &mut MOS6502 {
         &mut self.base
     }
+}
 
-    // Additional 65C02 functionality can be added here
-    // - New addressing modes
-    // - Additional instructions (STZ, BRA, etc.)
-    // - Improved interrupt handling
-    // - Low-power mode control
+/// Get the 6502 core of a component for modification
+/// Parameters: component - Component to inspect
+/// Returns: Some(&mut MOS6502) for a 6502 or the core of a 65C02, None otherwise
+pub fn mos6502_cpu_mut(component: &mut dyn Component) -> Option<&mut MOS6502> {
+    if component.as_any().is::<MOS6502>() {
+        return component.as_any_mut().downcast_mut::<MOS6502>();
+    }
+    component
+        .as_any_mut()
+        .downcast_mut::<WDC65C02>()
+        .map(|cpu| &mut cpu.base)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::components::cpu::mos_6502::{
+        FLAG_BREAK, FLAG_CARRY, FLAG_DECIMAL, FLAG_INTERRUPT, FLAG_ZERO,
+    };
+
+    /// 65C02 positioned at $0200 with the program loaded there in a 64 KB memory
+    fn cpu_with_program(program: &[u8]) -> (WDC65C02, Vec<u8>) {
+        let mut memory = vec![0u8; 0x10000];
+        memory[0x0200..0x0200 + program.len()].copy_from_slice(program);
+        let mut cpu = WDC65C02::new("CPU_65C02".to_string());
+        cpu.get_base_cpu_mut().set_program_counter(0x0200);
+        (cpu, memory)
+    }
+
+    /// Execute instructions and return the cycles of the last one
+    fn run(cpu: &mut WDC65C02, memory: &mut Vec<u8>, instructions: usize) -> u64 {
+        (0..instructions)
+            .map(|_| cpu.get_base_cpu_mut().step(memory))
+            .last()
+            .unwrap_or(0)
+    }
 
     #[test]
     fn test_65c02_creation() {
@@ -168,4 +197,188 @@ mod tests {
         assert_eq!(cpu.get_base_cpu().get_accumulator(), 0);
         assert_eq!(cpu.get_base_cpu().get_x_register(), 0);
     }
+
+    #[test]
+    fn test_65c02_new_instructions() {
+        let (mut cpu, mut memory) = cpu_with_program(&[
+            0xA2, 0x12, // LDX #$12
+            0xDA, // PHX
+            0x7A, // PLY
+            0x64, 0x10, // STZ $10
+            0x1A, // INC A
+            0x80, 0x01, // BRA +1
+            0xEA, // NOP (skipped)
+            0x89, 0x02, // BIT #$02
+        ]);
+        memory[0x10] = 0xFF;
+
+        run(&mut cpu, &mut memory, 3);
+        assert_eq!(cpu.get_base_cpu().get_y_register(), 0x12);
+        run(&mut cpu, &mut memory, 1);
+        assert_eq!(memory[0x10], 0x00);
+        run(&mut cpu, &mut memory, 1);
+        assert_eq!(cpu.get_base_cpu().get_accumulator(), 0x01);
+        assert_eq!(run(&mut cpu, &mut memory, 1), 3);
+        assert_eq!(cpu.get_base_cpu().get_program_counter(), 0x020A);
+
+        // BIT #imm only touches Z
+        run(&mut cpu, &mut memory, 1);
+        assert_eq!(
+            cpu.get_base_cpu().get_status_register() & FLAG_ZERO,
+            FLAG_ZERO
+        );
+    }
+
+    #[test]
+    fn test_65c02_bit_instructions() {
+        let (mut cpu, mut memory) = cpu_with_program(&[
+            0xA9, 0x0C, // LDA #$0C
+            0x04, 0x20, // TSB $20
+            0x14, 0x21, // TRB $21
+            0xC7, 0x22, // SMB4 $22
+            0x37, 0x22, // RMB3 $22
+            0x4F, 0x22, 0x10, // BBR4 $22,+16 (bit set, not taken)
+            0xCF, 0x22, 0x10, // BBS4 $22,+16 (taken)
+        ]);
+        memory[0x20] = 0x01;
+        memory[0x21] = 0xFF;
+        memory[0x22] = 0x08;
+
+        run(&mut cpu, &mut memory, 2);
+        assert_eq!(memory[0x20], 0x0D);
+        assert_eq!(
+            cpu.get_base_cpu().get_status_register() & FLAG_ZERO,
+            FLAG_ZERO
+        );
+        run(&mut cpu, &mut memory, 1);
+        assert_eq!(memory[0x21], 0xF3);
+        assert_eq!(cpu.get_base_cpu().get_status_register() & FLAG_ZERO, 0);
+        assert_eq!(run(&mut cpu, &mut memory, 1), 5);
+        run(&mut cpu, &mut memory, 1);
+        assert_eq!(memory[0x22], 0x10);
+
+        assert_eq!(run(&mut cpu, &mut memory, 1), 5);
+        assert_eq!(cpu.get_base_cpu().get_program_counter(), 0x020D);
+        assert_eq!(run(&mut cpu, &mut memory, 1), 6);
+        assert_eq!(cpu.get_base_cpu().get_program_counter(), 0x0220);
+    }
+
+    #[test]
+    fn test_65c02_indirect_addressing_and_jumps() {
+        // LDA ($30); JMP ($03FF)
+        let (mut cpu, mut memory) = cpu_with_program(&[0xB2, 0x30, 0x6C, 0xFF, 0x03]);
+        memory[0x30] = 0x00;
+        memory[0x31] = 0x05;
+        memory[0x0500] = 0x5A;
+        memory[0x03FF] = 0x00;
+        memory[0x0400] = 0x06; // The 65C02 carries into the next page
+        memory[0x0300] = 0x07;
+
+        assert_eq!(run(&mut cpu, &mut memory, 1), 5);
+        assert_eq!(cpu.get_base_cpu().get_accumulator(), 0x5A);
+        assert_eq!(run(&mut cpu, &mut memory, 1), 6);
+        assert_eq!(cpu.get_base_cpu().get_program_counter(), 0x0600);
+
+        // JMP ($0700,X)
+        memory[0x0600..0x0603].copy_from_slice(&[0x7C, 0x00, 0x07]);
+        memory[0x0704] = 0x34;
+        memory[0x0705] = 0x12;
+        cpu.get_base_cpu_mut().set_x_register(0x04);
+        assert_eq!(run(&mut cpu, &mut memory, 1), 6);
+        assert_eq!(cpu.get_base_cpu().get_program_counter(), 0x1234);
+    }
+
+    #[test]
+    fn test_65c02_cycle_differences() {
+        // ASL abs,X within a page: 6 cycles (7 on the NMOS part); INC abs,X: 7
+        let (mut cpu, mut memory) = cpu_with_program(&[0x1E, 0x00, 0x03, 0xFE, 0x00, 0x03]);
+        assert_eq!(run(&mut cpu, &mut memory, 1), 6);
+        assert_eq!(run(&mut cpu, &mut memory, 1), 7);
+
+        // Unused opcodes: x3 is a 1-cycle NOP, 0x02 skips one byte, 0xDC two
+        let (mut cpu, mut memory) = cpu_with_program(&[0x03, 0x02, 0xFF, 0xDC, 0xFF, 0xFF]);
+        assert_eq!(run(&mut cpu, &mut memory, 1), 1);
+        assert_eq!(run(&mut cpu, &mut memory, 1), 2);
+        assert_eq!(run(&mut cpu, &mut memory, 1), 4);
+        assert_eq!(cpu.get_base_cpu().get_program_counter(), 0x0206);
+    }
+
+    #[test]
+    fn test_65c02_decimal_mode() {
+        // SED; CLC; LDA #$99; ADC #$01 = $00, carry, and on the 65C02 also Z
+        let (mut cpu, mut memory) = cpu_with_program(&[0xF8, 0x18, 0xA9, 0x99, 0x69, 0x01]);
+        run(&mut cpu, &mut memory, 3);
+        assert_eq!(run(&mut cpu, &mut memory, 1), 3);
+        assert_eq!(cpu.get_base_cpu().get_accumulator(), 0x00);
+        assert_eq!(
+            cpu.get_base_cpu().get_status_register() & (FLAG_CARRY | FLAG_ZERO),
+            FLAG_CARRY | FLAG_ZERO
+        );
+
+        // SED; SEC; LDA #$00; SBC #$01 = $99 with borrow
+        let (mut cpu, mut memory) = cpu_with_program(&[0xF8, 0x38, 0xA9, 0x00, 0xE9, 0x01]);
+        run(&mut cpu, &mut memory, 4);
+        assert_eq!(cpu.get_base_cpu().get_accumulator(), 0x99);
+        assert_eq!(cpu.get_base_cpu().get_status_register() & FLAG_CARRY, 0);
+
+        // BRK clears D
+        let (mut cpu, mut memory) = cpu_with_program(&[0xF8, 0x00]);
+        run(&mut cpu, &mut memory, 2);
+        assert_eq!(cpu.get_base_cpu().get_status_register() & FLAG_DECIMAL, 0);
+    }
+
+    #[test]
+    fn test_65c02_wai_and_stp() {
+        let (mut cpu, mut memory) = cpu_with_program(&[0xCB, 0xE8, 0xDB, 0xE8]); // WAI; INX; STP; INX
+        memory[0xFFFC] = 0x00;
+        memory[0xFFFD] = 0x02;
+        cpu.get_base_cpu_mut().set_status_register(FLAG_INTERRUPT);
+
+        assert_eq!(run(&mut cpu, &mut memory, 1), 3);
+        assert!(cpu.is_in_wait_mode());
+        run(&mut cpu, &mut memory, 5);
+        assert_eq!(cpu.get_base_cpu().get_program_counter(), 0x0201);
+
+        // IRQ ends WAI even with interrupts disabled
+        cpu.get_base_cpu_mut().set_interrupt_lines(true, false);
+        run(&mut cpu, &mut memory, 1);
+        cpu.get_base_cpu_mut().set_interrupt_lines(false, false);
+        assert!(!cpu.is_in_wait_mode());
+        assert_eq!(cpu.get_base_cpu().get_x_register(), 1);
+
+        // STP ignores interrupts, only reset restarts the CPU
+        run(&mut cpu, &mut memory, 1);
+        assert!(cpu.is_in_stop_mode());
+        cpu.get_base_cpu_mut().set_interrupt_lines(true, true);
+        run(&mut cpu, &mut memory, 3);
+        assert!(cpu.is_in_stop_mode());
+        assert_eq!(cpu.get_base_cpu().get_x_register(), 1);
+
+        cpu.get_base_cpu_mut().reset();
+        assert!(!cpu.is_in_stop_mode());
+        run(&mut cpu, &mut memory, 1);
+        assert_eq!(cpu.get_base_cpu().get_program_counter(), 0x0200);
+    }
+
+    #[test]
+    fn test_65c02_wai_services_enabled_irq() {
+        let (mut cpu, mut memory) = cpu_with_program(&[0xCB, 0xE8]); // WAI; INX
+        memory[0xFFFE] = 0x00;
+        memory[0xFFFF] = 0x03;
+        memory[0x0300] = 0x40; // RTI
+
+        run(&mut cpu, &mut memory, 2);
+        assert!(cpu.is_in_wait_mode());
+
+        // With I clear the IRQ handler runs first, and RTI returns after WAI
+        cpu.get_base_cpu_mut().set_interrupt_lines(true, false);
+        run(&mut cpu, &mut memory, 1);
+        assert_eq!(cpu.get_base_cpu().get_program_counter(), 0x0300);
+        assert_eq!(cpu.get_base_cpu().get_x_register(), 0);
+        assert_eq!(memory[0x01FB] & FLAG_BREAK, 0);
+
+        cpu.get_base_cpu_mut().set_interrupt_lines(false, false);
+        run(&mut cpu, &mut memory, 2);
+        assert_eq!(cpu.get_base_cpu().get_x_register(), 1);
+    }
 }
@@ -0,0 +1,240 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::component::{BaseComponent, Component, RunnableComponent};
+use crate::pin::{Pin, PinValue};
+
+/// Address decoder for 8-bit microprocessor systems
+/// Combinational glue logic, like a PLA or a 74x138 and some gates: every output
+/// is asserted while the address bus matches its pattern.
+///
+/// Hardware Architecture:
+/// - A0-A15 address inputs
+/// - CS0-CSn chip select outputs, one per pattern, active high to match the
+///   chip select of the 8-bit bus `GenericRam` and `Rom8Bit`
+/// - A pattern lists the address lines from A15 down as '0', '1' or 'x' (don't care);
+///   lines past the end of a shorter pattern are don't care. Spaces and '_' separate
+///   groups, e.g. "1111_1111" selects $FF00-$FFFF
+///
+/// Hardware Deviations:
+/// - No propagation delay: the outputs follow the address lines immediately
+pub struct AddressDecoder {
+    base: BaseComponent,
+    patterns: Vec<String>,    // Patterns as configured, output order
+    decodes: Vec<(u16, u16)>, // (mask, value) of each pattern
+}
+
+impl AddressDecoder {
+    /// Create a new address decoder
+    /// Parameters: name - Component identifier, patterns - One address pattern per CS output
+    /// Returns: Ok(AddressDecoder) with outputs CS0.., Err(String) if a pattern is malformed
+    pub fn new(name: String, patterns: Vec<String>) -> Result<Self, String> {
+        let decodes = patterns
+            .iter()
+            .map(|pattern| Self::parse_pattern(pattern))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let mut pin_names: Vec<String> = (0..16).map(|i| format!("A{}", i)).collect();
+        pin_names.extend((0..patterns.len()).map(|i| format!("CS{}", i)));
+        let pin_refs: Vec<&str> = pin_names.iter().map(String::as_str).collect();
+        let pins = BaseComponent::create_pin_map(&pin_refs, &name);
+
+        Ok(AddressDecoder {
+            base: BaseComponent::new(name, pins),
+            patterns,
+            decodes,
+        })
+    }
+
+    /// Parse an address pattern
+    /// Parameters: pattern - Lines from A15 down as '0', '1' or 'x', with optional '_' or ' '
+    /// Returns: Ok((mask, value)) of the lines the pattern fixes, Err(String) if malformed
+    pub fn parse_pattern(pattern: &str) -> Result<(u16, u16), String> {
+        let lines: Vec<char> = pattern.chars().filter(|c| *c != '_' && *c != ' ').collect();
+        if lines.is_empty() || lines.len() > 16 {
+            return Err(format!(
+                "Address pattern '{}' must cover 1 to 16 lines",
+                pattern
+            ));
+        }
+
+        let mut mask = 0u16;
+        let mut value = 0u16;
+        for (index, line) in lines.iter().enumerate() {
+            let bit = 1u16 << (15 - index);
+            match line {
+                '0' => mask |= bit,
+                '1' => {
+                    mask |= bit;
+                    value |= bit;
+                }
+                'x' | 'X' => {}
+                other => {
+                    return Err(format!(
+                        "Address pattern '{}' has invalid line '{}'",
+                        pattern, other
+                    ))
+                }
+            }
+        }
+        Ok((mask, value))
+    }
+
+    /// Get the configured patterns
+    /// Returns: Pattern of CS0 first
+    pub fn get_patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Check which outputs an address selects
+    /// Parameters: address - Bus address
+    /// Returns: One flag per CS output, true if selected
+    pub fn decode(&self, address: u16) -> Vec<bool> {
+        self.decodes
+            .iter()
+            .map(|(mask, value)| address & mask == *value)
+            .collect()
+    }
+
+    /// Read the address lines
+    /// Returns: Address presented on A0-A15, undriven lines read as low
+    fn read_address_bus(&self) -> u16 {
+        (0..16)
+            .filter(|line| {
+                self.base
+                    .get_pin(&format!("A{}", line))
+                    .ok()
+                    .and_then(|pin| pin.lock().ok().map(|guard| guard.read()))
+                    == Some(PinValue::High)
+            })
+            .fold(0u16, |address, line| address | (1 << line))
+    }
+
+    /// Drive the chip select outputs
+    /// Parameters: selects - Level of each output, None to release them all
+    fn drive_outputs(&self, selects: Option<Vec<bool>>) {
+        for index in 0..self.decodes.len() {
+            if let Ok(pin) = self.base.get_pin(&format!("CS{}", index)) {
+                if let Ok(mut pin_guard) = pin.lock() {
+                    let value = selects
+                        .as_ref()
+                        .map_or(PinValue::HighZ, |s| PinValue::from_bool(s[index]));
+                    pin_guard.set_driver(Some(self.base.name()), value);
+                }
+            }
+        }
+    }
+}
+
+impl Component for AddressDecoder {
+    fn name(&self) -> String {
+        self.base.name()
+    }
+
+    fn pins(&self) -> HashMap<String, Arc<Mutex<Pin>>> {
+        self.base.pins()
+    }
+
+    fn get_pin(&self, name: &str) -> Result<Arc<Mutex<Pin>>, String> {
+        self.base.get_pin(name)
+    }
+
+    /// Main update cycle - follows the address lines
+    fn update(&mut self) {
+        let selects = self.decode(self.read_address_bus());
+        self.drive_outputs(Some(selects));
+    }
+
+    fn start(&mut self) {
+        self.base.set_running(true);
+    }
+
+    /// Run component in time-slice mode
+    fn run(&mut self) {
+        self.start();
+
+        while self.is_running() {
+            self.update();
+            thread::sleep(Duration::from_micros(1));
+        }
+    }
+
+    /// Stop component and release the outputs
+    fn stop(&mut self) {
+        self.base.set_running(false);
+        self.drive_outputs(None);
+    }
+
+    fn is_running(&self) -> bool {
+        self.base.is_running()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl RunnableComponent for AddressDecoder {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_decoder_patterns() {
+        assert_eq!(AddressDecoder::parse_pattern("0"), Ok((0x8000, 0x0000)));
+        assert_eq!(
+            AddressDecoder::parse_pattern("1111_1111"),
+            Ok((0xFF00, 0xFF00))
+        );
+        assert_eq!(
+            AddressDecoder::parse_pattern("110x 0000 0000 0001"),
+            Ok((0xEFFF, 0xC001))
+        );
+        assert!(AddressDecoder::parse_pattern("").is_err());
+        assert!(AddressDecoder::parse_pattern("10201").is_err());
+        assert!(AddressDecoder::parse_pattern("11111111111111111").is_err());
+    }
+
+    #[test]
+    fn test_address_decoder_drives_chip_selects() {
+        let mut decoder = AddressDecoder::new(
+            "DECODER".to_string(),
+            vec!["0".to_string(), "1111".to_string()],
+        )
+        .unwrap();
+        assert!(decoder.get_pin("CS1").is_ok());
+        assert!(decoder.get_pin("CS2").is_err());
+        assert_eq!(decoder.decode(0x8000), vec![false, false]);
+
+        let read_cs = |decoder: &AddressDecoder, pin: &str| {
+            decoder.get_pin(pin).unwrap().lock().unwrap().read()
+        };
+        for (address, cs0, cs1) in [(0x1234u16, true, false), (0xF000, false, true)] {
+            for line in 0..16 {
+                decoder
+                    .get_pin(&format!("A{}", line))
+                    .unwrap()
+                    .lock()
+                    .unwrap()
+                    .set_driver(
+                        Some("TEST".to_string()),
+                        PinValue::from_bool((address >> line) & 1 == 1),
+                    );
+            }
+            decoder.update();
+            assert_eq!(read_cs(&decoder, "CS0"), PinValue::from_bool(cs0));
+            assert_eq!(read_cs(&decoder, "CS1"), PinValue::from_bool(cs1));
+        }
+
+        decoder.stop();
+        assert_eq!(read_cs(&decoder, "CS0"), PinValue::HighZ);
+    }
+}
@@ -0,0 +1,504 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::component::{self, BaseComponent, Component, RunnableComponent};
+use crate::components::common::intel_400x::{
+    BusSubcycle, Intel400xBusCycle, Intel400xClockHandling, Intel400xControlPins, Intel400xDataBus,
+    Intel400xResetHandling,
+};
+use crate::pin::{Pin, PinValue};
+use serde::{Deserialize, Serialize};
+
+/// Intel 4008 - Address Latch for Standard Memory
+/// Part of the MCS-4 family; together with the 4009 it lets the 4004 run from standard
+/// byte-wide ROM or RAM instead of 4001s
+///
+/// Hardware Architecture:
+/// - Latches the 12-bit fetch address sent by the CPU during A1-A3 and holds it on
+///   A0-A7 (low 8 bits) and C0-C3 (page, i.e. the chip select of a 4001 system)
+/// - Latches the 8-bit address sent by SRC during X2-X3
+/// - For a WPM or the 4040's RPM instruction the SRC address is put on A0-A7 so the
+///   4009 can write or read the program memory location it names
+///
+/// Hardware Deviations:
+/// - The page for WPM and RPM is read from the PAGE0-PAGE3 inputs, standing in for the
+///   external page latch of a 4008/4009 board (for example a 4002 output port);
+///   undriven PAGE inputs select page 0
+/// - The I/O port select outputs used with 4009 I/O instructions are not modeled
+pub struct Intel4008 {
+    base: BaseComponent,
+    address: u16,                   // 12-bit address driven on C0-C3 and A0-A7
+    src_address: u8,                // Address sent by the last SRC instruction
+    address_low_nibble: Option<u8>, // Fetch address bits 0-3 from A1
+    address_mid_nibble: Option<u8>, // Fetch address bits 4-7 from A2
+    src_high_nibble: Option<u8>,    // SRC address bits 4-7 from X2
+    // Clock edge detection
+    prev_phi1: PinValue, // Previous Φ1 clock state for edge detection
+    prev_phi2: PinValue, // Previous Φ2 clock state for edge detection
+    // Instruction cycle tracking
+    subcycle: BusSubcycle,      // Current subcycle of the CPU instruction cycle
+    opr_latch: u8,              // Opcode high nibble seen during M1
+    opa_latch: u8,              // Opcode low nibble seen during M2
+    io_instruction: Option<u8>, // I/O opcode of this cycle (CM at M2, or at X1 for RPM)
+}
+
+/// Snapshot of the address latches and bus cycle state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Intel4008State {
+    address: u16,
+    src_address: u8,
+    address_low_nibble: Option<u8>,
+    address_mid_nibble: Option<u8>,
+    src_high_nibble: Option<u8>,
+    prev_phi1: PinValue,
+    prev_phi2: PinValue,
+    subcycle: BusSubcycle,
+    opr_latch: u8,
+    opa_latch: u8,
+    io_instruction: Option<u8>,
+}
+
+impl Intel400xClockHandling for Intel4008 {
+    fn get_base(&self) -> &BaseComponent {
+        &self.base
+    }
+}
+
+impl Intel400xDataBus for Intel4008 {
+    fn get_base(&self) -> &BaseComponent {
+        &self.base
+    }
+}
+
+impl Intel400xControlPins for Intel4008 {
+    fn get_base(&self) -> &BaseComponent {
+        &self.base
+    }
+}
+
+impl Intel400xBusCycle for Intel4008 {}
+
+impl Intel400xResetHandling for Intel4008 {
+    fn get_base(&self) -> &BaseComponent {
+        &self.base
+    }
+
+    fn perform_reset(&mut self) {
+        self.address_low_nibble = None;
+        self.address_mid_nibble = None;
+        self.src_high_nibble = None;
+        self.src_address = 0;
+        self.subcycle = BusSubcycle::X3;
+        self.io_instruction = None;
+        self.drive_address(0);
+    }
+}
+
+impl Intel4008 {
+    /// Create a new Intel 4008 Address Latch
+    /// Parameters: name - Component identifier
+    /// Returns: New Intel4008 instance driving address 0
+    pub fn new(name: String) -> Self {
+        // Intel 4008 pinout:
+        // - D0-D3: MCS-4 data bus (input only)
+        // - SYNC, CM (CM-ROM), RESET, PHI1, PHI2: MCS-4 control and clock inputs
+        // - A0-A7: Low 8 bits of the memory address
+        // - C0-C3: Page of the memory address
+        // - PAGE0-PAGE3: Page for WPM writes
+        let pin_names = vec![
+            "D0", "D1", "D2", "D3", // Data bus
+            "SYNC", "CM", "RESET", "PHI1", "PHI2", // Control and clock
+            "A0", "A1", "A2", "A3", "A4", "A5", "A6", "A7", // Address outputs
+            "C0", "C1", "C2", "C3", // Page outputs
+            "PAGE0", "PAGE1", "PAGE2", "PAGE3", // WPM page inputs
+        ];
+
+        let pins = BaseComponent::create_pin_map(&pin_names, &name);
+
+        Intel4008 {
+            base: BaseComponent::new(name, pins),
+            address: 0,
+            src_address: 0,
+            address_low_nibble: None,
+            address_mid_nibble: None,
+            src_high_nibble: None,
+            prev_phi1: PinValue::Low,
+            prev_phi2: PinValue::Low,
+            subcycle: BusSubcycle::X3,
+            opr_latch: 0,
+            opa_latch: 0,
+            io_instruction: None,
+        }
+    }
+
+    /// Drive a 12-bit address on C0-C3 and A0-A7
+    /// Parameters: address - Address to hold until the next one is latched
+    fn drive_address(&mut self, address: u16) {
+        self.address = address & 0x0FFF;
+        for line in 0..12 {
+            let pin_name = if line < 8 {
+                format!("A{}", line)
+            } else {
+                format!("C{}", line - 8)
+            };
+            if let Ok(pin) = self.base.get_pin(&pin_name) {
+                if let Ok(mut pin_guard) = pin.lock() {
+                    let value = PinValue::from_bool((self.address >> line) & 1 == 1);
+                    pin_guard.set_driver(Some(format!("{}_ADDRESS", self.base.name())), value);
+                }
+            }
+        }
+    }
+
+    /// Read the page for WPM and RPM from PAGE0-PAGE3
+    /// Returns: 4-bit page number, undriven inputs read as 0
+    fn read_write_page(&self) -> u8 {
+        (0..4)
+            .filter(|line| {
+                self.base
+                    .get_pin(&format!("PAGE{}", line))
+                    .ok()
+                    .and_then(|pin| pin.lock().ok().map(|guard| guard.read()))
+                    == Some(PinValue::High)
+            })
+            .fold(0u8, |page, line| page | (1 << line))
+    }
+
+    /// Handle Φ1 rising edge - Start of the next bus subcycle
+    fn handle_phi1_rising(&mut self) {
+        self.handle_reset("RESET");
+        self.subcycle = self.next_subcycle(self.subcycle);
+    }
+
+    /// Handle Φ2 rising edge - Sample the bus
+    /// Hardware: The fetch address arrives during A1-A3, the opcode during M1-M2 and the
+    /// SRC address during X2-X3
+    fn handle_phi2_rising(&mut self) {
+        let data = self.read_data_bus();
+        match self.subcycle {
+            BusSubcycle::A1 => self.address_low_nibble = Some(data),
+            BusSubcycle::A2 => self.address_mid_nibble = Some(data),
+            BusSubcycle::A3 => {
+                // CM-ROM accompanies the page nibble of a program memory access
+                if let (true, Some(low), Some(mid)) = (
+                    self.read_cm_rom_pin(),
+                    self.address_low_nibble.take(),
+                    self.address_mid_nibble.take(),
+                ) {
+                    let address = ((data as u16) << 8) | ((mid as u16) << 4) | low as u16;
+                    self.drive_address(address);
+                }
+            }
+            BusSubcycle::M1 => self.opr_latch = data,
+            BusSubcycle::M2 => {
+                // CM during M2 marks an I/O instruction, OPA selects which one
+                self.opa_latch = data;
+                self.io_instruction = if self.read_cm_rom_pin() && self.opr_latch == 0xE {
+                    Some(0xE0 | data)
+                } else {
+                    None
+                };
+                if self.io_instruction == Some(0xE3) {
+                    // WPM: address the location named by the last SRC
+                    self.select_program_ram_address();
+                }
+            }
+            BusSubcycle::X1 => {
+                // RPM's OPR is 0, so the 4040 flags it with CM once the whole opcode is in
+                if self.read_cm_rom_pin() && self.opr_latch == 0x0 && self.opa_latch == 0xE {
+                    self.io_instruction = Some(0x0E);
+                    self.select_program_ram_address();
+                }
+            }
+            BusSubcycle::X2 => {
+                // SRC: address bits 4-7 with CM
+                self.src_high_nibble = if self.read_cm_rom_pin() {
+                    Some(data)
+                } else {
+                    None
+                };
+            }
+            BusSubcycle::X3 => {
+                if let Some(high) = self.src_high_nibble.take() {
+                    self.src_address = (high << 4) | data;
+                }
+            }
+        }
+    }
+
+    /// Put the program memory location for WPM or RPM on the address lines
+    /// Hardware: The page comes from PAGE0-PAGE3, the low 8 bits from the last SRC.
+    /// A CPU in functional mode calls this directly instead of running a bus cycle.
+    /// Returns: 12-bit address now driven to memory
+    pub fn select_program_ram_address(&mut self) -> u16 {
+        let address = ((self.read_write_page() as u16) << 8) | self.src_address as u16;
+        self.drive_address(address);
+        self.address
+    }
+
+    /// Latch an SRC address without a bus cycle, for a CPU in functional mode
+    /// Parameters: address - Register pair sent by SRC
+    pub fn select_src_address(&mut self, address: u8) {
+        self.src_address = address;
+    }
+
+    /// Get the address currently driven to memory
+    /// Returns: 12-bit address, page in bits 8-11
+    pub fn get_address(&self) -> u16 {
+        self.address
+    }
+
+    /// Get the address sent by the last SRC instruction
+    /// Returns: 8-bit SRC address
+    pub fn get_src_address(&self) -> u8 {
+        self.src_address
+    }
+
+    /// Get the current subcycle of the instruction cycle
+    /// Returns: Subcycle the chip is tracking, X3 after reset so the next SYNC starts A1
+    pub fn get_subcycle(&self) -> BusSubcycle {
+        self.subcycle
+    }
+
+    /// Capture the address latches for a machine snapshot
+    /// Returns: Fetch and SRC addresses and bus cycle state
+    pub fn snapshot(&self) -> Intel4008State {
+        Intel4008State {
+            address: self.address,
+            src_address: self.src_address,
+            address_low_nibble: self.address_low_nibble,
+            address_mid_nibble: self.address_mid_nibble,
+            src_high_nibble: self.src_high_nibble,
+            prev_phi1: self.prev_phi1,
+            prev_phi2: self.prev_phi2,
+            subcycle: self.subcycle,
+            opr_latch: self.opr_latch,
+            opa_latch: self.opa_latch,
+            io_instruction: self.io_instruction,
+        }
+    }
+
+    /// Restore address latches captured by `snapshot` and drive the address to match
+    /// Parameters: state - Address latch state
+    pub fn restore(&mut self, state: &Intel4008State) {
+        self.src_address = state.src_address;
+        self.address_low_nibble = state.address_low_nibble;
+        self.address_mid_nibble = state.address_mid_nibble;
+        self.src_high_nibble = state.src_high_nibble;
+        self.prev_phi1 = state.prev_phi1;
+        self.prev_phi2 = state.prev_phi2;
+        self.subcycle = state.subcycle;
+        self.opr_latch = state.opr_latch;
+        self.opa_latch = state.opa_latch;
+        self.io_instruction = state.io_instruction;
+        self.drive_address(state.address);
+    }
+}
+
+impl Component for Intel4008 {
+    fn name(&self) -> String {
+        self.base.name()
+    }
+
+    fn pins(&self) -> HashMap<String, Arc<Mutex<Pin>>> {
+        self.base.pins()
+    }
+
+    fn get_pin(&self, name: &str) -> Result<Arc<Mutex<Pin>>, String> {
+        self.base.get_pin(name)
+    }
+
+    /// Main update cycle - handles clock edge detection and operation dispatch
+    fn update(&mut self) {
+        if !self.is_running() {
+            return;
+        }
+        let phi1_rising = self.is_phi1_rising_edge(self.prev_phi1);
+        let phi2_rising = self.is_phi2_rising_edge(self.prev_phi2);
+
+        // Update clock states for next edge detection
+        let (phi1, phi2) = self.read_clock_pins();
+        self.prev_phi1 = phi1;
+        self.prev_phi2 = phi2;
+
+        if phi1_rising {
+            self.handle_phi1_rising();
+        }
+
+        if phi2_rising {
+            self.handle_phi2_rising();
+        }
+    }
+
+    /// Mark component running for an external scheduler and drive the address lines
+    fn start(&mut self) {
+        self.base.set_running(true);
+
+        let (phi1, phi2) = self.read_clock_pins();
+        self.prev_phi1 = phi1;
+        self.prev_phi2 = phi2;
+        self.drive_address(self.address);
+    }
+
+    /// Run component in time-slice mode
+    fn run(&mut self) {
+        self.start();
+
+        while self.is_running() {
+            self.update();
+            thread::sleep(Duration::from_micros(1));
+        }
+    }
+
+    fn stop(&mut self) {
+        self.base.set_running(false);
+    }
+
+    fn is_running(&self) -> bool {
+        self.base.is_running()
+    }
+
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        component::state_to_json(&self.snapshot())
+    }
+
+    fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        let state = component::state_from_json(&self.base.name(), state)?;
+        self.restore(&state);
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl RunnableComponent for Intel4008 {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Drive a pin of the chip from the test bench
+    fn drive(chip: &Intel4008, pin: &str, value: PinValue) {
+        chip.get_pin(pin)
+            .unwrap()
+            .lock()
+            .unwrap()
+            .set_driver(Some("TEST".to_string()), value);
+    }
+
+    /// Run one subcycle: Φ1 with the given SYNC/CM/bus values, then Φ2
+    fn clock_subcycle(chip: &mut Intel4008, sync: bool, cm: bool, data: u8) {
+        drive(chip, "SYNC", PinValue::from_bool(sync));
+        drive(chip, "CM", PinValue::from_bool(cm));
+        for i in 0..4 {
+            drive(
+                chip,
+                &format!("D{}", i),
+                PinValue::from_bool((data >> i) & 1 == 1),
+            );
+        }
+
+        drive(chip, "PHI2", PinValue::Low);
+        drive(chip, "PHI1", PinValue::High);
+        chip.update();
+        drive(chip, "PHI1", PinValue::Low);
+        drive(chip, "PHI2", PinValue::High);
+        chip.update();
+    }
+
+    /// Run a complete instruction cycle fetched from `address`
+    fn run_instruction_cycle(chip: &mut Intel4008, address: u16, opcode: u8, x2: u8, x3: u8) {
+        let io = opcode >> 4 == 0xE;
+        let src = opcode & 0xF1 == 0x21;
+        clock_subcycle(chip, true, false, (address & 0x0F) as u8);
+        clock_subcycle(chip, false, false, ((address >> 4) & 0x0F) as u8);
+        clock_subcycle(chip, false, true, (address >> 8) as u8);
+        clock_subcycle(chip, false, false, opcode >> 4);
+        clock_subcycle(chip, false, io, opcode & 0x0F);
+        clock_subcycle(chip, false, false, 0);
+        clock_subcycle(chip, false, src, x2);
+        clock_subcycle(chip, false, false, x3);
+    }
+
+    fn read_pins(chip: &Intel4008, prefix: &str, width: usize) -> u16 {
+        (0..width)
+            .filter(|i| {
+                chip.get_pin(&format!("{}{}", prefix, i))
+                    .unwrap()
+                    .lock()
+                    .unwrap()
+                    .read()
+                    == PinValue::High
+            })
+            .fold(0, |value, i| value | (1 << i))
+    }
+
+    fn create_running_chip() -> Intel4008 {
+        let mut chip = Intel4008::new("ADDR_4008".to_string());
+        drive(&chip, "PHI1", PinValue::Low);
+        drive(&chip, "PHI2", PinValue::Low);
+        chip.start();
+        chip
+    }
+
+    #[test]
+    fn test_fetch_address_latching() {
+        let mut chip = create_running_chip();
+
+        run_instruction_cycle(&mut chip, 0x3A5, 0xD0, 0, 0);
+
+        assert_eq!(chip.get_address(), 0x3A5);
+        assert_eq!(read_pins(&chip, "A", 8), 0xA5);
+        assert_eq!(read_pins(&chip, "C", 4), 0x3);
+        assert_eq!(chip.get_subcycle(), BusSubcycle::X3);
+    }
+
+    #[test]
+    fn test_wpm_addresses_src_location() {
+        let mut chip = create_running_chip();
+        drive(&chip, "PAGE1", PinValue::High);
+
+        // SRC sends the write address, WPM puts it on the address lines with the page
+        run_instruction_cycle(&mut chip, 0x000, 0x21, 0x4, 0xC);
+        assert_eq!(chip.get_src_address(), 0x4C);
+        run_instruction_cycle(&mut chip, 0x001, 0xE3, 0x7, 0);
+        assert_eq!(chip.get_address(), 0x24C);
+        assert_eq!(read_pins(&chip, "A", 8), 0x4C);
+        assert_eq!(read_pins(&chip, "C", 4), 0x2);
+
+        // The next fetch puts the program address back
+        run_instruction_cycle(&mut chip, 0x002, 0xD0, 0, 0);
+        assert_eq!(chip.get_address(), 0x002);
+    }
+
+    #[test]
+    fn test_rpm_addresses_src_location_after_cm_at_x1() {
+        let mut chip = create_running_chip();
+        drive(&chip, "PAGE0", PinValue::High);
+        run_instruction_cycle(&mut chip, 0x000, 0x21, 0x4, 0xC);
+
+        // Without CM at X1 (a 4004 has no RPM) the fetch address stays
+        run_instruction_cycle(&mut chip, 0x001, 0x0E, 0, 0);
+        assert_eq!(chip.get_address(), 0x001);
+
+        // The 4040 flags RPM with CM during X1
+        clock_subcycle(&mut chip, true, false, 0x2);
+        clock_subcycle(&mut chip, false, false, 0x0);
+        clock_subcycle(&mut chip, false, true, 0x0);
+        clock_subcycle(&mut chip, false, false, 0x0);
+        clock_subcycle(&mut chip, false, false, 0xE);
+        assert_eq!(chip.get_address(), 0x002);
+        clock_subcycle(&mut chip, false, true, 0x0);
+        assert_eq!(chip.get_address(), 0x14C);
+        assert_eq!(read_pins(&chip, "C", 4), 0x1);
+    }
+}
@@ -0,0 +1,518 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::component::{self, BaseComponent, Component, RunnableComponent};
+use crate::components::common::intel_400x::{
+    BusSubcycle, Intel400xBusCycle, Intel400xClockHandling, Intel400xControlPins, Intel400xDataBus,
+    Intel400xResetHandling,
+};
+use crate::pin::{Pin, PinValue};
+use serde::{Deserialize, Serialize};
+
+/// Intel 4009 - Program and I/O Access Converter for Standard Memory
+/// Part of the MCS-4 family; works with the 4008 so the 4004 can use standard
+/// byte-wide ROM or RAM as program memory
+///
+/// Hardware Architecture:
+/// - Reads the instruction byte addressed by the 4008 from M0-M7 and sends it to the
+///   CPU as two nibbles, OPR during M1 and OPA during M2
+/// - WPM (write program memory) sends one nibble per instruction: the first WPM
+///   supplies the high nibble, the second the low nibble of the byte to write
+/// - The 4040's RPM (read program memory) reads the byte at the address the 4008 holds
+///   and sends one nibble per instruction during X2, the high nibble first
+///
+/// Hardware Deviations:
+/// - The real part writes each WPM nibble on its own; here the first nibble is held
+///   and the second WPM writes the whole byte with one W strobe, so a plain byte-wide
+///   RAM can be used as program memory
+/// - The 4-bit I/O port used by WRR/RDR is not modeled
+pub struct Intel4009 {
+    base: BaseComponent,
+    fetched_byte: u8,            // Instruction byte read from M0-M7 at M1
+    wpm_high_nibble: Option<u8>, // High nibble sent by the first of a pair of WPMs
+    rpm_low_nibble: bool,        // Next RPM reads the low nibble
+    writing: bool,               // W strobe active
+    // Clock edge detection
+    prev_phi1: PinValue, // Previous Φ1 clock state for edge detection
+    prev_phi2: PinValue, // Previous Φ2 clock state for edge detection
+    // Instruction cycle tracking
+    subcycle: BusSubcycle,      // Current subcycle of the CPU instruction cycle
+    opr_latch: u8,              // Opcode high nibble seen during M1
+    io_instruction: Option<u8>, // I/O opcode of this cycle (CM at M2, or at X1 for RPM)
+}
+
+/// Snapshot of the fetched byte, WPM and RPM nibble state and bus cycle state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Intel4009State {
+    fetched_byte: u8,
+    wpm_high_nibble: Option<u8>,
+    rpm_low_nibble: bool,
+    writing: bool,
+    prev_phi1: PinValue,
+    prev_phi2: PinValue,
+    subcycle: BusSubcycle,
+    opr_latch: u8,
+    io_instruction: Option<u8>,
+}
+
+impl Intel400xClockHandling for Intel4009 {
+    fn get_base(&self) -> &BaseComponent {
+        &self.base
+    }
+}
+
+impl Intel400xDataBus for Intel4009 {
+    fn get_base(&self) -> &BaseComponent {
+        &self.base
+    }
+}
+
+impl Intel400xControlPins for Intel4009 {
+    fn get_base(&self) -> &BaseComponent {
+        &self.base
+    }
+}
+
+impl Intel400xBusCycle for Intel4009 {}
+
+impl Intel400xResetHandling for Intel4009 {
+    fn get_base(&self) -> &BaseComponent {
+        &self.base
+    }
+
+    fn perform_reset(&mut self) {
+        self.tri_state_data_bus();
+        self.end_write();
+        self.wpm_high_nibble = None;
+        self.rpm_low_nibble = false;
+        self.subcycle = BusSubcycle::X3;
+        self.io_instruction = None;
+    }
+}
+
+impl Intel4009 {
+    /// Create a new Intel 4009 Access Converter
+    /// Parameters: name - Component identifier
+    /// Returns: New Intel4009 instance
+    pub fn new(name: String) -> Self {
+        // Intel 4009 pinout:
+        // - D0-D3: MCS-4 data bus
+        // - SYNC, CM (CM-ROM), RESET, PHI1, PHI2: MCS-4 control and clock inputs
+        // - M0-M7: Data lines of the standard memory
+        // - W: Write strobe for program memory
+        let pin_names = vec![
+            "D0", "D1", "D2", "D3", // Data bus
+            "SYNC", "CM", "RESET", "PHI1", "PHI2", // Control and clock
+            "M0", "M1", "M2", "M3", "M4", "M5", "M6", "M7", // Memory data
+            "W",  // Write strobe
+        ];
+
+        let pins = BaseComponent::create_pin_map(&pin_names, &name);
+
+        Intel4009 {
+            base: BaseComponent::new(name, pins),
+            fetched_byte: 0,
+            wpm_high_nibble: None,
+            rpm_low_nibble: false,
+            writing: false,
+            prev_phi1: PinValue::Low,
+            prev_phi2: PinValue::Low,
+            subcycle: BusSubcycle::X3,
+            opr_latch: 0,
+            io_instruction: None,
+        }
+    }
+
+    /// Drive a pin with this chip's memory-side driver
+    fn drive_memory_pin(&self, name: &str, value: PinValue) {
+        if let Ok(pin) = self.base.get_pin(name) {
+            if let Ok(mut pin_guard) = pin.lock() {
+                pin_guard.set_driver(Some(format!("{}_MEMORY", self.base.name())), value);
+            }
+        }
+    }
+
+    /// Read the byte on the memory data lines M0-M7
+    /// Returns: Byte driven by the addressed memory, undriven lines read as 0
+    fn read_memory_bus(&self) -> u8 {
+        (0..8)
+            .filter(|line| {
+                self.base
+                    .get_pin(&format!("M{}", line))
+                    .ok()
+                    .and_then(|pin| pin.lock().ok().map(|guard| guard.read()))
+                    == Some(PinValue::High)
+            })
+            .fold(0u8, |data, line| data | (1 << line))
+    }
+
+    /// Drive a byte on M0-M7 and raise W
+    /// Parameters: data - Byte to write to program memory
+    fn start_write(&mut self, data: u8) {
+        for line in 0..8 {
+            self.drive_memory_pin(
+                &format!("M{}", line),
+                PinValue::from_bool((data >> line) & 1 == 1),
+            );
+        }
+        self.drive_memory_pin("W", PinValue::High);
+        self.writing = true;
+    }
+
+    /// Drop W and release M0-M7
+    fn end_write(&mut self) {
+        self.drive_memory_pin("W", PinValue::Low);
+        for line in 0..8 {
+            self.drive_memory_pin(&format!("M{}", line), PinValue::HighZ);
+        }
+        self.writing = false;
+    }
+
+    /// Handle Φ1 rising edge - Start of the next bus subcycle
+    /// Hardware: The instruction byte goes out as OPR during M1 and OPA during M2, and
+    /// the program memory nibble read by RPM during X2
+    fn handle_phi1_rising(&mut self) {
+        self.handle_reset("RESET");
+
+        self.subcycle = self.next_subcycle(self.subcycle);
+        if self.writing {
+            self.end_write();
+        }
+        match self.subcycle {
+            BusSubcycle::M1 => {
+                // The 4008 has held the fetch address since A3
+                self.fetched_byte = self.read_memory_bus();
+                self.write_data_bus(self.fetched_byte >> 4);
+            }
+            BusSubcycle::M2 => self.write_data_bus(self.fetched_byte & 0x0F),
+            BusSubcycle::X2 if self.io_instruction == Some(0x0E) => {
+                // The 4008 has held the RPM address since X1
+                let nibble = self.read_program_nibble(self.read_memory_bus());
+                self.write_data_bus(nibble);
+            }
+            _ => self.tri_state_data_bus(),
+        }
+    }
+
+    /// Handle Φ2 rising edge - Sample the bus
+    /// Hardware: CM during M2 flags an I/O instruction and CM during X1 the 4040's RPM;
+    /// WPM data arrives during X2
+    fn handle_phi2_rising(&mut self) {
+        match self.subcycle {
+            BusSubcycle::M1 => self.opr_latch = self.read_data_bus(),
+            BusSubcycle::M2 => {
+                self.io_instruction = if self.read_cm_rom_pin() && self.opr_latch == 0xE {
+                    Some(0xE0 | self.read_data_bus())
+                } else {
+                    None
+                };
+            }
+            BusSubcycle::X1 if self.read_cm_rom_pin() && self.fetched_byte == 0x0E => {
+                self.io_instruction = Some(0x0E);
+            }
+            BusSubcycle::X2 if self.io_instruction == Some(0xE3) => {
+                if let Some(byte) = self.write_program_nibble(self.read_data_bus()) {
+                    self.start_write(byte);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Take the nibble of a WPM instruction
+    /// A CPU in functional mode calls this directly instead of running a bus cycle.
+    /// Parameters: nibble - Accumulator sent by WPM
+    /// Returns: Some(byte) to write on the second WPM of a pair, None on the first
+    pub fn write_program_nibble(&mut self, nibble: u8) -> Option<u8> {
+        match self.wpm_high_nibble.take() {
+            None => {
+                self.wpm_high_nibble = Some(nibble & 0x0F);
+                None
+            }
+            Some(high) => Some((high << 4) | (nibble & 0x0F)),
+        }
+    }
+
+    /// Pick the nibble an RPM instruction returns
+    /// A CPU in functional mode calls this directly instead of running a bus cycle.
+    /// Parameters: byte - Program memory byte at the address the 4008 holds
+    /// Returns: High nibble on the first RPM of a pair, low nibble on the second
+    pub fn read_program_nibble(&mut self, byte: u8) -> u8 {
+        let nibble = if self.rpm_low_nibble {
+            byte & 0x0F
+        } else {
+            byte >> 4
+        };
+        self.rpm_low_nibble = !self.rpm_low_nibble;
+        nibble
+    }
+
+    /// Get the high nibble held from the first WPM of a pair
+    /// Returns: Some(nibble) between the two WPMs, None otherwise
+    pub fn get_wpm_high_nibble(&self) -> Option<u8> {
+        self.wpm_high_nibble
+    }
+
+    /// Get the current subcycle of the instruction cycle
+    /// Returns: Subcycle the chip is tracking, X3 after reset so the next SYNC starts A1
+    pub fn get_subcycle(&self) -> BusSubcycle {
+        self.subcycle
+    }
+
+    /// Capture the converter state for a machine snapshot
+    /// Returns: Fetched byte, pending WPM and RPM nibbles, write strobe and bus cycle state
+    pub fn snapshot(&self) -> Intel4009State {
+        Intel4009State {
+            fetched_byte: self.fetched_byte,
+            wpm_high_nibble: self.wpm_high_nibble,
+            rpm_low_nibble: self.rpm_low_nibble,
+            writing: self.writing,
+            prev_phi1: self.prev_phi1,
+            prev_phi2: self.prev_phi2,
+            subcycle: self.subcycle,
+            opr_latch: self.opr_latch,
+            io_instruction: self.io_instruction,
+        }
+    }
+
+    /// Restore a converter state captured by `snapshot`
+    /// The memory bus and W strobe levels are restored with the system's pins
+    /// Parameters: state - Converter state
+    pub fn restore(&mut self, state: &Intel4009State) {
+        self.fetched_byte = state.fetched_byte;
+        self.wpm_high_nibble = state.wpm_high_nibble;
+        self.rpm_low_nibble = state.rpm_low_nibble;
+        self.writing = state.writing;
+        self.prev_phi1 = state.prev_phi1;
+        self.prev_phi2 = state.prev_phi2;
+        self.subcycle = state.subcycle;
+        self.opr_latch = state.opr_latch;
+        self.io_instruction = state.io_instruction;
+    }
+}
+
+impl Component for Intel4009 {
+    fn name(&self) -> String {
+        self.base.name()
+    }
+
+    fn pins(&self) -> HashMap<String, Arc<Mutex<Pin>>> {
+        self.base.pins()
+    }
+
+    fn get_pin(&self, name: &str) -> Result<Arc<Mutex<Pin>>, String> {
+        self.base.get_pin(name)
+    }
+
+    /// Main update cycle - handles clock edge detection and operation dispatch
+    fn update(&mut self) {
+        if !self.is_running() {
+            return;
+        }
+        let phi1_rising = self.is_phi1_rising_edge(self.prev_phi1);
+        let phi2_rising = self.is_phi2_rising_edge(self.prev_phi2);
+
+        // Update clock states for next edge detection
+        let (phi1, phi2) = self.read_clock_pins();
+        self.prev_phi1 = phi1;
+        self.prev_phi2 = phi2;
+
+        if phi1_rising {
+            self.handle_phi1_rising();
+        }
+
+        if phi2_rising {
+            self.handle_phi2_rising();
+        }
+    }
+
+    /// Mark component running for an external scheduler and sync clock edge state
+    fn start(&mut self) {
+        self.base.set_running(true);
+
+        let (phi1, phi2) = self.read_clock_pins();
+        self.prev_phi1 = phi1;
+        self.prev_phi2 = phi2;
+        self.end_write();
+    }
+
+    /// Run component in time-slice mode
+    fn run(&mut self) {
+        self.start();
+
+        while self.is_running() {
+            self.update();
+            thread::sleep(Duration::from_micros(1));
+        }
+    }
+
+    /// Stop component and release the data bus and memory lines
+    fn stop(&mut self) {
+        self.base.set_running(false);
+        self.tri_state_data_bus();
+        self.end_write();
+    }
+
+    fn is_running(&self) -> bool {
+        self.base.is_running()
+    }
+
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        component::state_to_json(&self.snapshot())
+    }
+
+    fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        let state = component::state_from_json(&self.base.name(), state)?;
+        self.restore(&state);
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl RunnableComponent for Intel4009 {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Drive a pin of the chip from the test bench
+    fn drive(chip: &Intel4009, pin: &str, value: PinValue) {
+        chip.get_pin(pin)
+            .unwrap()
+            .lock()
+            .unwrap()
+            .set_driver(Some("TEST".to_string()), value);
+    }
+
+    /// Drive the memory data lines as the addressed RAM would
+    fn drive_memory(chip: &Intel4009, data: u8) {
+        for i in 0..8 {
+            drive(
+                chip,
+                &format!("M{}", i),
+                PinValue::from_bool((data >> i) & 1 == 1),
+            );
+        }
+    }
+
+    /// Run one subcycle: Φ1 with the given SYNC/CM/bus values, then Φ2
+    /// Parameters: data - Nibble the CPU drives, None while memory has the bus
+    fn clock_subcycle(chip: &mut Intel4009, sync: bool, cm: bool, data: Option<u8>) {
+        drive(chip, "SYNC", PinValue::from_bool(sync));
+        drive(chip, "CM", PinValue::from_bool(cm));
+        for i in 0..4 {
+            let value = data.map_or(PinValue::HighZ, |d| PinValue::from_bool((d >> i) & 1 == 1));
+            drive(chip, &format!("D{}", i), value);
+        }
+
+        drive(chip, "PHI2", PinValue::Low);
+        drive(chip, "PHI1", PinValue::High);
+        chip.update();
+        drive(chip, "PHI1", PinValue::Low);
+        drive(chip, "PHI2", PinValue::High);
+        chip.update();
+    }
+
+    /// Run an instruction cycle whose opcode the memory presents on M0-M7
+    /// Returns: Opcode as the CPU saw it on D0-D3 during M1 and M2
+    fn run_instruction_cycle(chip: &mut Intel4009, opcode: u8, x2: Option<u8>) -> u8 {
+        let io = opcode >> 4 == 0xE;
+        drive_memory(chip, opcode);
+        clock_subcycle(chip, true, false, Some(0));
+        clock_subcycle(chip, false, false, Some(0));
+        clock_subcycle(chip, false, true, Some(0));
+        clock_subcycle(chip, false, false, None);
+        let opr = chip.read_data_bus();
+        clock_subcycle(chip, false, io, None);
+        let opa = chip.read_data_bus();
+        clock_subcycle(chip, false, false, None);
+        clock_subcycle(chip, false, false, x2);
+        clock_subcycle(chip, false, false, None);
+        (opr << 4) | opa
+    }
+
+    fn create_running_chip() -> Intel4009 {
+        let mut chip = Intel4009::new("DATA_4009".to_string());
+        drive(&chip, "PHI1", PinValue::Low);
+        drive(&chip, "PHI2", PinValue::Low);
+        chip.start();
+        chip
+    }
+
+    #[test]
+    fn test_instruction_byte_split_into_nibbles() {
+        let mut chip = create_running_chip();
+        assert_eq!(run_instruction_cycle(&mut chip, 0x5C, None), 0x5C);
+        assert_eq!(run_instruction_cycle(&mut chip, 0xA7, None), 0xA7);
+    }
+
+    #[test]
+    fn test_wpm_pair_writes_one_byte() {
+        let mut chip = create_running_chip();
+
+        // The first WPM only holds its nibble
+        run_instruction_cycle(&mut chip, 0xE3, Some(0xA));
+        assert_eq!(chip.get_wpm_high_nibble(), Some(0xA));
+        assert_eq!(
+            chip.get_pin("W").unwrap().lock().unwrap().read(),
+            PinValue::Low
+        );
+
+        // The second WPM strobes W during X2 with the assembled byte on M0-M7
+        drive_memory(&chip, 0xE3);
+        clock_subcycle(&mut chip, true, false, Some(0));
+        for _ in 0..3 {
+            clock_subcycle(&mut chip, false, false, None);
+        }
+        clock_subcycle(&mut chip, false, true, None);
+        clock_subcycle(&mut chip, false, false, None);
+        for i in 0..8 {
+            drive(&chip, &format!("M{}", i), PinValue::HighZ);
+        }
+        clock_subcycle(&mut chip, false, false, Some(0x5));
+        assert_eq!(chip.read_memory_bus(), 0xA5);
+        assert_eq!(
+            chip.get_pin("W").unwrap().lock().unwrap().read(),
+            PinValue::High
+        );
+        assert_eq!(chip.get_wpm_high_nibble(), None);
+
+        // X3 ends the write
+        clock_subcycle(&mut chip, false, false, None);
+        assert_eq!(
+            chip.get_pin("W").unwrap().lock().unwrap().read(),
+            PinValue::Low
+        );
+    }
+
+    #[test]
+    fn test_rpm_pair_reads_one_byte() {
+        let mut chip = create_running_chip();
+
+        for expected in [0xA, 0x5] {
+            drive_memory(&chip, 0x0E);
+            clock_subcycle(&mut chip, true, false, Some(0));
+            clock_subcycle(&mut chip, false, false, Some(0));
+            clock_subcycle(&mut chip, false, true, Some(0));
+            clock_subcycle(&mut chip, false, false, None);
+            clock_subcycle(&mut chip, false, false, None);
+
+            // The 4040 flags RPM with CM during X1 and the 4008 addresses program RAM
+            drive_memory(&chip, 0xA5);
+            clock_subcycle(&mut chip, false, true, None);
+            clock_subcycle(&mut chip, false, false, None);
+            assert_eq!(chip.read_data_bus(), expected);
+            clock_subcycle(&mut chip, false, false, None);
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod address_decoder;
+pub mod intel_4008;
+pub mod intel_4009;
@@ -0,0 +1,434 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::component::{self, BaseComponent, Component, RunnableComponent};
+use crate::components::common::bus_8bit::{
+    bus_pin_names, check_address_window, load_block, Bus8BitMemory,
+};
+use crate::pin::{Pin, PinValue};
+use serde::{Deserialize, Serialize};
+
+/// Control inputs of a generic RAM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RamControl {
+    ChipEnables, // CS, OE and WE, as on a standard memory part
+    ReadWrite,   // CS and a single RW line, as on a 6502-style bus
+}
+
+/// Generic byte-wide static RAM
+/// A standard memory part with a parallel address bus, an 8-bit data bus and
+/// chip select, output enable and write enable inputs. Built with `new_8bit_bus` it
+/// instead sits on the full 16-bit address bus of a 6502-style system, answers the
+/// addresses from its base address up and takes its direction from a single RW line.
+///
+/// Hardware Architecture:
+/// - A0-An address lines, as many as the configured size needs (up to 16); on an
+///   8-bit bus all of A0-A15
+/// - D0-D7 bidirectional data lines, driven while selected, OE is active and WE is not
+/// - CS, OE: active high; a control input that is not driven counts as active, like an
+///   enable that is tied to the supply, so a RAM without an address decoder answers
+/// - WE: active high; while high the RAM releases D0-D7 and stores the byte on them
+/// - RW (8-bit bus): high to read, low to write, as driven by the 6502; the RAM
+///   responds while selected and the address falls in base..base+size
+///
+/// Hardware Deviations:
+/// - No access or write cycle timing: reads follow the address lines immediately
+/// - Writes are not qualified by a clock phase; the byte on D0-D7 is stored while WE
+///   is high or RW is low
+/// - Address lines that are not driven read as low
+pub struct GenericRam {
+    base: BaseComponent,
+    memory: Vec<u8>,      // Memory contents
+    address_lines: usize, // Number of address pins A0..A(n-1)
+    base_address: u16,    // First bus address of the RAM
+    control: RamControl,  // CS/OE/WE or CS/RW control inputs
+}
+
+/// Snapshot of the RAM contents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenericRamState {
+    memory: Vec<u8>,
+}
+
+impl GenericRam {
+    /// Largest supported RAM size in bytes (16 address lines)
+    pub const MAX_SIZE: usize = 0x10000;
+
+    /// Create a new byte-wide RAM
+    /// Parameters: name - Component identifier, size - Size in bytes (1-65536)
+    /// Returns: Ok(GenericRam) with cleared memory, Err(String) if the size is out of range
+    pub fn new(name: String, size: usize) -> Result<Self, String> {
+        if size == 0 || size > Self::MAX_SIZE {
+            return Err(format!(
+                "RAM size {} out of range 1-{}",
+                size,
+                Self::MAX_SIZE
+            ));
+        }
+
+        // Enough address lines to reach every byte
+        let address_lines = (usize::BITS - (size - 1).leading_zeros()) as usize;
+
+        let mut pin_names: Vec<String> = (0..address_lines).map(|i| format!("A{}", i)).collect();
+        pin_names.extend((0..8).map(|i| format!("D{}", i)));
+        pin_names.extend(["CS", "OE", "WE"].iter().map(|name| name.to_string()));
+        let pin_refs: Vec<&str> = pin_names.iter().map(String::as_str).collect();
+
+        let pins = BaseComponent::create_pin_map(&pin_refs, &name);
+
+        Ok(GenericRam {
+            base: BaseComponent::new(name, pins),
+            memory: vec![0u8; size],
+            address_lines,
+            base_address: 0,
+            control: RamControl::ChipEnables,
+        })
+    }
+
+    /// Create a new RAM for an 8-bit microprocessor bus
+    /// Parameters: name - Component identifier, size - Size in bytes (1-65536),
+    ///             base_address - First bus address the RAM answers
+    /// Returns: Ok(GenericRam) with cleared memory, Err(String) if the RAM does not fit the address space
+    pub fn new_8bit_bus(name: String, size: usize, base_address: u16) -> Result<Self, String> {
+        check_address_window(size, base_address)?;
+
+        let pins = BaseComponent::create_pin_map(&bus_pin_names(), &name);
+        Ok(GenericRam {
+            base: BaseComponent::new(name, pins),
+            memory: vec![0u8; size],
+            address_lines: 16,
+            base_address,
+            control: RamControl::ReadWrite,
+        })
+    }
+
+    /// Find the byte the bus addresses
+    /// Returns: Some(offset) while selected, None otherwise. A standard part wraps the
+    ///          address into its size; on an 8-bit bus the address must be in the window.
+    fn addressed_offset(&self) -> Option<usize> {
+        match self.control {
+            RamControl::ChipEnables => (self.read_pin("CS") != PinValue::Low)
+                .then(|| self.read_lines("A", self.address_lines) % self.memory.len()),
+            RamControl::ReadWrite => self.selected_offset(self.base_address, self.memory.len()),
+        }
+    }
+
+    /// Get the RAM size in bytes
+    /// Returns: Number of bytes
+    pub fn get_size(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Get the number of address lines
+    /// Returns: Number of A pins
+    pub fn get_address_lines(&self) -> usize {
+        self.address_lines
+    }
+
+    /// Get the first bus address of the RAM
+    /// Returns: Base address, 0 for a standard part
+    pub fn get_base_address(&self) -> u16 {
+        self.base_address
+    }
+
+    /// Get the control inputs of the RAM
+    /// Returns: CS/OE/WE for a standard part, CS/RW on an 8-bit bus
+    pub fn get_control(&self) -> RamControl {
+        self.control
+    }
+
+    /// Read a byte directly from memory
+    /// Parameters: address - Byte offset from the base address
+    /// Returns: Some(data) if address valid, None if out of bounds
+    pub fn read_byte(&self, address: usize) -> Option<u8> {
+        self.memory.get(address).copied()
+    }
+
+    /// Write a byte directly into memory
+    /// Parameters: address - Byte offset from the base address, data - Byte to store
+    /// Returns: Ok(()) on success, Err(String) if address is out of bounds
+    pub fn write_byte(&mut self, address: usize, data: u8) -> Result<(), String> {
+        let size = self.memory.len();
+        let byte = self
+            .memory
+            .get_mut(address)
+            .ok_or_else(|| format!("Address 0x{:04X} out of range (size {})", address, size))?;
+        *byte = data;
+        Ok(())
+    }
+
+    /// Load a block of data into memory
+    /// Parameters: data - Bytes to load, offset - Offset of the first byte from the base address
+    /// Returns: Ok(()) on success, Err(String) if the block does not fit
+    pub fn load_data(&mut self, data: &[u8], offset: usize) -> Result<(), String> {
+        load_block(&mut self.memory, data, offset)
+    }
+
+    /// Get the whole memory contents
+    /// Returns: Slice of all bytes, offset 0 first
+    pub fn get_contents(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Clear all memory to zero
+    pub fn clear(&mut self) {
+        self.memory.fill(0);
+    }
+
+    /// Capture the RAM contents for a machine snapshot
+    /// Returns: RAM state
+    pub fn snapshot(&self) -> GenericRamState {
+        GenericRamState {
+            memory: self.memory.clone(),
+        }
+    }
+
+    /// Restore RAM contents captured by `snapshot`
+    /// Parameters: state - RAM state
+    /// Returns: Ok(()) on success, Err(String) if the RAM size differs
+    pub fn restore(&mut self, state: &GenericRamState) -> Result<(), String> {
+        if state.memory.len() != self.memory.len() {
+            return Err(format!(
+                "RAM state holds {} bytes, {} has {}",
+                state.memory.len(),
+                self.base.name(),
+                self.memory.len()
+            ));
+        }
+        self.memory.copy_from_slice(&state.memory);
+        Ok(())
+    }
+}
+
+impl Bus8BitMemory for GenericRam {
+    fn get_base(&self) -> &BaseComponent {
+        &self.base
+    }
+}
+
+impl Component for GenericRam {
+    fn name(&self) -> String {
+        self.base.name()
+    }
+
+    fn pins(&self) -> HashMap<String, Arc<Mutex<Pin>>> {
+        self.base.pins()
+    }
+
+    fn get_pin(&self, name: &str) -> Result<Arc<Mutex<Pin>>, String> {
+        self.base.get_pin(name)
+    }
+
+    /// Main update cycle - follows the control lines like an asynchronous SRAM
+    fn update(&mut self) {
+        let Some(offset) = self.addressed_offset() else {
+            self.drive_data_bus(None);
+            return;
+        };
+
+        let (write, output_enabled) = match self.control {
+            RamControl::ChipEnables => (
+                self.read_pin("WE") == PinValue::High,
+                self.read_pin("OE") != PinValue::Low,
+            ),
+            RamControl::ReadWrite => (self.read_pin("RW") == PinValue::Low, true),
+        };
+        if write {
+            // Release the data lines first so only the writer's byte is seen
+            self.drive_data_bus(None);
+            self.memory[offset] = self.read_lines("D", 8) as u8;
+        } else if output_enabled {
+            self.drive_data_bus(Some(self.memory[offset]));
+        } else {
+            self.drive_data_bus(None);
+        }
+    }
+
+    fn start(&mut self) {
+        self.base.set_running(true);
+    }
+
+    /// Run component in time-slice mode
+    fn run(&mut self) {
+        self.start();
+
+        while self.is_running() {
+            self.update();
+            thread::sleep(Duration::from_micros(1));
+        }
+    }
+
+    /// Stop component and release the data lines
+    fn stop(&mut self) {
+        self.base.set_running(false);
+        self.drive_data_bus(None);
+    }
+
+    fn is_running(&self) -> bool {
+        self.base.is_running()
+    }
+
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        component::state_to_json(&self.snapshot())
+    }
+
+    fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        let state = component::state_from_json(&self.base.name(), state)?;
+        self.restore(&state)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl RunnableComponent for GenericRam {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Drive a pin of the RAM from the test bench
+    fn drive(ram: &GenericRam, pin: &str, value: PinValue) {
+        ram.get_pin(pin)
+            .unwrap()
+            .lock()
+            .unwrap()
+            .set_driver(Some("TEST".to_string()), value);
+    }
+
+    /// Drive a value onto a group of pins such as A0-A11 or D0-D7
+    fn drive_bus(ram: &GenericRam, prefix: &str, width: usize, value: Option<usize>) {
+        for line in 0..width {
+            let level = value.map_or(PinValue::HighZ, |v| {
+                PinValue::from_bool((v >> line) & 1 == 1)
+            });
+            drive(ram, &format!("{}{}", prefix, line), level);
+        }
+    }
+
+    #[test]
+    fn test_generic_ram_creation() {
+        let ram = GenericRam::new("RAM".to_string(), 4096).unwrap();
+        assert_eq!(ram.get_size(), 4096);
+        assert_eq!(ram.get_address_lines(), 12);
+        assert!(ram.get_pin("A11").is_ok());
+        assert!(ram.get_pin("A12").is_err());
+        assert_eq!(
+            GenericRam::new("RAM".to_string(), 1)
+                .unwrap()
+                .get_address_lines(),
+            0
+        );
+
+        assert!(GenericRam::new("RAM".to_string(), 0).is_err());
+        assert!(GenericRam::new("RAM".to_string(), 0x10001).is_err());
+    }
+
+    #[test]
+    fn test_generic_ram_direct_access() {
+        let mut ram = GenericRam::new("RAM".to_string(), 256).unwrap();
+        ram.write_byte(0x10, 0xA5).unwrap();
+        assert_eq!(ram.read_byte(0x10), Some(0xA5));
+        assert!(ram.write_byte(0x100, 0).is_err());
+        assert_eq!(ram.read_byte(0x100), None);
+
+        ram.load_data(&[1, 2, 3], 0xFD).unwrap();
+        assert_eq!(ram.read_byte(0xFF), Some(3));
+        assert!(ram.load_data(&[1, 2, 3], 0xFE).is_err());
+
+        ram.clear();
+        assert_eq!(ram.read_byte(0x10), Some(0));
+    }
+
+    #[test]
+    fn test_generic_ram_bus_read_and_write() {
+        let mut ram = GenericRam::new("RAM".to_string(), 4096).unwrap();
+        ram.write_byte(0x234, 0x5A).unwrap();
+        ram.start();
+
+        // Undriven CS and OE count as active: the addressed byte appears on D0-D7
+        drive_bus(&ram, "A", 12, Some(0x234));
+        ram.update();
+        assert_eq!(ram.read_lines("D", 8), 0x5A);
+
+        // WE stores the byte the writer drives
+        drive(&ram, "WE", PinValue::High);
+        drive_bus(&ram, "A", 12, Some(0x123));
+        drive_bus(&ram, "D", 8, Some(0xC3));
+        ram.update();
+        assert_eq!(ram.read_byte(0x123), Some(0xC3));
+
+        // Deselected, the RAM neither writes nor drives
+        drive(&ram, "CS", PinValue::Low);
+        drive_bus(&ram, "D", 8, Some(0x00));
+        ram.update();
+        assert_eq!(ram.read_byte(0x123), Some(0xC3));
+        drive_bus(&ram, "D", 8, None);
+        drive(&ram, "WE", PinValue::Low);
+        ram.update();
+        assert_eq!(
+            ram.get_pin("D0").unwrap().lock().unwrap().read(),
+            PinValue::HighZ
+        );
+    }
+
+    #[test]
+    fn test_generic_ram_8bit_bus_creation() {
+        let ram = GenericRam::new_8bit_bus("RAM".to_string(), 0x8000, 0x0000).unwrap();
+        assert_eq!(ram.get_size(), 0x8000);
+        assert_eq!(ram.get_base_address(), 0x0000);
+        assert_eq!(ram.get_control(), RamControl::ReadWrite);
+        assert!(ram.get_pin("A15").is_ok());
+        assert!(ram.get_pin("RW").is_ok());
+        assert!(ram.get_pin("WE").is_err());
+
+        assert!(GenericRam::new_8bit_bus("RAM".to_string(), 0, 0).is_err());
+        assert!(GenericRam::new_8bit_bus("RAM".to_string(), 0x2000, 0xF000).is_err());
+        assert!(GenericRam::new_8bit_bus("RAM".to_string(), 0x1000, 0xF000).is_ok());
+    }
+
+    #[test]
+    fn test_generic_ram_8bit_bus_answers_its_address_window() {
+        let mut ram = GenericRam::new_8bit_bus("RAM".to_string(), 0x100, 0x0200).unwrap();
+        ram.write_byte(0x34, 0x5A).unwrap();
+        ram.start();
+
+        drive(&ram, "RW", PinValue::High);
+        drive_bus(&ram, "A", 16, Some(0x0234));
+        ram.update();
+        assert_eq!(ram.read_lines("D", 8), 0x5A);
+
+        // RW low stores the byte on D0-D7
+        drive(&ram, "RW", PinValue::Low);
+        drive_bus(&ram, "A", 16, Some(0x02FF));
+        drive_bus(&ram, "D", 8, Some(0xC3));
+        ram.update();
+        assert_eq!(ram.read_byte(0xFF), Some(0xC3));
+
+        // Outside the window or deselected, the RAM neither writes nor drives
+        drive_bus(&ram, "A", 16, Some(0x0300));
+        drive_bus(&ram, "D", 8, Some(0x11));
+        ram.update();
+        drive_bus(&ram, "A", 16, Some(0x0200));
+        drive(&ram, "CS", PinValue::Low);
+        ram.update();
+        assert_eq!(ram.read_byte(0x00), Some(0x00));
+        assert!(ram.get_contents().iter().all(|&b| b != 0x11));
+
+        drive_bus(&ram, "D", 8, None);
+        drive(&ram, "RW", PinValue::High);
+        ram.update();
+        assert_eq!(
+            ram.get_pin("D0").unwrap().lock().unwrap().read(),
+            PinValue::HighZ
+        );
+    }
+}
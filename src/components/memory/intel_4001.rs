@@ -1,48 +1,66 @@
+use std::any::Any;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
-use crate::component::{BaseComponent, Component, RunnableComponent};
+use crate::component::{self, BaseComponent, Component, RunnableComponent};
 use crate::components::common::intel_400x::{
-    Intel400xAddressHandling, Intel400xClockHandling, Intel400xControlPins, Intel400xDataBus,
-    Intel400xResetHandling, Intel400xTimingState, MemoryState, TimingState,
+    BusSubcycle, Intel400xAddressHandling, Intel400xBusCycle, Intel400xClockHandling,
+    Intel400xControlPins, Intel400xDataBus, Intel400xResetHandling, Intel400xTimingState,
+    MemoryState, TimingState,
 };
-use crate::pin::{Pin, PinValue};
+use crate::pin::{DriveStrength, Pin, PinValue};
+use crate::sim_time::{SimTime, TimeBase};
+use serde::{Deserialize, Serialize};
 
 /// Intel 4001 - 256-byte ROM with integrated I/O
 /// Part of the MCS-4 family, designed to work with Intel 4004 CPU
 /// Features 256 bytes of mask-programmable ROM and 4 I/O pins
 ///
-/// Hardware Deviations:
-/// - I/O read results are written to D0-D3 for test convenience
-///   (real hardware reads directly from I/O pins)
-/// - SYNC logic simplified for unit testing (may need refinement for CPU integration)
+/// The chip follows the CPU's 8-subcycle instruction cycle on the shared bus:
+/// it latches the address during A1-A3, returns the instruction during M1-M2 and
+/// serves WRR/RDR on its I/O port during X2 once selected by SRC.
+///
+/// Each I/O line is mask-configured as well: it can be fixed as an input or an output,
+/// inverted, and given a pull-up or pull-down resistor (see IoLineConfig).
+///
+/// Each chip carries a mask-programmed chip number (0-15). It only answers program
+/// fetches whose A3 nibble matches it, and its I/O port only takes part in RDR/WRR
+/// after an SRC that named it.
 pub struct Intel4001 {
     base: BaseComponent,
+    chip_id: u8,                     // Mask-programmed chip number (0-15)
     memory: Vec<u8>,                 // 256-byte ROM storage
     last_address: u16,               // Last accessed memory address
     access_time: Duration,           // ROM access latency (500ns)
+    time_base: TimeBase,             // Shared simulation time base for latency
     output_latch: u8,                // 4-bit output latch for I/O operations
     input_latch: u8,                 // 4-bit input latch for I/O operations
     io_mode: IoMode,                 // Current I/O mode configuration
     io_ports: [u8; 4],               // 4 I/O ports (4 bits each) - matches datasheet
     io_direction: [IoDirection; 4],  // I/O direction for each port
+    io_config: [IoLineConfig; 4],    // Metal-mask options of each I/O line
     selected_io_port: Option<usize>, // Currently selected I/O port (0-3)
     // Clock edge detection
     prev_phi1: PinValue, // Previous Φ1 clock state for edge detection
     prev_phi2: PinValue, // Previous Φ2 clock state for edge detection
     // Access latency modeling
-    address_latch_time: Option<Instant>, // Timestamp when address was latched
+    address_latch_time: Option<SimTime>, // Simulated time when address was latched
     // Two-phase addressing for 8-bit address
     address_high_nibble: Option<u8>, // High nibble of 8-bit address
     address_low_nibble: Option<u8>,  // Low nibble of 8-bit address
     full_address_ready: bool,        // Whether complete address is assembled
     // Memory operation state machine
     memory_state: MemoryState, // Current state of memory operation
+    // Instruction cycle tracking
+    subcycle: BusSubcycle,      // Current subcycle of the CPU instruction cycle
+    opr_latch: u8,              // Opcode high nibble seen during M1
+    io_instruction: Option<u8>, // OPA of the I/O instruction in this cycle (CM at M2)
+    src_chip_select: Option<u8>, // Chip number sent by the last SRC instruction
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 /// I/O mode configuration for the 4001 ROM
 /// Determines how the I/O pins are configured during read/write operations
 pub enum IoMode {
@@ -52,12 +70,56 @@ pub enum IoMode {
 }
 
 /// I/O direction for each I/O port
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum IoDirection {
     Input,  // Port configured as input
     Output, // Port configured as output
 }
 
+/// Pull resistor mask option of an I/O line
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum IoPull {
+    #[default]
+    None, // Line floats when nothing drives it
+    Up,   // Weak pull-up to High
+    Down, // Weak pull-down to Low
+}
+
+/// Metal-mask configuration of a single 4001 I/O line
+/// Hardware: Each line is fixed at manufacturing time; the default leaves the direction
+/// unmasked, so WRR turns the line into an output as on earlier revisions of this model
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct IoLineConfig {
+    pub direction: Option<IoDirection>, // Masked direction, None if WRR may switch the line
+    pub inverted: bool,                 // Line level is the complement of the latch bit
+    pub pull: IoPull,                   // Pull resistor on the line
+}
+
+/// Snapshot of the ROM contents, I/O latches and bus cycle state
+/// The chip number, access time and I/O line mask options are configuration and not included
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Intel4001State {
+    memory: Vec<u8>,
+    last_address: u16,
+    output_latch: u8,
+    input_latch: u8,
+    io_mode: IoMode,
+    io_ports: [u8; 4],
+    io_direction: [IoDirection; 4],
+    selected_io_port: Option<usize>,
+    prev_phi1: PinValue,
+    prev_phi2: PinValue,
+    address_latch_time: Option<SimTime>,
+    address_high_nibble: Option<u8>,
+    address_low_nibble: Option<u8>,
+    full_address_ready: bool,
+    memory_state: MemoryState,
+    subcycle: BusSubcycle,
+    opr_latch: u8,
+    io_instruction: Option<u8>,
+    src_chip_select: Option<u8>,
+}
+
 impl Intel400xClockHandling for Intel4001 {
     fn get_base(&self) -> &BaseComponent {
         &self.base
@@ -82,6 +144,8 @@ impl Intel400xControlPins for Intel4001 {
     }
 }
 
+impl Intel400xBusCycle for Intel4001 {}
+
 impl Intel400xResetHandling for Intel4001 {
     fn get_base(&self) -> &BaseComponent {
         &self.base
@@ -95,13 +159,18 @@ impl Intel400xResetHandling for Intel4001 {
         self.address_low_nibble = None;
         self.address_high_nibble = None;
         self.full_address_ready = false;
+        self.subcycle = BusSubcycle::X3;
+        self.io_instruction = None;
+        self.src_chip_select = None;
 
         // Reset I/O state
         self.io_ports = [0u8; 4];
-        self.io_direction = [IoDirection::Input; 4];
+        self.io_direction = self
+            .io_config
+            .map(|config| config.direction.unwrap_or(IoDirection::Input));
         self.selected_io_port = None;
         self.io_mode = IoMode::Input; // Reset I/O mode to Input
-        self.tri_state_io_pins();
+        self.update_io_pins();
     }
 }
 
@@ -114,11 +183,11 @@ impl Intel400xTimingState for Intel4001 {
         self.memory_state = state.into();
     }
 
-    fn get_address_latch_time(&self) -> Option<Instant> {
+    fn get_address_latch_time(&self) -> Option<SimTime> {
         self.address_latch_time
     }
 
-    fn set_address_latch_time(&mut self, time: Option<Instant>) {
+    fn set_address_latch_time(&mut self, time: Option<SimTime>) {
         self.address_latch_time = time;
     }
 
@@ -173,16 +242,17 @@ impl Intel4001 {
         // - Clock pins: Φ1, Φ2 (two-phase clock from 4004 CPU)
         //
         // Control pin behavior:
-        // - SYNC: Marks start of instruction cycle
+        // - SYNC: Marks start of instruction cycle (subcycle A1)
         // - RESET: Clears internal state
-        // - CM: Chip select (must be HIGH for ROM access)
-        // - CI: I/O select (distinguishes I/O vs memory when chip selected)
+        // - CM: CM-ROM from the CPU, qualifies the chip number at A3, I/O instructions at M2
+        //   and the SRC chip number at X2
+        // - CI: Clear input, not used by the bus cycle
         let pin_names = vec![
             "D0", "D1", "D2", "D3", // Data/Address pins
             "IO0", "IO1", "IO2", "IO3",   // I/O pins
             "SYNC",  // Sync signal
             "CM",    // CM-ROM: ROM/RAM Chip Select
-            "CI",    // CM-RAM: RAM Chip Select (I/O vs ROM access)
+            "CI",    // Clear input
             "RESET", // Reset
             "PHI1",  // Clock phase 1
             "PHI2",  // Clock phase 2
@@ -193,15 +263,18 @@ impl Intel4001 {
 
         Intel4001 {
             base: BaseComponent::new(name, pins),
+            chip_id: 0,
             memory,
             last_address: 0,
             access_time: Duration::from_nanos(access_time_ns),
+            time_base: TimeBase::new(),
             output_latch: 0,
             input_latch: 0,
             io_mode: IoMode::Input,
             io_ports: [0u8; 4],                    // Initialize all I/O ports to 0
             io_direction: [IoDirection::Input; 4], // Default all ports to input
-            selected_io_port: None,                // No I/O port selected initially
+            io_config: [IoLineConfig::default(); 4],
+            selected_io_port: None, // No I/O port selected initially
             prev_phi1: PinValue::Low,
             prev_phi2: PinValue::Low,
             address_latch_time: None,
@@ -209,6 +282,10 @@ impl Intel4001 {
             address_low_nibble: None,
             full_address_ready: false,
             memory_state: MemoryState::Idle,
+            subcycle: BusSubcycle::X3,
+            opr_latch: 0,
+            io_instruction: None,
+            src_chip_select: None,
         }
     }
 
@@ -218,6 +295,27 @@ impl Intel4001 {
         self.access_time = Duration::from_nanos(access_time_ns);
     }
 
+    /// Set the mask-programmed chip number
+    /// Hardware: The chip number selects the ROM page (A3 nibble) and the SRC I/O port
+    /// Parameters: chip_id - Chip number (0-15)
+    /// Returns: Ok(()) on success, Err(String) if the number does not fit in 4 bits
+    pub fn set_chip_id(&mut self, chip_id: u8) -> Result<(), String> {
+        if chip_id > 0x0F {
+            return Err(format!(
+                "Intel 4001 chip number {} out of range 0-15",
+                chip_id
+            ));
+        }
+        self.chip_id = chip_id;
+        Ok(())
+    }
+
+    /// Get the mask-programmed chip number
+    /// Returns: Chip number (0-15)
+    pub fn get_chip_id(&self) -> u8 {
+        self.chip_id
+    }
+
     /// Get the current memory access time
     /// Returns: Access time in nanoseconds
     pub fn get_access_time(&self) -> u64 {
@@ -258,13 +356,18 @@ impl Intel4001 {
 
     /// Data bus methods now use common functionality
 
+    /// Read the I/O lines as seen by RDR
+    /// Hardware: Pull resistors act on lines nobody drives; inverted lines read back
+    /// the complement of their level
+    /// Returns: 4-bit value, line 0 in bit 0
     fn read_io_pins(&self) -> u8 {
         let mut data = 0;
 
         for i in 0..4 {
             if let Ok(pin) = self.base.get_pin(&format!("IO{}", i)) {
                 if let Ok(pin_guard) = pin.lock() {
-                    if pin_guard.read() == PinValue::High {
+                    let high = pin_guard.read() == PinValue::High;
+                    if high != self.io_config[i].inverted {
                         data |= 1 << i;
                     }
                 }
@@ -274,75 +377,145 @@ impl Intel4001 {
         data
     }
 
-    /// Handle Φ1 rising edge - Address and control phase
-    /// Hardware: Φ1 high = CPU drives bus with address/control information
-    /// Memory operations start when SYNC goes high during Φ1 rising edge
-    /// Focus: Address latching, reset, I/O operations
+    /// Handle Φ1 rising edge - Start of the next bus subcycle
+    /// Hardware: The CPU drives the bus during A1-A3 and X2-X3 of SRC, the ROM drives
+    /// the instruction during M1-M2 and its I/O port value during X2 of RDR
     fn handle_phi1_rising(&mut self) {
         // Handle system reset first (highest priority)
         self.handle_reset("RESET");
 
-        // Check for memory operation start on Φ1 rising edge with SYNC high
-        // Hardware: Memory operations start when SYNC goes high during Φ1
-        // Note: SYNC marks instruction fetch start, but exact logic involves CPU cycle state.
-        // ROM access: CM=1 (chip_select), CI=0 (!io_select)
-        // I/O access: CM=1 (chip_select), CI=1 (io_select)
-        let sync = self.read_sync_pin();
-        let chip_select = self.read_cm_rom_pin();
-        let io_select = self.read_ci_pin();
-
-        println!(
-            "DEBUG: [ROM] Checking memory access - SYNC: {}, CM: {}, CI: {}",
-            sync, chip_select, io_select
-        );
+        self.subcycle = self.next_subcycle(self.subcycle);
+        match self.subcycle {
+            BusSubcycle::A1 => {
+                // SYNC starts a new instruction cycle with a fresh address
+                self.tri_state_data_bus();
+                self.return_to_idle();
+                self.start_memory_address_phase();
+            }
+            BusSubcycle::M1 => {
+                if self.memory_state == MemoryState::WaitLatency {
+                    self.handle_latency_wait();
+                }
+                self.handle_data_driving(|data| data >> 4);
+            }
+            BusSubcycle::M2 => {
+                self.handle_data_driving(|data| data & 0x0F);
+            }
+            BusSubcycle::X1 => {
+                self.tri_state_data_bus();
+                self.return_to_idle();
+            }
+            BusSubcycle::X2 if self.is_io_selected() && self.io_instruction == Some(0xA) => {
+                // RDR: send the port lines to the CPU
+                let data = self.read_io_port();
+                self.write_data_bus(data);
+            }
+            _ => self.tri_state_data_bus(),
+        }
+    }
 
-        if sync && chip_select && !io_select {
-            // Start memory address phase on Φ1 rising edge (ROM access)
-            println!(
-                "DEBUG: [ROM] Starting memory address phase - SYNC: {}, CM: {}, CI: {}",
-                sync, chip_select, io_select
-            );
-            self.start_memory_address_phase();
-        } else {
-            println!(
-                "DEBUG: [ROM] Memory access conditions not met - SYNC: {}, CM: {}, CI: {}",
-                sync, chip_select, io_select
-            );
+    /// Handle Φ2 rising edge - Sample the bus
+    /// Hardware: Address nibbles arrive during A1-A3, the opcode during M1-M2 and the
+    /// SRC chip number or WRR data during X2
+    fn handle_phi2_rising(&mut self) {
+        let in_address_phase = self.memory_state == MemoryState::AddressPhase;
+        match self.subcycle {
+            BusSubcycle::A1 if in_address_phase => {
+                self.address_low_nibble = Some(self.read_data_bus());
+            }
+            BusSubcycle::A2 if in_address_phase => {
+                self.address_high_nibble = Some(self.read_data_bus());
+            }
+            BusSubcycle::A3 if in_address_phase => {
+                // CM-ROM accompanies the chip number of a program memory access
+                let selected = self.read_cm_rom_pin() && self.read_data_bus() == self.chip_id;
+                let address =
+                    self.assemble_full_address(self.address_high_nibble, self.address_low_nibble);
+                match address {
+                    Some(address) if selected => {
+                        self.last_address = address;
+                        self.full_address_ready = true;
+                        self.start_latency_wait();
+                    }
+                    _ => self.return_to_idle(),
+                }
+            }
+            BusSubcycle::M1 => {
+                self.opr_latch = self.read_data_bus();
+            }
+            BusSubcycle::M2 => {
+                // CM during M2 marks an I/O or RAM instruction, OPA selects which one
+                self.io_instruction = if self.read_cm_rom_pin() && self.opr_latch == 0xE {
+                    Some(self.read_data_bus())
+                } else {
+                    None
+                };
+            }
+            BusSubcycle::X2 => {
+                if self.read_cm_rom_pin() {
+                    // SRC: chip number with CM
+                    self.src_chip_select = Some(self.read_data_bus());
+                } else if self.is_io_selected() && self.io_instruction == Some(0x2) {
+                    // WRR: latch the accumulator into the output port
+                    let data = self.read_data_bus();
+                    self.write_output_port(data);
+                }
+            }
+            _ => {}
         }
+    }
 
-        // Handle I/O operations (higher priority than memory operations)
-        // I/O operations: CM=1 (chip_select), CI=1 (io_select)
-        // Note: SYNC is NOT required for I/O - only marks instruction fetch (ROM access)
-        if chip_select && io_select {
-            self.handle_io_operation();
+    /// Latch the port lines for RDR
+    /// Returns: 4-bit value of the I/O lines, line 0 in bit 0
+    fn read_io_port(&mut self) -> u8 {
+        self.input_latch = self.read_io_pins();
+        self.input_latch
+    }
+
+    /// Latch the accumulator sent by WRR into the output port
+    /// Parameters: data - 4-bit value, bit n drives line n
+    fn write_output_port(&mut self, data: u8) {
+        for line in 0..4 {
+            self.write_io_port(line, (data >> line) & 1);
         }
+        self.output_latch = data & 0x0F;
+        self.io_mode = IoMode::Output;
+    }
 
-        // Handle memory address phase operations during Φ1
-        self.handle_memory_address_operations();
+    /// Take the chip number of an SRC without a bus cycle, for a CPU in functional mode
+    /// Parameters: chip - High nibble of the register pair sent by SRC
+    pub fn select_io_port(&mut self, chip: u8) {
+        self.src_chip_select = Some(chip & 0x0F);
     }
 
-    /// Handle Φ1 falling edge - End of address phase
-    /// Hardware: Φ1 low = End of CPU address driving phase
-    fn handle_phi1_falling(&mut self) {
-        // Currently no specific operations needed on Φ1 falling
-        // Address latching happens on Φ1 rising, data driving on Φ2 rising
+    /// Execute an I/O instruction without a bus cycle, for a CPU in functional mode
+    /// Only WRR and RDR address the ROM's port, and only if the last SRC selected this chip
+    /// Parameters: opa - Low nibble of the I/O instruction, accumulator - Data of WRR
+    /// Returns: Some(4-bit port value) if this chip answers RDR, None otherwise
+    pub fn execute_io(&mut self, opa: u8, accumulator: u8) -> Option<u8> {
+        if !self.is_io_selected() {
+            return None;
+        }
+        match opa {
+            0x2 => {
+                self.write_output_port(accumulator);
+                None
+            }
+            0xA => Some(self.read_io_port()),
+            _ => None,
+        }
     }
 
-    /// Handle Φ2 rising edge - Data phase
-    /// Hardware: Φ2 high = Peripherals drive bus with data
-    /// Focus: Data driving operations
-    fn handle_phi2_rising(&mut self) {
-        // Handle memory data phase operations during Φ2
-        self.handle_memory_data_operations();
+    /// Check whether the last SRC instruction selected this chip's I/O port
+    /// Returns: true if the last SRC chip number matches this chip
+    fn is_io_selected(&self) -> bool {
+        self.src_chip_select == Some(self.chip_id)
     }
 
-    /// Handle Φ2 falling edge - End of data phase
-    /// Hardware: Φ2 low = End of data driving phase, time to tri-state bus
-    /// Data is held on bus during Φ2 high, tri-stated when Φ2 falls
-    /// Focus: Clean up and return to idle state
-    fn handle_phi2_falling(&mut self) {
-        // Handle memory cleanup operations when Φ2 falls
-        self.handle_memory_cleanup_operations();
+    /// Get the current subcycle of the instruction cycle
+    /// Returns: Subcycle the chip is tracking, X3 after reset so the next SYNC starts A1
+    pub fn get_subcycle(&self) -> BusSubcycle {
+        self.subcycle
     }
 
     fn tri_state_io_pins(&self) {
@@ -367,123 +540,42 @@ impl Intel4001 {
         self.io_mode
     }
 
-    /// Handle I/O port operations according to Intel 4001 datasheet
-    /// Hardware: I/O operations occur during I/O instructions (WRM/RDM)
-    /// Direction determined by CPU instruction, not manual configuration
-    /// Note: I/O pins remain driven after WRM until another WRM or reset.
-    /// This matches real 4001 hardware where I/O ports are latched.
-    ///
-    /// I/O Port Addressing (datasheet):
-    /// - I/O ports are addressed using the lower 2 bits of the address
-    /// - Port 0: Address 0x00, Port 1: Address 0x01, etc.
-    /// - Each port is 4 bits wide (matches the data bus width)
-    ///
-    /// I/O Latch Persistence:
-    /// - Output latch persists after WRM until next WRM or reset
-    /// - Input latch overwrites every RDM (I/O input pins are live reads)
-    fn handle_io_operation(&mut self) {
-        let chip_select = self.read_cm_rom_pin();
-        let io_select = self.read_ci_pin(); // Proper CI pin for I/O vs ROM selection
-
-        // I/O operations occur when: CM=1 (chip_select), CI=1 (io_select)
-        // Note: SYNC is NOT required for I/O - only marks instruction fetch (ROM access)
-        if chip_select && io_select {
-            let port_address = self.read_data_bus() & 0x03; // Lower 2 bits for port selection (0-3)
-            self.selected_io_port = Some(port_address as usize);
-
-            // Determine operation type based on current instruction phase
-            // In real hardware, this would be determined by the CPU instruction (WRM vs RDM)
-            match self.io_mode {
-                IoMode::Input => {
-                    // RDM instruction: Read from I/O port
-                    if let Some(port) = self.selected_io_port {
-                        self.input_latch = self.read_io_port(port);
-                        // Write input data to data bus for CPU to read
-                        self.write_data_bus(self.input_latch);
-                    }
-                }
-                IoMode::Output => {
-                    // WRM instruction: Write to I/O port
-                    if let Some(port) = self.selected_io_port {
-                        let data = self.read_data_bus();
-                        self.write_io_port(port, data);
-                    }
-                }
-                IoMode::Bidirectional => {
-                    // Not used in 4001, treat as input
-                    if let Some(port) = self.selected_io_port {
-                        self.input_latch = self.read_io_port(port);
-                        self.write_data_bus(self.input_latch);
-                    }
-                }
-            }
-        }
-    }
-
-    /// Read the CI (I/O select) pin state
-    /// Hardware: CI pin distinguishes I/O vs ROM access when chip selected (CM=1)
-    fn read_ci_pin(&self) -> bool {
-        if let Ok(pin) = self.base.get_pin("CI") {
-            if let Ok(pin_guard) = pin.lock() {
-                pin_guard.read() == PinValue::High
-            } else {
-                false
-            }
-        } else {
-            false
-        }
-    }
-
-    /// Read from a specific I/O port
-    /// Parameters: port - I/O port number (0-3)
-    /// Returns: 4-bit value from the I/O port
-    fn read_io_port(&self, port: usize) -> u8 {
-        if port < 4 {
-            match self.io_direction[port] {
-                IoDirection::Input => {
-                    // Read from actual I/O pins
-                    self.read_io_pins()
-                }
-                IoDirection::Output => {
-                    // Read from output latch (for read-back capability)
-                    self.io_ports[port]
-                }
-            }
-        } else {
-            0
-        }
-    }
-
     /// Write to a specific I/O port
+    /// Hardware: Lines masked as inputs ignore WRR
     /// Parameters: port - I/O port number (0-3), data - 4-bit data to write
     fn write_io_port(&mut self, port: usize, data: u8) {
-        if port < 4 {
+        if port < 4 && self.io_config[port].direction != Some(IoDirection::Input) {
             self.io_ports[port] = data & 0x0F;
             self.io_direction[port] = IoDirection::Output;
             self.update_io_pins();
         }
     }
 
-    /// Update I/O pins based on current port values and directions
-    /// Hardware: Only output ports drive the I/O pins
+    /// Update I/O pins based on current port values, directions and mask options
+    /// Hardware: Output lines drive their latch bit (complemented if inverted), input
+    /// lines only apply their pull resistor
     fn update_io_pins(&self) {
         for i in 0..4 {
             if let Ok(pin) = self.base.get_pin(&format!("IO{}", i)) {
                 if let Ok(mut pin_guard) = pin.lock() {
+                    let config = self.io_config[i];
                     match self.io_direction[i] {
                         IoDirection::Output => {
                             // Drive pin with output port value
-                            let bit_value = (self.io_ports[i]) & 1;
-                            let pin_value = if bit_value == 1 {
-                                PinValue::High
-                            } else {
-                                PinValue::Low
-                            };
-                            pin_guard.set_driver(Some(self.base.name()), pin_value);
+                            let high = (self.io_ports[i] & 1 == 1) != config.inverted;
+                            pin_guard.set_driver(Some(self.base.name()), PinValue::from_bool(high));
                         }
                         IoDirection::Input => {
-                            // Input mode - tri-state the pin
-                            pin_guard.set_driver(Some(self.base.name()), PinValue::HighZ);
+                            let (value, strength) = match config.pull {
+                                IoPull::None => (PinValue::HighZ, DriveStrength::Standard),
+                                IoPull::Up => (PinValue::High, DriveStrength::Weak),
+                                IoPull::Down => (PinValue::Low, DriveStrength::Weak),
+                            };
+                            pin_guard.set_driver_with_strength(
+                                Some(self.base.name()),
+                                value,
+                                strength,
+                            );
                         }
                     }
                 }
@@ -491,132 +583,49 @@ impl Intel4001 {
         }
     }
 
-    /// Handle memory address-related operations during Φ1
-    /// Hardware-accurate: Φ1 is when CPU drives address, so we handle address latching
-    /// Memory operation start is checked in handle_phi1_rising() on SYNC high + Φ1 rising
-    /// Focus: Address latching and setup operations
-    fn handle_memory_address_operations(&mut self) {
-        match self.memory_state {
-            MemoryState::Idle => {
-                // In idle state, ensure bus is tri-stated
-                // Memory operation start is checked in handle_phi1_rising()
-                self.tri_state_data_bus();
-            }
-
-            MemoryState::AddressPhase => {
-                // Currently latching address nibbles during Φ1
-                self.handle_address_latching();
-            }
-
-            MemoryState::WaitLatency => {
-                // Address latched, waiting for access latency
-                // Check if latency has elapsed and we can transition to data phase
-                self.handle_latency_wait();
-            }
-
-            MemoryState::DriveData => {
-                // Data phase should be handled by Φ2, not Φ1
-                // Just ensure bus is tri-stated during wrong phase
-                self.tri_state_data_bus();
-            }
+    /// Set the metal-mask configuration of one I/O line
+    /// A masked direction takes effect immediately and survives reset
+    /// Parameters: line - I/O line number (0-3), config - Mask options for the line
+    /// Returns: Ok(()) on success, Err(String) if the line number is out of range
+    pub fn set_io_line_config(&mut self, line: usize, config: IoLineConfig) -> Result<(), String> {
+        if line >= 4 {
+            return Err("I/O line number out of range (0-3)".to_string());
         }
-    }
 
-    /// Handle memory data-related operations during Φ2
-    /// Hardware-accurate: Φ2 is when peripherals drive data, so we handle data driving
-    /// Focus: Data driving operations
-    fn handle_memory_data_operations(&mut self) {
-        // Reduced debug output - only show when actually driving data
-        match self.memory_state {
-            MemoryState::Idle => {
-                // During data phase, idle state means tri-state the bus
-                self.tri_state_data_bus();
-            }
-
-            MemoryState::AddressPhase => {
-                // Address phase should be handled by Φ1, not Φ2
-                // Tri-state bus during wrong phase
-                self.tri_state_data_bus();
-            }
-
-            MemoryState::WaitLatency => {
-                // Address latched, waiting for access latency
-                // Check if latency has elapsed and we can transition to data phase
-                self.handle_latency_wait();
-                // If we transitioned to DriveData, handle data driving
-                if self.memory_state == MemoryState::DriveData {
-                    self.handle_data_driving();
-                }
-            }
-
-            MemoryState::DriveData => {
-                // Latency elapsed, drive data on bus during Φ2
-                // Data will remain on bus until Φ2 falling edge
-                self.handle_data_driving();
-            }
+        self.io_config[line] = config;
+        if let Some(direction) = config.direction {
+            self.io_direction[line] = direction;
         }
+        self.update_io_pins();
+        Ok(())
     }
 
-    /// Handle memory cleanup operations on Φ2 falling edge
-    /// Hardware-accurate: Clean up when Φ2 falls (end of data phase)
-    /// Focus: Tri-state bus and return to idle
-    fn handle_memory_cleanup_operations(&mut self) {
-        match self.memory_state {
-            MemoryState::DriveData => {
-                // End of data phase - tri-state bus and return to idle
-                self.tri_state_data_bus();
-                self.return_to_idle();
-            }
-
-            MemoryState::Idle | MemoryState::AddressPhase | MemoryState::WaitLatency => {
-                // For other states, just ensure bus is tri-stated
-                self.tri_state_data_bus();
-            }
-        }
+    /// Get the metal-mask configuration of one I/O line
+    /// Parameters: line - I/O line number (0-3)
+    /// Returns: Some(config) if the line is valid, None otherwise
+    pub fn get_io_line_config(&self, line: usize) -> Option<IoLineConfig> {
+        self.io_config.get(line).copied()
     }
 
     /// Transition to address phase state
     /// Hardware: Start of memory read cycle, CPU begins providing address
     fn start_memory_address_phase(&mut self) {
         self.memory_state = MemoryState::AddressPhase;
-        // Don't clear nibbles here - they should only be cleared after successful assembly
-        // or when explicitly resetting the state machine
         self.full_address_ready = false;
     }
 
-    /// Handle address nibble latching during address phase
-    /// Hardware: CPU provides 8-bit address as two 4-bit nibbles
-    fn handle_address_latching(&mut self) {
-        // Use common address latching functionality
-        let nibble = self.read_data_bus();
-
-        if self.address_high_nibble.is_none() {
-            // First cycle: latch high nibble (bits 7-4)
-            self.address_high_nibble = Some(nibble);
-        } else if self.address_low_nibble.is_none() {
-            // Second cycle: latch low nibble (bits 3-0) and transition to latency wait
-            self.address_low_nibble = Some(nibble);
-            if let Some(address) =
-                self.assemble_full_address(self.address_high_nibble, self.address_low_nibble)
-            {
-                self.last_address = address;
-                self.start_latency_wait();
-            }
-        }
-    }
-
     /// Transition to latency wait state
     /// Hardware: Address captured, start 500ns access time before data available
     fn start_latency_wait(&mut self) {
         self.memory_state = MemoryState::WaitLatency;
-        self.address_latch_time = Some(Instant::now());
+        self.address_latch_time = Some(self.time_base.now());
     }
 
     /// Handle latency timing during wait state
     /// Hardware: ROM needs 500ns to access data after address is latched
     fn handle_latency_wait(&mut self) {
         if let Some(latch_time) = self.address_latch_time {
-            let elapsed = latch_time.elapsed();
+            let elapsed = self.time_base.elapsed_since(latch_time);
             if elapsed >= self.access_time {
                 // Latency elapsed, transition to data driving
                 // Data will be driven on next Φ2 rising edge
@@ -632,28 +641,13 @@ impl Intel4001 {
     }
 
     /// Handle data driving during DriveData state
-    /// Hardware: ROM drives data bus when all conditions are met
-    /// Data stays on bus during Φ2 high period, tri-stated on Φ2 falling edge
-    fn handle_data_driving(&mut self) {
-        let sync = self.read_sync_pin();
-        let chip_select = self.read_cm_rom_pin();
-        let io_select = self.read_ci_pin();
-
-        // Memory read: CM=1 (chip_select), CI=0 (!io_select), valid address
-        if sync && chip_select && !io_select && self.full_address_ready {
-            // All conditions met: drive data on bus
-            // Data will remain on bus until Φ2 falling edge
-            let address = self.last_address;
-            if (address as usize) < self.memory.len() {
-                let data = self.memory[address as usize];
-                self.write_data_bus(data);
-                // Note: Don't call return_to_idle() here - wait for Φ2 falling edge
-            } else {
-                // Invalid address, tri-state
-                self.tri_state_data_bus();
-            }
+    /// Hardware: ROM drives one nibble of the addressed byte per subcycle (OPR in M1, OPA in M2)
+    /// Parameters: nibble - Selects the nibble of the addressed byte to drive
+    fn handle_data_driving(&mut self, nibble: impl Fn(u8) -> u8) {
+        if self.memory_state == MemoryState::DriveData && self.full_address_ready {
+            let data = self.memory[self.last_address as usize];
+            self.write_data_bus(nibble(data));
         } else {
-            // Conditions not met, tri-state
             self.tri_state_data_bus();
         }
     }
@@ -689,11 +683,9 @@ impl Component for Intel4001 {
             println!("DEBUG: Component not running, returning");
             return;
         }
-        // Handle both rising and falling edges for proper two-phase operation
+        // The bus cycle advances on Φ1 and is sampled on Φ2
         let phi1_rising = self.is_phi1_rising_edge(self.prev_phi1);
-        let phi1_falling = self.is_phi1_falling_edge(self.prev_phi1);
         let phi2_rising = self.is_phi2_rising_edge(self.prev_phi2);
-        let phi2_falling = self.is_phi2_falling_edge(self.prev_phi2);
 
         // Update clock states for next edge detection
         let (phi1, phi2) = self.read_clock_pins();
@@ -701,35 +693,30 @@ impl Component for Intel4001 {
         self.prev_phi2 = phi2;
 
         if phi1_rising {
-            // Φ1 Rising Edge: Address phase (CPU drives bus) - handle address latching
+            // Φ1 Rising Edge: Next subcycle - drive the bus if it is the ROM's turn
             self.handle_phi1_rising();
         }
 
-        if phi1_falling {
-            // Φ1 Falling Edge: End of address phase
-            self.handle_phi1_falling();
-        }
-
         if phi2_rising {
-            // Φ2 Rising Edge: Data phase (ROM drives bus if ready) - handle data driving
+            // Φ2 Rising Edge: Sample what the CPU put on the bus
             self.handle_phi2_rising();
         }
-
-        if phi2_falling {
-            // Φ2 Falling Edge: End of data phase - tri-state bus and return to idle
-            self.handle_phi2_falling();
-        }
     }
 
-    /// Run component in time-slice mode (manual control)
-    /// Hardware: Simulates continuous operation with clock edge detection
-    fn run(&mut self) {
+    /// Mark component running for an external scheduler and sync clock edge state
+    fn start(&mut self) {
         self.base.set_running(true);
 
         // Initialize clock states for edge detection
         let (phi1, phi2) = self.read_clock_pins();
         self.prev_phi1 = phi1;
         self.prev_phi2 = phi2;
+    }
+
+    /// Run component in time-slice mode (manual control)
+    /// Hardware: Simulates continuous operation with clock edge detection
+    fn run(&mut self) {
+        self.start();
 
         while self.is_running() {
             self.update();
@@ -754,6 +741,27 @@ impl Component for Intel4001 {
     fn is_running(&self) -> bool {
         self.base.is_running()
     }
+
+    fn attach_time_base(&mut self, time_base: TimeBase) {
+        self.time_base = time_base;
+    }
+
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        component::state_to_json(&self.snapshot())
+    }
+
+    fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        let state = component::state_from_json(&self.base.name(), state)?;
+        self.restore(&state)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
 }
 
 impl RunnableComponent for Intel4001 {}
@@ -844,6 +852,68 @@ impl Intel4001 {
         self.selected_io_port
     }
 
+    /// Capture the ROM state for a machine snapshot
+    /// Returns: ROM contents, I/O latches and ports, and bus cycle state
+    pub fn snapshot(&self) -> Intel4001State {
+        Intel4001State {
+            memory: self.memory.clone(),
+            last_address: self.last_address,
+            output_latch: self.output_latch,
+            input_latch: self.input_latch,
+            io_mode: self.io_mode,
+            io_ports: self.io_ports,
+            io_direction: self.io_direction,
+            selected_io_port: self.selected_io_port,
+            prev_phi1: self.prev_phi1,
+            prev_phi2: self.prev_phi2,
+            address_latch_time: self.address_latch_time,
+            address_high_nibble: self.address_high_nibble,
+            address_low_nibble: self.address_low_nibble,
+            full_address_ready: self.full_address_ready,
+            memory_state: self.memory_state,
+            subcycle: self.subcycle,
+            opr_latch: self.opr_latch,
+            io_instruction: self.io_instruction,
+            src_chip_select: self.src_chip_select,
+        }
+    }
+
+    /// Restore a ROM state captured by `snapshot` and drive the I/O lines to match
+    /// Parameters: state - ROM state
+    /// Returns: Ok(()) on success, Err(String) if the ROM size differs
+    pub fn restore(&mut self, state: &Intel4001State) -> Result<(), String> {
+        if state.memory.len() != self.memory.len() {
+            return Err(format!(
+                "ROM state holds {} bytes, {} has {}",
+                state.memory.len(),
+                self.base.name(),
+                self.memory.len()
+            ));
+        }
+
+        self.memory.copy_from_slice(&state.memory);
+        self.last_address = state.last_address;
+        self.output_latch = state.output_latch;
+        self.input_latch = state.input_latch;
+        self.io_mode = state.io_mode;
+        self.io_ports = state.io_ports;
+        self.io_direction = state.io_direction;
+        self.selected_io_port = state.selected_io_port;
+        self.prev_phi1 = state.prev_phi1;
+        self.prev_phi2 = state.prev_phi2;
+        self.address_latch_time = state.address_latch_time;
+        self.address_high_nibble = state.address_high_nibble;
+        self.address_low_nibble = state.address_low_nibble;
+        self.full_address_ready = state.full_address_ready;
+        self.memory_state = state.memory_state;
+        self.subcycle = state.subcycle;
+        self.opr_latch = state.opr_latch;
+        self.io_instruction = state.io_instruction;
+        self.src_chip_select = state.src_chip_select;
+        self.update_io_pins();
+        Ok(())
+    }
+
     /// Debug function to log state transitions for troubleshooting
     /// Parameters: test_name - Name of the test for context
     pub fn debug_state_transitions(&self, test_name: &str) {
@@ -893,6 +963,17 @@ mod tests {
         assert!(!rom.is_running());
     }
 
+    #[test]
+    fn test_intel4001_chip_id() {
+        let mut rom = Intel4001::new("ROM_4001".to_string());
+        assert_eq!(rom.get_chip_id(), 0);
+
+        assert!(rom.set_chip_id(0x0F).is_ok());
+        assert_eq!(rom.get_chip_id(), 0x0F);
+        assert!(rom.set_chip_id(0x10).is_err());
+        assert_eq!(rom.get_chip_id(), 0x0F);
+    }
+
     #[test]
     fn test_intel4001_rom_loading() {
         let mut rom = Intel4001::new("ROM_4001".to_string());
@@ -1013,26 +1094,8 @@ mod tests {
             phi1_pin.lock().unwrap().read()
         );
 
-        // Debug: Check conditions that would trigger memory operation
-        let sync = rom.read_sync_pin();
-        let chip_select = rom.read_cm_rom_pin();
-        let io_select = rom.read_ci_pin();
-        println!(
-            "DEBUG: Conditions - sync: {}, chip_select: {}, io_select: {}, combined: {}",
-            sync,
-            chip_select,
-            io_select,
-            sync && chip_select && !io_select
-        );
-
-        // Debug: Check edge detection
-        println!("DEBUG: Before update - prev_phi1: {:?}", rom.prev_phi1);
-
-        // Debug: Check what read_clock_pins returns
-        let (read_phi1, _) = rom.read_clock_pins();
-        println!("DEBUG: read_clock_pins() returns PHI1: {:?}", read_phi1);
-
         // Debug: Check edge detection logic manually
+        let (read_phi1, _) = rom.read_clock_pins();
         let phi1_rising = read_phi1 == PinValue::High && rom.prev_phi1 == PinValue::Low;
         println!(
             "DEBUG: Manual edge detection - PHI1: {:?}, prev_phi1: {:?}, rising: {}",
@@ -1045,7 +1108,8 @@ mod tests {
         // Debug: Check edge detection results
         println!("DEBUG: After update - prev_phi1: {:?}", rom.prev_phi1);
 
-        // Should have started memory operation and transitioned to AddressPhase
+        // SYNC marks A1, so the ROM starts latching a new address
+        assert_eq!(rom.subcycle, BusSubcycle::A1);
         assert_eq!(rom.memory_state, MemoryState::AddressPhase);
         println!("Memory state after Φ1 rising: {:?}", rom.memory_state);
     }
@@ -1302,6 +1366,56 @@ mod tests {
         assert_eq!(rom.selected_io_port, None);
     }
 
+    #[test]
+    fn test_io_line_mask_options() {
+        let mut rom = Intel4001::new("MaskROM".to_string());
+        let configs = [
+            (Some(IoDirection::Output), true, IoPull::None),
+            (Some(IoDirection::Input), false, IoPull::Up),
+            (Some(IoDirection::Input), true, IoPull::Down),
+            (Some(IoDirection::Input), false, IoPull::None),
+        ];
+        for (line, (direction, inverted, pull)) in configs.into_iter().enumerate() {
+            let config = IoLineConfig {
+                direction,
+                inverted,
+                pull,
+            };
+            rom.set_io_line_config(line, config).unwrap();
+        }
+        assert!(rom.set_io_line_config(4, IoLineConfig::default()).is_err());
+        let level = |rom: &Intel4001, line: usize| {
+            rom.get_pin(&format!("IO{}", line))
+                .unwrap()
+                .lock()
+                .unwrap()
+                .read()
+        };
+
+        // WRR only reaches the output line, which drives the complement of its bit
+        for line in 0..4 {
+            rom.write_io_port(line, 1);
+        }
+        assert_eq!(level(&rom, 0), PinValue::Low);
+        assert_eq!(level(&rom, 1), PinValue::High);
+        assert_eq!(level(&rom, 2), PinValue::Low);
+        assert_eq!(rom.get_io_port(1), Some(0));
+
+        // Pulls decide floating inputs, inverted lines read back complemented
+        assert_eq!(rom.read_io_pins(), 0b0111);
+        rom.get_pin("IO1")
+            .unwrap()
+            .lock()
+            .unwrap()
+            .set_driver(Some("SWITCH".to_string()), PinValue::Low);
+        assert_eq!(rom.read_io_pins(), 0b0101);
+
+        // Masked directions survive reset
+        rom.perform_reset();
+        assert_eq!(rom.get_io_direction(0), Some(IoDirection::Output));
+        assert_eq!(level(&rom, 0), PinValue::High);
+    }
+
     #[test]
     fn test_io_direction_display() {
         assert_eq!(IoDirection::Input.to_string(), "Input");
@@ -1,41 +1,56 @@
+use std::any::Any;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
-use crate::component::{BaseComponent, Component, RunnableComponent};
+use crate::component::{self, BaseComponent, Component, RunnableComponent};
 use crate::components::common::intel_400x::{
-    Intel400xAddressHandling, Intel400xClockHandling, Intel400xControlPins, Intel400xDataBus,
-    Intel400xResetHandling, Intel400xTimingState, RamState, TimingState,
+    BusSubcycle, Intel400xAddressHandling, Intel400xBusCycle, Intel400xClockHandling,
+    Intel400xControlPins, Intel400xDataBus, Intel400xResetHandling, Intel400xTimingState, RamState,
+    TimingState,
 };
 use crate::pin::{Pin, PinValue};
+use crate::sim_time::{SimTime, TimeBase};
+use serde::{Deserialize, Serialize};
 
 /// Intel 4002 - 320-bit RAM (80 nibbles × 4 bits) with integrated output ports
 /// Part of the MCS-4 family, designed to work with Intel 4004 CPU
-/// Features 80 nibbles of RAM organized in 4 banks of 20 nibbles each,
-/// plus 4 status characters and 4 output ports
+/// Features 80 nibbles of RAM organized in 4 registers of 20 characters each,
+/// plus 4 output ports
 ///
 /// Hardware Architecture:
-/// - 4 banks × 20 registers × 4 bits = 80 nibbles total
-/// - Status characters are separate 4-bit latches (not part of RAM)
+/// - 4 registers × (16 main + 4 status characters) × 4 bits = 80 nibbles total
+/// - Main characters are addressed by SRC; status characters by WR0-WR3 / RD0-RD3
+///   within the register selected by the last SRC
 /// - 4 output ports, each 4 bits wide
 /// - Complex addressing via SRC/WRM/RDM instructions from CPU
 ///
+/// Bus protocol: the chip follows the CPU's 8-subcycle instruction cycle. SRC sends the
+/// register address during X2 (with CM) and the character address during X3. A later
+/// I/O instruction, flagged by CM during M2, transfers its data during X2.
+///
+/// The two high bits of the SRC register nibble select one of four chips per bank. The
+/// chip number is made up of the metal option (4002-1 or 4002-2) in bit 1 and the P0
+/// strap in bit 0; only the chip whose number matches takes part in I/O instructions.
+///
 /// Hardware Deviations:
-/// - Simplified timing model for unit testing (may need refinement for CPU integration)
+/// - The P0 strap is configured with set_chip_id rather than read from the P0 pin
 /// - Output port behavior matches 4001 I/O latch persistence
-/// - Bank selection and status character handling follows Intel MCS-4 architecture
+/// - Bank selection is done by wiring CM to one of the CPU's CM-RAM lines: the chip
+///   only sees SRC and I/O instructions while the CPU has its bank selected with DCL
+/// - Status character handling follows Intel MCS-4 architecture
 pub struct Intel4002 {
     base: BaseComponent,
     variant: RamVariant,                 // RAM variant (4002-1 or 4002-2)
-    memory: [u8; 80], // 80 nibbles of RAM (320 bits total) - 4 banks × 20 nibbles
-    last_address: u8, // Last accessed memory address
-    access_time: Duration, // RAM access latency (500ns typical)
-    address_latch_time: Option<Instant>, // Timestamp when address was latched
+    chip_id: u8,                         // Chip number within the bank (variant and P0 strap)
+    registers: [RamRegister; 4],         // 4 registers × (16 main + 4 status) = 80 nibbles
+    last_address: u8,                    // Last accessed memory address
+    access_time: Duration,               // RAM access latency (500ns typical)
+    time_base: TimeBase,                 // Shared simulation time base for latency
+    address_latch_time: Option<SimTime>, // Simulated time when address was latched
     output_ports: [u8; 4], // 4 output ports (4 bits each) - TODO: Make [[u8; 4]; 4] for 4-bit ports
-    input_latch: u8,  // Input data latch for I/O operations
-    status_characters: [u8; 4], // 4 separate status character latches (4 bits each)
-    bank_select: u8,  // RAM bank selection (2 bits)
+    input_latch: u8,       // Input data latch for I/O operations
     // Clock edge detection (same as 4001)
     prev_phi1: PinValue, // Previous Φ1 clock state for edge detection
     prev_phi2: PinValue, // Previous Φ2 clock state for edge detection
@@ -45,15 +60,23 @@ pub struct Intel4002 {
     full_address_ready: bool,        // Whether complete address is assembled
     // RAM operation state machine
     ram_state: RamState, // Current state of RAM operation
-    // Data latching for RAM operations
-    data_latch: Option<u8>, // Latched data for write operations
     // Instruction cycle tracking
-    instruction_phase: bool, // Whether we're in instruction phase
-    current_instruction: u8, // Current instruction being processed
+    subcycle: BusSubcycle,      // Current subcycle of the CPU instruction cycle
+    opr_latch: u8,              // Opcode high nibble seen during M1
+    io_instruction: Option<u8>, // OPA of the I/O instruction in this cycle (CM at M2)
     // Cycle counting for debug output
     cycle_count: u64, // Total number of clock cycles executed
 }
 
+/// One of the four registers of an Intel 4002
+/// Main characters are addressed by the character nibble of SRC; the status characters
+/// are reached with WR0-WR3 / RD0-RD3 while the register is selected
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RamRegister {
+    pub main: [u8; 16],  // Main memory characters 0-15
+    pub status: [u8; 4], // Status characters 0-3
+}
+
 /// Intel 4002 RAM variants
 /// - 4002-1: Standard RAM variant
 /// - 4002-2: RAM variant with different configuration
@@ -63,6 +86,27 @@ pub enum RamVariant {
     Type2, // 4002-2
 }
 
+/// Snapshot of the RAM characters, output ports and bus cycle state
+/// The variant, chip number and access time are configuration and not included
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Intel4002State {
+    registers: [RamRegister; 4],
+    last_address: u8,
+    address_latch_time: Option<SimTime>,
+    output_ports: [u8; 4],
+    input_latch: u8,
+    prev_phi1: PinValue,
+    prev_phi2: PinValue,
+    address_high_nibble: Option<u8>,
+    address_low_nibble: Option<u8>,
+    full_address_ready: bool,
+    ram_state: RamState,
+    subcycle: BusSubcycle,
+    opr_latch: u8,
+    io_instruction: Option<u8>,
+    cycle_count: u64,
+}
+
 impl Intel400xClockHandling for Intel4002 {
     fn get_base(&self) -> &BaseComponent {
         &self.base
@@ -87,6 +131,8 @@ impl Intel400xControlPins for Intel4002 {
     }
 }
 
+impl Intel400xBusCycle for Intel4002 {}
+
 impl Intel400xResetHandling for Intel4002 {
     fn get_base(&self) -> &BaseComponent {
         &self.base
@@ -108,11 +154,11 @@ impl Intel400xTimingState for Intel4002 {
         self.ram_state = state.into();
     }
 
-    fn get_address_latch_time(&self) -> Option<Instant> {
+    fn get_address_latch_time(&self) -> Option<SimTime> {
         self.address_latch_time
     }
 
-    fn set_address_latch_time(&mut self, time: Option<Instant>) {
+    fn set_address_latch_time(&mut self, time: Option<SimTime>) {
         self.address_latch_time = time;
     }
 
@@ -175,16 +221,16 @@ impl Intel4002 {
         // - Clock pins: Φ1, Φ2 (two-phase clock from 4004 CPU)
         //
         // Control pin behavior:
-        // - SYNC: Marks start of instruction cycle
-        // - CM: ROM chip select (must be HIGH for ROM access)
-        // - P0: RAM chip select (must be HIGH for RAM access)
+        // - SYNC: Marks start of instruction cycle (subcycle A1)
+        // - CM: CM-RAM from the CPU, qualifies I/O instructions at M2 and the SRC address at X2
+        // - P0: Chip number strap, low bit of the chip number (see set_chip_id)
         // - RESET: Clears internal state
         let pin_names = vec![
             "D0", "D1", "D2", "D3", // Data/Address pins
             "O0", "O1", "O2", "O3",    // Output port pins
             "SYNC",  // Sync signal
-            "CM",    // ROM Chip Select
-            "P0",    // RAM Chip Select
+            "CM",    // CM-RAM command line
+            "P0",    // Chip select
             "RESET", // Reset
             "PHI1",  // Clock phase 1
             "PHI2",  // Clock phase 2
@@ -195,23 +241,26 @@ impl Intel4002 {
         Intel4002 {
             base: BaseComponent::new(name, pins),
             variant,
-            memory: [0u8; 80], // 80 nibbles = 4 banks × 20 nibbles each
+            chip_id: match variant {
+                RamVariant::Type1 => 0,
+                RamVariant::Type2 => 2,
+            },
+            registers: [RamRegister::default(); 4],
             last_address: 0,
             access_time: Duration::from_nanos(access_time_ns),
+            time_base: TimeBase::new(),
             address_latch_time: None,
             output_ports: [0u8; 4],
             input_latch: 0,
-            status_characters: [0u8; 4], // 4 separate status character latches
-            bank_select: 0,
             prev_phi1: PinValue::Low,
             prev_phi2: PinValue::Low,
             address_high_nibble: None,
             address_low_nibble: None,
             full_address_ready: false,
             ram_state: RamState::Idle,
-            data_latch: None,
-            instruction_phase: false,
-            current_instruction: 0,
+            subcycle: BusSubcycle::X3,
+            opr_latch: 0,
+            io_instruction: None,
             cycle_count: 0,
         }
     }
@@ -237,7 +286,7 @@ impl Intel4002 {
         }
 
         for (i, &byte) in data.iter().enumerate() {
-            self.memory[i] = byte & 0x0F; // Store only lower 4 bits
+            self.write_ram(i as u8, byte)?; // Store only lower 4 bits
         }
         Ok(())
     }
@@ -408,11 +457,9 @@ impl Intel4002 {
     fn handle_reset(&mut self) {
         if Intel400xResetHandling::handle_reset(self, "RESET") {
             // Hardware reset - clear all registers specific to Intel4002
-            self.memory = [0u8; 80]; // Clear 80 nibbles
+            self.registers = [RamRegister::default(); 4]; // Clear main and status characters
             self.output_ports = [0u8; 4];
             self.input_latch = 0;
-            self.status_characters = [0u8; 4]; // Clear 4 status character latches
-            self.bank_select = 0;
 
             // Reset all state machines using common functionality
             self.set_timing_state(TimingState::Idle);
@@ -420,9 +467,8 @@ impl Intel4002 {
             self.set_address_high_nibble(None);
             self.set_address_low_nibble(None);
             self.set_full_address_ready(false); // Reset address ready on reset
-            self.data_latch = None;
-            self.instruction_phase = false;
-            self.current_instruction = 0;
+            self.subcycle = BusSubcycle::X3;
+            self.io_instruction = None;
             self.cycle_count = 0;
 
             // Tri-state all outputs
@@ -439,7 +485,7 @@ impl Intel4002 {
             // Assemble 8-bit address: (high << 4) | low
             self.last_address = (high << 4) | low;
             self.full_address_ready = true;
-            self.address_latch_time = Some(Instant::now());
+            self.address_latch_time = Some(self.time_base.now());
 
             println!("DEBUG: [{}] Addr Assembled | High: 0x{:X} | Low: 0x{:X} | Full: 0x{:02X} | Ready: {} | Cycles: {}",
                     self.base.name(), high, low, self.last_address, self.full_address_ready, self.get_cycle_count());
@@ -459,112 +505,178 @@ impl Intel4002 {
         }
     }
 
-    /// Handle input latch operations
-    /// Hardware: RDM (Read Data Memory) instructions read from input pins
-    fn handle_input_latch_operation(&mut self) {
-        // In real hardware, this would read from external input pins
-        // For emulation, we use the input latch
-        self.write_data_bus(self.input_latch);
-    }
-
-    /// Handle Φ1 rising edge - Address and control phase
-    /// Hardware: Φ1 high = CPU drives bus with address/control information
-    /// RAM operations start when SYNC goes high during Φ1 rising edge
+    /// Handle Φ1 rising edge - Start of the next bus subcycle
+    /// Hardware: The RAM only drives the bus during X2 of a read instruction
+    /// (SBM, RDM, ADM, RD0-RD3) addressed by the last SRC
     fn handle_phi1_rising(&mut self) {
         // Handle system reset first (highest priority)
         self.handle_reset();
 
-        // Check for RAM operation start on Φ1 rising edge with SYNC high
-        let (sync, _cm, p0, _) = self.read_control_pins();
-        if sync && p0 && self.ram_state == RamState::Idle {
-            // Start RAM address phase on Φ1 rising edge (only when idle)
-            self.start_ram_address_phase();
+        self.subcycle = self.next_subcycle(self.subcycle);
+        match self.subcycle {
+            BusSubcycle::X2 => {
+                if self.ram_state == RamState::WaitLatency {
+                    self.handle_latency_wait();
+                }
+                match self.io_instruction {
+                    Some(opa) if self.is_ready_for_io() => self.handle_io_read(opa),
+                    _ => self.tri_state_data_bus(),
+                }
+            }
+            BusSubcycle::X3 => {
+                self.tri_state_data_bus();
+                if matches!(
+                    self.ram_state,
+                    RamState::ReadData | RamState::WriteData | RamState::OutputPort
+                ) {
+                    self.return_to_idle();
+                }
+            }
+            _ => self.tri_state_data_bus(),
         }
-
-        // Handle RAM address phase operations during Φ1
-        self.handle_ram_address_operations();
-    }
-
-    /// Handle Φ1 falling edge - End of address phase
-    fn handle_phi1_falling(&mut self) {
-        // Currently no specific operations needed on Φ1 falling
     }
 
-    /// Handle Φ2 rising edge - Data phase
+    /// Handle Φ2 rising edge - Sample the bus
+    /// Hardware: The opcode arrives during M1-M2, the SRC address or write data during X2
+    /// and the SRC character address during X3
     fn handle_phi2_rising(&mut self) {
-        self.handle_ram_data_operations();
+        let (_, cm, _, _) = self.read_control_pins();
+        match self.subcycle {
+            BusSubcycle::M1 => {
+                self.opr_latch = self.read_data_bus();
+            }
+            BusSubcycle::M2 => {
+                // CM during M2 marks an I/O or RAM instruction, OPA selects which one
+                self.io_instruction = if cm && self.opr_latch == 0xE {
+                    Some(self.read_data_bus())
+                } else {
+                    None
+                };
+            }
+            BusSubcycle::X2 if cm => {
+                // SRC: chip and register number, only the addressed chip stays selected
+                let nibble = self.read_data_bus();
+                self.start_ram_address_phase();
+                if nibble >> 2 == self.chip_id {
+                    self.address_high_nibble = Some(nibble);
+                } else {
+                    self.return_to_idle();
+                }
+            }
+            BusSubcycle::X2 => {
+                if let Some(opa) = self.io_instruction {
+                    if self.is_ready_for_io() {
+                        self.handle_io_write(opa);
+                    }
+                }
+            }
+            BusSubcycle::X3 if self.ram_state == RamState::AddressPhase => {
+                // SRC: character number
+                self.address_low_nibble = Some(self.read_data_bus());
+                self.assemble_full_address();
+                self.start_latency_wait();
+            }
+            _ => {}
+        }
     }
 
-    /// Handle Φ2 falling edge - End of data phase
-    fn handle_phi2_falling(&mut self) {
-        self.handle_ram_cleanup_operations();
+    /// Check whether an SRC address is latched and its access time has passed
+    /// Returns: true if the chip can serve an I/O instruction
+    fn is_ready_for_io(&self) -> bool {
+        self.full_address_ready && self.ram_state != RamState::WaitLatency
     }
 
-    /// Handle RAM address-related operations during Φ1
-    fn handle_ram_address_operations(&mut self) {
-        match self.ram_state {
-            RamState::Idle => {
-                self.tri_state_data_bus();
-            }
-
-            RamState::AddressPhase => {
-                self.handle_address_latching();
-            }
+    /// Get the register and main character selected by the last SRC
+    /// Returns: (register index 0-3, character index 0-15)
+    fn selected_character(&self) -> (usize, usize) {
+        let register = ((self.last_address >> 4) & 0x03) as usize;
+        let character = (self.last_address & 0x0F) as usize;
+        (register, character)
+    }
 
-            RamState::WaitLatency => {
-                self.handle_latency_wait();
+    /// Drive the operand of a read instruction during X2
+    /// Parameters: opa - Low nibble of the I/O instruction
+    fn handle_io_read(&mut self, opa: u8) {
+        match self.io_read_data(opa) {
+            Some(data) => {
+                self.ram_state = RamState::ReadData;
+                self.write_data_bus(data);
             }
+            None => self.tri_state_data_bus(),
+        }
+    }
 
-            RamState::ReadData | RamState::WriteData | RamState::OutputPort => {
-                self.tri_state_data_bus();
-            }
+    /// Get the operand of a read instruction
+    /// Parameters: opa - Low nibble of the I/O instruction
+    /// Returns: Some(4-bit data) for SBM, RDM, ADM and RD0-RD3, None for other instructions
+    fn io_read_data(&self, opa: u8) -> Option<u8> {
+        let (register, character) = self.selected_character();
+        match opa {
+            // SBM, RDM, ADM
+            0x8 | 0x9 | 0xB => Some(self.registers[register].main[character]),
+            // RD0-RD3: status characters of the register selected by SRC
+            0xC..=0xF => Some(self.registers[register].status[(opa - 0xC) as usize]),
+            _ => None,
         }
     }
 
-    /// Handle RAM data-related operations during Φ2
-    fn handle_ram_data_operations(&mut self) {
-        match self.ram_state {
-            RamState::Idle => {
-                self.tri_state_data_bus();
-            }
+    /// Store the accumulator sent by a write instruction during X2
+    /// Parameters: opa - Low nibble of the I/O instruction
+    fn handle_io_write(&mut self, opa: u8) {
+        let data = self.read_data_bus();
+        self.io_write_data(opa, data);
+    }
 
-            RamState::AddressPhase => {
-                self.tri_state_data_bus();
+    /// Store the data of a write instruction
+    /// Parameters: opa - Low nibble of the I/O instruction, data - Accumulator sent by the CPU
+    fn io_write_data(&mut self, opa: u8, data: u8) {
+        let data = data & 0x0F;
+        match opa {
+            // WRM
+            0x0 => {
+                let (register, character) = self.selected_character();
+                self.registers[register].main[character] = data;
+                self.ram_state = RamState::WriteData;
             }
-
-            RamState::WaitLatency => {
-                self.handle_latency_wait();
-                if self.ram_state == RamState::ReadData || self.ram_state == RamState::WriteData {
-                    self.handle_data_operations();
+            // WMP: each output line takes one bit of the accumulator
+            0x1 => {
+                for port in 0..4 {
+                    self.handle_output_port_operation(port, (data >> port) & 1);
                 }
+                self.ram_state = RamState::OutputPort;
             }
-
-            RamState::ReadData => {
-                self.handle_data_operations();
-            }
-
-            RamState::WriteData => {
-                self.handle_data_operations();
-            }
-
-            RamState::OutputPort => {
-                self.handle_output_port_state();
+            // WR0-WR3: status characters of the register selected by SRC
+            0x4..=0x7 => {
+                let (register, _) = self.selected_character();
+                self.registers[register].status[(opa - 0x4) as usize] = data;
+                self.ram_state = RamState::WriteData;
             }
+            _ => {}
         }
     }
 
-    /// Handle RAM cleanup operations on Φ2 falling edge
-    fn handle_ram_cleanup_operations(&mut self) {
-        match self.ram_state {
-            RamState::ReadData | RamState::WriteData | RamState::OutputPort => {
-                self.tri_state_data_bus();
-                self.return_to_idle();
-            }
+    /// Take an SRC address without a bus cycle, for a CPU in functional mode
+    /// Only the chip named by bits 7-6 stays selected, as when SRC arrives over the bus
+    /// Parameters: address - Register pair sent by SRC
+    pub fn select_character(&mut self, address: u8) {
+        self.start_ram_address_phase();
+        if address >> 6 == self.chip_id {
+            self.last_address = address;
+            self.full_address_ready = true;
+        }
+        self.return_to_idle();
+    }
 
-            RamState::Idle | RamState::AddressPhase | RamState::WaitLatency => {
-                self.tri_state_data_bus();
-            }
+    /// Execute an I/O instruction without a bus cycle, for a CPU in functional mode
+    /// Parameters: opa - Low nibble of the I/O instruction, accumulator - Data of a write
+    /// Returns: Some(4-bit data) if this chip answers a read, None otherwise
+    pub fn execute_io(&mut self, opa: u8, accumulator: u8) -> Option<u8> {
+        if !self.full_address_ready {
+            return None;
         }
+        self.io_write_data(opa, accumulator);
+        self.return_to_idle();
+        self.io_read_data(opa)
     }
 
     /// Transition to address phase state
@@ -577,136 +689,20 @@ impl Intel4002 {
         self.last_address = 0;
     }
 
-    /// Handle address nibble latching during address phase
-    fn handle_address_latching(&mut self) {
-        let nibble = self.read_data_bus();
-
-        if self.address_high_nibble.is_none() {
-            self.address_high_nibble = Some(nibble);
-            println!(
-                "DEBUG: [{}] Addr Latch High | Nibble: 0x{:X} | State: {:?} | Cycles: {}",
-                self.base.name(),
-                nibble,
-                self.ram_state,
-                self.get_cycle_count()
-            );
-        } else if self.address_low_nibble.is_none() {
-            self.address_low_nibble = Some(nibble);
-            println!("DEBUG: [{}] Addr Latch Low | High: 0x{:X} | Low: 0x{:X} | State: {:?} | Cycles: {}",
-                    self.base.name(), self.address_high_nibble.unwrap(), nibble, self.ram_state, self.get_cycle_count());
-            self.assemble_full_address();
-            self.start_latency_wait();
-        }
-    }
-
     /// Transition to latency wait state
     fn start_latency_wait(&mut self) {
         self.ram_state = RamState::WaitLatency;
-        self.address_latch_time = Some(Instant::now());
+        self.address_latch_time = Some(self.time_base.now());
     }
 
     /// Handle latency timing during wait state
+    /// Hardware: The SRC address stays selected for the following I/O instructions,
+    /// so the chip only leaves the wait state here
     fn handle_latency_wait(&mut self) {
         if let Some(latch_time) = self.address_latch_time {
-            if latch_time.elapsed() >= self.access_time {
-                self.start_data_operation();
-            }
-        }
-    }
-
-    /// Transition to data operation state
-    fn start_data_operation(&mut self) {
-        // Determine operation type based on control signals and address
-        let (sync, _cm, p0, _) = self.read_control_pins();
-
-        if sync && p0 && self.full_address_ready {
-            let address = self.last_address;
-
-            if address >= 0x14 && address <= 0x17 {
-                // Output port operation
-                self.ram_state = RamState::OutputPort;
-            } else {
-                // RAM read/write operation
-                // For now, assume read - write detection happens in data phase
-                self.ram_state = RamState::ReadData;
-            }
-        }
-    }
-
-    /// Handle data operations during ReadData or WriteData state
-    fn handle_data_operations(&mut self) {
-        let (sync, cm, p0, _) = self.read_control_pins();
-
-        if sync && p0 && self.full_address_ready {
-            let address = self.last_address;
-
-            if address == 0xFF {
-                // Status character operation
-                let data = self.read_data_bus();
-                if !cm {
-                    // First cycle - read from status character
-                    // For now, return status character 0 - this should be determined by instruction
-                    self.write_data_bus(self.status_characters[0]);
-                    println!("DEBUG: [{}] Status Char Read | Index: 0 | Data: 0x{:X} | State: {:?} | Cycles: {}",
-                            self.base.name(), self.status_characters[0], self.ram_state, self.get_cycle_count());
-                } else {
-                    // Second cycle - write to status character
-                    // For now, write to status character 0 - this should be determined by instruction
-                    let old_status = self.status_characters[0];
-                    self.status_characters[0] = data & 0x0F;
-                    self.ram_state = RamState::WriteData;
-                    println!("DEBUG: [{}] Status Char Write | Index: 0 | Old: 0x{:X} | New: 0x{:X} | State: {:?} | Cycles: {}",
-                            self.base.name(), old_status, data & 0x0F, self.ram_state, self.get_cycle_count());
-                }
-            } else if address < 80 {
-                // RAM operation
-                let data = self.read_data_bus();
-                if !cm {
-                    // First cycle - read from RAM
-                    let ram_data = self.memory[address as usize];
-                    self.write_data_bus(ram_data);
-                    println!("DEBUG: [{}] RAM Read | Addr: 0x{:02X} | Data: 0x{:X} | Bank: {} | State: {:?} | Cycles: {}",
-                            self.base.name(), address, ram_data, self.bank_select, self.ram_state, self.get_cycle_count());
-                } else {
-                    // Second cycle - write to RAM
-                    let old_data = self.memory[address as usize];
-                    self.memory[address as usize] = data & 0x0F;
-                    self.ram_state = RamState::WriteData;
-                    println!("DEBUG: [{}] RAM Write | Addr: 0x{:02X} | Old: 0x{:X} | New: 0x{:X} | Bank: {} | State: {:?} | Cycles: {}",
-                            self.base.name(), address, old_data, data & 0x0F, self.bank_select, self.ram_state, self.get_cycle_count());
-                }
-            }
-        } else {
-            self.tri_state_data_bus();
-        }
-    }
-
-    /// Handle output port state operations
-    fn handle_output_port_state(&mut self) {
-        let (sync, cm, p0, _) = self.read_control_pins();
-
-        if sync && p0 && self.full_address_ready {
-            let address = self.last_address;
-
-            if address >= 0x14 && address <= 0x17 {
-                let port = (address - 0x14) as usize;
-                if !cm {
-                    // First cycle - read from output port (not typical for 4002)
-                    let port_data = self.output_ports[port];
-                    self.write_data_bus(port_data);
-                    println!("DEBUG: [{}] Output Port Read | Port: {} | Data: 0x{:X} | State: {:?} | Cycles: {}",
-                            self.base.name(), port, port_data, self.ram_state, self.get_cycle_count());
-                } else {
-                    // Second cycle - write to output port
-                    let data = self.read_data_bus();
-                    let old_port_data = self.output_ports[port];
-                    self.handle_output_port_operation(port, data);
-                    println!("DEBUG: [{}] Output Port Write | Port: {} | Old: 0x{:X} | New: 0x{:X} | State: {:?} | Cycles: {}",
-                            self.base.name(), port, old_port_data, data & 0x0F, self.ram_state, self.get_cycle_count());
-                }
+            if self.time_base.elapsed_since(latch_time) >= self.access_time {
+                self.return_to_idle();
             }
-        } else {
-            self.tri_state_data_bus();
         }
     }
 
@@ -714,37 +710,7 @@ impl Intel4002 {
     fn return_to_idle(&mut self) {
         self.ram_state = RamState::Idle;
         self.address_latch_time = None;
-        // Don't reset address nibbles and ready flag immediately - keep them for potential reuse
-        // They will be cleared when a new address operation starts
-        self.data_latch = None;
-        self.instruction_phase = false;
-        self.current_instruction = 0;
-    }
-
-    /// Handle special RAM instructions (bank select, status characters, etc.)
-    /// Hardware: These are handled during instruction cycles, not memory cycles
-    fn handle_special_instructions(&mut self, instruction: u8) {
-        match instruction {
-            // DCL (Designate Command Line) - selects RAM bank
-            0xF0..=0xF3 => {
-                self.bank_select = instruction & 0x03;
-            }
-
-            // SRC (Send Register Control) - sets output port address
-            0x20..=0x2F => {
-                // SRC instruction sets up output port addressing
-                // For now, this is handled by the output port operation logic
-            }
-
-            // Input latch operations (RDM - Read Data Memory)
-            0x60..=0x63 => {
-                self.handle_input_latch_operation();
-            }
-
-            _ => {
-                // Other instructions may be handled elsewhere
-            }
-        }
+        // Don't reset the SRC address - it stays selected until the next SRC
     }
 }
 
@@ -773,69 +739,47 @@ impl Component for Intel4002 {
             CYCLE_COUNTER += 1;
             if CYCLE_COUNTER % 5000 == 0 {
                 // Less frequent than CPU to avoid spam
-                println!("DEBUG: [{}] RAM State | Bank: {} | Status: {:?} | Ready: {} | LastAddr: 0x{:02X} | Cycles: {} | HighNib: {:?} | LowNib: {:?}",
-                        self.base.name(), self.bank_select, self.ram_state, self.full_address_ready, self.last_address, self.get_cycle_count(), self.address_high_nibble, self.address_low_nibble);
+                println!("DEBUG: [{}] RAM State | Status: {:?} | Ready: {} | LastAddr: 0x{:02X} | Cycles: {} | HighNib: {:?} | LowNib: {:?}",
+                        self.base.name(), self.ram_state, self.full_address_ready, self.last_address, self.get_cycle_count(), self.address_high_nibble, self.address_low_nibble);
             }
         }
-        // Handle both rising and falling edges for proper two-phase operation
+        // The bus cycle advances on Φ1 and is sampled on Φ2
         let (phi1, phi2) = self.read_clock_pins();
         let phi1_rising = phi1 == PinValue::High && self.prev_phi1 == PinValue::Low;
-        let phi1_falling = phi1 == PinValue::Low && self.prev_phi1 == PinValue::High;
         let phi2_rising = phi2 == PinValue::High && self.prev_phi2 == PinValue::Low;
-        let phi2_falling = phi2 == PinValue::Low && self.prev_phi2 == PinValue::High;
 
         // Update clock states for next edge detection
         self.prev_phi1 = phi1;
         self.prev_phi2 = phi2;
 
         if phi1_rising {
-            println!(
-                "DEBUG: [RAM] Φ1 Rising Edge - State: {:?}, Ready: {}",
-                self.ram_state, self.full_address_ready
-            );
-            // Φ1 Rising Edge: Address phase (CPU drives bus) - handle address latching
+            // Φ1 Rising Edge: Next subcycle - drive the bus if it is the RAM's turn
             self.handle_phi1_rising();
         }
 
-        if phi1_falling {
-            // Φ1 Falling Edge: End of address phase
-            println!("DEBUG: [RAM] Φ1 Falling Edge");
-            self.handle_phi1_falling();
-        }
-
         if phi2_rising {
-            // Φ2 Rising Edge: Data phase (RAM drives bus if ready) - handle data operations
-            println!("DEBUG: [RAM] Φ2 Rising Edge - State: {:?}", self.ram_state);
+            // Φ2 Rising Edge: Sample what the CPU put on the bus
             self.handle_phi2_rising();
         }
 
-        if phi2_falling {
-            // Φ2 Falling Edge: End of data phase - tri-state bus and return to idle
-            println!("DEBUG: [RAM] Φ2 Falling Edge");
-            self.handle_phi2_falling();
-        }
-
-        // Handle special instructions when not in memory operation
-        let (sync, cm, p0, _) = self.read_control_pins();
-        if sync && !p0 && !cm {
-            // Instruction phase - handle special RAM instructions
-            let instruction = self.read_data_bus();
-            self.handle_special_instructions(instruction);
-        }
-
         // Increment cycle count for debug output
         self.cycle_count += 1;
     }
 
-    /// Run component in time-slice mode (manual control)
-    /// Hardware: Simulates continuous operation with clock edge detection
-    fn run(&mut self) {
+    /// Mark component running for an external scheduler and sync clock edge state
+    fn start(&mut self) {
         self.base.set_running(true);
 
         // Initialize clock states for edge detection
         let (phi1, phi2) = self.read_clock_pins();
         self.prev_phi1 = phi1;
         self.prev_phi2 = phi2;
+    }
+
+    /// Run component in time-slice mode (manual control)
+    /// Hardware: Simulates continuous operation with clock edge detection
+    fn run(&mut self) {
+        self.start();
 
         while self.is_running() {
             self.update();
@@ -860,6 +804,28 @@ impl Component for Intel4002 {
     fn is_running(&self) -> bool {
         self.base.is_running()
     }
+
+    fn attach_time_base(&mut self, time_base: TimeBase) {
+        self.time_base = time_base;
+    }
+
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        component::state_to_json(&self.snapshot())
+    }
+
+    fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        let state = component::state_from_json(&self.base.name(), state)?;
+        self.restore(&state);
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
 }
 
 impl RunnableComponent for Intel4002 {}
@@ -869,30 +835,59 @@ impl Intel4002 {
     /// Get the RAM size in nibbles
     /// Returns: Total number of nibbles in RAM (80 for 4002)
     pub fn get_ram_size(&self) -> usize {
-        self.memory.len()
+        self.registers.len() * 20
     }
 
-    /// Read a nibble from RAM at specified address
-    /// Parameters: address - RAM address (0-79)
-    /// Returns: Some(data) if address valid, None if out of bounds
-    pub fn read_ram(&self, address: u8) -> Option<u8> {
-        if (address as usize) < self.memory.len() {
-            Some(self.memory[address as usize])
+    /// Map a flat RAM address onto the register layout
+    /// Parameters: address - RAM address (0-79), register × 20 + character, where
+    /// characters 16-19 are the register's status characters 0-3
+    /// Returns: (register, character) if address valid, None if out of bounds
+    fn flat_location(address: u8) -> Option<(usize, usize)> {
+        let address = address as usize;
+        if address < 80 {
+            Some((address / 20, address % 20))
         } else {
             None
         }
     }
 
+    /// Read a nibble from RAM at specified address
+    /// Parameters: address - RAM address (0-79), register × 20 + character
+    /// Returns: Some(data) if address valid, None if out of bounds
+    pub fn read_ram(&self, address: u8) -> Option<u8> {
+        let (register, character) = Self::flat_location(address)?;
+        let register = &self.registers[register];
+        Some(match character {
+            0..=15 => register.main[character],
+            _ => register.status[character - 16],
+        })
+    }
+
     /// Write a nibble to RAM at specified address
-    /// Parameters: address - RAM address (0-79), data - 4-bit data to write
+    /// Parameters: address - RAM address (0-79), register × 20 + character, data - 4-bit data to write
     /// Returns: Ok(()) on success, Err(String) on failure
     pub fn write_ram(&mut self, address: u8, data: u8) -> Result<(), String> {
-        if (address as usize) < self.memory.len() {
-            self.memory[address as usize] = data & 0x0F;
-            Ok(())
-        } else {
-            Err("Address out of range (0-79)".to_string())
+        let (register, character) = Self::flat_location(address)
+            .ok_or_else(|| "Address out of range (0-79)".to_string())?;
+        let register = &mut self.registers[register];
+        match character {
+            0..=15 => register.main[character] = data & 0x0F,
+            _ => register.status[character - 16] = data & 0x0F,
         }
+        Ok(())
+    }
+
+    /// Get one register with its main and status characters
+    /// Parameters: index - Register number (0-3)
+    /// Returns: Some(register) if index valid, None if out of bounds
+    pub fn get_register(&self, index: usize) -> Option<&RamRegister> {
+        self.registers.get(index)
+    }
+
+    /// Get all four registers
+    /// Returns: Registers 0-3, each with 16 main and 4 status characters
+    pub fn get_registers(&self) -> &[RamRegister; 4] {
+        &self.registers
     }
 
     /// Get the current output port value
@@ -931,27 +926,11 @@ impl Intel4002 {
         self.input_latch
     }
 
-    /// Get the current status character value
-    /// Parameters: index - Status character index (0-3)
-    /// Returns: 4-bit status character value
-    pub fn get_status_character(&self, index: usize) -> Option<u8> {
-        if index < 4 {
-            Some(self.status_characters[index])
-        } else {
-            None
-        }
-    }
-
-    /// Get all status characters
-    /// Returns: Array of 4 status character values
-    pub fn get_all_status_characters(&self) -> [u8; 4] {
-        self.status_characters
-    }
-
-    /// Get the current bank select value
-    /// Returns: 2-bit bank select value (0-3)
-    pub fn get_bank_select(&self) -> u8 {
-        self.bank_select
+    /// Get a status character of a register
+    /// Parameters: register - Register number (0-3), index - Status character index (0-3)
+    /// Returns: Some(4-bit status character value) if both indices valid, None otherwise
+    pub fn get_status_character(&self, register: usize, index: usize) -> Option<u8> {
+        self.registers.get(register)?.status.get(index).copied()
     }
 
     /// Get the RAM variant
@@ -960,6 +939,33 @@ impl Intel4002 {
         self.variant
     }
 
+    /// Set the chip number the chip answers to within its bank
+    /// Hardware: Bit 1 is the metal option, so it also selects the 4002-1 or 4002-2
+    /// variant; bit 0 is the P0 strap
+    /// Parameters: chip_id - Chip number (0-3)
+    /// Returns: Ok(()) on success, Err(String) if the number does not fit in 2 bits
+    pub fn set_chip_id(&mut self, chip_id: u8) -> Result<(), String> {
+        if chip_id > 0x03 {
+            return Err(format!(
+                "Intel 4002 chip number {} out of range 0-3",
+                chip_id
+            ));
+        }
+        self.chip_id = chip_id;
+        self.variant = if chip_id & 0x02 != 0 {
+            RamVariant::Type2
+        } else {
+            RamVariant::Type1
+        };
+        Ok(())
+    }
+
+    /// Get the chip number the chip answers to within its bank
+    /// Returns: Chip number (0-3), compared against bits 3-2 of the SRC register nibble
+    pub fn get_chip_id(&self) -> u8 {
+        self.chip_id
+    }
+
     /// Get the current cycle count for the RAM component
     /// Returns: Current cycle count since component start
     pub fn get_cycle_count(&self) -> u64 {
@@ -968,46 +974,78 @@ impl Intel4002 {
 
     /// Clear all RAM to zero
     pub fn clear_ram(&mut self) {
-        self.memory = [0u8; 80]; // Clear 80 nibbles
+        self.registers = [RamRegister::default(); 4]; // Clear 80 nibbles
     }
 
-    /// Get all RAM data for a specific bank
-    /// Parameters: bank - Bank number (0-3)
-    /// Returns: Vector of 20 nibbles for the bank (4 banks × 20 nibbles = 80 total)
+    /// Get all RAM data for a specific register
+    /// Parameters: bank - Register number (0-3)
+    /// Returns: Vector of 20 nibbles: 16 main characters followed by 4 status characters
     pub fn get_ram_bank(&self, bank: u8) -> Vec<u8> {
-        let bank = (bank & 0x03) as usize;
-        let start = bank * 20; // 20 nibbles per bank
-        let end = start + 20;
-
-        if end <= self.memory.len() {
-            self.memory[start..end].to_vec()
-        } else if start >= self.memory.len() {
-            Vec::new()
-        } else {
-            self.memory[start..].to_vec()
+        let register = &self.registers[(bank & 0x03) as usize];
+        register
+            .main
+            .iter()
+            .chain(register.status.iter())
+            .copied()
+            .collect()
+    }
+
+    /// Capture the RAM state for a machine snapshot
+    /// Returns: Main and status characters, output ports and bus cycle state
+    pub fn snapshot(&self) -> Intel4002State {
+        Intel4002State {
+            registers: self.registers,
+            last_address: self.last_address,
+            address_latch_time: self.address_latch_time,
+            output_ports: self.output_ports,
+            input_latch: self.input_latch,
+            prev_phi1: self.prev_phi1,
+            prev_phi2: self.prev_phi2,
+            address_high_nibble: self.address_high_nibble,
+            address_low_nibble: self.address_low_nibble,
+            full_address_ready: self.full_address_ready,
+            ram_state: self.ram_state,
+            subcycle: self.subcycle,
+            opr_latch: self.opr_latch,
+            io_instruction: self.io_instruction,
+            cycle_count: self.cycle_count,
         }
     }
 
+    /// Restore a RAM state captured by `snapshot` and drive the output ports to match
+    /// Parameters: state - RAM state
+    pub fn restore(&mut self, state: &Intel4002State) {
+        self.registers = state.registers;
+        self.last_address = state.last_address;
+        self.address_latch_time = state.address_latch_time;
+        self.output_ports = state.output_ports;
+        self.input_latch = state.input_latch;
+        self.prev_phi1 = state.prev_phi1;
+        self.prev_phi2 = state.prev_phi2;
+        self.address_high_nibble = state.address_high_nibble;
+        self.address_low_nibble = state.address_low_nibble;
+        self.full_address_ready = state.full_address_ready;
+        self.ram_state = state.ram_state;
+        self.subcycle = state.subcycle;
+        self.opr_latch = state.opr_latch;
+        self.io_instruction = state.io_instruction;
+        self.cycle_count = state.cycle_count;
+        self.update_output_ports();
+    }
+
     /// Debug function to log state transitions for troubleshooting
     /// Parameters: test_name - Name of the test for context
     pub fn debug_state_transitions(&self, test_name: &str) {
         println!(
-            "{} - State: {:?}, Bank: {}, High: {:?}, Low: {:?}, Address: 0x{:x}, Ready: {}",
+            "{} - State: {:?}, High: {:?}, Low: {:?}, Address: 0x{:x}, Ready: {}",
             test_name,
             self.ram_state,
-            self.bank_select,
             self.address_high_nibble,
             self.address_low_nibble,
             self.last_address,
             self.full_address_ready
         );
     }
-
-    /// Setup helper for instruction testing
-    pub fn setup_instruction_test(&mut self, instruction: u8) {
-        self.current_instruction = instruction;
-        self.instruction_phase = true;
-    }
 }
 
 // Custom formatter for debugging
@@ -1130,134 +1168,180 @@ mod tests {
         assert_eq!(fast_ram.name(), "FAST_RAM");
     }
 
-    #[test]
-    fn test_address_latching() {
-        let mut ram = Intel4002::new_with_access_time("RAM_4002".to_string(), 1);
-
-        // Start the component so it can process updates
-        ram.base.set_running(true);
-        assert!(ram.is_running());
-
-        // Get pin references
-        let sync_pin = ram.get_pin("SYNC").unwrap();
-        let p0_pin = ram.get_pin("P0").unwrap();
-        let phi1_pin = ram.get_pin("PHI1").unwrap();
-        let d0_pin = ram.get_pin("D0").unwrap();
-        let d1_pin = ram.get_pin("D1").unwrap();
-        let d2_pin = ram.get_pin("D2").unwrap();
-        let d3_pin = ram.get_pin("D3").unwrap();
-
-        // Initialize clock pins to Low
-        {
-            let mut phi1_guard = phi1_pin.lock().unwrap();
-            phi1_guard.set_driver(Some("TEST".to_string()), PinValue::Low);
-        }
-
-        // Set up RAM operation: SYNC=1, CM-RAM=1
-        {
-            let mut sync_guard = sync_pin.lock().unwrap();
-            let mut p0_guard = p0_pin.lock().unwrap();
-            sync_guard.set_driver(Some("TEST".to_string()), PinValue::High);
-            p0_guard.set_driver(Some("TEST".to_string()), PinValue::High);
-        }
-
-        // Set address high nibble (0x0) on data bus
-        {
-            let mut d0_guard = d0_pin.lock().unwrap();
-            let mut d1_guard = d1_pin.lock().unwrap();
-            let mut d2_guard = d2_pin.lock().unwrap();
-            let mut d3_guard = d3_pin.lock().unwrap();
-            d0_guard.set_driver(Some("TEST".to_string()), PinValue::Low);
-            d1_guard.set_driver(Some("TEST".to_string()), PinValue::Low);
-            d2_guard.set_driver(Some("TEST".to_string()), PinValue::Low);
-            d3_guard.set_driver(Some("TEST".to_string()), PinValue::Low);
-        }
-
-        // Debug: Check pin states before first update
-        println!(
-            "DEBUG: Before first update - SYNC: {:?}, P0: {:?}, PHI1: {:?}",
-            sync_pin.lock().unwrap().read(),
-            p0_pin.lock().unwrap().read(),
-            phi1_pin.lock().unwrap().read()
-        );
-
-        // Debug: Check conditions that would trigger RAM operation
-        let (sync, _cm, p0, _) = ram.read_control_pins();
-        println!(
-            "DEBUG: Control pins - sync: {}, p0: {}, combined: {}",
-            sync,
-            p0,
-            sync && p0
-        );
-
-        // Debug: Check initial clock state
-        println!(
-            "DEBUG: Before first update - prev_phi1: {:?}",
-            ram.prev_phi1
-        );
-
-        // High nibble
-        phi1_pin
+    /// Drive a pin of the RAM from the test bench
+    fn drive(ram: &Intel4002, pin: &str, value: PinValue) {
+        ram.get_pin(pin)
+            .unwrap()
             .lock()
             .unwrap()
-            .set_driver(Some("TEST".into()), PinValue::High);
-        ram.update(); // rising edge -> latch high nibble
+            .set_driver(Some("TEST".to_string()), value);
+    }
+
+    /// Run one subcycle: Φ1 with the given SYNC/CM/bus values, then Φ2
+    /// Parameters: ram - RAM under test, time_base - Time base attached to the RAM,
+    /// sync/cm - Control lines, data - Nibble the CPU or ROM drives, None for tri-state
+    fn clock_subcycle(
+        ram: &mut Intel4002,
+        time_base: &TimeBase,
+        sync: bool,
+        cm: bool,
+        data: Option<u8>,
+    ) {
+        drive(ram, "SYNC", PinValue::from_bool(sync));
+        drive(ram, "CM", PinValue::from_bool(cm));
+        for i in 0..4 {
+            let value = data.map_or(PinValue::HighZ, |d| PinValue::from_bool((d >> i) & 1 == 1));
+            drive(ram, &format!("D{}", i), value);
+        }
 
-        // Debug: Check clock state after first update
-        println!("DEBUG: After first update - prev_phi1: {:?}", ram.prev_phi1);
+        drive(ram, "PHI2", PinValue::Low);
+        drive(ram, "PHI1", PinValue::High);
+        ram.update();
+        time_base.advance(Duration::from_nanos(667));
 
-        phi1_pin
-            .lock()
-            .unwrap()
-            .set_driver(Some("TEST".into()), PinValue::Low);
-        ram.update(); // falling edge
+        drive(ram, "PHI1", PinValue::Low);
+        drive(ram, "PHI2", PinValue::High);
+        ram.update();
+        time_base.advance(Duration::from_nanos(667));
+    }
+
+    /// Run subcycles A1 to X1 of an instruction cycle as seen on the bus
+    /// Parameters: opcode - Instruction byte returned by ROM during M1/M2
+    fn run_fetch(ram: &mut Intel4002, time_base: &TimeBase, opcode: u8) {
+        let io = opcode >> 4 == 0xE;
+        clock_subcycle(ram, time_base, true, false, Some(0x0));
+        clock_subcycle(ram, time_base, false, false, Some(0x0));
+        clock_subcycle(ram, time_base, false, true, Some(0x0));
+        clock_subcycle(ram, time_base, false, false, Some(opcode >> 4));
+        clock_subcycle(ram, time_base, false, io, Some(opcode & 0x0F));
+        clock_subcycle(ram, time_base, false, false, None);
+    }
+
+    /// Run a complete instruction cycle as seen on the bus
+    /// Parameters: opcode - Instruction byte returned by ROM during M1/M2,
+    /// x2/x3 - Nibbles the CPU drives during X2/X3 (None for tri-state)
+    fn run_instruction_cycle(
+        ram: &mut Intel4002,
+        time_base: &TimeBase,
+        opcode: u8,
+        x2: Option<u8>,
+        x3: Option<u8>,
+    ) {
+        let src = opcode & 0xF1 == 0x21;
+        run_fetch(ram, time_base, opcode);
+        clock_subcycle(ram, time_base, false, src, x2);
+        clock_subcycle(ram, time_base, false, false, x3);
+    }
+
+    fn create_running_ram() -> (Intel4002, TimeBase) {
+        let mut ram = Intel4002::new("RAM_4002".to_string());
+        let time_base = TimeBase::new();
+        ram.attach_time_base(time_base.clone());
+        drive(&ram, "PHI1", PinValue::Low);
+        drive(&ram, "PHI2", PinValue::Low);
+        ram.start();
+        (ram, time_base)
+    }
 
-        // Should have transitioned to AddressPhase and latched high nibble
-        assert_eq!(ram.ram_state, RamState::AddressPhase);
-        assert_eq!(ram.address_high_nibble, Some(0x0));
-        // Note: full_address_ready should be false until both nibbles are assembled
+    #[test]
+    fn test_address_latching() {
+        let (mut ram, time_base) = create_running_ram();
 
-        // Set address low nibble (0x1) on data bus - different from high nibble
-        {
-            let mut d0_guard = d0_pin.lock().unwrap();
-            let mut d1_guard = d1_pin.lock().unwrap();
-            let mut d2_guard = d2_pin.lock().unwrap();
-            let mut d3_guard = d3_pin.lock().unwrap();
-            d0_guard.set_driver(Some("TEST".into()), PinValue::High); // Bit 0 = 1
-            d1_guard.set_driver(Some("TEST".into()), PinValue::Low);
-            d2_guard.set_driver(Some("TEST".into()), PinValue::Low);
-            d3_guard.set_driver(Some("TEST".into()), PinValue::Low);
-        }
+        // SRC P0 with register 1, character 5: X2 carries the register, X3 the character
+        run_instruction_cycle(&mut ram, &time_base, 0x21, Some(0x1), Some(0x5));
 
-        // Debug: Check state before low nibble
-        println!(
-            "DEBUG: Before low nibble - High: {:?}, Low: {:?}, Ready: {}",
-            ram.address_high_nibble, ram.address_low_nibble, ram.full_address_ready
+        assert_eq!(ram.subcycle, BusSubcycle::X3);
+        assert_eq!(ram.last_address, 0x15);
+        assert!(ram.full_address_ready);
+        assert_eq!(ram.ram_state, RamState::WaitLatency);
+        assert_eq!(ram.io_instruction, None);
+    }
+
+    #[test]
+    fn test_src_instruction() {
+        let (mut ram, time_base) = create_running_ram();
+
+        run_instruction_cycle(&mut ram, &time_base, 0x21, Some(0x1), Some(0x5));
+
+        // WRM sends the accumulator during X2
+        run_instruction_cycle(&mut ram, &time_base, 0xE0, Some(0x9), None);
+        assert_eq!(ram.read_ram(25), Some(0x9));
+
+        // WR2 stores a status character
+        run_instruction_cycle(&mut ram, &time_base, 0xE6, Some(0x3), None);
+        assert_eq!(ram.get_status_character(1, 2), Some(0x3));
+        assert_eq!(ram.get_status_character(0, 2), Some(0x0));
+
+        // WMP drives the output lines
+        run_instruction_cycle(&mut ram, &time_base, 0xE1, Some(0x5), None);
+        assert_eq!(ram.get_output_port(0), Some(1));
+        assert_eq!(ram.get_output_port(1), Some(0));
+        assert_eq!(ram.get_output_port(2), Some(1));
+
+        // RDM: the RAM drives the addressed character during X2
+        run_fetch(&mut ram, &time_base, 0xE9);
+        assert_eq!(ram.subcycle, BusSubcycle::X1);
+        assert_eq!(ram.io_instruction, Some(0x9));
+        clock_subcycle(&mut ram, &time_base, false, false, None);
+        assert_eq!(ram.read_data_bus(), 0x9);
+        assert_eq!(ram.ram_state, RamState::ReadData);
+
+        // X3 releases the bus again
+        clock_subcycle(&mut ram, &time_base, false, false, None);
+        assert_eq!(
+            ram.get_pin("D0").unwrap().lock().unwrap().read(),
+            PinValue::HighZ
         );
+        assert_eq!(ram.ram_state, RamState::Idle);
+    }
 
-        // Low nibble
-        phi1_pin
-            .lock()
-            .unwrap()
-            .set_driver(Some("TEST".into()), PinValue::High);
-        ram.update(); // rising edge -> latch low nibble
+    #[test]
+    fn test_src_chip_select() {
+        let (mut ram, time_base) = create_running_ram();
+        assert!(ram.set_chip_id(4).is_err());
+        ram.set_chip_id(2).unwrap();
+        assert_eq!(ram.get_variant(), RamVariant::Type2);
 
-        // Debug: Check state after low nibble
-        println!(
-            "DEBUG: After low nibble - High: {:?}, Low: {:?}, Ready: {}",
-            ram.address_high_nibble, ram.address_low_nibble, ram.full_address_ready
-        );
+        // Chip 2, register 1: bits 3-2 of the register nibble carry the chip number
+        run_instruction_cycle(&mut ram, &time_base, 0x21, Some(0x9), Some(0x5));
+        run_instruction_cycle(&mut ram, &time_base, 0xE0, Some(0x7), None);
+        assert_eq!(ram.read_ram(25), Some(0x7));
 
-        phi1_pin
-            .lock()
-            .unwrap()
-            .set_driver(Some("TEST".into()), PinValue::Low);
-        ram.update(); // falling edge
+        // An SRC naming chip 0 deselects this chip for the following writes
+        run_instruction_cycle(&mut ram, &time_base, 0x21, Some(0x1), Some(0x5));
+        assert!(!ram.full_address_ready);
+        run_instruction_cycle(&mut ram, &time_base, 0xE0, Some(0x3), None);
+        assert_eq!(ram.read_ram(25), Some(0x7));
+    }
 
-        // Should have assembled full address and transitioned to WaitLatency
-        assert_eq!(ram.last_address, 0x01); // High nibble 0x0, low nibble 0x1 = 0x01
-        assert_eq!(ram.full_address_ready, true); // Now both nibbles are assembled
-        assert_eq!(ram.ram_state, RamState::WaitLatency);
+    #[test]
+    fn test_status_characters_per_register() {
+        let (mut ram, time_base) = create_running_ram();
+
+        // WR0 lands in whichever register the last SRC selected
+        run_instruction_cycle(&mut ram, &time_base, 0x21, Some(0x2), Some(0x0));
+        run_instruction_cycle(&mut ram, &time_base, 0xE4, Some(0x6), None);
+        run_instruction_cycle(&mut ram, &time_base, 0x21, Some(0x3), Some(0x0));
+        run_instruction_cycle(&mut ram, &time_base, 0xE4, Some(0xB), None);
+        run_instruction_cycle(&mut ram, &time_base, 0xE7, Some(0x1), None);
+
+        assert_eq!(ram.get_register(2).unwrap().status, [0x6, 0, 0, 0]);
+        assert_eq!(ram.get_register(3).unwrap().status, [0xB, 0, 0, 0x1]);
+        assert!(ram.get_register(4).is_none());
+        assert!(ram.get_registers()[0..2]
+            .iter()
+            .all(|register| *register == RamRegister::default()));
+
+        // The flat view places status characters after the 16 main characters
+        assert_eq!(ram.read_ram(2 * 20 + 16), Some(0x6));
+        assert_eq!(ram.read_ram(3 * 20 + 19), Some(0x1));
+        assert_eq!(ram.get_ram_bank(3)[16..], [0xB, 0, 0, 0x1]);
+
+        // RD0 reads back register 2's status character after reselecting it
+        run_instruction_cycle(&mut ram, &time_base, 0x21, Some(0x2), Some(0x0));
+        run_fetch(&mut ram, &time_base, 0xEC);
+        clock_subcycle(&mut ram, &time_base, false, false, None);
+        assert_eq!(ram.read_data_bus(), 0x6);
     }
 
     #[test]
@@ -1292,7 +1376,6 @@ mod tests {
         // Verify all state is cleared
         assert_eq!(ram.get_output_port(0).unwrap(), 0);
         assert_eq!(ram.get_input_latch(), 0);
-        assert_eq!(ram.get_bank_select(), 0);
 
         // Verify data bus is tri-stated
         let d0_pin = ram.get_pin("D0").unwrap();
@@ -1380,23 +1463,19 @@ mod tests {
     }
 
     #[test]
-    fn test_dcl_instruction() {
-        let mut ram = Intel4002::new("TEST".to_string());
-
-        // Simulate DCL instruction on data bus during instruction phase
-        ram.setup_instruction_test(0xF1); // DCL to select bank 1
-        ram.handle_special_instructions(0xF1);
-
-        assert_eq!(ram.get_bank_select(), 1);
-    }
-
-    #[test]
-    fn test_src_instruction() {
-        let mut ram = Intel4002::new("TEST".to_string());
-
-        // SRC instruction sets up output port addressing
-        ram.setup_instruction_test(0x25); // SRC with specific address
-                                          // Verify output port addressing is set up correctly
-                                          // This would require more complex setup in a real implementation
+    fn test_intel4002_snapshot_round_trip() {
+        let mut ram = Intel4002::new("RAM_4002".to_string());
+        ram.write_ram(0x25, 0x9).unwrap();
+        ram.registers[3].status[1] = 0x6;
+        ram.set_output_port(0, 0xC).unwrap();
+
+        let state = ram.snapshot_state().unwrap();
+        let mut restored = Intel4002::new("RAM_4002".to_string());
+        restored.restore_state(&state).unwrap();
+
+        assert_eq!(restored.snapshot(), ram.snapshot());
+        assert_eq!(restored.read_ram(0x25), Some(0x9));
+        assert_eq!(restored.registers[3].status[1], 0x6);
+        assert_eq!(restored.get_output_port(0), Some(0xC));
     }
 }
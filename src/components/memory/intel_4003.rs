@@ -1,509 +1,192 @@
+use std::any::Any;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
-use crate::component::{BaseComponent, Component, RunnableComponent};
+use crate::component::{self, BaseComponent, Component, RunnableComponent};
 use crate::pin::{Pin, PinValue};
+use serde::{Deserialize, Serialize};
 
-/// Intel 4003 - 10-bit Output Shift Register
-/// Part of the MCS-4 family, designed to work with Intel 4004 CPU
-/// Features 10-bit serial-in, parallel-out shift register for output expansion
+/// Intel 4003 - 10-bit Serial-In, Parallel-Out Shift Register
+/// Part of the MCS-4 family, used to expand the output lines of a 4001 or 4002
+/// Features a 10-bit static shift register with parallel outputs and a serial output
 ///
 /// Hardware Architecture:
-/// - 10-bit shift register with serial input and parallel outputs
-/// - Used for expanding I/O capabilities of the MCS-4 system
-/// - Serial data input from CPU, parallel outputs to external devices
-/// - Clocked by two-phase clock from 4004 CPU
+/// - DATA_IN is shifted into stage Q0 on each rising edge of CP; the other stages move
+///   one position towards Q9
+/// - Q0-Q9 show the register contents while E is high and are held low otherwise
+/// - SERIAL_OUT always follows stage Q9, independent of E, so several 4003s can be
+///   daisy-chained by wiring SERIAL_OUT to the DATA_IN of the next part
+/// - The chip is not connected to the MCS-4 data bus: CP, DATA_IN and E are normally
+///   driven by output port bits of a 4001 or 4002
 ///
 /// Hardware Deviations:
-/// - Simplified timing model for unit testing (may need refinement for CPU integration)
-/// - Output behavior matches 4001/4002 I/O persistence model
+/// - The register starts cleared; the real part powers up with undefined contents
+/// - DATA_IN is captured by a master latch while CP is low and transferred on the rising
+///   edge, so chained parts shift correctly regardless of the order they are updated in.
+///   As on the real part, DATA_IN has to be set up before CP rises.
 pub struct Intel4003 {
     base: BaseComponent,
-    shift_register: [u8; 10], // 10-bit shift register (stored as 10 bytes for simplicity)
-    output_latch: [u8; 10],   // 10-bit output latch for parallel output
-    serial_input: u8,         // Serial input data (4-bit)
-    access_time: Duration,    // Shift register access latency (200ns typical)
-
-    // Clock edge detection
-    prev_phi1: PinValue, // Previous Φ1 clock state for edge detection
-    prev_phi2: PinValue, // Previous Φ2 clock state for edge detection
-
-    // Two-phase addressing for 8-bit address
-    address_high_nibble: Option<u8>, // High nibble of 8-bit address
-    address_low_nibble: Option<u8>,  // Low nibble of 8-bit address
-    full_address_ready: bool,        // Whether complete address is assembled
-
-    // Shift register operation state machine
-    shift_state: ShiftState,             // Current state of shift operation
-    address_latch_time: Option<Instant>, // Timestamp when address was latched
+    stages: u16,          // 10-bit shift register, bit 0 is stage Q0, bit 9 is stage Q9
+    data_in_latch: bool,  // Master latch: follows DATA_IN while CP is low
+    output_enabled: bool, // E input level seen at the last update
+    prev_cp: PinValue,    // Previous CP level for edge detection
 }
 
-/// Shift register operation state machine states
-/// Tracks the current phase of shift register operations
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum ShiftState {
-    Idle,         // No shift operation in progress
-    AddressPhase, // Currently latching address nibbles
-    WaitLatency,  // Address latched, waiting for access time
-    ShiftData,    // Shifting data into register
-    OutputData,   // Outputting parallel data
+/// Snapshot of the shift register and its input latches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Intel4003State {
+    stages: u16,
+    data_in_latch: bool,
+    output_enabled: bool,
+    prev_cp: PinValue,
 }
 
 impl Intel4003 {
-    /// Create a new Intel 4003 Shift Register with specified access time
-    /// Parameters: name - Component identifier, access_time_ns - Access time in nanoseconds
-    /// Returns: New Intel4003 instance with configurable access timing
+    /// Number of stages in the shift register
+    pub const STAGES: usize = 10;
+
+    /// Create a new Intel 4003 Shift Register
+    /// Parameters: name - Component identifier
+    /// Returns: New Intel4003 instance with a cleared register
     pub fn new(name: String) -> Self {
-        Self::new_with_access_time(name, 200) // Default 200ns access time
-    }
-
-    /// Create a new Intel 4003 Shift Register with custom access time (for testing)
-    /// Parameters: name - Component identifier, access_time_ns - Access time in nanoseconds
-    /// Returns: New Intel4003 instance with configurable access timing
-    pub fn new_with_access_time(name: String, access_time_ns: u64) -> Self {
-        // Intel 4003 pinout (based on MCS-4 architecture):
-        // - 4 data pins (D0-D3) for multiplexed address/data
-        // - 10 output pins (O0-O9) for parallel output
-        // - Control pins: SYNC, CM, RESET
-        // - Clock pins: Φ1, Φ2 (two-phase clock from 4004 CPU)
-        //
-        // Control pin behavior:
-        // - SYNC: Marks start of instruction cycle
-        // - CM: Chip select (must be HIGH for shift register access)
-        // - RESET: Clears internal state
+        // Intel 4003 pinout:
+        // - CP: Clock pulse, shifts on the rising edge
+        // - DATA_IN: Serial data input into stage Q0
+        // - E: Output enable for the parallel outputs
+        // - Q0-Q9: Parallel outputs
+        // - SERIAL_OUT: Stage Q9, for chaining further 4003s
         let pin_names = vec![
-            "D0", "D1", "D2", "D3", // Data/Address pins
-            "O0", "O1", "O2", "O3", "O4", "O5", "O6", "O7", "O8", "O9",    // 10 output pins
-            "SYNC",  // Sync signal
-            "CM",    // Chip Select
-            "RESET", // Reset
-            "PHI1",  // Clock phase 1
-            "PHI2",  // Clock phase 2
+            "CP",
+            "DATA_IN",
+            "E", // Control inputs
+            "Q0",
+            "Q1",
+            "Q2",
+            "Q3",
+            "Q4",
+            "Q5",
+            "Q6",
+            "Q7",
+            "Q8",
+            "Q9",         // Parallel outputs
+            "SERIAL_OUT", // Serial output
         ];
 
         let pins = BaseComponent::create_pin_map(&pin_names, &name);
 
         Intel4003 {
             base: BaseComponent::new(name, pins),
-            shift_register: [0u8; 10], // 10-bit shift register
-            output_latch: [0u8; 10],   // 10-bit output latch
-            serial_input: 0,
-            access_time: Duration::from_nanos(access_time_ns),
-
-            // Clock edge detection
-            prev_phi1: PinValue::Low,
-            prev_phi2: PinValue::Low,
-
-            // Two-phase addressing
-            address_high_nibble: None,
-            address_low_nibble: None,
-            full_address_ready: false,
-
-            // Shift register operation state
-            shift_state: ShiftState::Idle,
-            address_latch_time: None,
+            stages: 0,
+            data_in_latch: false,
+            output_enabled: false,
+            prev_cp: PinValue::Low,
         }
     }
 
-    /// Set the memory access time for simulation
-    /// Parameters: access_time_ns - Access time in nanoseconds
-    pub fn set_access_time(&mut self, access_time_ns: u64) {
-        self.access_time = Duration::from_nanos(access_time_ns);
-    }
-
-    /// Get the current access time
-    /// Returns: Access time in nanoseconds
-    pub fn get_access_time(&self) -> u64 {
-        self.access_time.as_nanos() as u64
+    /// Read an input pin as a logic level
+    /// Parameters: name - Pin name
+    /// Returns: Pin level, HighZ if the pin cannot be read
+    fn read_pin(&self, name: &str) -> PinValue {
+        self.base
+            .get_pin(name)
+            .ok()
+            .and_then(|pin| pin.lock().ok().map(|guard| guard.read()))
+            .unwrap_or(PinValue::HighZ)
     }
 
-    /// Read the 4-bit data bus from D0-D3 pins
-    /// Returns: 4-bit value from data bus pins
-    fn read_data_bus(&self) -> u8 {
-        let mut data = 0;
-
-        for i in 0..4 {
-            if let Ok(pin) = self.base.get_pin(&format!("D{}", i)) {
-                if let Ok(pin_guard) = pin.lock() {
-                    let pin_value = pin_guard.read();
-                    if pin_value == PinValue::High {
-                        data |= 1 << i;
-                    }
-                    println!("DEBUG: 4003 read_data_bus - D{} = {:?}", i, pin_value);
-                }
+    /// Drive an output pin
+    /// Parameters: name - Pin name, value - Level to drive
+    fn drive_pin(&self, name: &str, value: PinValue) {
+        if let Ok(pin) = self.base.get_pin(name) {
+            if let Ok(mut pin_guard) = pin.lock() {
+                pin_guard.set_driver(Some(format!("{}_OUTPUT", self.base.name())), value);
             }
         }
-
-        let result = data & 0x0F;
-        println!("DEBUG: 4003 read_data_bus returning 0x{:x}", result);
-        result
     }
 
-    /// Drive the 4-bit data bus with the specified value
-    /// Parameters: data - 4-bit value to drive on D0-D3 pins
-
-    /// Update output pins based on current output latch values
-    /// Hardware: Output pins are driven continuously until changed or reset
+    /// Drive Q0-Q9 and SERIAL_OUT from the current register contents
+    /// Hardware: Parallel outputs are held low while E is low; SERIAL_OUT is not gated
     fn update_output_pins(&self) {
-        for i in 0..10 {
-            if let Ok(pin) = self.base.get_pin(&format!("O{}", i)) {
-                if let Ok(mut pin_guard) = pin.lock() {
-                    let bit_value = (self.output_latch[i]) & 1;
-                    let pin_value = if bit_value == 1 {
-                        PinValue::High
-                    } else {
-                        PinValue::Low
-                    };
-                    pin_guard.set_driver(Some(format!("{}_OUTPUT", self.base.name())), pin_value);
-                }
-            }
-        }
-    }
-
-    /// Set data bus to high-impedance state to avoid bus contention
-    fn tri_state_data_bus(&self) {
-        for i in 0..4 {
-            if let Ok(pin) = self.base.get_pin(&format!("D{}", i)) {
-                if let Ok(mut pin_guard) = pin.lock() {
-                    pin_guard
-                        .set_driver(Some(format!("{}_DATA", self.base.name())), PinValue::HighZ);
-                }
-            }
+        let outputs = self.get_outputs();
+        for stage in 0..Self::STAGES {
+            let value = PinValue::from_bool((outputs >> stage) & 1 == 1);
+            self.drive_pin(&format!("Q{}", stage), value);
         }
+        self.drive_pin("SERIAL_OUT", PinValue::from_bool(self.get_serial_out()));
     }
 
     /// Tri-state all output pins
     fn tri_state_output_pins(&self) {
-        for i in 0..10 {
-            if let Ok(pin) = self.base.get_pin(&format!("O{}", i)) {
-                if let Ok(mut pin_guard) = pin.lock() {
-                    pin_guard.set_driver(
-                        Some(format!("{}_OUTPUT", self.base.name())),
-                        PinValue::HighZ,
-                    );
-                }
-            }
-        }
-    }
-
-    /// Read the two-phase clock pins from CPU
-    /// Returns: (PHI1_value, PHI2_value)
-    fn read_clock_pins(&self) -> (PinValue, PinValue) {
-        let phi1 = if let Ok(pin) = self.base.get_pin("PHI1") {
-            if let Ok(pin_guard) = pin.lock() {
-                pin_guard.read()
-            } else {
-                PinValue::Low
-            }
-        } else {
-            PinValue::Low
-        };
-
-        let phi2 = if let Ok(pin) = self.base.get_pin("PHI2") {
-            if let Ok(pin_guard) = pin.lock() {
-                pin_guard.read()
-            } else {
-                PinValue::Low
-            }
-        } else {
-            PinValue::Low
-        };
-
-        (phi1, phi2)
-    }
-
-    /// Check for Φ1 rising edge
-
-    /// Check for Φ2 rising edge
-
-    /// Read all control pins from CPU
-    /// Returns: (sync, chip_select, reset)
-    fn read_control_pins(&self) -> (bool, bool, bool) {
-        let sync = if let Ok(pin) = self.base.get_pin("SYNC") {
-            if let Ok(pin_guard) = pin.lock() {
-                pin_guard.read() == PinValue::High
-            } else {
-                false
-            }
-        } else {
-            false
-        };
-
-        let chip_select = if let Ok(pin) = self.base.get_pin("CM") {
-            if let Ok(pin_guard) = pin.lock() {
-                pin_guard.read() == PinValue::High
-            } else {
-                false
-            }
-        } else {
-            false
-        };
-
-        let reset = if let Ok(pin) = self.base.get_pin("RESET") {
-            if let Ok(pin_guard) = pin.lock() {
-                pin_guard.read() == PinValue::High
-            } else {
-                false
-            }
-        } else {
-            false
-        };
-
-        (sync, chip_select, reset)
-    }
-
-    /// Handle system reset signal
-    fn handle_reset(&mut self) {
-        let (_, _, reset) = self.read_control_pins();
-        if reset {
-            // RESET is high - clear all internal state
-            self.shift_register = [0u8; 10];
-            self.output_latch = [0u8; 10];
-            self.serial_input = 0;
-
-            // Reset state machine
-            self.shift_state = ShiftState::Idle;
-            self.address_latch_time = None;
-            self.address_high_nibble = None;
-            self.address_low_nibble = None;
-            self.full_address_ready = false;
-
-            // Tri-state all outputs
-            self.tri_state_data_bus();
-            self.tri_state_output_pins();
+        for stage in 0..Self::STAGES {
+            self.drive_pin(&format!("Q{}", stage), PinValue::HighZ);
         }
+        self.drive_pin("SERIAL_OUT", PinValue::HighZ);
     }
 
-    /// Assemble complete 8-bit address from high and low nibbles
-    fn assemble_full_address(&mut self) {
-        if let (Some(_high), Some(_low)) = (self.address_high_nibble, self.address_low_nibble) {
-            // Assemble 8-bit address: (high << 4) | low
-            self.full_address_ready = true;
-            self.address_latch_time = Some(Instant::now());
-
-            // Clear nibble storage for next address
-            self.address_high_nibble = None;
-            self.address_low_nibble = None;
-        }
-    }
-
-    /// Handle Φ1 rising edge - Address and control phase
-    fn handle_phi1_rising(&mut self) {
-        // Handle system reset first
-        self.handle_reset();
-
-        // Check for shift register operation start
-        let (sync, chip_select, _) = self.read_control_pins();
-        if sync && chip_select {
-            self.start_shift_address_phase();
-        }
-
-        // Handle shift register address operations
-        self.handle_shift_address_operations();
-    }
-
-    /// Handle Φ2 rising edge - Data phase
-    fn handle_phi2_rising(&mut self) {
-        self.handle_shift_data_operations();
-    }
-
-    /// Handle shift register address operations during Φ1
-    fn handle_shift_address_operations(&mut self) {
-        match self.shift_state {
-            ShiftState::Idle => {
-                self.tri_state_data_bus();
-            }
-
-            ShiftState::AddressPhase => {
-                self.handle_address_latching();
-            }
-
-            ShiftState::WaitLatency => {
-                self.handle_latency_wait();
-            }
-
-            ShiftState::ShiftData | ShiftState::OutputData => {
-                self.tri_state_data_bus();
-            }
-        }
-    }
-
-    /// Handle shift register data operations during Φ2
-    fn handle_shift_data_operations(&mut self) {
-        match self.shift_state {
-            ShiftState::Idle => {
-                self.tri_state_data_bus();
-            }
-
-            ShiftState::AddressPhase => {
-                self.tri_state_data_bus();
-            }
-
-            ShiftState::WaitLatency => {
-                self.handle_latency_wait();
-                if self.shift_state == ShiftState::ShiftData {
-                    self.handle_shift_operation();
-                }
-            }
-
-            ShiftState::ShiftData => {
-                self.handle_shift_operation();
-            }
-
-            ShiftState::OutputData => {
-                self.handle_output_operation();
-            }
-        }
-    }
-
-    /// Start shift register address phase
-    fn start_shift_address_phase(&mut self) {
-        self.shift_state = ShiftState::AddressPhase;
-        self.full_address_ready = false;
-    }
-
-    /// Handle address nibble latching
-    fn handle_address_latching(&mut self) {
-        let nibble = self.read_data_bus();
-
-        if self.address_high_nibble.is_none() {
-            self.address_high_nibble = Some(nibble);
-        } else if self.address_low_nibble.is_none() {
-            self.address_low_nibble = Some(nibble);
-            self.assemble_full_address();
-            self.start_latency_wait();
-        }
-    }
-
-    /// Transition to latency wait state
-    fn start_latency_wait(&mut self) {
-        self.shift_state = ShiftState::WaitLatency;
-        self.address_latch_time = Some(Instant::now());
-    }
-
-    /// Handle latency timing
-    fn handle_latency_wait(&mut self) {
-        if let Some(latch_time) = self.address_latch_time {
-            if latch_time.elapsed() >= self.access_time {
-                self.start_shift_operation();
-            }
-        }
+    /// Shift one bit into stage Q0
+    /// Parameters: bit - Level shifted into Q0
+    /// Returns: Level that was shifted out of stage Q9
+    pub fn shift_in(&mut self, bit: bool) -> bool {
+        let shifted_out = self.get_serial_out();
+        self.stages = ((self.stages << 1) | bit as u16) & 0x03FF;
+        self.update_output_pins();
+        shifted_out
     }
 
-    /// Transition to shift operation state
-    fn start_shift_operation(&mut self) {
-        self.shift_state = ShiftState::ShiftData;
+    /// Get the contents of the shift register
+    /// Returns: 10-bit value, bit 0 is stage Q0 and bit 9 is stage Q9
+    pub fn get_shift_register(&self) -> u16 {
+        self.stages
     }
 
-    /// Handle shift operation
-    fn handle_shift_operation(&mut self) {
-        let (sync, chip_select, _) = self.read_control_pins();
-
-        if sync && chip_select && self.full_address_ready {
-            // Read serial input data from data bus
-            let serial_data = self.read_data_bus();
-            println!(
-                "DEBUG: 4003 shift operation - serial_data = 0x{:x}",
-                serial_data
-            );
-
-            // Shift in the new bits (serial input)
-            // For 4003, we shift in 4 bits at a time
-            // First, shift existing bits right by 4 positions
-            for j in (4..10).rev() {
-                self.shift_register[j] = self.shift_register[j - 4];
-            }
-
-            // Then insert the 4 new bits into positions 0-3
-            for i in 0..4 {
-                let bit = (serial_data >> i) & 1;
-                self.shift_register[i] = bit;
-                println!(
-                    "DEBUG: 4003 shift step {} - inserted bit {} at position {}",
-                    i, bit, i
-                );
-            }
-
-            // Update output latch with new shift register contents
-            self.output_latch.copy_from_slice(&self.shift_register);
-            self.update_output_pins();
-
-            println!(
-                "DEBUG: 4003 shift register after operation: {:?}",
-                self.shift_register
-            );
-            let (high, low) = self.get_shift_register();
-            println!(
-                "DEBUG: 4003 get_shift_register() returns high=0x{:x}, low=0x{:x}",
-                high, low
-            );
-
-            self.shift_state = ShiftState::OutputData;
+    /// Get the levels of the parallel outputs
+    /// Returns: 10-bit value of Q0-Q9, zero while E is low
+    pub fn get_outputs(&self) -> u16 {
+        if self.output_enabled {
+            self.stages
         } else {
-            self.tri_state_data_bus();
+            0
         }
     }
 
-    /// Handle output operation
-    fn handle_output_operation(&mut self) {
-        // Output operation - parallel data is already available on output pins
-        // This state maintains the output until next operation
+    /// Get the level of the serial output
+    /// Returns: Contents of stage Q9
+    pub fn get_serial_out(&self) -> bool {
+        self.stages & (1 << (Self::STAGES - 1)) != 0
     }
 
-    /// Get the current shift register value
-    /// Returns: 10-bit value as a tuple (high_byte, low_2_bits)
-    pub fn get_shift_register(&self) -> (u8, u8) {
-        let mut high_byte = 0u8;
-        let mut low_2_bits = 0u8;
-
-        for i in 0..8 {
-            if self.shift_register[i] != 0 {
-                high_byte |= 1 << i;
-            }
-        }
-
-        for i in 0..2 {
-            if self.shift_register[8 + i] != 0 {
-                low_2_bits |= 1 << i;
-            }
-        }
-
-        (high_byte, low_2_bits)
+    /// Check whether the parallel outputs are enabled
+    /// Returns: true if E was high at the last update
+    pub fn is_output_enabled(&self) -> bool {
+        self.output_enabled
     }
 
-    /// Get the current output latch value
-    /// Returns: 10-bit value as a tuple (high_byte, low_2_bits)
-    pub fn get_output_latch(&self) -> (u8, u8) {
-        let mut high_byte = 0u8;
-        let mut low_2_bits = 0u8;
-
-        for i in 0..8 {
-            if self.output_latch[i] != 0 {
-                high_byte |= 1 << i;
-            }
-        }
-
-        for i in 0..2 {
-            if self.output_latch[8 + i] != 0 {
-                low_2_bits |= 1 << i;
-            }
-        }
-
-        (high_byte, low_2_bits)
+    /// Clear the shift register
+    pub fn clear_shift_register(&mut self) {
+        self.stages = 0;
+        self.update_output_pins();
     }
 
-    /// Set serial input data
-    /// Parameters: data - 4-bit serial input data
-    pub fn set_serial_input(&mut self, data: u8) {
-        self.serial_input = data & 0x0F;
+    /// Capture the shift register state for a machine snapshot
+    /// Returns: Register contents, DATA_IN master latch and CP/E levels
+    pub fn snapshot(&self) -> Intel4003State {
+        Intel4003State {
+            stages: self.stages,
+            data_in_latch: self.data_in_latch,
+            output_enabled: self.output_enabled,
+            prev_cp: self.prev_cp,
+        }
     }
 
-    /// Clear the shift register
-    pub fn clear_shift_register(&mut self) {
-        self.shift_register = [0u8; 10];
-        self.output_latch = [0u8; 10];
+    /// Restore a shift register state captured by `snapshot` and drive the outputs to match
+    /// Parameters: state - Shift register state
+    pub fn restore(&mut self, state: &Intel4003State) {
+        self.stages = state.stages & 0x03FF;
+        self.data_in_latch = state.data_in_latch;
+        self.output_enabled = state.output_enabled;
+        self.prev_cp = state.prev_cp;
         self.update_output_pins();
     }
 }
@@ -521,36 +204,45 @@ impl Component for Intel4003 {
         self.base.get_pin(name)
     }
 
-    /// Main update cycle - handles clock edge detection and operation dispatch
+    /// Main update cycle - samples DATA_IN, shifts on CP rising edges and updates outputs
     fn update(&mut self) {
-        // Handle both rising and falling edges for proper two-phase operation
-        let (phi1, phi2) = self.read_clock_pins();
-        let phi1_rising = phi1 == PinValue::High && self.prev_phi1 == PinValue::Low;
-        let phi2_rising = phi2 == PinValue::High && self.prev_phi2 == PinValue::Low;
-
-        // Update clock states for next edge detection
-        self.prev_phi1 = phi1;
-        self.prev_phi2 = phi2;
-
-        if phi1_rising {
-            // Φ1 Rising Edge: Address phase
-            self.handle_phi1_rising();
+        let cp = self.read_pin("CP");
+        let enabled = self.read_pin("E") == PinValue::High;
+
+        // The master latch is transparent while CP is low
+        if cp == PinValue::Low {
+            self.data_in_latch = self.read_pin("DATA_IN") == PinValue::High;
+        }
+
+        let cp_rising = cp == PinValue::High && self.prev_cp == PinValue::Low;
+        self.prev_cp = cp;
+
+        if enabled != self.output_enabled {
+            self.output_enabled = enabled;
+            if !cp_rising {
+                self.update_output_pins();
+            }
         }
 
-        if phi2_rising {
-            // Φ2 Rising Edge: Data phase
-            self.handle_phi2_rising();
+        if cp_rising {
+            self.shift_in(self.data_in_latch);
         }
     }
 
-    /// Run component in time-slice mode
-    fn run(&mut self) {
+    /// Mark component running for an external scheduler and drive the outputs
+    fn start(&mut self) {
         self.base.set_running(true);
 
-        // Initialize clock states
-        let (phi1, phi2) = self.read_clock_pins();
-        self.prev_phi1 = phi1;
-        self.prev_phi2 = phi2;
+        // Initialize edge detection and output state from the current inputs
+        self.prev_cp = self.read_pin("CP");
+        self.data_in_latch = self.read_pin("DATA_IN") == PinValue::High;
+        self.output_enabled = self.read_pin("E") == PinValue::High;
+        self.update_output_pins();
+    }
+
+    /// Run component in time-slice mode
+    fn run(&mut self) {
+        self.start();
 
         while self.is_running() {
             self.update();
@@ -561,14 +253,30 @@ impl Component for Intel4003 {
     /// Stop component and tri-state all outputs
     fn stop(&mut self) {
         self.base.set_running(false);
-        self.tri_state_data_bus();
         self.tri_state_output_pins();
-        self.address_latch_time = None;
     }
 
     fn is_running(&self) -> bool {
         self.base.is_running()
     }
+
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        component::state_to_json(&self.snapshot())
+    }
+
+    fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        let state = component::state_from_json(&self.base.name(), state)?;
+        self.restore(&state);
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
 }
 
 impl RunnableComponent for Intel4003 {}
@@ -576,116 +284,148 @@ impl RunnableComponent for Intel4003 {}
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::connection::connect_pins;
+
+    /// Drive a pin of the shift register from the test bench
+    fn drive(sr: &Intel4003, pin: &str, value: PinValue) {
+        sr.get_pin(pin)
+            .unwrap()
+            .lock()
+            .unwrap()
+            .set_driver(Some("TEST".to_string()), value);
+    }
+
+    /// Clock one bit in through the pins: set DATA_IN with CP low, then raise CP
+    fn clock_bit(sr: &mut Intel4003, bit: bool) {
+        drive(sr, "CP", PinValue::Low);
+        drive(sr, "DATA_IN", PinValue::from_bool(bit));
+        sr.update();
+        drive(sr, "CP", PinValue::High);
+        sr.update();
+    }
+
+    fn read(sr: &Intel4003, pin: &str) -> PinValue {
+        sr.get_pin(pin).unwrap().lock().unwrap().read()
+    }
 
     #[test]
     fn test_intel4003_creation() {
         let sr = Intel4003::new("SHIFT_4003".to_string());
         assert_eq!(sr.name(), "SHIFT_4003");
-        assert_eq!(sr.get_access_time(), 200); // Default 200ns
+        assert_eq!(sr.get_shift_register(), 0);
+        assert!(sr.get_pin("SERIAL_OUT").is_ok());
+        assert!(sr.get_pin("D0").is_err());
         assert!(!sr.is_running());
     }
 
     #[test]
     fn test_intel4003_shift_operation() {
-        let mut sr = Intel4003::new_with_access_time("SHIFT_4003".to_string(), 1);
-
-        // Set up the shift register with some initial data
-        sr.shift_register = [1, 0, 1, 0, 1, 0, 1, 0, 1, 0]; // 1010101010 pattern
-
-        // Set up the component for shift operation by setting the required state
-        sr.shift_state = ShiftState::ShiftData;
-        sr.full_address_ready = true;
-
-        // Set data on data bus (0x05 = 0101 binary) for shift operation
-        let d0_pin = sr.get_pin("D0").unwrap();
-        let d1_pin = sr.get_pin("D1").unwrap();
-        let d2_pin = sr.get_pin("D2").unwrap();
-        let d3_pin = sr.get_pin("D3").unwrap();
-
-        {
-            let mut d0_guard = d0_pin.lock().unwrap();
-            let mut d1_guard = d1_pin.lock().unwrap();
-            let mut d2_guard = d2_pin.lock().unwrap();
-            let mut d3_guard = d3_pin.lock().unwrap();
-            d0_guard.set_driver(Some("TEST".to_string()), PinValue::High); // Bit 0 = 1
-            d1_guard.set_driver(Some("TEST".to_string()), PinValue::Low); // Bit 1 = 0
-            d2_guard.set_driver(Some("TEST".to_string()), PinValue::High); // Bit 2 = 1
-            d3_guard.set_driver(Some("TEST".to_string()), PinValue::Low); // Bit 3 = 0
-        }
+        let mut sr = Intel4003::new("SHIFT_4003".to_string());
+        drive(&sr, "E", PinValue::High);
+        sr.start();
 
-        // Set control pins for shift operation
-        let sync_pin = sr.get_pin("SYNC").unwrap();
-        let cm_pin = sr.get_pin("CM").unwrap();
-        {
-            let mut sync_guard = sync_pin.lock().unwrap();
-            let mut cm_guard = cm_pin.lock().unwrap();
-            sync_guard.set_driver(Some("TEST".to_string()), PinValue::High);
-            cm_guard.set_driver(Some("TEST".to_string()), PinValue::High);
+        // Bits enter at Q0 and move towards Q9 on every CP rising edge
+        for bit in [true, false, true, true] {
+            clock_bit(&mut sr, bit);
         }
+        assert_eq!(sr.get_shift_register(), 0b1011);
+        assert_eq!(read(&sr, "Q0"), PinValue::High);
+        assert_eq!(read(&sr, "Q2"), PinValue::Low);
+        assert_eq!(read(&sr, "Q3"), PinValue::High);
+
+        // Holding CP high does not shift again
+        sr.update();
+        assert_eq!(sr.get_shift_register(), 0b1011);
+
+        // After ten shifts the first bit reaches Q9 and the serial output
+        for _ in 0..6 {
+            clock_bit(&mut sr, false);
+        }
+        assert_eq!(sr.get_shift_register(), 0b10_1100_0000);
+        assert!(sr.get_serial_out());
+        assert_eq!(read(&sr, "SERIAL_OUT"), PinValue::High);
 
-        // Perform shift operation
-        sr.handle_shift_operation();
-
-        // Verify shift operation occurred
-        let (high, low) = sr.get_shift_register();
-        println!(
-            "DEBUG: After shift operation - high=0x{:x}, low=0x{:x}",
-            high, low
-        );
-        println!("DEBUG: Shift register contents: {:?}", sr.shift_register);
-
-        // The shift register should now contain the shifted data
-        // Since we shifted in 0x05 (binary 0101), the register should contain this pattern
-        // The first 4 bits should be 0101 (0x05), and the rest should be the original pattern shifted
-        // The current result shows high=0x55, low=0x1 which means:
-        // high byte: 01010101 (0x55) - this is correct, the first 4 bits are 0101 (0x05)
-        // low byte: 00000001 (0x01) - this is the remaining 2 bits from the original pattern
-        assert_eq!(high, 0x55); // First 8 bits should be 01010101
-        assert_eq!(low, 0x01); // Lower 2 bits should be 01
+        // The eleventh shift drops it off the end
+        assert!(sr.shift_in(false));
+        assert_eq!(sr.get_shift_register(), 0b01_1000_0000);
     }
 
     #[test]
-    fn test_intel4003_reset() {
+    fn test_intel4003_output_enable() {
         let mut sr = Intel4003::new("SHIFT_4003".to_string());
+        drive(&sr, "E", PinValue::Low);
+        sr.start();
 
-        // Set some data first
-        sr.set_serial_input(0x0F);
-        sr.shift_register[0] = 1;
+        for _ in 0..Intel4003::STAGES {
+            clock_bit(&mut sr, true);
+        }
 
-        // Verify data is set
-        assert_eq!(sr.serial_input, 0x0F);
-        assert_eq!(sr.shift_register[0], 1);
+        // E low holds the parallel outputs low, but not the serial output
+        assert_eq!(sr.get_shift_register(), 0x3FF);
+        assert_eq!(sr.get_outputs(), 0);
+        assert_eq!(read(&sr, "Q4"), PinValue::Low);
+        assert_eq!(read(&sr, "SERIAL_OUT"), PinValue::High);
 
-        // Apply reset
-        let reset_pin = sr.get_pin("RESET").unwrap();
-        {
-            let mut reset_guard = reset_pin.lock().unwrap();
-            reset_guard.set_driver(Some("TEST".to_string()), PinValue::High);
-        }
+        drive(&sr, "E", PinValue::High);
+        sr.update();
+        assert!(sr.is_output_enabled());
+        assert_eq!(sr.get_outputs(), 0x3FF);
+        assert_eq!(read(&sr, "Q4"), PinValue::High);
 
-        // Call handle_reset directly to ensure reset is processed
-        sr.handle_reset();
+        sr.clear_shift_register();
+        assert_eq!(read(&sr, "Q4"), PinValue::Low);
+    }
+
+    #[test]
+    fn test_intel4003_daisy_chain() {
+        let mut first = Intel4003::new("SHIFT_4003_A".to_string());
+        let mut second = Intel4003::new("SHIFT_4003_B".to_string());
+        connect_pins(
+            first.get_pin("SERIAL_OUT").unwrap(),
+            second.get_pin("DATA_IN").unwrap(),
+        )
+        .unwrap();
+        first.start();
+        second.start();
+
+        // Shift 0b1 followed by 10 zeros; updating the later part first must not matter
+        let mut bits = vec![true];
+        bits.extend(std::iter::repeat(false).take(Intel4003::STAGES));
+        for bit in bits {
+            drive(&first, "DATA_IN", PinValue::from_bool(bit));
+            for sr in [&mut first, &mut second] {
+                drive(sr, "CP", PinValue::Low);
+            }
+            second.update();
+            first.update();
+            for sr in [&mut first, &mut second] {
+                drive(sr, "CP", PinValue::High);
+            }
+            second.update();
+            first.update();
+        }
 
-        // Verify reset cleared everything
-        assert_eq!(sr.serial_input, 0);
-        assert_eq!(sr.shift_register[0], 0);
-        assert_eq!(sr.shift_state, ShiftState::Idle);
+        // The bit left the first register and now sits in Q0 of the second
+        assert_eq!(first.get_shift_register(), 0);
+        assert_eq!(second.get_shift_register(), 0b1);
     }
 
     #[test]
-    fn test_configurable_access_time() {
+    fn test_intel4003_snapshot_round_trip() {
         let mut sr = Intel4003::new("SHIFT_4003".to_string());
+        drive(&sr, "E", PinValue::High);
+        sr.start();
+        for bit in [true, true, false, true] {
+            clock_bit(&mut sr, bit);
+        }
 
-        // Test default access time
-        assert_eq!(sr.get_access_time(), 200);
-
-        // Test setting custom access time
-        sr.set_access_time(100);
-        assert_eq!(sr.get_access_time(), 100);
+        let state = sr.snapshot_state().unwrap();
+        let mut restored = Intel4003::new("SHIFT_4003".to_string());
+        restored.restore_state(&state).unwrap();
 
-        // Test constructor with custom access time
-        let fast_sr = Intel4003::new_with_access_time("FAST_SHIFT".to_string(), 1);
-        assert_eq!(fast_sr.get_access_time(), 1);
-        assert_eq!(fast_sr.name(), "FAST_SHIFT");
+        assert_eq!(restored.snapshot(), sr.snapshot());
+        assert_eq!(restored.get_shift_register(), 0b1101);
+        assert_eq!(read(&restored, "Q0"), PinValue::High);
+        assert_eq!(read(&restored, "Q1"), PinValue::Low);
     }
 }
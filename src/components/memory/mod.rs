@@ -1,3 +1,5 @@
+pub mod generic_ram;
 pub mod intel_4001;
 pub mod intel_4002;
 pub mod intel_4003;
+pub mod rom_8bit;
@@ -0,0 +1,274 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::component::{self, BaseComponent, Component, RunnableComponent};
+use crate::components::common::bus_8bit::{
+    bus_pin_names, check_address_window, load_block, Bus8BitMemory,
+};
+use crate::pin::{Pin, PinValue};
+use serde::{Deserialize, Serialize};
+
+/// Byte-wide ROM for 8-bit microprocessor buses
+/// Counterpart of the 8-bit bus `GenericRam` for program memory: the same bus and
+/// address window, with contents loaded from a binary image instead of written by the CPU
+///
+/// Hardware Architecture:
+/// - A0-A15 address lines, D0-D7 data outputs
+/// - RW: the ROM drives D0-D7 only while RW is high; writes are ignored
+/// - CS: active high; a chip select that is not driven counts as active
+/// - The ROM responds while selected and the address falls in base..base+size
+///
+/// Hardware Deviations:
+/// - No access time: the addressed byte appears as soon as the address lines change
+pub struct Rom8Bit {
+    base: BaseComponent,
+    memory: Vec<u8>,   // ROM contents, unprogrammed bytes read as 0xFF
+    base_address: u16, // First bus address of the ROM
+}
+
+/// Snapshot of the ROM contents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rom8BitState {
+    memory: Vec<u8>,
+}
+
+impl Rom8Bit {
+    /// Create a new 8-bit bus ROM
+    /// Parameters: name - Component identifier, size - Size in bytes (1-65536),
+    ///             base_address - First bus address the ROM answers
+    /// Returns: Ok(Rom8Bit) with every byte 0xFF, Err(String) if the ROM does not fit the address space
+    pub fn new(name: String, size: usize, base_address: u16) -> Result<Self, String> {
+        check_address_window(size, base_address)?;
+
+        let pins = BaseComponent::create_pin_map(&bus_pin_names(), &name);
+        Ok(Rom8Bit {
+            base: BaseComponent::new(name, pins),
+            memory: vec![0xFFu8; size],
+            base_address,
+        })
+    }
+
+    /// Get the ROM size in bytes
+    /// Returns: Number of bytes
+    pub fn get_size(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Get the first bus address of the ROM
+    /// Returns: Base address
+    pub fn get_base_address(&self) -> u16 {
+        self.base_address
+    }
+
+    /// Read a byte directly from the ROM
+    /// Parameters: offset - Byte offset from the base address
+    /// Returns: Some(data) if the offset is valid, None if out of bounds
+    pub fn read_byte(&self, offset: usize) -> Option<u8> {
+        self.memory.get(offset).copied()
+    }
+
+    /// Program a block of the ROM
+    /// Parameters: data - Bytes to load, offset - Offset of the first byte from the base address
+    /// Returns: Ok(()) on success, Err(String) if the block does not fit
+    pub fn load_data(&mut self, data: &[u8], offset: usize) -> Result<(), String> {
+        load_block(&mut self.memory, data, offset)
+    }
+
+    /// Program the ROM from a binary image file
+    /// The image is loaded at offset 0; a shorter image leaves the rest of the ROM unprogrammed.
+    /// Parameters: path - Path of the binary file
+    /// Returns: Ok(bytes) loaded, Err(String) if the file cannot be read or is larger than the ROM
+    pub fn load_file(&mut self, path: &str) -> Result<usize, String> {
+        let image = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        if image.len() > self.memory.len() {
+            return Err(format!(
+                "{} is {} bytes, larger than the {} byte ROM",
+                path,
+                image.len(),
+                self.memory.len()
+            ));
+        }
+        self.erase();
+        self.load_data(&image, 0)?;
+        Ok(image.len())
+    }
+
+    /// Erase the ROM to its unprogrammed state, every byte 0xFF
+    pub fn erase(&mut self) {
+        self.memory.fill(0xFF);
+    }
+
+    /// Get the whole ROM contents
+    /// Returns: Slice of all bytes, offset 0 first
+    pub fn get_contents(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Capture the ROM contents for a machine snapshot
+    /// Returns: ROM state
+    pub fn snapshot(&self) -> Rom8BitState {
+        Rom8BitState {
+            memory: self.memory.clone(),
+        }
+    }
+
+    /// Restore ROM contents captured by `snapshot`
+    /// Parameters: state - ROM state
+    /// Returns: Ok(()) on success, Err(String) if the ROM size differs
+    pub fn restore(&mut self, state: &Rom8BitState) -> Result<(), String> {
+        if state.memory.len() != self.memory.len() {
+            return Err(format!(
+                "ROM state holds {} bytes, {} has {}",
+                state.memory.len(),
+                self.base.name(),
+                self.memory.len()
+            ));
+        }
+        self.memory.copy_from_slice(&state.memory);
+        Ok(())
+    }
+}
+
+impl Bus8BitMemory for Rom8Bit {
+    fn get_base(&self) -> &BaseComponent {
+        &self.base
+    }
+}
+
+impl Component for Rom8Bit {
+    fn name(&self) -> String {
+        self.base.name()
+    }
+
+    fn pins(&self) -> HashMap<String, Arc<Mutex<Pin>>> {
+        self.base.pins()
+    }
+
+    fn get_pin(&self, name: &str) -> Result<Arc<Mutex<Pin>>, String> {
+        self.base.get_pin(name)
+    }
+
+    /// Main update cycle - drives the addressed byte during read cycles
+    fn update(&mut self) {
+        let offset = self.selected_offset(self.base_address, self.memory.len());
+        match offset {
+            Some(offset) if self.read_pin("RW") != PinValue::Low => {
+                self.drive_data_bus(Some(self.memory[offset]));
+            }
+            _ => self.drive_data_bus(None),
+        }
+    }
+
+    fn start(&mut self) {
+        self.base.set_running(true);
+    }
+
+    /// Run component in time-slice mode
+    fn run(&mut self) {
+        self.start();
+
+        while self.is_running() {
+            self.update();
+            thread::sleep(Duration::from_micros(1));
+        }
+    }
+
+    /// Stop component and release the data lines
+    fn stop(&mut self) {
+        self.base.set_running(false);
+        self.drive_data_bus(None);
+    }
+
+    fn is_running(&self) -> bool {
+        self.base.is_running()
+    }
+
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        component::state_to_json(&self.snapshot())
+    }
+
+    fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        let state = component::state_from_json(&self.base.name(), state)?;
+        self.restore(&state)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl RunnableComponent for Rom8Bit {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drive(rom: &Rom8Bit, pin: &str, value: PinValue) {
+        rom.get_pin(pin)
+            .unwrap()
+            .lock()
+            .unwrap()
+            .set_driver(Some("TEST".to_string()), value);
+    }
+
+    fn drive_address(rom: &Rom8Bit, address: u16) {
+        for line in 0..16 {
+            drive(
+                rom,
+                &format!("A{}", line),
+                PinValue::from_bool((address >> line) & 1 == 1),
+            );
+        }
+    }
+
+    #[test]
+    fn test_rom_8bit_load_file() {
+        let path = std::env::temp_dir().join("rusty_emu_rom_8bit_test.bin");
+        std::fs::write(&path, [0xA9, 0x42, 0x60]).unwrap();
+
+        let mut rom = Rom8Bit::new("ROM".to_string(), 0x100, 0xFF00).unwrap();
+        assert_eq!(rom.read_byte(0), Some(0xFF));
+        assert_eq!(rom.load_file(path.to_str().unwrap()), Ok(3));
+        assert_eq!(&rom.get_contents()[..4], &[0xA9, 0x42, 0x60, 0xFF]);
+
+        let mut small = Rom8Bit::new("ROM".to_string(), 2, 0xFF00).unwrap();
+        assert!(small.load_file(path.to_str().unwrap()).is_err());
+        assert!(rom.load_file("does/not/exist.bin").is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_rom_8bit_drives_reads_only() {
+        let mut rom = Rom8Bit::new("ROM".to_string(), 0x100, 0xFF00).unwrap();
+        rom.load_data(&[0x12, 0x34], 0xFC).unwrap();
+        rom.start();
+
+        drive(&rom, "RW", PinValue::High);
+        drive_address(&rom, 0xFFFD);
+        rom.update();
+        assert_eq!(rom.read_lines("D", 8), 0x34);
+
+        // A write cycle or an address below the window releases the bus
+        drive(&rom, "RW", PinValue::Low);
+        rom.update();
+        assert_eq!(
+            rom.get_pin("D0").unwrap().lock().unwrap().read(),
+            PinValue::HighZ
+        );
+        drive(&rom, "RW", PinValue::High);
+        drive_address(&rom, 0xFEFC);
+        rom.update();
+        assert_eq!(
+            rom.get_pin("D0").unwrap().lock().unwrap().read(),
+            PinValue::HighZ
+        );
+        assert_eq!(rom.read_byte(0xFC), Some(0x12));
+    }
+}
@@ -1,4 +1,5 @@
 pub mod clock;
 pub mod common;
 pub mod cpu;
+pub mod interface;
 pub mod memory;
@@ -5,7 +5,9 @@
 //!
 //! ## Features
 //! - Real-time RAM and register display
+//! - Disassembly at the program counter, with optional symbols
 //! - Interactive command interface
+//! - Debugger with breakpoints, RAM watchpoints and single-step, see `debugger`
 //! - System state monitoring
 //! - Configurable display options
 //! - Graceful interrupt handling
@@ -29,6 +31,8 @@ use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
+use crate::debugger::{Debugger, DEBUGGER_HELP};
+use crate::disasm::{self, SymbolTable};
 use crate::system_config::ConfigurableSystem;
 
 /// Console configuration structure
@@ -41,6 +45,10 @@ pub struct ConsoleConfig {
     pub show_system_info: bool,
     pub ram_banks_per_row: usize,
     pub max_ram_rows: usize,
+    pub show_disassembly: bool,
+    pub disassembly_lines: usize, // Instructions shown from the program counter on
+    pub symbol_file: Option<String>, // Symbol file naming addresses in the disassembly
+    pub output_lines: usize,      // Command output lines kept on screen
 }
 
 impl Default for ConsoleConfig {
@@ -53,6 +61,10 @@ impl Default for ConsoleConfig {
             show_system_info: true,
             ram_banks_per_row: 4,
             max_ram_rows: 5,
+            show_disassembly: true,
+            disassembly_lines: 8,
+            symbol_file: None,
+            output_lines: 6,
         }
     }
 }
@@ -65,10 +77,25 @@ pub struct ConsoleApp {
     command_buffer: String,
     show_help: bool,
     selected_pane: usize,
+    symbols: Option<SymbolTable>,
+    debugger: Debugger,  // Breakpoints live in the CPU, watchpoints here
+    output: Vec<String>, // Command output, most recent line last
 }
 
 impl ConsoleApp {
     pub fn new(system: Arc<Mutex<ConfigurableSystem>>, config: ConsoleConfig) -> Self {
+        let symbols =
+            config
+                .symbol_file
+                .as_deref()
+                .and_then(|path| match SymbolTable::load(path) {
+                    Ok(symbols) => Some(symbols),
+                    Err(e) => {
+                        println!("DEBUG: Ignoring symbol file: {}", e);
+                        None
+                    }
+                });
+
         Self {
             system,
             config,
@@ -76,6 +103,9 @@ impl ConsoleApp {
             command_buffer: String::new(),
             show_help: false,
             selected_pane: 0,
+            symbols,
+            debugger: Debugger::new(),
+            output: Vec::new(),
         }
     }
 
@@ -139,39 +169,28 @@ impl ConsoleApp {
         Ok(())
     }
 
+    /// Handle one key press
+    /// Printable characters always go to the command line, so debugger commands can be
+    /// typed in full; the hotkeys are Esc and the function keys, plus Space on an empty
+    /// command line
     fn handle_key_event(&mut self, key: KeyCode) {
         match key {
-            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
+            KeyCode::Esc => {
                 println!("DEBUG: Quit key pressed, stopping console");
                 self.running = false;
                 if let Ok(mut system) = self.system.lock() {
                     system.stop();
                 }
             }
-            KeyCode::Char('h') | KeyCode::Char('H') => {
+            KeyCode::F(1) => {
                 println!("DEBUG: Help key pressed");
                 self.show_help = !self.show_help;
             }
-            KeyCode::Char('r') | KeyCode::Char('R') => {
-                println!("DEBUG: Run key pressed");
-                if let Ok(mut system) = self.system.lock() {
-                    if system.is_running() {
-                        system.stop();
-                    } else {
-                        system.run();
-                    }
-                }
-            }
-            KeyCode::Char('s') | KeyCode::Char('S') => {
-                println!("DEBUG: Stop key pressed");
-                if let Ok(mut system) = self.system.lock() {
-                    system.stop();
-                }
-            }
-            KeyCode::Char(' ') => {
-                println!("DEBUG: Space key pressed - single step not implemented");
-                // Single step (if supported)
-                // This would require adding step functionality to the system
+            KeyCode::F(5) => self.run_debugger_command("continue"),
+            KeyCode::F(10) => self.run_debugger_command("next"),
+            KeyCode::F(11) => self.run_debugger_command("step"),
+            KeyCode::Char(' ') if self.command_buffer.is_empty() => {
+                self.run_debugger_command("step");
             }
             KeyCode::Tab => {
                 println!("DEBUG: Tab key pressed - switching panes");
@@ -191,7 +210,7 @@ impl ConsoleApp {
             }
             KeyCode::Char(c) => {
                 println!("DEBUG: Character key pressed: '{}'", c);
-                if c.is_ascii_alphabetic() || c.is_ascii_digit() {
+                if c.is_ascii_graphic() || c == ' ' {
                     self.command_buffer.push(c);
                 }
             }
@@ -202,7 +221,8 @@ impl ConsoleApp {
     }
 
     fn execute_command(&mut self) {
-        let cmd = self.command_buffer.trim().to_lowercase();
+        let command = self.command_buffer.trim().to_string();
+        let cmd = command.to_lowercase();
         println!("DEBUG: Executing command: '{}'", cmd);
 
         match cmd.as_str() {
@@ -231,10 +251,11 @@ impl ConsoleApp {
             }
             "reset" => {
                 println!("DEBUG: Executing reset command");
-                if let Ok(mut system) = self.system.lock() {
-                    system.stop();
-                    // Reset would need to be implemented in the system
-                }
+                let result = match self.system.lock() {
+                    Ok(system) => system.with_mcs4_cpu(|cpu| cpu.reset()),
+                    Err(_) => Err("System lock poisoned".to_string()),
+                };
+                self.push_output(&command, result.map(|_| vec!["CPU reset".to_string()]));
             }
             "status" => {
                 println!("DEBUG: Executing status command");
@@ -250,20 +271,65 @@ impl ConsoleApp {
             }
             "ram" => {
                 println!("DEBUG: Executing RAM display command");
-                if let Ok(_system) = self.system.lock() {
-                    println!("RAM display requested - would show RAM contents here");
-                }
+                let lines = match self.system.lock() {
+                    Ok(system) => Ok(system
+                        .get_ram_contents()
+                        .iter()
+                        .flat_map(|chip| {
+                            chip.registers
+                                .iter()
+                                .enumerate()
+                                .map(move |(index, register)| {
+                                    format!(
+                                        "{} reg {}: {}",
+                                        chip.name,
+                                        index,
+                                        register
+                                            .main
+                                            .iter()
+                                            .map(|c| format!("{:X}", c))
+                                            .collect::<String>()
+                                    )
+                                })
+                        })
+                        .collect()),
+                    Err(_) => Err("System lock poisoned".to_string()),
+                };
+                self.push_output(&command, lines);
             }
             "" => {
                 // Empty command - do nothing
             }
-            _ => {
-                println!("DEBUG: Unknown command: '{}'", cmd);
-                println!("Available commands: quit, run, stop, help, reset, status, ram");
-            }
+            _ => self.run_debugger_command(&command),
         }
     }
 
+    /// Run a debugger command against the system and show its output
+    /// Parameters: command - Debugger command line
+    fn run_debugger_command(&mut self, command: &str) {
+        println!("DEBUG: Executing debugger command: '{}'", command);
+        let result = match self.system.lock() {
+            Ok(mut system) => self
+                .debugger
+                .execute(&mut system, command, self.symbols.as_ref()),
+            Err(_) => Err("System lock poisoned".to_string()),
+        };
+        self.push_output(command, result);
+    }
+
+    /// Append a command and its result to the output pane
+    /// Parameters: command - Command line as typed, result - Output lines or error message
+    fn push_output(&mut self, command: &str, result: Result<Vec<String>, String>) {
+        self.output.push(format!(": {}", command));
+        match result {
+            Ok(lines) => self.output.extend(lines),
+            Err(e) => self.output.push(format!("Error: {}", e)),
+        }
+
+        let excess = self.output.len().saturating_sub(self.config.output_lines);
+        self.output.drain(..excess);
+    }
+
     fn draw_ui(&self, f: &mut Frame) {
         let size = f.size();
 
@@ -276,9 +342,10 @@ impl ConsoleApp {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(4), // Title bar
-                Constraint::Min(8),    // Main content
-                Constraint::Length(3), // Command bar
+                Constraint::Length(4),                                   // Title bar
+                Constraint::Min(8),                                      // Main content
+                Constraint::Length(self.config.output_lines as u16 + 2), // Command output
+                Constraint::Length(3),                                   // Command bar
             ])
             .split(size);
 
@@ -291,15 +358,17 @@ impl ConsoleApp {
                     .add_modifier(Modifier::BOLD),
             )]),
             Line::from(vec![
-                Span::raw("Commands: "),
-                Span::styled("q/Q", Style::default().fg(Color::Yellow)),
+                Span::raw("Keys: "),
+                Span::styled("Esc", Style::default().fg(Color::Yellow)),
                 Span::raw("=quit, "),
-                Span::styled("r/R", Style::default().fg(Color::Yellow)),
-                Span::raw("=run, "),
-                Span::styled("s/S", Style::default().fg(Color::Yellow)),
-                Span::raw("=stop, "),
-                Span::styled("h/H", Style::default().fg(Color::Yellow)),
-                Span::raw("=help"),
+                Span::styled("F1", Style::default().fg(Color::Yellow)),
+                Span::raw("=help, "),
+                Span::styled("F5", Style::default().fg(Color::Yellow)),
+                Span::raw("=continue, "),
+                Span::styled("F10", Style::default().fg(Color::Yellow)),
+                Span::raw("=next, "),
+                Span::styled("F11/Space", Style::default().fg(Color::Yellow)),
+                Span::raw("=step"),
             ]),
         ];
 
@@ -320,8 +389,31 @@ impl ConsoleApp {
         // Left pane - System info and registers
         self.draw_system_info(f, content_chunks[0]);
 
-        // Right pane - RAM contents
-        self.draw_ram_contents(f, content_chunks[1]);
+        // Right pane - Disassembly and RAM contents
+        if self.config.show_disassembly {
+            let right_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(self.config.disassembly_lines as u16 + 2),
+                    Constraint::Min(5),
+                ])
+                .split(content_chunks[1]);
+            self.draw_disassembly(f, right_chunks[0]);
+            self.draw_ram_contents(f, right_chunks[1]);
+        } else {
+            self.draw_ram_contents(f, content_chunks[1]);
+        }
+
+        // Command output
+        let output: Vec<Line> = self
+            .output
+            .iter()
+            .map(|line| Line::from(vec![Span::raw(line.as_str())]))
+            .collect();
+        let output_widget = Paragraph::new(output)
+            .block(Block::default().borders(Borders::ALL).title("Output"))
+            .wrap(Wrap { trim: false });
+        f.render_widget(output_widget, chunks[2]);
 
         // Command bar
         let command_text = if self.command_buffer.is_empty() {
@@ -333,12 +425,12 @@ impl ConsoleApp {
         let command_bar = Paragraph::new(command_text)
             .style(Style::default().fg(Color::White))
             .block(Block::default().borders(Borders::ALL).title("Command"));
-        f.render_widget(command_bar, chunks[2]);
+        f.render_widget(command_bar, chunks[3]);
     }
 
     fn draw_help_screen(&self, f: &mut Frame) {
         let size = f.size();
-        let help_text = vec![
+        let mut help_text = vec![
             Line::from(vec![Span::styled(
                 "Intel MCS-4 Emulator Console Help",
                 Style::default()
@@ -368,13 +460,42 @@ impl ConsoleApp {
             ]),
             Line::from(vec![
                 Span::styled("  reset", Style::default().fg(Color::Yellow)),
-                Span::raw(" - Reset system"),
+                Span::raw(" - Reset the CPU"),
             ]),
+            Line::from(vec![
+                Span::styled("  ram", Style::default().fg(Color::Yellow)),
+                Span::raw(" - Show the main characters of every RAM register"),
+            ]),
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                "Debugger:",
+                Style::default().add_modifier(Modifier::BOLD),
+            )]),
+        ];
+        help_text.extend(DEBUGGER_HELP.iter().map(|(command, description)| {
+            Line::from(vec![
+                Span::styled(format!("  {}", command), Style::default().fg(Color::Yellow)),
+                Span::raw(format!(" - {}", description)),
+            ])
+        }));
+        help_text.extend([
             Line::from(""),
             Line::from(vec![Span::styled(
                 "Navigation:",
                 Style::default().add_modifier(Modifier::BOLD),
             )]),
+            Line::from(vec![
+                Span::styled("  Esc", Style::default().fg(Color::Yellow)),
+                Span::raw(" - Exit emulator"),
+            ]),
+            Line::from(vec![
+                Span::styled("  F1", Style::default().fg(Color::Yellow)),
+                Span::raw(" - Show/hide this help"),
+            ]),
+            Line::from(vec![
+                Span::styled("  F5, F10, F11", Style::default().fg(Color::Yellow)),
+                Span::raw(" - continue, next, step; Space steps on an empty command line"),
+            ]),
             Line::from(vec![
                 Span::styled("  Tab", Style::default().fg(Color::Yellow)),
                 Span::raw(" - Switch between panes"),
@@ -388,8 +509,8 @@ impl ConsoleApp {
                 Span::raw(" - Delete character"),
             ]),
             Line::from(""),
-            Line::from(vec![Span::raw("Press any key to return to main view...")]),
-        ];
+            Line::from(vec![Span::raw("Press F1 to return to main view...")]),
+        ]);
 
         let help = Paragraph::new(help_text)
             .style(Style::default().fg(Color::White))
@@ -448,16 +569,53 @@ impl ConsoleApp {
             .wrap(Wrap { trim: true });
         f.render_widget(system_widget, info_chunks[0]);
 
-        // CPU register display (enhanced with execution status)
-        let register_info = vec![
-            Line::from(vec![Span::raw("CPU Registers:")]),
-            Line::from(vec![Span::raw("Status: Running (check console output)")]),
-            Line::from(vec![Span::raw("PC: 0x000 (see DEBUG output)")]),
-            Line::from(vec![Span::raw("ACC: 0x0 (see DEBUG output)")]),
-            Line::from(vec![Span::raw(
-                "Instructions: Executing (see DEBUG output)",
-            )]),
-        ];
+        // CPU register display, including the return address stack
+        let registers = self
+            .system
+            .lock()
+            .map_err(|_| "System lock poisoned".to_string())
+            .and_then(|system| system.get_cpu_registers());
+        let register_info = match registers {
+            Ok(regs) => vec![
+                Line::from(vec![Span::raw(format!(
+                    "PC: 0x{:03X}  ACC: 0x{:X}  CY: {}",
+                    regs.program_counter, regs.accumulator, regs.carry as u8
+                ))]),
+                Line::from(vec![Span::raw(format!(
+                    "R0-R7:  {}",
+                    regs.index_registers[..8]
+                        .iter()
+                        .map(|r| format!("{:X}", r))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                ))]),
+                Line::from(vec![Span::raw(format!(
+                    "R8-R15: {}",
+                    regs.index_registers[8..]
+                        .iter()
+                        .map(|r| format!("{:X}", r))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                ))]),
+                Line::from(vec![Span::raw(format!(
+                    "Stack: {} (SP: {})",
+                    regs.stack
+                        .iter()
+                        .map(|address| format!("0x{:03X}", address))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    regs.stack_pointer
+                ))]),
+                Line::from(vec![Span::raw(format!(
+                    "Instructions: {}",
+                    regs.instruction_count
+                ))]),
+            ],
+            Err(e) => vec![Line::from(vec![Span::raw(format!(
+                "CPU registers unavailable: {}",
+                e
+            ))])],
+        };
 
         let register_widget = Paragraph::new(register_info)
             .block(
@@ -469,6 +627,54 @@ impl ConsoleApp {
         f.render_widget(register_widget, info_chunks[1]);
     }
 
+    /// Draw the instructions at the program counter, the current one highlighted
+    fn draw_disassembly(&self, f: &mut Frame, area: Rect) {
+        let instructions = self
+            .system
+            .lock()
+            .map_err(|_| "System lock poisoned".to_string())
+            .and_then(|system| {
+                disasm::disassemble_from_pc(
+                    &system,
+                    self.config.disassembly_lines,
+                    self.symbols.as_ref(),
+                )
+            });
+
+        let lines = match instructions {
+            Ok(instructions) => instructions
+                .iter()
+                .enumerate()
+                .map(|(index, instruction)| {
+                    let label = self
+                        .symbols
+                        .as_ref()
+                        .and_then(|symbols| symbols.name_at(instruction.address))
+                        .map_or(String::new(), |name| format!("{}: ", name));
+                    if index == 0 {
+                        Line::from(vec![Span::styled(
+                            format!("> {}{}", label, instruction),
+                            Style::default()
+                                .fg(Color::Yellow)
+                                .add_modifier(Modifier::BOLD),
+                        )])
+                    } else {
+                        Line::from(vec![Span::raw(format!("  {}{}", label, instruction))])
+                    }
+                })
+                .collect(),
+            Err(e) => vec![Line::from(vec![Span::raw(format!(
+                "Disassembly unavailable: {}",
+                e
+            ))])],
+        };
+
+        let disassembly_widget = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title("Disassembly"))
+            .wrap(Wrap { trim: false });
+        f.render_widget(disassembly_widget, area);
+    }
+
     fn draw_ram_contents(&self, f: &mut Frame, area: Rect) {
         let mut ram_info = vec![Line::from(vec![Span::raw("RAM Contents:")])];
 
@@ -0,0 +1,586 @@
+//! # MCS-4 Debugger
+//!
+//! Command language of the interactive debugger in the console interface.
+//!
+//! The debugger drives a `ConfigurableSystem` one instruction at a time through
+//! `ConfigurableSystem::step_instruction`, so RAM and I/O see every bus cycle unless
+//! the CPU runs in functional mode. Breakpoints live in the CPU and are checked by its
+//! execute path; watchpoints compare RAM characters after every instruction.
+//!
+//! ```text
+//! break DONE            stop when the PC reaches DONE
+//! watch ram 0:0:2       stop when bank 0, register 0, character 2 changes
+//! continue              run until a breakpoint or watchpoint
+//! x ram 0:0             examine a RAM register
+//! save boot.json        save the machine state, restore it later with `load`
+//! ```
+//!
+//! Addresses and values are decimal, `0x1F`, `$1F` or `1FH`; addresses may also be
+//! symbol names. RAM locations are `bank:register:character`, where the register is
+//! numbered as SRC addresses it (chip number in bits 3-2) and characters 16-19 are the
+//! status characters 0-3.
+
+use std::fmt;
+
+use crate::disasm::{self, SymbolTable};
+use crate::system_config::ConfigurableSystem;
+
+/// Instructions `continue`, `next` and `finish` run before giving up
+pub const DEFAULT_RUN_LIMIT: u64 = 1_000_000;
+
+/// Help text listing every debugger command
+pub const DEBUGGER_HELP: &[(&str, &str)] = &[
+    ("break [addr]", "Set a breakpoint, or list them"),
+    ("delete [addr]", "Remove a breakpoint, or all of them"),
+    ("watch ram b:r:c", "Stop when a RAM character changes"),
+    ("unwatch [b:r:c]", "Remove a watchpoint, or all of them"),
+    ("step [n]", "Execute n instructions (default 1)"),
+    ("next", "Step over a subroutine call"),
+    ("finish", "Run until the current subroutine returns"),
+    ("continue, c", "Run until a breakpoint or watchpoint"),
+    ("set pc|acc|cy|rN v", "Change a CPU register"),
+    ("set ram b:r:c v", "Change a RAM character"),
+    ("x addr [n]", "Examine n bytes of program memory"),
+    ("x ram b:r", "Examine a RAM register"),
+    ("save file", "Save the machine state to a file"),
+    ("load file", "Restore a machine state saved with save"),
+];
+
+/// One RAM character, addressed the way SRC selects it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RamLocation {
+    pub bank: u8,      // RAM bank (0-3), selected by DCL
+    pub register: u8,  // Register within the bank (0-15), chip number in bits 3-2
+    pub character: u8, // Main character 0-15, or 16-19 for status characters 0-3
+}
+
+impl RamLocation {
+    /// Parse a RAM location
+    /// Parameters: text - `bank:register:character`
+    /// Returns: Ok(RamLocation), Err(String) if malformed or out of range
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match parse_fields(text, 3)?.as_slice() {
+            [bank, register, character] => Ok(RamLocation {
+                bank: *bank,
+                register: *register,
+                character: *character,
+            }),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Read the character
+    /// Parameters: system - System holding the RAM
+    /// Returns: Ok(4-bit value), Err(String) if no RAM answers the location
+    pub fn read(&self, system: &ConfigurableSystem) -> Result<u8, String> {
+        system.read_ram_character(self.bank, self.register, self.character)
+    }
+}
+
+impl fmt::Display for RamLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.bank, self.register, self.character)
+    }
+}
+
+/// A watched RAM character and the value it had when last checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub location: RamLocation,
+    pub value: u8,
+}
+
+/// Why execution returned to the debugger
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    Stepped(u64),    // The requested number of instructions ran
+    Breakpoint(u16), // The PC reached a breakpoint
+    Watchpoint {
+        location: RamLocation,
+        old: u8,
+        new: u8,
+    },
+    Returned,   // `next` or `finish` reached the return address
+    Limit(u64), // Gave up after this many instructions
+}
+
+/// How long a run command executes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RunGoal {
+    Steps(u64), // A fixed number of instructions
+    Depth(i32), // Until the call depth, counted from JMS and BBL, reaches this value
+    Forever,    // Until a breakpoint or watchpoint
+}
+
+/// Interactive debugger state
+pub struct Debugger {
+    watchpoints: Vec<Watchpoint>, // Watched RAM characters, in the order they were set
+    run_limit: u64,               // Instructions a run command executes before giving up
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debugger {
+    /// Create a debugger without watchpoints
+    /// Returns: New Debugger instance
+    pub fn new() -> Self {
+        Debugger {
+            watchpoints: Vec::new(),
+            run_limit: DEFAULT_RUN_LIMIT,
+        }
+    }
+
+    /// Set how many instructions `continue`, `next` and `finish` run before giving up
+    /// Parameters: limit - Instruction count
+    pub fn set_run_limit(&mut self, limit: u64) {
+        self.run_limit = limit;
+    }
+
+    /// Get the watchpoints
+    /// Returns: Watchpoints in the order they were set
+    pub fn get_watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    /// Execute one debugger command
+    /// The system is prepared for lockstep execution first, unless it already runs, so
+    /// edits made before the first step survive the CPU's start-up reset
+    /// Parameters: system - System to debug, command - Command line,
+    ///             symbols - Optional symbol table for addresses and listings
+    /// Returns: Ok(output lines), Err(String) if the command is unknown or fails
+    pub fn execute(
+        &mut self,
+        system: &mut ConfigurableSystem,
+        command: &str,
+        symbols: Option<&SymbolTable>,
+    ) -> Result<Vec<String>, String> {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        let Some((&name, args)) = words.split_first() else {
+            return Ok(Vec::new());
+        };
+
+        if !system.is_running() {
+            system.start_lockstep();
+        }
+
+        match (name.to_ascii_lowercase().as_str(), args) {
+            ("break" | "b", []) => {
+                let breakpoints = system.with_mcs4_cpu(|cpu| cpu.get_breakpoints())?;
+                if breakpoints.is_empty() {
+                    return Ok(vec!["No breakpoints".to_string()]);
+                }
+                Ok(breakpoints
+                    .iter()
+                    .map(|&address| format!("Breakpoint at {}", describe(address, symbols)))
+                    .collect())
+            }
+            ("break" | "b", [address]) => {
+                let address = parse_address(address, symbols)?;
+                system.with_mcs4_cpu(|cpu| cpu.add_breakpoint(address))?;
+                Ok(vec![format!(
+                    "Breakpoint at {}",
+                    describe(address, symbols)
+                )])
+            }
+            ("delete" | "d", []) => {
+                system.with_mcs4_cpu(|cpu| cpu.clear_breakpoints())?;
+                Ok(vec!["Deleted all breakpoints".to_string()])
+            }
+            ("delete" | "d", [address]) => {
+                let address = parse_address(address, symbols)?;
+                if !system.with_mcs4_cpu(|cpu| cpu.remove_breakpoint(address))? {
+                    return Err(format!("No breakpoint at 0x{:03X}", address));
+                }
+                Ok(vec![format!("Deleted breakpoint at 0x{:03X}", address)])
+            }
+            ("watch", []) => {
+                if self.watchpoints.is_empty() {
+                    return Ok(vec!["No watchpoints".to_string()]);
+                }
+                Ok(self
+                    .watchpoints
+                    .iter()
+                    .map(|watch| format!("Watching ram {} = 0x{:X}", watch.location, watch.value))
+                    .collect())
+            }
+            ("watch", [space, location]) if space.eq_ignore_ascii_case("ram") => {
+                let location = RamLocation::parse(location)?;
+                let value = location.read(system)?;
+                self.watchpoints.retain(|watch| watch.location != location);
+                self.watchpoints.push(Watchpoint { location, value });
+                Ok(vec![format!("Watching ram {} = 0x{:X}", location, value)])
+            }
+            ("unwatch", []) => {
+                self.watchpoints.clear();
+                Ok(vec!["Deleted all watchpoints".to_string()])
+            }
+            ("unwatch", [location]) => {
+                let location = RamLocation::parse(location)?;
+                let count = self.watchpoints.len();
+                self.watchpoints.retain(|watch| watch.location != location);
+                if self.watchpoints.len() == count {
+                    return Err(format!("No watchpoint on ram {}", location));
+                }
+                Ok(vec![format!("Deleted watchpoint on ram {}", location)])
+            }
+            ("step", []) => self.run_command(system, RunGoal::Steps(1), symbols),
+            ("step", [count]) => {
+                let count = parse_number(count)?;
+                self.run_command(system, RunGoal::Steps(count as u64), symbols)
+            }
+            ("next" | "n", []) => {
+                let goal = if Self::current_opcode(system)? >> 4 == 0x5 {
+                    RunGoal::Depth(0) // JMS: run until the matching BBL
+                } else {
+                    RunGoal::Steps(1)
+                };
+                self.run_command(system, goal, symbols)
+            }
+            ("finish", []) => self.run_command(system, RunGoal::Depth(-1), symbols),
+            ("continue" | "c", []) => self.run_command(system, RunGoal::Forever, symbols),
+            ("set", [target, value]) => Self::set_register(system, target, value, symbols),
+            ("set", [space, location, value]) if space.eq_ignore_ascii_case("ram") => {
+                let location = RamLocation::parse(location)?;
+                let value = parse_nibble(value)?;
+                system.write_ram_character(
+                    location.bank,
+                    location.register,
+                    location.character,
+                    value,
+                )?;
+                // Edits made by the user do not trigger the watchpoint
+                for watch in &mut self.watchpoints {
+                    if watch.location == location {
+                        watch.value = value;
+                    }
+                }
+                Ok(vec![format!("ram {} = 0x{:X}", location, value)])
+            }
+            ("x", [space, register]) if space.eq_ignore_ascii_case("ram") => {
+                Self::examine_ram(system, register)
+            }
+            ("x", [address]) => Ok(Self::examine_program(
+                system,
+                parse_address(address, symbols)?,
+                16,
+            )),
+            ("x", [address, count]) => Ok(Self::examine_program(
+                system,
+                parse_address(address, symbols)?,
+                parse_number(count)? as usize,
+            )),
+            ("save", [path]) => {
+                system.save_state(path)?;
+                Ok(vec![format!("Saved machine state to {}", path)])
+            }
+            ("load", [path]) => {
+                system.load_state(path)?;
+                // Watch the restored values rather than report the jump as a change
+                for watch in &mut self.watchpoints {
+                    watch.value = watch.location.read(system)?;
+                }
+                Ok(vec![format!("Loaded machine state from {}", path)])
+            }
+            _ => Err(format!("Unknown debugger command '{}'", command.trim())),
+        }
+    }
+
+    /// Run instructions until the goal is met or something stops execution
+    /// Parameters: system - System to run, goal - When to stop
+    /// Returns: Ok(StopReason), Err(String) if the CPU cannot be stepped
+    fn run(
+        &mut self,
+        system: &mut ConfigurableSystem,
+        goal: RunGoal,
+    ) -> Result<StopReason, String> {
+        // A CPU parked at a breakpoint resumes with the instruction there
+        system.with_mcs4_cpu(|cpu| cpu.take_breakpoint_hit())?;
+
+        let limit = match goal {
+            RunGoal::Steps(count) => count,
+            RunGoal::Depth(_) | RunGoal::Forever => self.run_limit,
+        };
+        let mut depth = 0;
+        for _ in 0..limit {
+            let opcode = Self::current_opcode(system)?;
+            system.step_instruction()?;
+
+            match opcode >> 4 {
+                0x5 => depth += 1, // JMS
+                0xC => depth -= 1, // BBL
+                _ => {}
+            }
+
+            if let Some(address) = system.with_mcs4_cpu(|cpu| cpu.take_breakpoint_hit())? {
+                return Ok(StopReason::Breakpoint(address));
+            }
+            if let Some(stop) = self.check_watchpoints(system)? {
+                return Ok(stop);
+            }
+            if goal == RunGoal::Depth(depth) {
+                return Ok(StopReason::Returned);
+            }
+        }
+
+        Ok(match goal {
+            RunGoal::Steps(count) => StopReason::Stepped(count),
+            RunGoal::Depth(_) | RunGoal::Forever => StopReason::Limit(limit),
+        })
+    }
+
+    /// Run instructions and describe where execution stopped
+    /// Parameters: system - System to run, goal - When to stop, symbols - Optional symbols
+    /// Returns: Ok(output lines), Err(String) if the CPU cannot be stepped
+    fn run_command(
+        &mut self,
+        system: &mut ConfigurableSystem,
+        goal: RunGoal,
+        symbols: Option<&SymbolTable>,
+    ) -> Result<Vec<String>, String> {
+        let mut output = match self.run(system, goal)? {
+            StopReason::Stepped(_) | StopReason::Returned => Vec::new(),
+            StopReason::Breakpoint(address) => {
+                vec![format!("Breakpoint at {}", describe(address, symbols))]
+            }
+            StopReason::Watchpoint { location, old, new } => {
+                vec![format!(
+                    "Watchpoint ram {}: 0x{:X} -> 0x{:X}",
+                    location, old, new
+                )]
+            }
+            StopReason::Limit(count) => vec![format!("Still running after {} instructions", count)],
+        };
+
+        let instruction = disasm::disassemble_from_pc(system, 1, symbols)?;
+        output.extend(
+            instruction
+                .iter()
+                .map(|instruction| format!("> {}", instruction)),
+        );
+        Ok(output)
+    }
+
+    /// Compare every watched RAM character against its last value
+    /// Parameters: system - System holding the RAM
+    /// Returns: Ok(Some(StopReason)) for the first changed character, Ok(None) if none changed
+    fn check_watchpoints(
+        &mut self,
+        system: &ConfigurableSystem,
+    ) -> Result<Option<StopReason>, String> {
+        let mut stop = None;
+        for watch in &mut self.watchpoints {
+            let value = watch.location.read(system)?;
+            if value != watch.value && stop.is_none() {
+                stop = Some(StopReason::Watchpoint {
+                    location: watch.location,
+                    old: watch.value,
+                    new: value,
+                });
+            }
+            watch.value = value;
+        }
+        Ok(stop)
+    }
+
+    /// Read the opcode at the program counter
+    /// Parameters: system - System with an MCS-4 CPU
+    /// Returns: Ok(first byte of the next instruction), Err(String) if there is no MCS-4 CPU
+    fn current_opcode(system: &ConfigurableSystem) -> Result<u8, String> {
+        let pc = system.with_mcs4_cpu(|cpu| cpu.get_program_counter())?;
+        Ok(system.read_program_memory(pc, 1)[0])
+    }
+
+    /// Change a CPU register
+    /// Parameters: system - System with an MCS-4 CPU, target - pc, acc, cy or r0-r15,
+    ///             value - New value, symbols - Optional symbols for the PC
+    /// Returns: Ok(output lines), Err(String) if the register or value is invalid
+    fn set_register(
+        system: &mut ConfigurableSystem,
+        target: &str,
+        value: &str,
+        symbols: Option<&SymbolTable>,
+    ) -> Result<Vec<String>, String> {
+        let target = target.to_ascii_lowercase();
+        match target.as_str() {
+            "pc" => {
+                let address = parse_address(value, symbols)?;
+                system.with_mcs4_cpu(|cpu| cpu.set_program_counter(address))?;
+                Ok(vec![format!("PC = {}", describe(address, symbols))])
+            }
+            "acc" | "a" => {
+                let value = parse_nibble(value)?;
+                system.with_mcs4_cpu(|cpu| cpu.set_accumulator(value))?;
+                Ok(vec![format!("ACC = 0x{:X}", value)])
+            }
+            "cy" | "carry" => {
+                let carry = match parse_number(value)? {
+                    0 => false,
+                    1 => true,
+                    _ => return Err("Carry must be 0 or 1".to_string()),
+                };
+                system.with_mcs4_cpu(|cpu| cpu.set_carry(carry))?;
+                Ok(vec![format!("CY = {}", carry as u8)])
+            }
+            _ => {
+                let index = target
+                    .strip_prefix('r')
+                    .and_then(|index| index.parse::<u8>().ok())
+                    .filter(|index| *index < 16)
+                    .ok_or_else(|| format!("Unknown register '{}'", target))?;
+                let value = parse_nibble(value)?;
+                system.with_mcs4_cpu(|cpu| cpu.set_register(index, value))??;
+                Ok(vec![format!("R{} = 0x{:X}", index, value)])
+            }
+        }
+    }
+
+    /// Dump program memory, 16 bytes per line
+    /// Parameters: system - System to read, address - First address, count - Number of bytes
+    /// Returns: Output lines
+    fn examine_program(system: &ConfigurableSystem, address: u16, count: usize) -> Vec<String> {
+        system
+            .read_program_memory(address, count)
+            .chunks(16)
+            .enumerate()
+            .map(|(line, bytes)| {
+                let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+                format!(
+                    "{:03X}: {}",
+                    (address as usize + line * 16) & 0xFFF,
+                    hex.join(" ")
+                )
+            })
+            .collect()
+    }
+
+    /// Dump one RAM register with its status characters
+    /// Parameters: system - System holding the RAM, register - `bank:register`
+    /// Returns: Ok(output lines), Err(String) if malformed or no RAM answers the register
+    fn examine_ram(system: &ConfigurableSystem, register: &str) -> Result<Vec<String>, String> {
+        let (bank, register) = match parse_fields(register, 2)?.as_slice() {
+            [bank, register] => (*bank, *register),
+            _ => unreachable!(),
+        };
+        let characters = (0..20)
+            .map(|character| system.read_ram_character(bank, register, character))
+            .collect::<Result<Vec<u8>, String>>()?;
+        let hex = |values: &[u8]| {
+            values
+                .iter()
+                .map(|value| format!("{:X}", value))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        Ok(vec![format!(
+            "ram {}:{}  {}  status {}",
+            bank,
+            register,
+            hex(&characters[..16]),
+            hex(&characters[16..])
+        )])
+    }
+}
+
+/// Format an address with its symbol
+/// Parameters: address - Program address, symbols - Optional symbol table
+/// Returns: `0x012` or `0x012 (DONE)`
+fn describe(address: u16, symbols: Option<&SymbolTable>) -> String {
+    match symbols.and_then(|symbols| symbols.name_at(address)) {
+        Some(name) => format!("0x{:03X} ({})", address, name),
+        None => format!("0x{:03X}", address),
+    }
+}
+
+/// Parse a number
+/// Parameters: text - Decimal, `0x`, `$` or `H`-suffixed hex number
+/// Returns: Ok(value), Err(String) if malformed
+fn parse_number(text: &str) -> Result<u32, String> {
+    disasm::parse_number(text).ok_or_else(|| format!("Invalid number '{}'", text))
+}
+
+/// Parse a 4-bit value
+/// Parameters: text - Number text
+/// Returns: Ok(value), Err(String) if malformed or above 0xF
+fn parse_nibble(text: &str) -> Result<u8, String> {
+    match parse_number(text)? {
+        value @ 0..=0xF => Ok(value as u8),
+        value => Err(format!("Value 0x{:X} does not fit in 4 bits", value)),
+    }
+}
+
+/// Parse a program address
+/// Parameters: text - Number or symbol name, symbols - Optional symbol table
+/// Returns: Ok(12-bit address), Err(String) if neither a number nor a known symbol
+fn parse_address(text: &str, symbols: Option<&SymbolTable>) -> Result<u16, String> {
+    match parse_number(text) {
+        Ok(address) if address < 0x1000 => Ok(address as u16),
+        Ok(address) => Err(format!("Address 0x{:X} out of range 0-0xFFF", address)),
+        Err(e) => symbols
+            .and_then(|symbols| symbols.address_of(text))
+            .ok_or(e),
+    }
+}
+
+/// Parse colon-separated RAM address fields
+/// Parameters: text - `bank:register[:character]`, count - Number of fields expected
+/// Returns: Ok(fields), Err(String) if malformed or a field is out of range
+fn parse_fields(text: &str, count: usize) -> Result<Vec<u8>, String> {
+    let limits = [3, 15, 19];
+    let fields: Vec<&str> = text.split(':').collect();
+    if fields.len() != count {
+        let form = ["bank", "register", "character"][..count].join(":");
+        return Err(format!("Expected {}, got '{}'", form, text));
+    }
+    fields
+        .iter()
+        .zip(limits)
+        .map(|(field, limit)| match parse_number(field)? {
+            value if value <= limit => Ok(value as u8),
+            value => Err(format!(
+                "RAM address field {} out of range 0-{}",
+                value, limit
+            )),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debugger_parses_ram_locations() {
+        assert_eq!(
+            RamLocation::parse("1:0xA:19"),
+            Ok(RamLocation {
+                bank: 1,
+                register: 10,
+                character: 19
+            })
+        );
+        assert_eq!(RamLocation::parse("0:5:3").unwrap().to_string(), "0:5:3");
+        assert!(RamLocation::parse("4:0:0").is_err());
+        assert!(RamLocation::parse("0:16:0").is_err());
+        assert!(RamLocation::parse("0:0").is_err());
+        assert!(parse_fields("0:x", 2).is_err());
+    }
+
+    #[test]
+    fn test_debugger_parses_addresses_and_values() {
+        let symbols = SymbolTable::parse("DONE EQU 0x012").unwrap();
+        assert_eq!(parse_address("0x12", None), Ok(0x012));
+        assert_eq!(parse_address("12H", None), Ok(0x012));
+        assert_eq!(parse_address("done", Some(&symbols)), Ok(0x012));
+        assert_eq!(
+            parse_address("LOOP", Some(&symbols)),
+            Err("Invalid number 'LOOP'".to_string())
+        );
+        assert_eq!(parse_nibble("$F"), Ok(0xF));
+        assert!(parse_nibble("16").is_err());
+        assert_eq!(describe(0x012, Some(&symbols)), "0x012 (DONE)");
+    }
+}
@@ -0,0 +1,553 @@
+//! # MCS-4 Disassembler
+//!
+//! Turns Intel 4004 (and 4040) ROM images back into annotated assembly source.
+//!
+//! The listing uses the syntax of the `asm` module, so a disassembled image
+//! assembles back to the same bytes. Addresses and raw bytes go into the comment
+//! column. A symbol file, such as the `.sym` file `--assemble` writes, names jump
+//! targets and marks where labels start.
+//!
+//! ```text
+//!         ORG 0x000
+//! START:  FIM P0, 0x00            ; 000: 20 00
+//! LOOP:   ISZ R4, LOOP            ; 002: 74 02  R4 += 1, jump unless R4 = 0
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+
+use crate::system_config::ConfigurableSystem;
+
+/// Program addresses mapped to symbol names
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SymbolTable {
+    names: BTreeMap<u16, String>, // Address to name, first definition wins
+}
+
+impl SymbolTable {
+    /// Create an empty symbol table
+    /// Returns: New SymbolTable instance
+    pub fn new() -> Self {
+        SymbolTable {
+            names: BTreeMap::new(),
+        }
+    }
+
+    /// Build a symbol table from assembler labels
+    /// Parameters: labels - Label names mapped to addresses, as in `asm::Assembly::symbols`
+    /// Returns: New SymbolTable instance
+    pub fn from_labels(labels: &BTreeMap<String, u16>) -> Self {
+        let mut table = Self::new();
+        for (name, &address) in labels {
+            table.insert(address, name.clone());
+        }
+        table
+    }
+
+    /// Parse a symbol file
+    /// Each line is `NAME EQU value`, `NAME = value` or `NAME value`; `;` starts a comment.
+    /// Values are decimal, `0x1F`, `$1F` or `1FH`.
+    /// Parameters: text - Symbol file contents
+    /// Returns: Ok(SymbolTable), Err(String) naming the first malformed line
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut table = Self::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split(';').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let (name, value) = match words.as_slice() {
+                [name, keyword, value]
+                    if keyword.eq_ignore_ascii_case("EQU") || *keyword == "=" =>
+                {
+                    (*name, *value)
+                }
+                [name, value] => (*name, *value),
+                _ => return Err(format!("Line {}: expected 'NAME EQU value'", index + 1)),
+            };
+            let address = parse_address(value)
+                .ok_or_else(|| format!("Line {}: invalid address '{}'", index + 1, value))?;
+            table.insert(address, name.to_string());
+        }
+        Ok(table)
+    }
+
+    /// Load a symbol file
+    /// Parameters: path - Path of the symbol file
+    /// Returns: Ok(SymbolTable), Err(String) if the file cannot be read or parsed
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// Name an address; an address keeps the first name it is given
+    /// Parameters: address - Program address, name - Symbol name
+    pub fn insert(&mut self, address: u16, name: String) {
+        self.names.entry(address & 0xFFF).or_insert(name);
+    }
+
+    /// Look up the name of an address
+    /// Parameters: address - Program address
+    /// Returns: Some(name) if the address has a symbol, None otherwise
+    pub fn name_at(&self, address: u16) -> Option<&str> {
+        self.names.get(&(address & 0xFFF)).map(String::as_str)
+    }
+
+    /// Look up the address of a name, ignoring case
+    /// Parameters: name - Symbol name
+    /// Returns: Some(address) of the first symbol with that name, None if it is not defined
+    pub fn address_of(&self, name: &str) -> Option<u16> {
+        self.iter()
+            .find(|(_, symbol)| symbol.eq_ignore_ascii_case(name))
+            .map(|(address, _)| address)
+    }
+
+    /// Iterate over the symbols in address order
+    /// Returns: Iterator of (address, name)
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &str)> {
+        self.names
+            .iter()
+            .map(|(&address, name)| (address, name.as_str()))
+    }
+
+    /// Get the number of symbols
+    /// Returns: Symbol count
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Check whether the table is empty
+    /// Returns: true if no symbols are defined
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+/// Parse a symbol file address
+/// Parameters: text - Decimal, `0x`, `$` or `H`-suffixed hex number
+/// Returns: Some(address) within the 12-bit program space, None if malformed
+pub(crate) fn parse_address(text: &str) -> Option<u16> {
+    parse_number(text)
+        .filter(|value| *value < 0x1000)
+        .map(|value| value as u16)
+}
+
+/// Parse a number in symbol file syntax
+/// Parameters: text - Decimal, `0x`, `$` or `H`-suffixed hex number
+/// Returns: Some(value), None if malformed
+pub(crate) fn parse_number(text: &str) -> Option<u32> {
+    let lower = text.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix("0x").or(lower.strip_prefix('$')) {
+        u32::from_str_radix(hex, 16).ok()
+    } else if let Some(hex) = lower.strip_suffix('h') {
+        u32::from_str_radix(hex, 16).ok()
+    } else {
+        lower.parse().ok()
+    }
+}
+
+/// One disassembled instruction or data byte
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisassembledInstruction {
+    pub address: u16,            // Address of the first byte
+    pub bytes: Vec<u8>,          // Encoded bytes, one or two
+    pub text: String,            // Assembly text, e.g. "ISZ R4, LOOP" or "DB 0xFF"
+    pub comment: Option<String>, // What the instruction does beyond its mnemonic
+}
+
+impl DisassembledInstruction {
+    /// Get the number of bytes the instruction occupies
+    /// Returns: 1 or 2
+    pub fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Get the raw bytes as hex
+    /// Returns: Bytes separated by spaces, e.g. "74 02"
+    pub fn hex_bytes(&self) -> String {
+        self.bytes
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl fmt::Display for DisassembledInstruction {
+    /// Compact one-line form for monitors: address, bytes, text and comment
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:03X}: {:<5}  {}",
+            self.address,
+            self.hex_bytes(),
+            self.text
+        )?;
+        if let Some(comment) = &self.comment {
+            write!(f, "  ; {}", comment)?;
+        }
+        Ok(())
+    }
+}
+
+/// Name an address operand
+/// Parameters: address - Target address, symbols - Optional symbol table
+/// Returns: Symbol name, or the address in hex
+fn address_operand(address: u16, symbols: Option<&SymbolTable>) -> String {
+    symbols
+        .and_then(|table| table.name_at(address))
+        .map_or_else(|| format!("0x{:03X}", address), str::to_string)
+}
+
+/// Render a JCN condition the way the assembler accepts it
+/// Parameters: condition - 4-bit condition code
+/// Returns: Condition name, or its number
+fn condition_operand(condition: u8) -> String {
+    match condition {
+        0x1 => "TZ".to_string(),
+        0x2 => "C1".to_string(),
+        0x4 => "AZ".to_string(),
+        0x9 => "TN".to_string(),
+        0xA => "C0".to_string(),
+        0xC => "AN".to_string(),
+        _ => condition.to_string(),
+    }
+}
+
+/// Describe when a JCN jumps
+/// C1 inverts, C2 tests ACC = 0, C3 tests carry, C4 tests the TEST pin low
+/// Parameters: condition - 4-bit condition code
+/// Returns: Description such as "jump if ACC = 0 or CY = 1"
+fn describe_condition(condition: u8) -> String {
+    let tests: Vec<&str> = [(0x4, "ACC = 0"), (0x2, "CY = 1"), (0x1, "TEST = 0")]
+        .iter()
+        .filter(|(bit, _)| condition & bit != 0)
+        .map(|(_, test)| *test)
+        .collect();
+    match (tests.is_empty(), condition & 0x8 != 0) {
+        (true, false) => "never jumps".to_string(),
+        (true, true) => "always jumps".to_string(),
+        (false, false) => format!("jump if {}", tests.join(" or ")),
+        (false, true) => format!("jump unless {}", tests.join(" or ")),
+    }
+}
+
+/// Disassemble the instruction at the start of a byte slice
+/// Two-byte instructions whose second byte is missing come out as a DB of the opcode.
+/// JCN and ISZ targets are resolved against the page of the word after the second byte, as on
+/// the chip, so an opcode in either of the last two words of a page jumps into the next page.
+/// Parameters: bytes - Program bytes starting at the instruction, address - Its program address,
+///             symbols - Optional symbol table for naming targets
+/// Returns: DisassembledInstruction of one or two bytes; empty input yields a NOP
+pub fn disassemble_instruction(
+    bytes: &[u8],
+    address: u16,
+    symbols: Option<&SymbolTable>,
+) -> DisassembledInstruction {
+    let address = address & 0xFFF;
+    let opcode = bytes.first().copied().unwrap_or(0);
+    let second = bytes.get(1).copied();
+    let opa = opcode & 0x0F;
+    let last_in_page = address & 0xFF == 0xFF;
+    let next_page = (address + 1) & 0xF00;
+    let target_page = (address + 2) & 0xF00;
+
+    let single = |text: String, comment: Option<String>| DisassembledInstruction {
+        address,
+        bytes: vec![opcode],
+        text,
+        comment,
+    };
+    let data_byte = |comment: &str| DisassembledInstruction {
+        address,
+        bytes: vec![opcode],
+        text: format!("DB 0x{:02X}", opcode),
+        comment: Some(comment.to_string()),
+    };
+
+    let two_byte = matches!(opcode >> 4, 0x1 | 0x4 | 0x5 | 0x7) || opcode & 0xF1 == 0x20;
+    if two_byte {
+        let Some(operand) = second else {
+            return data_byte("two-byte instruction cut off at the end of the image");
+        };
+        let page_target = target_page | operand as u16;
+        let long_target = ((opa as u16) << 8) | operand as u16;
+        let (text, mut comment) = match opcode >> 4 {
+            0x1 => (
+                format!(
+                    "JCN {}, {}",
+                    condition_operand(opa),
+                    address_operand(page_target, symbols)
+                ),
+                Some(describe_condition(opa)),
+            ),
+            0x2 => (format!("FIM P{}, 0x{:02X}", opa >> 1, operand), None),
+            0x4 => (
+                format!("JUN {}", address_operand(long_target, symbols)),
+                None,
+            ),
+            0x5 => (
+                format!("JMS {}", address_operand(long_target, symbols)),
+                Some("call".to_string()),
+            ),
+            _ => (
+                format!("ISZ R{}, {}", opa, address_operand(page_target, symbols)),
+                Some(format!("R{} += 1, jump unless R{} = 0", opa, opa)),
+            ),
+        };
+        let page_jump = matches!(opcode >> 4, 0x1 | 0x7);
+        let note = if page_jump && target_page != address & 0xF00 {
+            Some(format!("target in page 0x{:03X}", target_page))
+        } else if !page_jump && last_in_page {
+            Some(format!("operand in page 0x{:03X}", next_page))
+        } else {
+            None
+        };
+        if let Some(note) = note {
+            comment = Some(comment.map_or(note.clone(), |c| format!("{}; {}", c, note)));
+        }
+        return DisassembledInstruction {
+            address,
+            bytes: vec![opcode, operand],
+            text,
+            comment,
+        };
+    }
+
+    // FIN and JIN use the page of the next instruction
+    let page_note = last_in_page.then(|| format!("uses page 0x{:03X}", next_page));
+    const IMPLIED_E: [&str; 16] = [
+        "WRM", "WMP", "WRR", "WPM", "WR0", "WR1", "WR2", "WR3", "SBM", "RDM", "RDR", "ADM", "RD0",
+        "RD1", "RD2", "RD3",
+    ];
+    const IMPLIED_F: [&str; 14] = [
+        "CLB", "CLC", "IAC", "CMC", "CMA", "RAL", "RAR", "TCC", "DAC", "TCS", "STC", "DAA", "KBP",
+        "DCL",
+    ];
+    const INTEL_4040: [&str; 14] = [
+        "HLT", "BBS", "LCR", "OR4", "OR5", "AN6", "AN7", "DB0", "DB1", "SB0", "SB1", "EIN", "DIN",
+        "RPM",
+    ];
+
+    match opcode >> 4 {
+        0x0 if opa == 0 => single("NOP".to_string(), None),
+        0x0 if opa < 0xF => single(
+            INTEL_4040[opa as usize - 1].to_string(),
+            Some("4040 only".to_string()),
+        ),
+        0x2 => single(format!("SRC P{}", opa >> 1), None),
+        0x3 if opa & 1 == 0 => single(format!("FIN P{}", opa >> 1), page_note),
+        0x3 => single(format!("JIN P{}", opa >> 1), page_note),
+        0x6 => single(format!("INC R{}", opa), None),
+        0x8 => single(format!("ADD R{}", opa), None),
+        0x9 => single(format!("SUB R{}", opa), None),
+        0xA => single(format!("LD R{}", opa), None),
+        0xB => single(format!("XCH R{}", opa), None),
+        0xC => single(format!("BBL {}", opa), Some("return".to_string())),
+        0xD => single(format!("LDM {}", opa), None),
+        0xE => single(IMPLIED_E[opa as usize].to_string(), None),
+        0xF if (opa as usize) < IMPLIED_F.len() => {
+            single(IMPLIED_F[opa as usize].to_string(), None)
+        }
+        _ => data_byte("invalid opcode"),
+    }
+}
+
+/// Disassemble a block of program memory by linear sweep
+/// A two-byte instruction whose second byte carries a label is split, so decoding
+/// resynchronises at the label; the first byte comes out as a DB.
+/// Parameters: image - Program bytes, origin - Address of the first byte,
+///             symbols - Optional symbol table
+/// Returns: Instructions covering every byte of the image
+pub fn disassemble(
+    image: &[u8],
+    origin: u16,
+    symbols: Option<&SymbolTable>,
+) -> Vec<DisassembledInstruction> {
+    let mut instructions = Vec::new();
+    let mut offset = 0;
+    while offset < image.len() {
+        let address = (origin as usize + offset) as u16 & 0xFFF;
+        let mut instruction = disassemble_instruction(&image[offset..], address, symbols);
+        let split_label = symbols.and_then(|table| table.name_at(address.wrapping_add(1)));
+        if let (2, Some(label)) = (instruction.size(), split_label) {
+            instruction = DisassembledInstruction {
+                address,
+                bytes: vec![image[offset]],
+                text: format!("DB 0x{:02X}", image[offset]),
+                comment: Some(format!("split by label {}", label)),
+            };
+        }
+        offset += instruction.size();
+        instructions.push(instruction);
+    }
+    instructions
+}
+
+/// Format one instruction as a listing line
+/// Parameters: instruction - Disassembled instruction, label - Label at its address, if any
+/// Returns: Source line without a trailing newline; long labels get a line of their own
+pub fn format_line(instruction: &DisassembledInstruction, label: Option<&str>) -> String {
+    let mut comment = format!("; {:03X}: {}", instruction.address, instruction.hex_bytes());
+    if let Some(note) = &instruction.comment {
+        comment.push_str("  ");
+        comment.push_str(note);
+    }
+    let body = format!("{:<24}{}", instruction.text, comment);
+    match label {
+        Some(label) if label.len() < 7 => format!("{:<8}{}", format!("{}:", label), body),
+        Some(label) => format!("{}:\n        {}", label, body),
+        None => format!("        {}", body),
+    }
+}
+
+/// Disassemble an image into a complete listing
+/// Symbols that do not start an instruction in the image are emitted as EQU lines, so the
+/// listing assembles back to the same bytes.
+/// Parameters: image - Program bytes, origin - Address of the first byte,
+///             symbols - Optional symbol table
+/// Returns: Listing text, one line per instruction
+pub fn format_listing(image: &[u8], origin: u16, symbols: Option<&SymbolTable>) -> String {
+    let instructions = disassemble(image, origin, symbols);
+    let starts: BTreeSet<u16> = instructions.iter().map(|i| i.address).collect();
+
+    let mut listing = String::new();
+    for (address, name) in symbols.into_iter().flat_map(SymbolTable::iter) {
+        if !starts.contains(&address) {
+            listing.push_str(&format!("{} EQU 0x{:03X}\n", name, address));
+        }
+    }
+    listing.push_str(&format!("        ORG 0x{:03X}\n", origin & 0xFFF));
+    for instruction in &instructions {
+        let label = symbols.and_then(|table| table.name_at(instruction.address));
+        listing.push_str(&format_line(instruction, label));
+        listing.push('\n');
+    }
+    listing
+}
+
+/// Disassemble a running system from its program counter
+/// Parameters: system - System with an MCS-4 CPU, count - Number of instructions,
+///             symbols - Optional symbol table
+/// Returns: Ok(instructions) starting at the PC, Err(String) if the system has no MCS-4 CPU
+pub fn disassemble_from_pc(
+    system: &ConfigurableSystem,
+    count: usize,
+    symbols: Option<&SymbolTable>,
+) -> Result<Vec<DisassembledInstruction>, String> {
+    let pc = system.get_cpu_registers()?.program_counter;
+    let image = system.read_program_memory(pc, count * 2);
+    let mut instructions = disassemble(&image, pc, symbols);
+    instructions.truncate(count);
+    Ok(instructions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+
+    #[test]
+    fn test_disasm_decodes_one_and_two_byte_instructions() {
+        let text = |bytes: &[u8]| disassemble_instruction(bytes, 0x010, None).text;
+        assert_eq!(text(&[0x00]), "NOP");
+        assert_eq!(text(&[0x24, 0xA5]), "FIM P2, 0xA5");
+        assert_eq!(text(&[0x25]), "SRC P2");
+        assert_eq!(text(&[0x3F]), "JIN P7");
+        assert_eq!(text(&[0x41, 0x23]), "JUN 0x123");
+        assert_eq!(text(&[0x1C, 0x40]), "JCN AN, 0x040");
+        assert_eq!(text(&[0x74, 0x12]), "ISZ R4, 0x012");
+        assert_eq!(text(&[0xCF]), "BBL 15");
+        assert_eq!(text(&[0xEC]), "RD0");
+        assert_eq!(text(&[0xFD]), "DCL");
+        assert_eq!(text(&[0x01]), "HLT");
+        assert_eq!(text(&[0xFE]), "DB 0xFE");
+        assert_eq!(text(&[0x40]), "DB 0x40");
+        assert_eq!(disassemble_instruction(&[0x40], 0, None).size(), 1);
+
+        let jcn = disassemble_instruction(&[0x14, 0x00], 0x0FF, None);
+        assert_eq!(jcn.text, "JCN AZ, 0x100");
+        assert_eq!(
+            jcn.comment.as_deref(),
+            Some("jump if ACC = 0; target in page 0x100")
+        );
+        // The CPU takes the page after the second byte, which ends page 0 here
+        let isz = disassemble_instruction(&[0x74, 0x10], 0x0FE, None);
+        assert_eq!(isz.text, "ISZ R4, 0x110");
+        assert!(isz.comment.unwrap().ends_with("target in page 0x100"));
+        let fim = disassemble_instruction(&[0x20, 0x10], 0x0FF, None);
+        assert_eq!(fim.comment.as_deref(), Some("operand in page 0x100"));
+        assert_eq!(describe_condition(0xB), "jump unless CY = 1 or TEST = 0");
+    }
+
+    #[test]
+    fn test_disasm_symbol_file() {
+        let table =
+            SymbolTable::parse("; comment\nSTART EQU 0x000\nLOOP = $8\nDONE 12H\n").unwrap();
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.name_at(0x008), Some("LOOP"));
+        assert_eq!(table.name_at(0x012), Some("DONE"));
+        assert_eq!(table.address_of("done"), Some(0x012));
+        assert_eq!(table.address_of("MISSING"), None);
+        assert_eq!(
+            SymbolTable::parse("X EQU 0x1000").unwrap_err(),
+            "Line 1: invalid address '0x1000'"
+        );
+        assert!(SymbolTable::parse("A B C D").is_err());
+
+        let instruction = disassemble_instruction(&[0x74, 0x08], 0x010, Some(&table));
+        assert_eq!(instruction.text, "ISZ R4, LOOP");
+        assert_eq!(
+            instruction.to_string(),
+            "010: 74 08  ISZ R4, LOOP  ; R4 += 1, jump unless R4 = 0"
+        );
+    }
+
+    #[test]
+    fn test_disasm_listing_reassembles_to_the_same_image() {
+        let source = "
+START:  FIM P0, 0x00
+        LDM 8
+LOOP:   SRC P0
+        JMS SUB
+        ISZ R4, LOOP
+        JUN START
+SUB:    BBL 0
+        DB 0xFF, 0x40
+";
+        let assembly = assemble(source).unwrap();
+        let symbols = SymbolTable::from_labels(&assembly.symbols);
+
+        let listing = format_listing(&assembly.binary, 0, Some(&symbols));
+        assert!(listing.contains("LOOP:   SRC P0"));
+        assert!(listing.contains("ISZ R4, LOOP"));
+        assert!(listing.contains("; 00B: FF  invalid opcode"));
+        assert_eq!(assemble(&listing).unwrap().binary, assembly.binary);
+
+        // Without symbols the listing still round-trips
+        let listing = format_listing(&assembly.binary, 0, None);
+        assert_eq!(assemble(&listing).unwrap().binary, assembly.binary);
+    }
+
+    #[test]
+    fn test_disasm_resynchronises_at_labels() {
+        // A table byte that looks like a JUN opcode must not swallow the labelled code after it
+        let mut symbols = SymbolTable::new();
+        symbols.insert(0x001, "CODE".to_string());
+        symbols.insert(0x100, "FAR".to_string());
+        let instructions = disassemble(&[0x40, 0xD5, 0x41, 0x00], 0, Some(&symbols));
+        assert_eq!(instructions[0].text, "DB 0x40");
+        assert_eq!(instructions[1].text, "LDM 5");
+        assert_eq!(instructions[2].text, "JUN FAR");
+
+        let listing = format_listing(&[0x40, 0xD5, 0x41, 0x00], 0, Some(&symbols));
+        assert!(listing.starts_with("FAR EQU 0x100\n        ORG 0x000\n"));
+        assert_eq!(
+            assemble(&listing).unwrap().binary,
+            vec![0x40, 0xD5, 0x41, 0x00]
+        );
+    }
+}
@@ -0,0 +1,727 @@
+//! # GDB Remote Serial Protocol Stub
+//!
+//! Lets an external debugger front-end attach to a `ConfigurableSystem` over a local
+//! TCP socket, speaking the GDB remote serial protocol (RSP).
+//!
+//! ## Supported Packets
+//! - `?` stop reason, `g`/`G` read and write all registers
+//! - `m`/`M` read and write memory, `Z0`/`z0` (and `Z1`/`z1`) breakpoints
+//! - `s` single step, `c` continue, interrupted by Ctrl-C (0x03)
+//! - `qSupported`, `qXfer:features:read` for the target description, `D` detach, `k` kill
+//!
+//! Everything else gets the empty reply, which tells the front-end the packet is unsupported.
+//!
+//! ## Targets
+//! GDB has no built-in MCS-4 or 6502 architecture, so both targets send an XML target
+//! description that names their registers:
+//!
+//! | Target  | Registers (in `g` order)                        | Memory                      |
+//! |---------|-------------------------------------------------|-----------------------------|
+//! | MCS-4   | r0-r15, acc, cy (8 bits each), pc (16 bits)     | program memory 0x0000-0x0FFF, RAM at 0x10000 |
+//! | 6502    | a, x, y, sp, p (8 bits each), pc (16 bits)      | 64 KiB bus, RAM and ROM     |
+//!
+//! MCS-4 RAM characters are one byte each at
+//! `0x10000 | bank << 9 | register << 5 | character`, with characters 16-19 being the
+//! status characters. Breakpoints are kept by the stub and checked after every
+//! instruction, so they never modify program memory.
+//!
+//! ```bash
+//! cargo run -- --system basic --file programs/fibonacci_ram.bin --gdb 1234
+//! gdb -ex "target remote localhost:1234"
+//! ```
+
+use std::collections::BTreeSet;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+use crate::system_config::ConfigurableSystem;
+
+/// Base address of the MCS-4 RAM characters in the debugger's address space
+pub const MCS4_RAM_BASE: u32 = 0x10000;
+
+/// Largest packet the stub accepts, announced in `qSupported`; it also caps the length of
+/// a memory or target description request
+const PACKET_SIZE: usize = 0x4000;
+
+/// Instructions `c` runs between checks for a Ctrl-C from the front-end
+const CONTINUE_BATCH: usize = 1000;
+
+/// Stop reply for a breakpoint, single step or error (SIGTRAP)
+const STOP_TRAP: &str = "S05";
+
+/// Stop reply for an interrupt from the front-end (SIGINT)
+const STOP_INTERRUPT: &str = "S02";
+
+/// CPU architecture the stub debugs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GdbArch {
+    Mcs4,    // Intel 4004 or 4040
+    Mos6502, // 6502 or 65C02
+}
+
+impl GdbArch {
+    /// Find the architecture of a system's CPU
+    /// Parameters: system - System to debug
+    /// Returns: Ok(GdbArch), Err(String) if the system has no supported CPU
+    pub fn detect(system: &ConfigurableSystem) -> Result<Self, String> {
+        if system.with_mcs4_cpu(|_| ()).is_ok() {
+            Ok(GdbArch::Mcs4)
+        } else if system.with_mos6502_cpu(|_| ()).is_ok() {
+            Ok(GdbArch::Mos6502)
+        } else {
+            Err("No Intel 4004, 4040 or 6502 CPU component found in system".to_string())
+        }
+    }
+
+    /// Get the register names and sizes in `g` packet order
+    /// Returns: (name, size in bytes) per register
+    fn registers(&self) -> Vec<(String, usize)> {
+        match self {
+            GdbArch::Mcs4 => (0..16)
+                .map(|index| (format!("r{}", index), 1))
+                .chain([("acc".to_string(), 1), ("cy".to_string(), 1)])
+                .chain([("pc".to_string(), 2)])
+                .collect(),
+            GdbArch::Mos6502 => ["a", "x", "y", "sp", "p"]
+                .iter()
+                .map(|name| (name.to_string(), 1))
+                .chain([("pc".to_string(), 2)])
+                .collect(),
+        }
+    }
+
+    /// Build the XML target description sent for `qXfer:features:read:target.xml`
+    /// Returns: Target description naming every register in `g` order
+    pub fn target_description(&self) -> String {
+        let feature = match self {
+            GdbArch::Mcs4 => "org.rusty_emu.mcs4",
+            GdbArch::Mos6502 => "org.rusty_emu.mos6502",
+        };
+        let registers: String = self
+            .registers()
+            .iter()
+            .map(|(name, size)| {
+                let kind = if name == "pc" { "code_ptr" } else { "uint8" };
+                format!(
+                    "    <reg name=\"{}\" bitsize=\"{}\" type=\"{}\"/>\n",
+                    name,
+                    size * 8,
+                    kind
+                )
+            })
+            .collect();
+        format!(
+            "<?xml version=\"1.0\"?>\n<!DOCTYPE target SYSTEM \"gdb-target.dtd\">\n\
+             <target version=\"1.0\">\n  <feature name=\"{}\">\n{}  </feature>\n</target>\n",
+            feature, registers
+        )
+    }
+}
+
+/// What the connection does after a packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    Packet(String), // Send this reply and wait for the next packet
+    Continue,       // Run until a breakpoint or Ctrl-C, then send a stop reply
+    Close(String),  // Send this reply and end the session
+}
+
+/// GDB remote serial protocol stub for one system
+pub struct GdbStub {
+    system: Arc<Mutex<ConfigurableSystem>>,
+    arch: GdbArch,
+    breakpoints: BTreeSet<u32>, // Program addresses that stop `c`
+}
+
+impl GdbStub {
+    /// Create a stub for a system
+    /// The system is prepared for lockstep execution unless it already runs, and a 6502 is
+    /// stepped through its reset sequence, so the front-end attaches at an instruction boundary
+    /// Parameters: system - System to debug
+    /// Returns: Ok(GdbStub), Err(String) if the system has no supported CPU
+    pub fn new(system: Arc<Mutex<ConfigurableSystem>>) -> Result<Self, String> {
+        let arch = {
+            let mut guard = system
+                .lock()
+                .map_err(|_| "System lock poisoned".to_string())?;
+            let arch = GdbArch::detect(&guard)?;
+            if !guard.is_running() {
+                guard.start_lockstep();
+            }
+            if arch == GdbArch::Mos6502 {
+                while !guard.with_mos6502_cpu(|cpu| cpu.is_at_instruction_boundary())? {
+                    guard.step_lockstep()?;
+                }
+            }
+            arch
+        };
+
+        Ok(GdbStub {
+            system,
+            arch,
+            breakpoints: BTreeSet::new(),
+        })
+    }
+
+    /// Get the architecture being debugged
+    /// Returns: GdbArch of the system's CPU
+    pub fn get_arch(&self) -> GdbArch {
+        self.arch
+    }
+
+    /// Wait for one front-end on a local address and serve it until it detaches
+    /// Parameters: address - Socket address to listen on, e.g. "127.0.0.1:1234"
+    /// Returns: Ok(()) when the session ends, Err(String) if the socket fails
+    pub fn listen(&mut self, address: &str) -> Result<(), String> {
+        let listener = TcpListener::bind(address)
+            .map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+        println!("DEBUG: GDB stub waiting for a connection on {}", address);
+        let (stream, peer) = listener
+            .accept()
+            .map_err(|e| format!("Failed to accept a connection: {}", e))?;
+        println!("DEBUG: GDB stub connected to {}", peer);
+        self.serve(stream)
+    }
+
+    /// Serve one front-end connection until it detaches, kills or disconnects
+    /// Parameters: stream - Connected socket
+    /// Returns: Ok(()) when the session ends, Err(String) if the socket fails
+    pub fn serve(&mut self, stream: TcpStream) -> Result<(), String> {
+        let mut writer = stream
+            .try_clone()
+            .map_err(|e| format!("Failed to clone socket: {}", e))?;
+        let mut reader = BufReader::new(stream);
+
+        while let Some(packet) = read_packet(&mut reader, &mut writer)? {
+            let reply = match packet {
+                Incoming::Interrupt => Reply::Packet(STOP_INTERRUPT.to_string()),
+                Incoming::Packet(packet) => self.handle_packet(&packet),
+            };
+            match reply {
+                Reply::Packet(reply) => send_packet(&mut writer, &reply)?,
+                Reply::Continue => {
+                    let stop = self.continue_execution(&mut reader);
+                    send_packet(&mut writer, stop)?;
+                }
+                Reply::Close(reply) => {
+                    send_packet(&mut writer, &reply)?;
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle one packet
+    /// Parameters: packet - Packet data without framing or checksum
+    /// Returns: Reply to send; an unsupported packet gets the empty reply
+    pub fn handle_packet(&mut self, packet: &str) -> Reply {
+        // Split after the first character; the packet may carry non-ASCII text
+        let command_length = packet.chars().next().map_or(0, char::len_utf8);
+        let result = match packet.split_at(command_length) {
+            ("?", _) => Ok(STOP_TRAP.to_string()),
+            ("g", "") => self.read_registers().map(|bytes| to_hex(&bytes)),
+            ("G", data) => from_hex(data)
+                .and_then(|bytes| self.write_registers(&bytes))
+                .map(|_| "OK".to_string()),
+            ("m", args) => parse_memory_range(args)
+                .and_then(|(address, length)| self.read_memory(address, length))
+                .map(|bytes| to_hex(&bytes)),
+            ("M", args) => args
+                .split_once(':')
+                .ok_or_else(|| "Missing data".to_string())
+                .and_then(|(range, data)| {
+                    let (address, length) = parse_memory_range(range)?;
+                    let bytes = from_hex(data)?;
+                    if bytes.len() != length {
+                        return Err("Data length does not match".to_string());
+                    }
+                    self.write_memory(address, &bytes)
+                })
+                .map(|_| "OK".to_string()),
+            ("Z" | "z", args) => return Reply::Packet(self.update_breakpoint(packet, args)),
+            ("s", _) => {
+                if let Err(e) = self.step() {
+                    println!("DEBUG: GDB stub step failed: {}", e);
+                }
+                Ok(STOP_TRAP.to_string())
+            }
+            ("c", _) => return Reply::Continue,
+            ("H", _) => Ok("OK".to_string()),
+            ("D", _) => return Reply::Close("OK".to_string()),
+            ("k", _) => return Reply::Close(String::new()),
+            ("q", _) => Ok(self.query(packet)),
+            _ => Ok(String::new()),
+        };
+
+        Reply::Packet(result.unwrap_or_else(|e| {
+            println!("DEBUG: GDB stub packet '{}' failed: {}", packet, e);
+            "E01".to_string()
+        }))
+    }
+
+    /// Answer a general query
+    /// Parameters: packet - Query packet starting with 'q'
+    /// Returns: Reply data, empty for unsupported queries
+    fn query(&self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            return format!("PacketSize={:x};qXfer:features:read+", PACKET_SIZE);
+        }
+        if let Some(args) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            return match parse_memory_range(args) {
+                Ok((offset, length)) => {
+                    let description = self.arch.target_description();
+                    let start = (offset as usize).min(description.len());
+                    let end = start.saturating_add(length).min(description.len());
+                    let prefix = if end < description.len() { 'm' } else { 'l' };
+                    format!("{}{}", prefix, &description[start..end])
+                }
+                Err(_) => "E01".to_string(),
+            };
+        }
+        match packet {
+            "qAttached" => "1".to_string(),
+            "qC" => "QC1".to_string(),
+            "qfThreadInfo" => "m1".to_string(),
+            "qsThreadInfo" => "l".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// Set or clear a breakpoint
+    /// Software (type 0) and hardware (type 1) breakpoints both stop `c` at the address
+    /// Parameters: packet - Whole `Z` or `z` packet, args - Packet after the command letter
+    /// Returns: "OK", "E01" if malformed, empty for unsupported watchpoint types
+    fn update_breakpoint(&mut self, packet: &str, args: &str) -> String {
+        let fields: Vec<&str> = args.split(',').collect();
+        let address = match fields.as_slice() {
+            ["0" | "1", address, _kind] => u32::from_str_radix(address, 16),
+            [_, _, _] => return String::new(),
+            _ => return "E01".to_string(),
+        };
+        match address {
+            Ok(address) if packet.starts_with('Z') => {
+                self.breakpoints.insert(address);
+                "OK".to_string()
+            }
+            Ok(address) => {
+                self.breakpoints.remove(&address);
+                "OK".to_string()
+            }
+            Err(_) => "E01".to_string(),
+        }
+    }
+
+    /// Lock the system
+    /// Returns: Ok(guard), Err(String) if the lock is poisoned
+    fn lock_system(&self) -> Result<std::sync::MutexGuard<'_, ConfigurableSystem>, String> {
+        self.system
+            .lock()
+            .map_err(|_| "System lock poisoned".to_string())
+    }
+
+    /// Read every register in `g` packet order
+    /// Returns: Ok(register bytes, multi-byte registers little-endian), Err(String) on failure
+    pub fn read_registers(&self) -> Result<Vec<u8>, String> {
+        let system = self.lock_system()?;
+        match self.arch {
+            GdbArch::Mcs4 => system.with_mcs4_cpu(|cpu| {
+                let mut bytes: Vec<u8> = (0..16)
+                    .map(|index| cpu.get_register(index).unwrap_or(0))
+                    .collect();
+                bytes.push(cpu.get_accumulator());
+                bytes.push(cpu.get_carry() as u8);
+                bytes.extend(cpu.get_program_counter().to_le_bytes());
+                bytes
+            }),
+            GdbArch::Mos6502 => system.with_mos6502_cpu(|cpu| {
+                let mut bytes = vec![
+                    cpu.get_accumulator(),
+                    cpu.get_x_register(),
+                    cpu.get_y_register(),
+                    cpu.get_stack_pointer(),
+                    cpu.get_status_register(),
+                ];
+                bytes.extend(cpu.get_program_counter().to_le_bytes());
+                bytes
+            }),
+        }
+    }
+
+    /// Write every register in `g` packet order
+    /// Parameters: bytes - Register bytes, multi-byte registers little-endian
+    /// Returns: Ok(()) on success, Err(String) if the length is wrong or the CPU is missing
+    pub fn write_registers(&self, bytes: &[u8]) -> Result<(), String> {
+        let expected: usize = self.arch.registers().iter().map(|(_, size)| size).sum();
+        if bytes.len() != expected {
+            return Err(format!(
+                "Expected {} register bytes, got {}",
+                expected,
+                bytes.len()
+            ));
+        }
+
+        let system = self.lock_system()?;
+        let pc = u16::from_le_bytes([bytes[expected - 2], bytes[expected - 1]]);
+        match self.arch {
+            GdbArch::Mcs4 => system.with_mcs4_cpu(|cpu| {
+                for (index, &value) in bytes[..16].iter().enumerate() {
+                    cpu.set_register(index as u8, value & 0x0F)?;
+                }
+                cpu.set_accumulator(bytes[16]);
+                cpu.set_carry(bytes[17] != 0);
+                cpu.set_program_counter(pc);
+                Ok(())
+            })?,
+            GdbArch::Mos6502 => system.with_mos6502_cpu(|cpu| {
+                cpu.set_accumulator(bytes[0]);
+                cpu.set_x_register(bytes[1]);
+                cpu.set_y_register(bytes[2]);
+                cpu.set_stack_pointer(bytes[3]);
+                cpu.set_status_register(bytes[4]);
+                cpu.set_program_counter(pc);
+                Ok(())
+            })?,
+        }
+    }
+
+    /// Read memory in the debugger's address space
+    /// Parameters: address - First address, length - Number of bytes
+    /// Returns: Ok(bytes), Err(String) if any address has no memory
+    pub fn read_memory(&self, address: u32, length: usize) -> Result<Vec<u8>, String> {
+        let system = self.lock_system()?;
+        match self.arch {
+            GdbArch::Mcs4 if address < MCS4_RAM_BASE => {
+                if range_end(address, length)? > 0x1000 {
+                    return Err(format!("Program memory ends at 0x{:03X}", 0xFFF));
+                }
+                Ok(system.read_program_memory(address as u16, length))
+            }
+            GdbArch::Mcs4 => (address..range_end(address, length)?)
+                .map(|address| {
+                    let (bank, register, character) = Self::mcs4_ram_location(address)?;
+                    system.read_ram_character(bank, register, character)
+                })
+                .collect(),
+            GdbArch::Mos6502 => {
+                if range_end(address, length)? > 0x10000 {
+                    return Err("Address beyond 0xFFFF".to_string());
+                }
+                system.read_bus_memory(address as u16, length)
+            }
+        }
+    }
+
+    /// Write memory in the debugger's address space
+    /// Parameters: address - First address, data - Bytes to store
+    /// Returns: Ok(()) on success, Err(String) if any address has no memory
+    pub fn write_memory(&self, address: u32, data: &[u8]) -> Result<(), String> {
+        let system = self.lock_system()?;
+        let end = range_end(address, data.len())?;
+        match self.arch {
+            GdbArch::Mcs4 if address < MCS4_RAM_BASE => {
+                if end > 0x1000 {
+                    return Err(format!("Program memory ends at 0x{:03X}", 0xFFF));
+                }
+                system.write_program_memory(address as u16, data)
+            }
+            GdbArch::Mcs4 => data
+                .iter()
+                .zip(address..end)
+                .try_for_each(|(&value, address)| {
+                    let (bank, register, character) = Self::mcs4_ram_location(address)?;
+                    system.write_ram_character(bank, register, character, value)
+                }),
+            GdbArch::Mos6502 => {
+                if end > 0x10000 {
+                    return Err("Address beyond 0xFFFF".to_string());
+                }
+                system.write_bus_memory(address as u16, data)
+            }
+        }
+    }
+
+    /// Decode an MCS-4 RAM address
+    /// Parameters: address - `MCS4_RAM_BASE | bank << 9 | register << 5 | character`
+    /// Returns: Ok((bank, register, character)), Err(String) outside the RAM window
+    fn mcs4_ram_location(address: u32) -> Result<(u8, u8, u8), String> {
+        let offset = address
+            .checked_sub(MCS4_RAM_BASE)
+            .filter(|offset| *offset < 4 << 9 && offset & 0x1F < 20)
+            .ok_or_else(|| format!("No RAM character at 0x{:05X}", address))?;
+        Ok((
+            (offset >> 9) as u8,
+            ((offset >> 5) & 0x0F) as u8,
+            (offset & 0x1F) as u8,
+        ))
+    }
+
+    /// Get the program counter
+    /// Returns: Ok(PC), Err(String) if the CPU is missing
+    fn program_counter(&self) -> Result<u32, String> {
+        let system = self.lock_system()?;
+        match self.arch {
+            GdbArch::Mcs4 => system.with_mcs4_cpu(|cpu| cpu.get_program_counter() as u32),
+            GdbArch::Mos6502 => system.with_mos6502_cpu(|cpu| cpu.get_program_counter() as u32),
+        }
+    }
+
+    /// Execute one instruction
+    /// Returns: Ok(()) on success, Err(String) if the CPU completes no instruction
+    pub fn step(&self) -> Result<(), String> {
+        self.lock_system()?.step_instruction()
+    }
+
+    /// Run until a breakpoint, a Ctrl-C from the front-end or an execution error
+    /// The instruction at the current PC always runs, so continuing from a breakpoint
+    /// does not stop at it again
+    /// Parameters: reader - Connection the front-end sends Ctrl-C on
+    /// Returns: Stop reply to send
+    fn continue_execution(&self, reader: &mut BufReader<TcpStream>) -> &'static str {
+        loop {
+            for _ in 0..CONTINUE_BATCH {
+                let stopped = self
+                    .step()
+                    .and_then(|_| self.program_counter())
+                    .map(|pc| self.breakpoints.contains(&pc));
+                match stopped {
+                    Ok(false) => {}
+                    Ok(true) => return STOP_TRAP,
+                    Err(e) => {
+                        println!("DEBUG: GDB stub stopped: {}", e);
+                        return STOP_TRAP;
+                    }
+                }
+            }
+            if interrupt_pending(reader) {
+                return STOP_INTERRUPT;
+            }
+        }
+    }
+}
+
+/// Data received from the front-end
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Incoming {
+    Packet(String), // A packet with a valid checksum, already acknowledged
+    Interrupt,      // Ctrl-C sent outside a packet
+}
+
+/// Compute the checksum of packet data
+/// Parameters: data - Packet data between '$' and '#'
+/// Returns: Sum of the bytes modulo 256
+pub fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+/// Frame packet data for sending
+/// '#', '$', '}' and '*' are escaped as '}' followed by the byte XOR 0x20
+/// Parameters: data - Reply data
+/// Returns: `$data#checksum`
+pub fn encode_packet(data: &str) -> String {
+    let mut escaped = Vec::with_capacity(data.len());
+    for byte in data.bytes() {
+        if matches!(byte, b'#' | b'$' | b'}' | b'*') {
+            escaped.extend([b'}', byte ^ 0x20]);
+        } else {
+            escaped.push(byte);
+        }
+    }
+    format!(
+        "${}#{:02x}",
+        String::from_utf8_lossy(&escaped),
+        checksum(&escaped)
+    )
+}
+
+/// Send a framed packet
+/// Parameters: writer - Connection, data - Reply data
+/// Returns: Ok(()) on success, Err(String) if the socket fails
+fn send_packet(writer: &mut impl Write, data: &str) -> Result<(), String> {
+    writer
+        .write_all(encode_packet(data).as_bytes())
+        .and_then(|_| writer.flush())
+        .map_err(|e| format!("Failed to send packet: {}", e))
+}
+
+/// Read the next packet or interrupt, acknowledging packets
+/// A packet with a bad checksum is answered with '-' so the front-end resends it
+/// Parameters: reader - Connection to read, writer - Connection for acknowledgements
+/// Returns: Ok(Some(Incoming)), Ok(None) when the connection closes, Err(String) on socket errors
+fn read_packet(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write,
+) -> Result<Option<Incoming>, String> {
+    let read_error = |e: std::io::Error| format!("Failed to read packet: {}", e);
+    loop {
+        let mut byte = [0u8];
+        if reader.read(&mut byte).map_err(read_error)? == 0 {
+            return Ok(None);
+        }
+        match byte[0] {
+            0x03 => return Ok(Some(Incoming::Interrupt)),
+            b'$' => {}
+            _ => continue, // Acknowledgements and noise between packets
+        }
+
+        let mut data = Vec::new();
+        if reader.read_until(b'#', &mut data).map_err(read_error)? == 0 {
+            return Ok(None);
+        }
+        data.pop();
+        let mut sum = [0u8; 2];
+        reader.read_exact(&mut sum).map_err(read_error)?;
+
+        let valid = std::str::from_utf8(&sum)
+            .ok()
+            .and_then(|sum| u8::from_str_radix(sum, 16).ok())
+            == Some(checksum(&data));
+        let ack: &[u8] = if valid { b"+" } else { b"-" };
+        writer
+            .write_all(ack)
+            .map_err(|e| format!("Failed to acknowledge packet: {}", e))?;
+        if valid {
+            return Ok(Some(Incoming::Packet(
+                String::from_utf8_lossy(&data).into_owned(),
+            )));
+        }
+    }
+}
+
+/// Check for a Ctrl-C without blocking
+/// Parameters: reader - Connection to the front-end
+/// Returns: true if the next byte received is 0x03, which is consumed
+fn interrupt_pending(reader: &mut BufReader<TcpStream>) -> bool {
+    if reader.buffer().is_empty() {
+        if reader.get_ref().set_nonblocking(true).is_err() {
+            return false;
+        }
+        if let Err(e) = reader.fill_buf() {
+            if e.kind() != ErrorKind::WouldBlock {
+                println!("DEBUG: GDB stub failed to poll for Ctrl-C: {}", e);
+            }
+        }
+        let _ = reader.get_ref().set_nonblocking(false);
+    }
+    if reader.buffer().first() == Some(&0x03) {
+        reader.consume(1);
+        true
+    } else {
+        false
+    }
+}
+
+/// Encode bytes as lowercase hex
+/// Parameters: bytes - Data
+/// Returns: Two hex digits per byte
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Decode hex digits
+/// Parameters: text - Two hex digits per byte
+/// Returns: Ok(bytes), Err(String) if malformed
+fn from_hex(text: &str) -> Result<Vec<u8>, String> {
+    if !text.len().is_multiple_of(2) {
+        return Err("Odd number of hex digits".to_string());
+    }
+    // Decode from the raw bytes so non-ASCII input is an error rather than a bad slice
+    let digit = |byte: u8| (byte as char).to_digit(16);
+    text.as_bytes()
+        .chunks(2)
+        .map(|pair| match (digit(pair[0]), digit(pair[1])) {
+            (Some(high), Some(low)) => Ok((high << 4 | low) as u8),
+            _ => Err(format!("Invalid hex data '{}'", text)),
+        })
+        .collect()
+}
+
+/// Parse the `addr,length` arguments of `m`, `M` and `qXfer`
+/// Parameters: text - Hex address and length separated by a comma
+/// Returns: Ok((address, length)), Err(String) if malformed or longer than a packet
+fn parse_memory_range(text: &str) -> Result<(u32, usize), String> {
+    let (address, length) = text
+        .split_once(',')
+        .ok_or_else(|| format!("Expected 'address,length', got '{}'", text))?;
+    let address =
+        u32::from_str_radix(address, 16).map_err(|_| format!("Invalid address '{}'", address))?;
+    let length =
+        usize::from_str_radix(length, 16).map_err(|_| format!("Invalid length '{}'", length))?;
+    if length > PACKET_SIZE {
+        return Err(format!("Length 0x{:x} exceeds the packet size", length));
+    }
+    Ok((address, length))
+}
+
+/// Get the end of an address range
+/// Parameters: address - First address, length - Number of bytes
+/// Returns: Ok(first address past the range), Err(String) if it passes the 32-bit address space
+fn range_end(address: u32, length: usize) -> Result<u32, String> {
+    u32::try_from(length)
+        .ok()
+        .and_then(|length| address.checked_add(length))
+        .ok_or_else(|| format!("Range of 0x{:x} bytes at 0x{:x} wraps", length, address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gdb_packet_framing() {
+        assert_eq!(encode_packet("OK"), "$OK#9a");
+        assert_eq!(encode_packet(""), "$#00");
+        // '#' is escaped and the checksum covers the escaped bytes
+        assert_eq!(encode_packet("a#"), "$a}\x03#e1");
+
+        let mut input: &[u8] = b"+$g#67$m0,2#00\x03";
+        let mut acks = Vec::new();
+        assert_eq!(
+            read_packet(&mut input, &mut acks),
+            Ok(Some(Incoming::Packet("g".to_string())))
+        );
+        // The bad checksum is refused and reading goes on to the interrupt
+        assert_eq!(
+            read_packet(&mut input, &mut acks),
+            Ok(Some(Incoming::Interrupt))
+        );
+        assert_eq!(read_packet(&mut input, &mut acks), Ok(None));
+        assert_eq!(acks, b"+-");
+    }
+
+    #[test]
+    fn test_gdb_hex_and_ranges() {
+        assert_eq!(to_hex(&[0x00, 0xAB]), "00ab");
+        assert_eq!(from_hex("00ab"), Ok(vec![0x00, 0xAB]));
+        assert!(from_hex("abc").is_err());
+        assert!(from_hex("zz").is_err());
+        assert!(from_hex("+1").is_err());
+        assert!(from_hex("\u{FFFD}0").is_err());
+        assert_eq!(parse_memory_range("ff00,10"), Ok((0xFF00, 16)));
+        assert!(parse_memory_range("ff00").is_err());
+        assert!(parse_memory_range("1,ffffffffffffffff").is_err());
+        assert!(parse_memory_range("0,4001").is_err());
+        assert_eq!(
+            range_end(0xFFFF_FFF0, 0x10),
+            Err("Range of 0x10 bytes at 0xfffffff0 wraps".to_string())
+        );
+        assert_eq!(
+            GdbStub::mcs4_ram_location(0x10000 | 1 << 9 | 5 << 5 | 19),
+            Ok((1, 5, 19))
+        );
+        assert!(GdbStub::mcs4_ram_location(0x10014).is_err());
+        assert!(GdbStub::mcs4_ram_location(0x10800).is_err());
+    }
+
+    #[test]
+    fn test_gdb_target_descriptions() {
+        let mcs4 = GdbArch::Mcs4.target_description();
+        assert!(mcs4.contains("<feature name=\"org.rusty_emu.mcs4\">"));
+        assert!(mcs4.contains("<reg name=\"r15\" bitsize=\"8\" type=\"uint8\"/>"));
+        assert!(mcs4.contains("<reg name=\"pc\" bitsize=\"16\" type=\"code_ptr\"/>"));
+        let mos6502 = GdbArch::Mos6502.target_description();
+        assert!(mos6502.contains("<reg name=\"sp\" bitsize=\"8\" type=\"uint8\"/>"));
+        assert!(!mos6502.contains("r0"));
+    }
+}
@@ -12,6 +12,7 @@
 //! - **`ControlPanel`**: System control buttons and actions
 //! - **`MemoryViewer`**: RAM content display and inspection
 //! - **`RegisterViewer`**: CPU register state visualization
+//! - **`DisassemblyViewer`**: Instructions at the program counter
 //! - **`RomLoader`**: File dialog integration for ROM loading
 //! - **`StatusBar`**: System status and component health display
 //!
@@ -48,7 +49,9 @@
 //! - Recovery options are provided where applicable
 
 use super::state::GuiState;
+use crate::system_config::ConfigurableSystem;
 use eframe::egui;
+use std::sync::{Arc, Mutex};
 
 /// Container for all GUI components
 ///
@@ -76,6 +79,8 @@ pub struct GuiComponents {
     memory_viewer: MemoryViewer,
     /// CPU register state display
     register_viewer: RegisterViewer,
+    /// Disassembly at the program counter
+    disassembly_viewer: DisassemblyViewer,
     /// ROM file loading and management
     rom_loader: RomLoader,
     /// System status and health monitoring
@@ -103,6 +108,7 @@ impl GuiComponents {
             control_panel: ControlPanel::new(),
             memory_viewer: MemoryViewer::new(),
             register_viewer: RegisterViewer::new(),
+            disassembly_viewer: DisassemblyViewer::new(),
             rom_loader: RomLoader::new(),
             status_bar: StatusBar::new(),
         }
@@ -116,8 +122,14 @@ impl GuiComponents {
     /// # Arguments
     /// * `ui` - egui UI context for rendering
     /// * `state` - Mutable reference to GUI state for control operations
-    pub fn render_control_panel(&mut self, ui: &mut egui::Ui, state: &mut GuiState) {
-        self.control_panel.render(ui, state);
+    /// * `system` - System whose machine state is saved and loaded, if one is loaded
+    pub fn render_control_panel(
+        &mut self,
+        ui: &mut egui::Ui,
+        state: &mut GuiState,
+        system: Option<&Arc<Mutex<ConfigurableSystem>>>,
+    ) {
+        self.control_panel.render(ui, state, system);
     }
 
     /// Render the memory viewer component
@@ -144,6 +156,18 @@ impl GuiComponents {
         self.register_viewer.render(ui, state);
     }
 
+    /// Render the disassembly viewer component
+    ///
+    /// Lists the instructions starting at the program counter,
+    /// with labels and jump targets named from the symbol table.
+    ///
+    /// # Arguments
+    /// * `ui` - egui UI context for rendering
+    /// * `state` - Immutable reference to GUI state for display
+    pub fn render_disassembly_viewer(&self, ui: &mut egui::Ui, state: &GuiState) {
+        self.disassembly_viewer.render(ui, state);
+    }
+
     /// Render the status bar component
     ///
     /// Displays system health, component status, cycle counts,
@@ -166,6 +190,7 @@ impl GuiComponents {
 /// ## Features
 ///
 /// - **System Lifecycle Management**: Start, stop, and reset emulator execution
+/// - **Machine Snapshots**: Save the machine state to a file and load it back
 /// - **Visual State Feedback**: Dynamic button states reflecting current system status
 /// - **Error Handling**: Clear error display and recovery mechanisms
 /// - **Application Control**: Window management and application termination
@@ -183,6 +208,8 @@ pub struct ControlPanel {
     stop_button_enabled: bool,
     /// Whether the reset button should be enabled
     reset_button_enabled: bool,
+    /// File the Save State and Load State buttons use
+    state_path: String,
 }
 
 impl ControlPanel {
@@ -198,6 +225,7 @@ impl ControlPanel {
             start_button_text: "Start System".to_string(),
             stop_button_enabled: false,
             reset_button_enabled: false,
+            state_path: "machine_state.json".to_string(),
         }
     }
 
@@ -209,15 +237,22 @@ impl ControlPanel {
     /// # Arguments
     /// * `ui` - egui UI context for rendering and interaction
     /// * `state` - Mutable reference to GUI state for system control
+    /// * `system` - System whose machine state is saved and loaded, if one is loaded
     ///
     /// # Layout
     /// ```text
     /// ┌─────────────────────────────────────────────────┐
     /// │ System Control ■■■■■■■■■■■■■■■■■■■■■■■■■■■■■ │
     /// │ [Load ROM] [Start System] [Stop] [Reset] [Close] │
+    /// │ State file: [machine_state.json] [Save] [Load]   │
     /// └─────────────────────────────────────────────────┘
     /// ```
-    pub fn render(&mut self, ui: &mut egui::Ui, state: &mut GuiState) {
+    pub fn render(
+        &mut self,
+        ui: &mut egui::Ui,
+        state: &mut GuiState,
+        system: Option<&Arc<Mutex<ConfigurableSystem>>>,
+    ) {
         ui.horizontal(|ui| {
             // Section header
             ui.heading("System Control");
@@ -252,9 +287,68 @@ impl ControlPanel {
             }
         });
 
+        // Machine snapshots
+        ui.horizontal(|ui| {
+            ui.label("State file:");
+            ui.text_edit_singleline(&mut self.state_path);
+
+            if ui.button("Save State").clicked() {
+                self.save_state(state, system);
+            }
+
+            if ui.button("Load State").clicked() {
+                self.load_state(state, system);
+            }
+        });
+
         ui.separator();
     }
 
+    /// Save the machine state to the state file
+    ///
+    /// # Arguments
+    /// * `state` - Mutable reference to GUI state for error reporting
+    /// * `system` - System to save, if one is loaded
+    fn save_state(&self, state: &mut GuiState, system: Option<&Arc<Mutex<ConfigurableSystem>>>) {
+        let result = Self::with_system(system, |system| system.save_state(&self.state_path));
+        match result {
+            Ok(()) => state.clear_error(),
+            Err(e) => state.set_error(e),
+        }
+    }
+
+    /// Restore the machine state from the state file
+    ///
+    /// # Arguments
+    /// * `state` - Mutable reference to GUI state for error reporting
+    /// * `system` - System to restore into, if one is loaded
+    fn load_state(&self, state: &mut GuiState, system: Option<&Arc<Mutex<ConfigurableSystem>>>) {
+        let result = Self::with_system(system, |system| system.load_state(&self.state_path));
+        match result {
+            Ok(()) => state.clear_error(),
+            Err(e) => state.set_error(e),
+        }
+    }
+
+    /// Run an operation on the system without blocking the GUI
+    ///
+    /// # Arguments
+    /// * `system` - System to operate on, if one is loaded
+    /// * `f` - Operation to run while the system is locked
+    ///
+    /// # Returns
+    /// The operation's result, or an error if no system is loaded or it is busy
+    fn with_system(
+        system: Option<&Arc<Mutex<ConfigurableSystem>>>,
+        f: impl FnOnce(&mut ConfigurableSystem) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let system = system.ok_or_else(|| "No system loaded".to_string())?;
+        let mut guard = system
+            .try_lock()
+            .map_err(|_| "System is busy, try again".to_string())?;
+        f(&mut guard)
+    }
+
     /// Start the emulator system
     ///
     /// Transitions the system from stopped to running state and updates
@@ -304,29 +398,29 @@ impl ControlPanel {
 /// Memory viewer component for displaying RAM contents and state
 ///
 /// The memory viewer provides comprehensive RAM inspection capabilities,
-/// allowing users to examine memory contents across different banks with
+/// allowing users to examine the registers of every RAM chip with
 /// flexible display options and intuitive navigation.
 ///
 /// ## Features
 ///
-/// - **Multi-Bank Display**: View RAM contents across 4 banks simultaneously
+/// - **Multi-Chip Display**: View the registers of every Intel 4002 in the system
 /// - **Flexible Formatting**: Toggle between hexadecimal and decimal display
-/// - **Interactive Bank Selection**: Choose which memory bank to inspect
 /// - **Scrollable Interface**: Navigate through memory contents efficiently
 /// - **Real-time Updates**: Live memory content updates during emulation
 ///
 /// ## Display Format
 ///
-/// The memory viewer shows data in a structured grid:
-/// - **Address Column**: Memory addresses in hexadecimal format
-/// - **Bank Columns**: B0-B3 showing contents of each memory bank
+/// The memory viewer shows one grid per RAM chip:
+/// - **Register Column**: Register number R0-R3
+/// - **Main Columns**: Main characters 0-F, addressed by SRC
+/// - **Status Columns**: Status characters S0-S3, accessed by WR0-WR3 / RD0-RD3
 /// - **Value Display**: Configurable hex or decimal representation
 ///
 /// ## Memory Organization
 ///
 /// The Intel 4002 RAM has the following structure:
-/// - **4 Banks**: Independent memory banks (B0-B3)
-/// - **4 Bytes per Bank**: Addresses 0x00-0x03 in each bank
+/// - **4 Registers**: Selected by the SRC register nibble (R0-R3)
+/// - **16 + 4 Characters per Register**: 16 main characters plus 4 status characters
 /// - **4-bit Values**: Each memory location stores a 4-bit nibble
 pub struct MemoryViewer {
     /// Display mode: true for hexadecimal, false for decimal
@@ -352,8 +446,8 @@ impl MemoryViewer {
 
     /// Render the memory viewer interface
     ///
-    /// Creates a comprehensive memory inspection interface with bank selection,
-    /// display options, and a scrollable memory contents grid.
+    /// Creates a comprehensive memory inspection interface with display options
+    /// and a scrollable grid of registers for each RAM chip.
     ///
     /// # Arguments
     /// * `ui` - egui UI context for rendering
@@ -363,13 +457,14 @@ impl MemoryViewer {
     /// ```text
     /// ┌─────────────────────────────────────────────────┐
     /// │ Memory Viewer                          [─] [□] │
-    /// │ Bank: [0] □ Hex View                           │
+    /// │ □ Hex View                                      │
     /// ├─────────────────────────────────────────────────┤
-    /// │ Address B0 B1 B2 B3                             │
-    /// │ [00]    [12] [34] [56] [78]                    │
-    /// │ [01]    [9A] [BC] [DE] [F0]                    │
-    /// │ [02]    [11] [22] [33] [44]                    │
-    /// │ [03]    [55] [66] [77] [88]                    │
+    /// │ RAM_4002 (chip 0)                               │
+    /// │ Reg 0 1 2 ... F  S0 S1 S2 S3                    │
+    /// │ R0  1 2 3 ... 0  0  0  0  0                     │
+    /// │ R1  0 0 0 ... 0  4  0  0  0                     │
+    /// │ R2  0 0 0 ... 0  0  0  0  0                     │
+    /// │ R3  0 0 0 ... 0  0  0  0  0                     │
     /// └─────────────────────────────────────────────────┘
     /// ```
     pub fn render(&self, ui: &mut egui::Ui, state: &GuiState) {
@@ -377,46 +472,48 @@ impl MemoryViewer {
             // Section header
             ui.heading("Memory Viewer");
 
-            // Control bar with bank selection and display options
+            // Control bar with display options
             ui.horizontal(|ui| {
-                ui.label("Bank:");
-                // Bank selector (0-3 for Intel 4002)
-                ui.add(
-                    egui::DragValue::new(&mut state.memory_state.selected_bank.clone())
-                        .clamp_range(0..=3),
-                );
                 ui.checkbox(&mut self.show_hex.clone(), "Hex View");
             });
 
             ui.separator();
 
-            // Memory contents in scrollable area
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                egui::Grid::new("memory_grid").striped(true).show(ui, |ui| {
-                    // Header row with bank labels
-                    ui.label("Address");
-                    for i in 0..4 {
-                        ui.label(format!("B{}", i));
-                    }
-                    ui.end_row();
+            if state.memory_state.ram_chips.is_empty() {
+                ui.label("No Intel 4002 RAM in this system");
+            }
 
-                    // Memory contents rows
-                    for addr in 0..4 {
-                        // Address column
-                        ui.label(format!("{:02X}", addr));
-
-                        // Bank data columns
-                        for bank in 0..4 {
-                            let value = state.memory_state.ram_contents[bank][addr];
-                            if self.show_hex {
-                                ui.label(format!("{:02X}", value));
-                            } else {
-                                ui.label(format!("{}", value));
+            // One grid per RAM chip in a scrollable area
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for chip in &state.memory_state.ram_chips {
+                    ui.label(format!("{} (chip {})", chip.name, chip.chip_id));
+                    egui::Grid::new(format!("memory_grid_{}", chip.name))
+                        .striped(true)
+                        .show(ui, |ui| {
+                            // Header row with main and status character labels
+                            ui.label("Reg");
+                            for character in 0..16 {
+                                ui.label(format!("{:X}", character));
                             }
-                        }
-                        ui.end_row();
-                    }
-                });
+                            for status in 0..4 {
+                                ui.label(format!("S{}", status));
+                            }
+                            ui.end_row();
+
+                            // One row per register: 16 main characters, then 4 status characters
+                            for (index, register) in chip.registers.iter().enumerate() {
+                                ui.label(format!("R{}", index));
+                                for value in register.main.iter().chain(register.status.iter()) {
+                                    if self.show_hex {
+                                        ui.label(format!("{:X}", value));
+                                    } else {
+                                        ui.label(format!("{}", value));
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                }
             });
         });
 
@@ -493,7 +590,10 @@ impl RegisterViewer {
     /// │ Carry Flag     │ 1           │ Set              │
     /// │ Program Counter│ 123         │ 291              │
     /// │ Index R0       │ 2A          │ 42               │
-    /// │ Stack Pointer  │ 08          │ 8                │
+    /// │ Stack Pointer  │ 01          │ 1                │
+    /// │ Stack Level 0  │ 032         │ 50               │
+    /// │ Stack Level 1  │ 022         │ 34               │
+    /// │ Stack Level 2  │ 012         │ 18               │
     /// └─────────────────────────────────────────────────┘
     /// ```
     pub fn render(&self, ui: &mut egui::Ui, state: &GuiState) {
@@ -564,6 +664,14 @@ impl RegisterViewer {
                     ui.label(format!("{:02X}", state.register_state.stack_pointer));
                     ui.label(format!("{}", state.register_state.stack_pointer));
                     ui.end_row();
+
+                    // Return address stack, newest entry first
+                    for (level, address) in state.register_state.stack.iter().enumerate() {
+                        ui.label(format!("Stack Level {}", level));
+                        ui.label(format!("{:03X}", address));
+                        ui.label(format!("{}", address));
+                        ui.end_row();
+                    }
                 });
         });
 
@@ -571,6 +679,74 @@ impl RegisterViewer {
     }
 }
 
+/// Disassembly viewer component showing the instructions at the program counter
+///
+/// Decodes program memory from the current PC on, so the first line is the
+/// instruction the CPU executes next. Two-byte instructions show both bytes.
+///
+/// ## Features
+///
+/// - **Current Instruction**: Highlighted first line at the program counter
+/// - **Symbols**: Labels and jump targets named from a loaded symbol file
+/// - **Annotations**: Jump conditions and page effects in the comment column
+#[derive(Default)]
+pub struct DisassemblyViewer;
+
+impl DisassemblyViewer {
+    /// Create a new disassembly viewer
+    ///
+    /// # Returns
+    /// A new `DisassemblyViewer` instance
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Render the disassembly viewer interface
+    ///
+    /// # Arguments
+    /// * `ui` - egui UI context for rendering
+    /// * `state` - Immutable reference to GUI state containing the disassembly
+    ///
+    /// # Layout Structure
+    /// ```text
+    /// ┌─────────────────────────────────────────────────┐
+    /// │ Disassembly                                     │
+    /// │ LOOP:                                           │
+    /// │ ▶ 008: 20 00  FIM P0, 0x00                      │
+    /// │   00A: 74 08  ISZ R4, LOOP  ; R4 += 1, ...      │
+    /// └─────────────────────────────────────────────────┘
+    /// ```
+    pub fn render(&self, ui: &mut egui::Ui, state: &GuiState) {
+        ui.vertical(|ui| {
+            ui.heading("Disassembly");
+
+            if state.disassembly.is_empty() {
+                ui.label("No MCS-4 program to disassemble");
+            }
+
+            for (index, instruction) in state.disassembly.iter().enumerate() {
+                if let Some(label) = state
+                    .symbols
+                    .as_ref()
+                    .and_then(|symbols| symbols.name_at(instruction.address))
+                {
+                    ui.monospace(format!("{}:", label));
+                }
+                if index == 0 {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        egui::RichText::new(format!("▶ {}", instruction)).monospace(),
+                    );
+                } else {
+                    ui.monospace(format!("  {}", instruction));
+                }
+            }
+        });
+
+        ui.separator();
+    }
+}
+
 /// ROM loader component for file dialog integration and program management
 ///
 /// The ROM loader handles program file selection, loading, and management.
@@ -6,6 +6,7 @@
 pub mod components;
 pub mod state;
 
+use crate::disasm::SymbolTable;
 use crate::system_config::ConfigurableSystem;
 use eframe::egui;
 use std::sync::{Arc, Mutex};
@@ -90,6 +91,14 @@ impl GuiApp {
         self.gui_state.system_loaded = true;
     }
 
+    /// Set the symbol table used by the disassembly view
+    ///
+    /// # Arguments
+    /// * `symbols` - Symbol table naming program addresses, or None to show plain addresses
+    pub fn set_symbols(&mut self, symbols: Option<SymbolTable>) {
+        self.gui_state.symbols = symbols;
+    }
+
     /// Get current system reference if available
     ///
     /// Returns a cloned Arc reference to the system for thread-safe access.
@@ -148,6 +157,7 @@ impl GuiApp {
     /// - Control panel for system management
     /// - Memory viewer for RAM inspection
     /// - Register viewer for CPU state
+    /// - Disassembly around the program counter
     /// - Status bar for system health and errors
     fn render_gui(&mut self, ctx: &egui::Context) {
         egui::CentralPanel::default().show(ctx, |ui| {
@@ -157,10 +167,13 @@ impl GuiApp {
             ui.separator();
 
             // Main GUI sections - organized for optimal workflow
+            let system = self.get_system();
             self.components
-                .render_control_panel(ui, &mut self.gui_state);
+                .render_control_panel(ui, &mut self.gui_state, system.as_ref());
             self.components.render_memory_viewer(ui, &self.gui_state);
             self.components.render_register_viewer(ui, &self.gui_state);
+            self.components
+                .render_disassembly_viewer(ui, &self.gui_state);
             self.components.render_status_bar(ui, &self.gui_state);
         });
     }
@@ -213,6 +226,24 @@ impl GuiApp {
 /// - **Linux**: Requires X11 or Wayland display server
 /// - **macOS**: Requires macOS 10.15 or later
 pub fn run_gui(system: Option<Arc<Mutex<ConfigurableSystem>>>) -> eframe::Result<()> {
+    run_gui_with_symbols(system, None)
+}
+
+/// Run the GUI application with a symbol table for the disassembly view
+///
+/// Behaves like [`run_gui`]; the symbols name jump targets and labels in the
+/// disassembly shown around the program counter.
+///
+/// # Arguments
+/// * `system` - Optional system to load into the GUI
+/// * `symbols` - Optional symbol table, e.g. loaded from an assembler `.sym` file
+///
+/// # Returns
+/// `eframe::Result<()>` - Success or failure of GUI initialization and execution
+pub fn run_gui_with_symbols(
+    system: Option<Arc<Mutex<ConfigurableSystem>>>,
+    symbols: Option<SymbolTable>,
+) -> eframe::Result<()> {
     // Configure native window options
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -233,6 +264,7 @@ pub fn run_gui(system: Option<Arc<Mutex<ConfigurableSystem>>>) -> eframe::Result
             if let Some(sys) = system {
                 app.set_system(sys);
             }
+            app.set_symbols(symbols);
 
             Box::new(app)
         }),
@@ -4,6 +4,7 @@
 //! component states, and user interface state. It provides thread-safe access
 //! to emulator state for real-time GUI updates.
 
+use crate::disasm::{self, DisassembledInstruction, SymbolTable};
 use crate::system_config::ConfigurableSystem;
 use std::sync::{Arc, Mutex};
 
@@ -26,8 +27,15 @@ pub struct GuiState {
     pub register_state: RegisterState,
     /// Last error message
     pub last_error: Option<String>,
+    /// Instructions from the program counter on, the current one first
+    pub disassembly: Vec<DisassembledInstruction>,
+    /// Symbols naming addresses in the disassembly
+    pub symbols: Option<SymbolTable>,
 }
 
+/// Number of instructions shown in the disassembly view
+pub const DISASSEMBLY_LINES: usize = 8;
+
 /// System information for display
 #[derive(Debug, Clone)]
 pub struct SystemInfo {
@@ -51,7 +59,7 @@ pub struct ComponentStates {
 /// Memory state for display
 #[derive(Debug, Clone)]
 pub struct MemoryState {
-    pub ram_contents: Vec<[u8; 4]>, // 4 banks of RAM data
+    pub ram_chips: Vec<crate::system_config::RamChipContents>, // Registers of every 4002
     pub selected_bank: usize,
     pub selected_address: usize,
 }
@@ -64,6 +72,7 @@ pub struct RegisterState {
     pub program_counter: u16,
     pub index_registers: [u8; 16], // 16 index registers (R0-R15)
     pub stack_pointer: u8,
+    pub stack: Vec<u16>, // Return addresses, most recently pushed first
 }
 
 impl GuiState {
@@ -81,7 +90,7 @@ impl GuiState {
                 clock_running: false,
             },
             memory_state: MemoryState {
-                ram_contents: vec![[0; 4]; 4], // Initialize 4 banks with 4 bytes each
+                ram_chips: Vec::new(),
                 selected_bank: 0,
                 selected_address: 0,
             },
@@ -91,8 +100,11 @@ impl GuiState {
                 program_counter: 0,
                 index_registers: [0; 16],
                 stack_pointer: 0,
+                stack: vec![0; 3],
             },
             last_error: None,
+            disassembly: Vec::new(),
+            symbols: None,
         }
     }
 
@@ -110,6 +122,22 @@ impl GuiState {
             // Update component states
             self.update_component_states(&system_guard);
 
+            // Update CPU registers if the system has a 4004
+            if let Ok(registers) = system_guard.get_cpu_registers() {
+                self.register_state = registers.into();
+            }
+
+            // Decode the instructions at the program counter
+            self.disassembly = disasm::disassemble_from_pc(
+                &system_guard,
+                DISASSEMBLY_LINES,
+                self.symbols.as_ref(),
+            )
+            .unwrap_or_default();
+
+            // Update the main and status characters of every 4002
+            self.memory_state.ram_chips = system_guard.get_ram_contents();
+
             // Update cycle count (simulate for now)
             if self.system_running {
                 self.cycle_count += 1;
@@ -155,6 +183,19 @@ impl GuiState {
     }
 }
 
+impl From<crate::system_config::CpuRegisters> for RegisterState {
+    fn from(registers: crate::system_config::CpuRegisters) -> Self {
+        Self {
+            accumulator: registers.accumulator,
+            carry_flag: registers.carry,
+            program_counter: registers.program_counter,
+            index_registers: registers.index_registers,
+            stack_pointer: registers.stack_pointer,
+            stack: registers.stack,
+        }
+    }
+}
+
 impl From<crate::system_config::SystemInfo> for SystemInfo {
     fn from(info: crate::system_config::SystemInfo) -> Self {
         Self {
@@ -7,14 +7,23 @@
 //! - Cycle-accurate hardware simulation of Intel 4004/4001/4002/4003 components
 //! - Comprehensive testing framework with multiple testing strategies
 //! - Extensible component system with trait-based architecture
+//! - MCS-4 assembler producing ROM images the system loads directly, and a matching disassembler
+//! - Interactive debugger with breakpoints, RAM watchpoints and single-step
+//! - GDB remote serial protocol stub for attaching external debuggers over TCP
+//! - Serializable machine snapshots for saving and restoring complete system state
 //! - Professional project organization with clean separation of concerns
 
+pub mod asm;
 pub mod component;
 pub mod components;
 pub mod connection;
 pub mod console;
+pub mod debugger;
+pub mod disasm;
+pub mod gdb;
 pub mod gui;
 pub mod pin;
+pub mod sim_time;
 pub mod system_config;
 pub mod types;
 
@@ -62,17 +62,51 @@
 //! cargo run -- --gui --system basic --file programs/myprogram.bin
 //! ```
 //!
+//! ### Assembler
+//! ```bash
+//! # Assemble MCS-4 source into a ROM image (programs/myprogram.bin) and symbol file
+//! cargo run -- --assemble programs/myprogram.asm
+//!
+//! # Run assembly source directly
+//! cargo run -- --system basic --file programs/myprogram.asm
+//! ```
+//!
+//! ### Disassembler
+//! ```bash
+//! # Print an annotated listing, naming addresses from programs/myprogram.sym if it exists
+//! cargo run -- --disassemble programs/myprogram.bin
+//!
+//! # Use an explicit symbol file and write the listing to a file
+//! cargo run -- --disassemble rom.bin --symbols rom.sym -o rom.asm
+//! ```
+//!
+//! ### GDB Remote Debugging
+//! ```bash
+//! # Wait for a GDB front-end on localhost:1234 instead of running freely
+//! cargo run -- --system basic --gdb 1234
+//! ```
+//!
+//! ### Machine Snapshots
+//! ```bash
+//! # Resume from a state saved with the console `save` command or the GUI Save State button
+//! cargo run -- --console --system basic --load-state booted.json
+//! ```
+//!
 //! ### Help and Information
 //! ```bash
 //! # Show comprehensive help
 //! cargo run -- --help
 //! ```
 
+use rusty_emu::asm;
 use rusty_emu::console::{run_console, ConsoleConfig};
-use rusty_emu::gui::run_gui;
+use rusty_emu::disasm::{self, SymbolTable};
+use rusty_emu::gdb::GdbStub;
+use rusty_emu::gui::run_gui_with_symbols;
 use rusty_emu::system_config::{ConfigurableSystem, SystemFactory};
 use std::env;
 use std::fs;
+use std::path::Path;
 use std::process;
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -106,6 +140,12 @@ fn main() {
     let mut program_file = "programs/fibonacci.bin".to_string();
     let mut use_console = false;
     let mut use_gui = false;
+    let mut assemble_source: Option<String> = None;
+    let mut output_file: Option<String> = None;
+    let mut disassemble_file: Option<String> = None;
+    let mut symbol_file: Option<String> = None;
+    let mut gdb_port: Option<u16> = None;
+    let mut state_file: Option<String> = None;
 
     // Command-line argument parsing with validation
     let mut i = 1;
@@ -133,6 +173,73 @@ fn main() {
                     process::exit(1);
                 }
             }
+            // Assembler mode: translate a source file instead of running a system
+            "-a" | "--assemble" => {
+                if i + 1 < args.len() {
+                    assemble_source = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: --assemble requires a value");
+                    print_usage(&args[0]);
+                    process::exit(1);
+                }
+            }
+            // Disassembler mode: print a listing of a ROM image instead of running a system
+            "-d" | "--disassemble" => {
+                if i + 1 < args.len() {
+                    disassemble_file = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: --disassemble requires a value");
+                    print_usage(&args[0]);
+                    process::exit(1);
+                }
+            }
+            // Symbol file for the disassembler, console and GUI
+            "--symbols" => {
+                if i + 1 < args.len() {
+                    symbol_file = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: --symbols requires a value");
+                    print_usage(&args[0]);
+                    process::exit(1);
+                }
+            }
+            // Assembler and disassembler output file
+            "-o" | "--output" => {
+                if i + 1 < args.len() {
+                    output_file = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: --output requires a value");
+                    print_usage(&args[0]);
+                    process::exit(1);
+                }
+            }
+            // GDB remote debugging port on localhost
+            "--gdb" => match args.get(i + 1).map(|port| port.parse::<u16>()) {
+                Some(Ok(port)) => {
+                    gdb_port = Some(port);
+                    i += 2;
+                }
+                _ => {
+                    eprintln!("Error: --gdb requires a port number");
+                    print_usage(&args[0]);
+                    process::exit(1);
+                }
+            },
+            // Machine state to resume from instead of booting the program
+            "--load-state" => {
+                if i + 1 < args.len() {
+                    state_file = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: --load-state requires a value");
+                    print_usage(&args[0]);
+                    process::exit(1);
+                }
+            }
             // Interactive console interface mode
             "-c" | "--console" => {
                 use_console = true;
@@ -157,6 +264,35 @@ fn main() {
         }
     }
 
+    if let Some(source) = assemble_source {
+        match assemble_to_file(&source, output_file.as_deref()) {
+            Ok(()) => return,
+            Err(e) => {
+                eprintln!("Assembly failed: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    if let Some(binary) = disassemble_file {
+        match disassemble_to_output(&binary, symbol_file.as_deref(), output_file.as_deref()) {
+            Ok(()) => return,
+            Err(e) => {
+                eprintln!("Disassembly failed: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    // Symbols name addresses in the console and GUI disassembly views
+    let symbols = match symbol_file.as_deref().map(SymbolTable::load).transpose() {
+        Ok(symbols) => symbols,
+        Err(e) => {
+            eprintln!("Failed to load symbols: {}", e);
+            process::exit(1);
+        }
+    };
+
     println!("Intel MCS-4 Emulator");
     println!("===================");
     println!("System: {}", system_type);
@@ -183,7 +319,7 @@ fn main() {
     };
 
     // Create and configure the system
-    let system = match create_system(&system_type, &program_data) {
+    let mut system = match create_system(&system_type, &program_data) {
         Ok(sys) => {
             println!("DEBUG: System created successfully");
             sys
@@ -194,12 +330,39 @@ fn main() {
         }
     };
 
+    // A restored system is prepared for lockstep execution, so it is never handed to
+    // `run`, whose start-up would reset the CPU
+    if let Some(path) = &state_file {
+        if let Err(e) = system.load_state(path) {
+            eprintln!("Failed to load state: {}", e);
+            process::exit(1);
+        }
+        println!(
+            "Restored machine state from {} at {} ns",
+            path,
+            system.get_simulated_time_ns()
+        );
+    }
+    let state_loaded = state_file.is_some();
+
     // Interface mode selection and launch
-    if use_console {
+    if let Some(port) = gdb_port {
+        // Serve one GDB front-end; the stub steps the system itself
+        let address = format!("127.0.0.1:{}", port);
+        let result =
+            GdbStub::new(Arc::new(Mutex::new(system))).and_then(|mut stub| stub.listen(&address));
+        if let Err(e) = result {
+            eprintln!("GDB stub error: {}", e);
+            process::exit(1);
+        }
+    } else if use_console {
         // Launch interactive console interface
         // The console provides a terminal-based UI with real-time system monitoring
         let system_arc = Arc::new(Mutex::new(system));
-        let console_config = ConsoleConfig::default();
+        let console_config = ConsoleConfig {
+            symbol_file: symbol_file.clone(),
+            ..ConsoleConfig::default()
+        };
 
         println!("Starting interactive console interface...");
         println!(
@@ -208,13 +371,16 @@ fn main() {
 
         // Start the emulator system in a separate thread
         // This allows the console interface to run while emulation continues
-        let system_runner = system_arc.clone();
-        thread::spawn(move || {
-            if let Ok(mut system) = system_runner.lock() {
-                println!("DEBUG: Starting system for console mode");
-                system.run();
-            }
-        });
+        // A restored system is driven by the debugger instead
+        if !state_loaded {
+            let system_runner = system_arc.clone();
+            thread::spawn(move || {
+                if let Ok(mut system) = system_runner.lock() {
+                    println!("DEBUG: Starting system for console mode");
+                    system.run();
+                }
+            });
+        }
 
         // Launch console interface (blocks until console is closed)
         if let Err(e) = run_console(system_arc, console_config) {
@@ -231,18 +397,21 @@ fn main() {
 
         // Start the emulator system in a separate thread
         // This allows the GUI to remain responsive while emulation runs
-        let system_runner = system_arc.clone();
-        thread::spawn(move || {
-            if let Ok(mut system) = system_runner.lock() {
-                println!("DEBUG: Starting system for GUI mode");
-                system.run();
-            }
-        });
+        // A restored system stays paused so its state can be inspected
+        if !state_loaded {
+            let system_runner = system_arc.clone();
+            thread::spawn(move || {
+                if let Ok(mut system) = system_runner.lock() {
+                    println!("DEBUG: Starting system for GUI mode");
+                    system.run();
+                }
+            });
+        }
 
         // Launch GUI application (blocks until GUI window is closed)
         // The GUI will handle all user interactions and system monitoring
         println!("DEBUG: About to call run_gui()...");
-        match run_gui(Some(system_arc)) {
+        match run_gui_with_symbols(Some(system_arc), symbols) {
             Ok(_) => {
                 println!("DEBUG: GUI completed successfully");
             }
@@ -274,7 +443,11 @@ fn main() {
         println!();
 
         // Run the system
-        run_system_demo(system);
+        if state_loaded {
+            run_restored_system(system);
+        } else {
+            run_system_demo(system);
+        }
     }
 }
 
@@ -287,6 +460,17 @@ fn print_usage(program_name: &str) {
     println!("  -s, --system <SYSTEM>    System type to run (default: basic)");
     println!("                           Available: basic, max, fig1, or JSON config file");
     println!("  -f, --file <FILE>        Program binary file to load (default: fibonacci.bin)");
+    println!("                           A .asm file is assembled before loading");
+    println!(
+        "  -a, --assemble <SOURCE>  Assemble MCS-4 source to a binary and symbol file, then exit"
+    );
+    println!("  -d, --disassemble <BIN>  Print an annotated listing of a ROM image, then exit");
+    println!(
+        "      --symbols <FILE>     Symbol file naming addresses in disassembly (default: BIN.sym)"
+    );
+    println!("  -o, --output <FILE>      File written by --assemble (default: SOURCE.bin) or --disassemble");
+    println!("      --gdb <PORT>         Wait for a GDB front-end on localhost:PORT");
+    println!("      --load-state <FILE>  Resume from a machine state saved by the console or GUI");
     println!("  -c, --console           Enable interactive console interface");
     println!("  -g, --gui               Enable graphical user interface");
     println!("  -h, --help              Show this help message");
@@ -301,6 +485,7 @@ fn print_usage(program_name: &str) {
     println!("  Default (no flags)      - Traditional console with system monitoring");
     println!("  -c, --console           - Interactive terminal UI with real-time display");
     println!("  -g, --gui               - Graphical desktop application");
+    println!("  --gdb <PORT>            - GDB remote serial protocol stub (4004/4040/6502)");
     println!();
     println!("Console Interface (-c/--console):");
     println!("  Provides an interactive terminal UI with:");
@@ -308,6 +493,8 @@ fn print_usage(program_name: &str) {
     println!("  • Live RAM and register state display");
     println!("  • Non-blocking operation that doesn't interfere with emulation");
     println!("  • Clean tabular display of system components");
+    println!("  • Debugger: break, watch ram, step, next, finish, continue, set, x (F1 for help)");
+    println!("  • Machine snapshots: save FILE, load FILE");
     println!();
     println!("GUI Interface (-g/--gui):");
     println!("  Provides a graphical desktop application with:");
@@ -343,6 +530,22 @@ fn print_usage(program_name: &str) {
         "  {} --gui --system basic --file prog.bin # GUI with custom program",
         program_name
     );
+    println!(
+        "  {} --assemble prog.asm -o prog.bin    # Assemble a program",
+        program_name
+    );
+    println!(
+        "  {} --disassemble prog.bin             # Disassemble a ROM image",
+        program_name
+    );
+    println!(
+        "  {} --system basic --gdb 1234          # Debug with gdb -ex 'target remote :1234'",
+        program_name
+    );
+    println!(
+        "  {} --console --load-state boot.json   # Resume a saved machine state",
+        program_name
+    );
     println!();
     println!("For more information about the GUI interface, see:");
     println!("  • GUI Features: Real-time monitoring, interactive controls");
@@ -350,8 +553,81 @@ fn print_usage(program_name: &str) {
     println!("  • Integration: Thread-safe operation with emulator system");
 }
 
+/// Assemble a source file and write the ROM image and symbol file
+/// The symbol file is written next to the binary with a `.sym` extension.
+/// Parameters: source - Assembly source path, output - Binary path, None for SOURCE.bin
+/// Returns: Ok(()) on success, Err(String) on assembly or file errors
+fn assemble_to_file(source: &str, output: Option<&str>) -> Result<(), String> {
+    let assembly = asm::assemble_file(source)?;
+    for warning in &assembly.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
+    let binary_path = output.map_or_else(
+        || Path::new(source).with_extension("bin"),
+        |path| Path::new(path).to_path_buf(),
+    );
+    let symbol_path = binary_path.with_extension("sym");
+    fs::write(&binary_path, &assembly.binary)
+        .map_err(|e| format!("Failed to write {}: {}", binary_path.display(), e))?;
+    fs::write(&symbol_path, assembly.symbol_file())
+        .map_err(|e| format!("Failed to write {}: {}", symbol_path.display(), e))?;
+
+    println!(
+        "Assembled {} bytes to {} ({} symbols in {})",
+        assembly.binary.len(),
+        binary_path.display(),
+        assembly.symbols.len(),
+        symbol_path.display()
+    );
+    Ok(())
+}
+
+/// Disassemble a ROM image into an annotated listing
+/// Without an explicit symbol file, `BIN.sym` next to the image is used if it exists.
+/// Parameters: binary - ROM image path, symbols - Symbol file path,
+///             output - Listing path, None to print to stdout
+/// Returns: Ok(()) on success, Err(String) on file errors
+fn disassemble_to_output(
+    binary: &str,
+    symbols: Option<&str>,
+    output: Option<&str>,
+) -> Result<(), String> {
+    let image = fs::read(binary).map_err(|e| format!("Failed to read {}: {}", binary, e))?;
+    let default_symbols = Path::new(binary).with_extension("sym");
+    let symbols = match symbols {
+        Some(path) => Some(SymbolTable::load(path)?),
+        None if default_symbols.exists() => {
+            Some(SymbolTable::load(&default_symbols.to_string_lossy())?)
+        }
+        None => None,
+    };
+
+    let listing = disasm::format_listing(&image, 0, symbols.as_ref());
+    match output {
+        Some(path) => {
+            fs::write(path, &listing).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+            println!("Disassembled {} bytes to {}", image.len(), path);
+        }
+        None => print!("{}", listing),
+    }
+    Ok(())
+}
+
 fn load_program_data(filename: &str) -> Result<Vec<u8>, String> {
     println!("DEBUG: Attempting to load program from: {}", filename);
+    if filename.ends_with(".asm") {
+        let assembly = asm::assemble_file(filename)?;
+        for warning in &assembly.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        println!(
+            "DEBUG: Assembled {} bytes from {}",
+            assembly.binary.len(),
+            filename
+        );
+        return Ok(assembly.binary);
+    }
     match fs::read(filename) {
         Ok(data) => {
             println!(
@@ -517,6 +793,40 @@ fn run_system_demo(system: ConfigurableSystem) {
     println!("\nExecution completed in {:?}", duration);
 }
 
+/// Continue a restored system in lockstep mode for the demo's 10 seconds
+/// Threaded execution would restart and so reset the components, which lockstep avoids.
+/// Parameters: system - System restored by `--load-state`
+fn run_restored_system(mut system: ConfigurableSystem) {
+    let info = system.get_system_info();
+    println!("System: {} - {}", info.name, info.description);
+
+    let start_time = Instant::now();
+    let timeout = Duration::from_secs(10);
+    while start_time.elapsed() < timeout {
+        if let Err(e) = system.run_lockstep(10_000) {
+            eprintln!("Lockstep execution failed: {}", e);
+            break;
+        }
+    }
+
+    println!(
+        "\nSimulated {} clock edges ({} ns)",
+        system.get_edge_count(),
+        system.get_simulated_time_ns()
+    );
+    if let Ok(registers) = system.get_cpu_registers() {
+        println!(
+            "PC: 0x{:03X}  ACC: 0x{:X}  CY: {}  Instructions: {}",
+            registers.program_counter,
+            registers.accumulator,
+            registers.carry as u8,
+            registers.instruction_count
+        );
+    }
+    system.stop_lockstep();
+    println!("Execution completed in {:?}", start_time.elapsed());
+}
+
 /// Monitor and display system state periodically
 /// This function runs in a separate thread and displays CPU registers, clock signals,
 /// data/address bus states, and RAM contents at regular intervals
@@ -1,9 +1,10 @@
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum PinValue {
     Low,
     High,
@@ -44,7 +45,7 @@ impl PinValue {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DriveStrength {
     HighImpedance = 0,
     Weak = 1,
@@ -132,6 +133,14 @@ impl Pin {
         &self.drivers
     }
 
+    /// Replace every driver of the pin, e.g. when restoring a machine snapshot
+    /// Parameters: drivers - Driver name to value and strength, as from `get_drivers`
+    pub fn restore_drivers(&mut self, drivers: HashMap<String, (PinValue, DriveStrength)>) {
+        self.drivers = drivers;
+        self.last_update = Instant::now();
+        self.recalculate_value();
+    }
+
     pub fn is_settled(&self) -> bool {
         self.last_update.elapsed() >= self.settlement_time
     }
@@ -162,14 +171,19 @@ impl Pin {
         &self.connected_pins
     }
 
+    /// Copy this pin's drivers to every connected pin
+    /// Propagation recurses through the targets' own connections, so a bidirectional
+    /// net (e.g. a shared data bus) would lead back to a pin that is already locked
+    /// further up the chain. Such pins are skipped instead of waited on: they are the
+    /// origin of the drivers being propagated and already hold them.
     pub fn propagate(&self) {
         for connected_pin in &self.connected_pins {
-            if let Ok(mut pin) = connected_pin.lock() {
+            if let Ok(mut pin) = connected_pin.try_lock() {
                 // Copy our drivers to the connected pin (simulate electrical connection)
-                let mut new_drivers = self.drivers.clone();
-
-                // Merge with existing drivers on the connected pin
-                for (driver, value) in &pin.drivers {
+                // The connected pin keeps its own drivers, but entries that originate
+                // from this pin are refreshed so value changes actually reach the target
+                let mut new_drivers = pin.drivers.clone();
+                for (driver, value) in &self.drivers {
                     new_drivers.insert(driver.clone(), *value);
                 }
 
@@ -186,17 +200,14 @@ impl Pin {
             return;
         }
 
-        // Find the strongest driver strength manually
+        // Find the strongest driver strength manually. Tri-stated drivers do not take
+        // part, so a weak pull still decides the level of an otherwise floating line
         let mut max_strength = DriveStrength::HighImpedance;
-        for (_, strength) in self.drivers.values() {
-            if *strength > max_strength {
+        for (value, strength) in self.drivers.values() {
+            if *value != PinValue::HighZ && *strength > max_strength {
                 max_strength = *strength;
             }
         }
-        if max_strength == DriveStrength::HighImpedance {
-            self.settled_value = PinValue::HighZ;
-            return;
-        }
 
         // Get all drivers with the strongest strength
         let strong_drivers: Vec<PinValue> = self
@@ -329,6 +340,22 @@ mod tests {
         assert_eq!(pin.read(), PinValue::Low);
     }
 
+    #[test]
+    fn test_pin_weak_pull_with_tri_stated_driver() {
+        let mut pin = Pin::new("TEST".to_string());
+
+        pin.set_driver_with_strength(
+            Some("pull_up".to_string()),
+            PinValue::High,
+            DriveStrength::Weak,
+        );
+        pin.set_driver(Some("chip".to_string()), PinValue::HighZ);
+        assert_eq!(pin.read(), PinValue::High);
+
+        pin.set_driver(Some("chip".to_string()), PinValue::Low);
+        assert_eq!(pin.read(), PinValue::Low);
+    }
+
     #[test]
     fn test_pin_connection() {
         let pin1 = Arc::new(Mutex::new(Pin::new("PIN1".to_string())));
@@ -354,6 +381,29 @@ mod tests {
         assert_eq!(p2.read(), PinValue::High);
     }
 
+    #[test]
+    fn test_pin_bidirectional_connection() {
+        let cpu = Arc::new(Mutex::new(Pin::new("CPU_D0".to_string())));
+        let rom = Arc::new(Mutex::new(Pin::new("ROM_D0".to_string())));
+        cpu.lock().unwrap().connect_to(rom.clone());
+        rom.lock().unwrap().connect_to(cpu.clone());
+
+        // Propagation leads back to the driving pin and must not deadlock
+        cpu.lock()
+            .unwrap()
+            .set_driver(Some("cpu".to_string()), PinValue::High);
+        assert_eq!(rom.lock().unwrap().read(), PinValue::High);
+
+        // The other side can drive the net once the first driver releases it
+        cpu.lock()
+            .unwrap()
+            .set_driver(Some("cpu".to_string()), PinValue::HighZ);
+        rom.lock()
+            .unwrap()
+            .set_driver(Some("rom".to_string()), PinValue::Low);
+        assert_eq!(cpu.lock().unwrap().read(), PinValue::Low);
+    }
+
     #[test]
     fn test_pin_conflict_resolution() {
         let mut pin = Pin::new("TEST".to_string());
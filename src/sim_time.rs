@@ -0,0 +1,131 @@
+//! # Simulation Time Base
+//!
+//! Shared simulated clock used by all components to measure access latencies.
+//!
+//! Simulated time only advances when the system clock produces an edge, so latency
+//! windows (for example the 500ns ROM access time) cover the same number of clock
+//! phases whether the emulator runs at 750 kHz, 100x faster, or is single-stepped
+//! in a debugger.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A point in simulated time, measured in nanoseconds since the time base started
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+pub struct SimTime(u64);
+
+impl SimTime {
+    /// Create a simulated timestamp from a nanosecond count
+    /// Parameters: nanos - Nanoseconds since the start of simulation
+    /// Returns: New SimTime instance
+    pub fn from_nanos(nanos: u64) -> Self {
+        SimTime(nanos)
+    }
+
+    /// Get the timestamp as nanoseconds since the start of simulation
+    /// Returns: Nanosecond count
+    pub fn as_nanos(&self) -> u64 {
+        self.0
+    }
+
+    /// Get the simulated time elapsed between an earlier timestamp and this one
+    /// Parameters: earlier - Earlier timestamp
+    /// Returns: Elapsed duration, or zero if `earlier` is later than `self`
+    pub fn saturating_duration_since(&self, earlier: SimTime) -> Duration {
+        Duration::from_nanos(self.0.saturating_sub(earlier.0))
+    }
+}
+
+/// Shared, thread-safe simulated clock
+/// Cloning a TimeBase yields a handle to the same underlying time, so one instance
+/// can be distributed to every component of a system
+#[derive(Debug, Clone, Default)]
+pub struct TimeBase {
+    now_ns: Arc<AtomicU64>,
+}
+
+impl TimeBase {
+    /// Create a new time base starting at zero
+    /// Returns: New TimeBase instance
+    pub fn new() -> Self {
+        TimeBase {
+            now_ns: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Get the current simulated time
+    /// Returns: Current SimTime
+    pub fn now(&self) -> SimTime {
+        SimTime(self.now_ns.load(Ordering::SeqCst))
+    }
+
+    /// Advance simulated time
+    /// Parameters: delta - Amount of simulated time to add
+    /// Returns: Simulated time after advancing
+    pub fn advance(&self, delta: Duration) -> SimTime {
+        let delta_ns = delta.as_nanos() as u64;
+        SimTime(self.now_ns.fetch_add(delta_ns, Ordering::SeqCst) + delta_ns)
+    }
+
+    /// Set simulated time, e.g. when restoring a machine snapshot
+    /// Parameters: time - New current time
+    pub fn set(&self, time: SimTime) {
+        self.now_ns.store(time.0, Ordering::SeqCst);
+    }
+
+    /// Reset simulated time back to zero
+    pub fn reset(&self) {
+        self.now_ns.store(0, Ordering::SeqCst);
+    }
+
+    /// Get the simulated time elapsed since a timestamp
+    /// Parameters: since - Earlier timestamp
+    /// Returns: Elapsed simulated duration
+    pub fn elapsed_since(&self, since: SimTime) -> Duration {
+        self.now().saturating_duration_since(since)
+    }
+
+    /// Check whether two handles refer to the same underlying time
+    /// Parameters: other - Time base to compare against
+    /// Returns: true if both handles share the same clock
+    pub fn is_shared_with(&self, other: &TimeBase) -> bool {
+        Arc::ptr_eq(&self.now_ns, &other.now_ns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_base_advances_only_when_told() {
+        let time_base = TimeBase::new();
+        assert_eq!(time_base.now(), SimTime::from_nanos(0));
+
+        let start = time_base.now();
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(time_base.elapsed_since(start), Duration::ZERO);
+
+        time_base.advance(Duration::from_nanos(667));
+        assert_eq!(time_base.now().as_nanos(), 667);
+        assert_eq!(time_base.elapsed_since(start), Duration::from_nanos(667));
+    }
+
+    #[test]
+    fn test_time_base_handles_are_shared() {
+        let time_base = TimeBase::new();
+        let handle = time_base.clone();
+        handle.advance(Duration::from_nanos(100));
+
+        assert_eq!(time_base.now().as_nanos(), 100);
+        assert!(time_base.is_shared_with(&handle));
+        assert!(!time_base.is_shared_with(&TimeBase::new()));
+
+        time_base.reset();
+        assert_eq!(handle.now().as_nanos(), 0);
+    }
+}
@@ -48,12 +48,28 @@
 //!   }
 //! }
 //! ```
+//!
+//! Connections of type `data_bus` or `bus` are wired in both directions, so a target
+//! driving the bus (e.g. a ROM returning an opcode) is seen by the source as well.
+//! All other connection types only carry signals from the source to its targets.
+//!
+//! A connection may refer to a component entry key instead of an instance name. For
+//! targets this expands to every instance of the entry, e.g. all four RAMs of a bank
+//! sharing one CM-RAM line; a source must resolve to a single instance.
 
 use crate::component::Component;
+use crate::components::cpu::intel_4004::Intel4004;
+use crate::components::cpu::mos_6502::MOS6502;
+use crate::pin::{DriveStrength, PinValue};
+use crate::sim_time::{SimTime, TimeBase};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, Mutex};
 
+/// Clock edges `ConfigurableSystem::step_instruction` waits for one instruction to complete,
+/// enough for a two-cycle instruction with room to spare
+pub const MAX_EDGES_PER_INSTRUCTION: u64 = 256;
+
 /// JSON-based system configuration structures
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemConfig {
@@ -74,6 +90,74 @@ pub enum ComponentConfig {
     Array(ArrayComponentConfig),
 }
 
+impl ComponentConfig {
+    /// Get the registry type name of this component entry
+    /// Returns: Component type string such as "intel_4004"
+    pub fn component_type(&self) -> &str {
+        match self {
+            ComponentConfig::Single(single) => &single.component_type,
+            ComponentConfig::Array(array) => &array.component_type,
+        }
+    }
+
+    /// Expand this entry into the names of the component instances it creates
+    /// Returns: One name for a single component, `count` names for an array
+    pub fn instance_names(&self) -> Vec<String> {
+        match self {
+            ComponentConfig::Single(single) => vec![single.name.clone()],
+            ComponentConfig::Array(array) => match &array.names {
+                Some(names) => names.iter().take(array.count).cloned().collect(),
+                None => (0..array.count)
+                    .map(|i| array.naming_pattern.replace("{:02}", &format!("{:02}", i)))
+                    .collect(),
+            },
+        }
+    }
+
+    /// Get the properties for one instance created by this entry
+    /// Array overrides are keyed by the two-digit instance index and replace the shared
+    /// properties they name
+    /// Parameters: name - Instance name as returned by instance_names()
+    /// Returns: Merged property map for that instance
+    pub fn properties_for(&self, name: &str) -> HashMap<String, serde_json::Value> {
+        match self {
+            ComponentConfig::Single(single) => single.properties.clone(),
+            ComponentConfig::Array(array) => {
+                let mut properties = array.properties.clone();
+                if let (Some(index), Some(overrides)) =
+                    (self.instance_index(name), &array.overrides)
+                {
+                    if let Some(instance_overrides) = overrides.get(&format!("{:02}", index)) {
+                        properties.extend(instance_overrides.clone());
+                    }
+                }
+                properties
+            }
+        }
+    }
+
+    /// Get the position of an instance within an array entry
+    /// Parameters: name - Instance name as returned by instance_names()
+    /// Returns: Index within the array, None for single components or unknown names
+    pub fn instance_index(&self, name: &str) -> Option<usize> {
+        match self {
+            ComponentConfig::Single(_) => None,
+            ComponentConfig::Array(_) => self.instance_names().iter().position(|n| n == name),
+        }
+    }
+
+    /// Get the chip number strapped for one instance created by this entry
+    /// Uses the "chip_id" property if present, otherwise the instance's index in an array
+    /// Parameters: name - Instance name as returned by instance_names()
+    /// Returns: Chip number, None for a single component without "chip_id"
+    pub fn chip_id_for(&self, name: &str) -> Option<u64> {
+        self.properties_for(name)
+            .get("chip_id")
+            .and_then(|v| v.as_u64())
+            .or_else(|| self.instance_index(name).map(|index| index as u64))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SingleComponentConfig {
     pub component_type: String,
@@ -85,7 +169,9 @@ pub struct SingleComponentConfig {
 pub struct ArrayComponentConfig {
     pub component_type: String,
     pub count: usize,
+    #[serde(default)]
     pub naming_pattern: String,
+    pub names: Option<Vec<String>>, // Explicit instance names, used instead of naming_pattern
     pub properties: HashMap<String, serde_json::Value>,
     pub overrides: Option<HashMap<String, HashMap<String, serde_json::Value>>>,
 }
@@ -147,6 +233,53 @@ impl SystemFactory {
             },
         );
 
+        self.component_registry.insert(
+            "intel_4040".to_string(),
+            |config: &ComponentConfig, name: String| {
+                if let ComponentConfig::Single(single) = config {
+                    let clock_speed = single
+                        .properties
+                        .get("clock_speed")
+                        .and_then(|v| v.as_f64())
+                        .unwrap_or(740000.0);
+                    Ok(Box::new(
+                        crate::components::cpu::intel_4040::Intel4040::new(name, clock_speed),
+                    ))
+                } else {
+                    Err("Intel 4040 must be single component".to_string())
+                }
+            },
+        );
+
+        self.component_registry.insert(
+            "mos_6502".to_string(),
+            |config: &ComponentConfig, name: String| {
+                use crate::components::cpu::mos_6502::{Mos6502Variant, MOS6502};
+
+                // Undocumented opcodes are opt-in, plain NMOS parts run them as NOPs
+                let illegal = config
+                    .properties_for(&name)
+                    .get("nmos_illegal")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let variant = if illegal {
+                    Mos6502Variant::Nmos6502Illegal
+                } else {
+                    Mos6502Variant::Nmos6502
+                };
+                Ok(Box::new(MOS6502::new_with_variant(name, variant)))
+            },
+        );
+
+        self.component_registry.insert(
+            "wdc_65c02".to_string(),
+            |_config: &ComponentConfig, name: String| {
+                Ok(Box::new(crate::components::cpu::wdc_65c02::WDC65C02::new(
+                    name,
+                )))
+            },
+        );
+
         self.component_registry.insert(
             "generic_clock".to_string(),
             |config: &ComponentConfig, name: String| {
@@ -188,53 +321,253 @@ impl SystemFactory {
         self.component_registry.insert(
             "intel_4001".to_string(),
             |config: &ComponentConfig, name: String| {
-                if let ComponentConfig::Single(_single) = config {
-                    Ok(Box::new(
-                        crate::components::memory::intel_4001::Intel4001::new(name),
-                    ))
-                } else {
-                    Err("Intel 4001 must be single component".to_string())
+                let mut rom = crate::components::memory::intel_4001::Intel4001::new(name.clone());
+                if let Some(chip_id) = config.chip_id_for(&name) {
+                    let chip_id = u8::try_from(chip_id)
+                        .map_err(|_| format!("{}: chip_id {} out of range", name, chip_id))?;
+                    rom.set_chip_id(chip_id)
+                        .map_err(|e| format!("{}: {}", name, e))?;
+                }
+
+                // Metal-mask options of the I/O lines, line 0 first
+                if let Some(lines) = config.properties_for(&name).get("io_lines") {
+                    let lines = lines
+                        .as_array()
+                        .ok_or_else(|| format!("{}: io_lines must be an array", name))?;
+                    if lines.len() > 4 {
+                        return Err(format!("{}: io_lines has more than 4 entries", name));
+                    }
+                    for (line, value) in lines.iter().enumerate() {
+                        let line_config = Self::parse_io_line_config(value)
+                            .map_err(|e| format!("{}: I/O line {}: {}", name, line, e))?;
+                        rom.set_io_line_config(line, line_config)?;
+                    }
                 }
+                Ok(Box::new(rom))
             },
         );
 
         self.component_registry.insert(
             "intel_4002".to_string(),
             |config: &ComponentConfig, name: String| {
-                if let ComponentConfig::Single(single) = config {
-                    let variant = single.properties.get("variant")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("Type1");
-                    let access_time = single.properties.get("access_time")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(500);
-
-                    let ram_variant = match variant {
-                        "Type2" => crate::components::memory::intel_4002::RamVariant::Type2,
-                        _ => crate::components::memory::intel_4002::RamVariant::Type1,
-                    };
-
-                    Ok(Box::new(crate::components::memory::intel_4002::Intel4002::new_with_variant_and_access_time(
-                        name, ram_variant, access_time
-                    )))
-                } else {
-                    Err("Intel 4002 must be single component".to_string())
+                let properties = config.properties_for(&name);
+                let variant = properties.get("variant")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Type1");
+                let access_time = properties.get("access_time")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(500);
+
+                let ram_variant = match variant {
+                    "Type2" => crate::components::memory::intel_4002::RamVariant::Type2,
+                    _ => crate::components::memory::intel_4002::RamVariant::Type1,
+                };
+
+                let mut ram = crate::components::memory::intel_4002::Intel4002::new_with_variant_and_access_time(
+                    name.clone(), ram_variant, access_time
+                );
+
+                // The chip number's high bit is the metal option, so it decides the variant
+                if let Some(chip_id) = config.chip_id_for(&name) {
+                    let chip_id = u8::try_from(chip_id)
+                        .map_err(|_| format!("{}: chip_id {} out of range", name, chip_id))?;
+                    ram.set_chip_id(chip_id)
+                        .map_err(|e| format!("{}: {}", name, e))?;
+                    if properties.contains_key("variant") && ram.get_variant() != ram_variant {
+                        return Err(format!(
+                            "{}: chip_id {} does not match variant {}",
+                            name, chip_id, variant
+                        ));
+                    }
                 }
+
+                Ok(Box::new(ram))
             }
         );
 
         self.component_registry.insert(
             "intel_4003".to_string(),
+            |_config: &ComponentConfig, name: String| {
+                // Single chips and arrays of chips are both valid
+                Ok(Box::new(
+                    crate::components::memory::intel_4003::Intel4003::new(name),
+                ))
+            },
+        );
+
+        self.component_registry.insert(
+            "intel_4008".to_string(),
+            |_config: &ComponentConfig, name: String| {
+                Ok(Box::new(
+                    crate::components::interface::intel_4008::Intel4008::new(name),
+                ))
+            },
+        );
+
+        self.component_registry.insert(
+            "intel_4009".to_string(),
+            |_config: &ComponentConfig, name: String| {
+                Ok(Box::new(
+                    crate::components::interface::intel_4009::Intel4009::new(name),
+                ))
+            },
+        );
+
+        self.component_registry.insert(
+            "generic_ram".to_string(),
             |config: &ComponentConfig, name: String| {
-                if let ComponentConfig::Single(_single) = config {
-                    Ok(Box::new(
-                        crate::components::memory::intel_4003::Intel4003::new(name),
-                    ))
-                } else {
-                    Err("Intel 4003 must be single component".to_string())
+                let size = config
+                    .properties_for(&name)
+                    .get("size")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(4096);
+                let ram = crate::components::memory::generic_ram::GenericRam::new(
+                    name.clone(),
+                    size as usize,
+                )
+                .map_err(|e| format!("{}: {}", name, e))?;
+                Ok(Box::new(ram))
+            },
+        );
+
+        self.component_registry.insert(
+            "ram_8bit".to_string(),
+            |config: &ComponentConfig, name: String| {
+                let properties = config.properties_for(&name);
+                let size = properties
+                    .get("size")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(4096);
+                let base_address = match properties.get("base_address") {
+                    Some(value) => Self::parse_address_property(value)
+                        .map_err(|e| format!("{}: base_address {}", name, e))?,
+                    None => 0,
+                };
+                let ram = crate::components::memory::generic_ram::GenericRam::new_8bit_bus(
+                    name.clone(),
+                    size as usize,
+                    base_address,
+                )
+                .map_err(|e| format!("{}: {}", name, e))?;
+                Ok(Box::new(ram))
+            },
+        );
+
+        self.component_registry.insert(
+            "rom_8bit".to_string(),
+            |config: &ComponentConfig, name: String| {
+                let properties = config.properties_for(&name);
+                let file = properties.get("file").and_then(|v| v.as_str());
+
+                // Without an explicit size the ROM is as large as its image
+                let size = match (properties.get("size").and_then(|v| v.as_u64()), file) {
+                    (Some(size), _) => size as usize,
+                    (None, Some(path)) => std::fs::metadata(path)
+                        .map_err(|e| format!("{}: Failed to read {}: {}", name, path, e))?
+                        .len() as usize,
+                    (None, None) => 4096,
+                };
+                let base_address = match properties.get("base_address") {
+                    Some(value) => Self::parse_address_property(value)
+                        .map_err(|e| format!("{}: base_address {}", name, e))?,
+                    None => 0,
+                };
+
+                let mut rom = crate::components::memory::rom_8bit::Rom8Bit::new(
+                    name.clone(),
+                    size,
+                    base_address,
+                )
+                .map_err(|e| format!("{}: {}", name, e))?;
+                if let Some(path) = file {
+                    rom.load_file(path)
+                        .map_err(|e| format!("{}: {}", name, e))?;
                 }
+                Ok(Box::new(rom))
             },
         );
+
+        self.component_registry.insert(
+            "address_decoder".to_string(),
+            |config: &ComponentConfig, name: String| {
+                let patterns = config
+                    .properties_for(&name)
+                    .get("patterns")
+                    .and_then(|v| v.as_array())
+                    .ok_or_else(|| format!("{}: patterns must be an array", name))?
+                    .iter()
+                    .map(|pattern| {
+                        pattern
+                            .as_str()
+                            .map(str::to_string)
+                            .ok_or_else(|| format!("{}: patterns must be strings", name))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                let decoder = crate::components::interface::address_decoder::AddressDecoder::new(
+                    name.clone(),
+                    patterns,
+                )
+                .map_err(|e| format!("{}: {}", name, e))?;
+                Ok(Box::new(decoder))
+            },
+        );
+    }
+
+    /// Parse a 16-bit bus address property
+    /// Format: a number, or a string in hex with a "0x" or "$" prefix
+    /// Parameters: value - JSON value of the property
+    /// Returns: Address, Err(String) if it is malformed or above $FFFF
+    fn parse_address_property(value: &serde_json::Value) -> Result<u16, String> {
+        let address = match value {
+            serde_json::Value::Number(number) => number
+                .as_u64()
+                .ok_or_else(|| format!("{} is not an address", number))?,
+            serde_json::Value::String(text) => {
+                let digits = text
+                    .strip_prefix("0x")
+                    .or_else(|| text.strip_prefix("0X"))
+                    .or_else(|| text.strip_prefix('$'))
+                    .ok_or_else(|| format!("'{}' needs a 0x or $ prefix", text))?;
+                u64::from_str_radix(digits, 16)
+                    .map_err(|_| format!("'{}' is not a hex address", text))?
+            }
+            other => return Err(format!("{} is not an address", other)),
+        };
+        u16::try_from(address).map_err(|_| format!("0x{:X} is above 0xFFFF", address))
+    }
+
+    /// Parse the mask options of one Intel 4001 I/O line
+    /// Format: {"direction": "input"|"output", "inverted": bool, "pull": "none"|"up"|"down"},
+    /// every key is optional
+    /// Parameters: value - JSON object from the "io_lines" property
+    /// Returns: Parsed line configuration, Err(String) on unknown option values
+    fn parse_io_line_config(
+        value: &serde_json::Value,
+    ) -> Result<crate::components::memory::intel_4001::IoLineConfig, String> {
+        use crate::components::memory::intel_4001::{IoDirection, IoLineConfig, IoPull};
+
+        let direction = match value.get("direction").and_then(|v| v.as_str()) {
+            None => None,
+            Some("input") => Some(IoDirection::Input),
+            Some("output") => Some(IoDirection::Output),
+            Some(other) => return Err(format!("unknown direction '{}'", other)),
+        };
+        let pull = match value.get("pull").and_then(|v| v.as_str()) {
+            None | Some("none") => IoPull::None,
+            Some("up") => IoPull::Up,
+            Some("down") => IoPull::Down,
+            Some(other) => return Err(format!("unknown pull '{}'", other)),
+        };
+        let inverted = value
+            .get("inverted")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        Ok(IoLineConfig {
+            direction,
+            inverted,
+            pull,
+        })
     }
 
     pub fn create_from_json(&self, json_path: &str) -> Result<ConfigurableSystem, String> {
@@ -258,10 +591,8 @@ impl SystemFactory {
     ) -> Result<HashMap<String, Arc<Mutex<Box<dyn Component>>>>, String> {
         let mut components = HashMap::new();
 
-        for (id, component_config) in &config.components {
-            let component_names = self.expand_component_names(id, component_config);
-
-            for component_name in component_names {
+        for component_config in config.components.values() {
+            for component_name in component_config.instance_names() {
                 let component =
                     self.create_single_component(component_config, component_name.clone())?;
                 components.insert(component_name, Arc::new(Mutex::new(component)));
@@ -271,20 +602,6 @@ impl SystemFactory {
         Ok(components)
     }
 
-    fn expand_component_names(&self, _id: &str, config: &ComponentConfig) -> Vec<String> {
-        match config {
-            ComponentConfig::Single(single) => vec![single.name.clone()],
-            ComponentConfig::Array(array) => {
-                let mut names = Vec::new();
-                for i in 0..array.count {
-                    let name = array.naming_pattern.replace("{:02}", &format!("{:02}", i));
-                    names.push(name);
-                }
-                names
-            }
-        }
-    }
-
     fn create_single_component(
         &self,
         config: &ComponentConfig,
@@ -308,6 +625,25 @@ impl SystemFactory {
         }
     }
 
+    /// Check whether a connection type describes a shared, bidirectional bus
+    /// Parameters: connection_type - Connection type from the JSON configuration
+    /// Returns: true for "data_bus" and "bus" connections
+    fn is_bidirectional_connection(connection_type: &str) -> bool {
+        matches!(connection_type, "data_bus" | "bus")
+    }
+
+    /// Resolve a connection's component reference into instance names
+    /// A reference may name an instance directly or a component entry key, which
+    /// expands to every instance that entry creates (e.g. all RAMs of one bank)
+    /// Parameters: config - System configuration, component - Name from a PinReference
+    /// Returns: Instance names the reference stands for
+    fn resolve_component_reference(config: &SystemConfig, component: &str) -> Vec<String> {
+        match config.components.get(component) {
+            Some(component_config) => component_config.instance_names(),
+            None => vec![component.to_string()],
+        }
+    }
+
     fn connect_components(
         &self,
         config: &SystemConfig,
@@ -319,15 +655,21 @@ impl SystemFactory {
                 connection_id, connection_config.source.component, connection_config.targets
             );
 
-            // Get source pin
+            // Get source pin, which must belong to exactly one component
+            let source_names =
+                Self::resolve_component_reference(config, &connection_config.source.component);
+            let source_name = match source_names.as_slice() {
+                [name] => name,
+                _ => {
+                    return Err(format!(
+                        "Source of connection '{}' must be a single component: {}",
+                        connection_id, connection_config.source.component
+                    ))
+                }
+            };
             let source_component = components
-                .get(&connection_config.source.component)
-                .ok_or_else(|| {
-                    format!(
-                        "Source component not found: {}",
-                        connection_config.source.component
-                    )
-                })?;
+                .get(source_name)
+                .ok_or_else(|| format!("Source component not found: {}", source_name))?;
 
             let source_pin = {
                 let component = source_component.lock().unwrap();
@@ -337,21 +679,30 @@ impl SystemFactory {
             };
 
             // Connect to all targets
-            for target_ref in &connection_config.targets {
-                let target_component = components.get(&target_ref.component).ok_or_else(|| {
-                    format!("Target component not found: {}", target_ref.component)
-                })?;
+            let targets = connection_config.targets.iter().flat_map(|target_ref| {
+                Self::resolve_component_reference(config, &target_ref.component)
+                    .into_iter()
+                    .map(move |name| (name, &target_ref.pin))
+            });
+            for (target_name, target_pin_name) in targets {
+                let target_component = components
+                    .get(&target_name)
+                    .ok_or_else(|| format!("Target component not found: {}", target_name))?;
 
                 let target_pin = {
                     let component = target_component.lock().unwrap();
                     component
-                        .get_pin(&target_ref.pin)
+                        .get_pin(target_pin_name)
                         .map_err(|e| format!("Failed to get target pin: {}", e))?
                 };
 
                 // Connect the pins - source connects to target for proper signal flow
-                let mut source_pin_guard = source_pin.lock().unwrap();
-                source_pin_guard.connect_to(target_pin.clone());
+                source_pin.lock().unwrap().connect_to(target_pin.clone());
+
+                // Bus lines are driven from both ends, so memory data reaches the CPU too
+                if Self::is_bidirectional_connection(&connection_config.connection_type) {
+                    target_pin.lock().unwrap().connect_to(source_pin.clone());
+                }
             }
         }
 
@@ -364,6 +715,11 @@ pub struct ConfigurableSystem {
     config: SystemConfig,
     components: HashMap<String, Arc<Mutex<Box<dyn Component>>>>,
     is_running: bool,
+
+    // Lockstep scheduler state
+    update_order: Vec<String>, // Deterministic component update order
+    time_base: TimeBase,       // Simulation time base shared by all components
+    edge_count: u64,           // Number of clock edges stepped in lockstep mode
 }
 
 impl ConfigurableSystem {
@@ -371,13 +727,79 @@ impl ConfigurableSystem {
         config: SystemConfig,
         components: HashMap<String, Arc<Mutex<Box<dyn Component>>>>,
     ) -> Self {
+        let update_order = Self::build_update_order(&config, &components);
+        let time_base = TimeBase::new();
+
+        // Distribute the shared time base. Only the primary clock (first in update
+        // order) may advance it, so additional clocks keep their own private time.
+        let types = Self::component_types(&config);
+        let mut primary_clock_attached = false;
+        for name in &update_order {
+            let is_clock = types
+                .get(name)
+                .is_some_and(|component_type| Self::is_clock_type(component_type));
+            if is_clock && primary_clock_attached {
+                continue;
+            }
+            primary_clock_attached |= is_clock;
+
+            if let Ok(mut component) = components[name].lock() {
+                component.attach_time_base(time_base.clone());
+            }
+        }
+
         ConfigurableSystem {
             config,
             components,
             is_running: false,
+            update_order,
+            time_base,
+            edge_count: 0,
         }
     }
 
+    /// Map every component instance name to its registry type
+    /// Parameters: config - System configuration
+    /// Returns: HashMap from component name to component type
+    fn component_types(config: &SystemConfig) -> HashMap<String, String> {
+        let mut types = HashMap::new();
+        for component_config in config.components.values() {
+            for name in component_config.instance_names() {
+                types.insert(name, component_config.component_type().to_string());
+            }
+        }
+        types
+    }
+
+    /// Check whether a component type is a clock source
+    fn is_clock_type(component_type: &str) -> bool {
+        matches!(component_type, "two_phase_clock" | "generic_clock")
+    }
+
+    /// Build the deterministic order in which components are updated in lockstep mode
+    /// Clocks come first so every edge is visible to the rest of the system, then CPUs
+    /// drive the bus, address decoders settle the chip selects, then memories and
+    /// peripherals respond. Ties are broken by name.
+    /// Parameters: config - System configuration, components - Instantiated components
+    /// Returns: Component names in update order
+    fn build_update_order(
+        config: &SystemConfig,
+        components: &HashMap<String, Arc<Mutex<Box<dyn Component>>>>,
+    ) -> Vec<String> {
+        let types = Self::component_types(config);
+
+        let rank = |name: &String| match types.get(name).map(String::as_str) {
+            Some(component_type) if Self::is_clock_type(component_type) => 0,
+            Some("intel_4004" | "intel_4040" | "mos_6502" | "wdc_65c02") => 1,
+            Some("address_decoder") => 2,
+            _ => 3,
+        };
+
+        let mut order: Vec<String> = components.keys().cloned().collect();
+        order.sort_by(|a, b| rank(a).cmp(&rank(b)).then_with(|| a.cmp(b)));
+        order
+    }
+
     pub fn run(&mut self) {
         self.is_running = true;
         let mut handles = vec![];
@@ -536,6 +958,510 @@ impl ConfigurableSystem {
         println!("└─────────────────────────────────────────────────────────────┘");
     }
 
+    /// Prepare the system for lockstep execution on the calling thread
+    /// Marks every component as running without spawning component threads
+    pub fn start_lockstep(&mut self) {
+        self.is_running = true;
+        self.time_base.reset();
+        self.edge_count = 0;
+
+        for name in &self.update_order {
+            if let Ok(mut component) = self.components[name].lock() {
+                component.start();
+            }
+        }
+    }
+
+    /// Advance the system by exactly one clock edge in simulated time
+    /// Every clock is ticked first, then all components are updated in `update_order`
+    /// Returns: Ok(()) on success, Err(String) if the system contains no steppable clock
+    pub fn step_lockstep(&mut self) -> Result<(), String> {
+        use crate::components::clock::generic_clock::GenericClock;
+        use crate::components::clock::two_phase_clock::TwoPhaseClock;
+
+        let mut clock_ticked = false;
+
+        for name in &self.update_order {
+            let mut component = self.components[name]
+                .lock()
+                .map_err(|_| format!("Failed to lock component {}", name))?;
+            let any = component.as_any_mut();
+
+            // Ticking the primary clock advances the shared time base
+            if let Some(clock) = any.downcast_mut::<TwoPhaseClock>() {
+                clock.tick();
+                clock_ticked = true;
+            } else if let Some(clock) = any.downcast_mut::<GenericClock>() {
+                clock.tick();
+                clock_ticked = true;
+            } else {
+                component.update();
+            }
+        }
+
+        if !clock_ticked {
+            return Err("No clock component found for lockstep execution".to_string());
+        }
+        self.edge_count += 1;
+        Ok(())
+    }
+
+    /// Run the system in lockstep mode for a fixed number of clock edges
+    /// The same program and configuration always produce the same trace and cycle counts
+    /// Parameters: edges - Number of clock edges to simulate
+    /// Returns: Ok(()) on success, Err(String) if stepping fails
+    pub fn run_lockstep(&mut self, edges: u64) -> Result<(), String> {
+        if !self.is_running {
+            self.start_lockstep();
+        }
+
+        for _ in 0..edges {
+            self.step_lockstep()?;
+        }
+        Ok(())
+    }
+
+    /// Stop lockstep execution and tri-state all component outputs
+    pub fn stop_lockstep(&mut self) {
+        self.is_running = false;
+        for name in &self.update_order {
+            if let Ok(mut component) = self.components[name].lock() {
+                component.stop();
+            }
+        }
+    }
+
+    /// Switch the system's Intel 4004 or 4040 to functional execution
+    /// The ROMs are attached as the CPU's program memory, each serving the page of its chip
+    /// number, and the 4002s as its data memory on the CM-RAM line they are wired to
+    /// Returns: Ok(()) on success, Err(String) if the system has no MCS-4 CPU
+    pub fn enable_functional_mode(&mut self) -> Result<(), String> {
+        use crate::components::cpu::intel_4004::ExecutionMode;
+        use crate::components::cpu::intel_4040::mcs4_cpu_mut;
+        use crate::components::interface::intel_4008::Intel4008;
+        use crate::components::interface::intel_4009::Intel4009;
+        use crate::components::memory::generic_ram::GenericRam;
+        use crate::components::memory::intel_4002::Intel4002;
+
+        // Standard memory behind a 4008/4009 pair serves as program memory like the 4001s
+        let mut standard_memory_names: Vec<&String> = self
+            .components
+            .iter()
+            .filter(|(_, component)| {
+                component.lock().is_ok_and(|guard| {
+                    let chip = guard.as_any();
+                    chip.is::<Intel4008>() || chip.is::<Intel4009>() || chip.is::<GenericRam>()
+                })
+            })
+            .map(|(name, _)| name)
+            .collect();
+        standard_memory_names.sort();
+        let roms = self
+            .get_rom_names()
+            .iter()
+            .chain(standard_memory_names)
+            .map(|name| (self.rom_bank_of(name), Arc::clone(&self.components[name])))
+            .collect();
+
+        let mut ram_names: Vec<&String> = self
+            .components
+            .iter()
+            .filter(|(_, component)| {
+                component
+                    .lock()
+                    .is_ok_and(|guard| guard.as_any().is::<Intel4002>())
+            })
+            .map(|(name, _)| name)
+            .collect();
+        ram_names.sort();
+        let rams = ram_names
+            .into_iter()
+            .map(|name| (self.ram_bank_of(name), Arc::clone(&self.components[name])))
+            .collect();
+
+        let cpu_name = self.find_cpu_name()?;
+        let mut guard = self.components[&cpu_name]
+            .lock()
+            .map_err(|_| format!("Failed to lock CPU component {}", cpu_name))?;
+        let cpu = mcs4_cpu_mut(guard.as_mut())
+            .ok_or_else(|| format!("Component {} is not an Intel 4004 or 4040", cpu_name))?;
+
+        cpu.attach_program_memory(roms);
+        cpu.attach_data_memory(rams);
+        cpu.set_execution_mode(ExecutionMode::Functional);
+        Ok(())
+    }
+
+    /// Execute instructions on the Intel 4004 or 4040 in functional mode on the calling thread
+    /// No clock or bus activity is simulated, so this runs as fast as the host allows
+    /// Parameters: instructions - Number of instructions to execute
+    /// Returns: Ok(()) on success, Err(String) if functional mode is not enabled or execution fails
+    pub fn run_functional(&mut self, instructions: u64) -> Result<(), String> {
+        use crate::components::cpu::intel_4004::ExecutionMode;
+        use crate::components::cpu::intel_4040::mcs4_cpu_mut;
+
+        let cpu_name = self.find_cpu_name()?;
+        let mut guard = self.components[&cpu_name]
+            .lock()
+            .map_err(|_| format!("Failed to lock CPU component {}", cpu_name))?;
+        let cpu = mcs4_cpu_mut(guard.as_mut())
+            .ok_or_else(|| format!("Component {} is not an Intel 4004 or 4040", cpu_name))?;
+
+        if cpu.get_execution_mode() != ExecutionMode::Functional {
+            return Err("Functional mode is not enabled".to_string());
+        }
+        if !cpu.is_running() {
+            cpu.start();
+        }
+
+        for _ in 0..instructions {
+            cpu.step_functional()?;
+        }
+        Ok(())
+    }
+
+    /// Run a closure on the system's Intel 4004 or 4040
+    /// Parameters: f - Closure given mutable access to the CPU
+    /// Returns: Ok(result of f), or Err(String) if the system has no MCS-4 CPU
+    pub fn with_mcs4_cpu<T>(&self, f: impl FnOnce(&mut Intel4004) -> T) -> Result<T, String> {
+        use crate::components::cpu::intel_4040::mcs4_cpu_mut;
+
+        let cpu_name = self.find_cpu_name()?;
+        let mut guard = self.components[&cpu_name]
+            .lock()
+            .map_err(|_| format!("Failed to lock CPU component {}", cpu_name))?;
+        let cpu = mcs4_cpu_mut(guard.as_mut())
+            .ok_or_else(|| format!("Component {} is not an Intel 4004 or 4040", cpu_name))?;
+        Ok(f(cpu))
+    }
+
+    /// Run a closure on the system's 6502
+    /// Parameters: f - Closure given mutable access to the CPU
+    /// Returns: Ok(result of f), or Err(String) if the system has no 6502 or 65C02
+    pub fn with_mos6502_cpu<T>(&self, f: impl FnOnce(&mut MOS6502) -> T) -> Result<T, String> {
+        use crate::components::cpu::wdc_65c02::mos6502_cpu_mut;
+
+        for name in &self.update_order {
+            let mut guard = self.components[name]
+                .lock()
+                .map_err(|_| format!("Failed to lock component {}", name))?;
+            if let Some(cpu) = mos6502_cpu_mut(guard.as_mut()) {
+                return Ok(f(cpu));
+            }
+        }
+        Err("No 6502 CPU component found in system".to_string())
+    }
+
+    /// Get the instruction count of the system's CPU
+    /// Returns: Ok(count) of the MCS-4 CPU or the 6502, Err(String) if the system has neither
+    fn cpu_instruction_count(&self) -> Result<u64, String> {
+        self.with_mcs4_cpu(|cpu| cpu.get_instruction_count())
+            .or_else(|_| self.with_mos6502_cpu(|cpu| cpu.get_instruction_count()))
+            .map_err(|_| "No Intel 4004, 4040 or 6502 CPU component found in system".to_string())
+    }
+
+    /// Execute exactly one instruction on the system's CPU
+    /// An MCS-4 CPU in functional mode steps on its own; otherwise the whole system advances
+    /// in lockstep until the CPU completes an instruction, so memory and I/O see the bus
+    /// cycles. A 4004 waiting at a breakpoint completes nothing until the hit is taken.
+    /// Returns: Ok(()) on success, Err(String) if no instruction completes within
+    ///          `MAX_EDGES_PER_INSTRUCTION` clock edges or stepping fails
+    pub fn step_instruction(&mut self) -> Result<(), String> {
+        use crate::components::cpu::intel_4004::ExecutionMode;
+
+        let mode = self.with_mcs4_cpu(|cpu| cpu.get_execution_mode());
+        if mode == Ok(ExecutionMode::Functional) {
+            return self.run_functional(1);
+        }
+
+        let count = self.cpu_instruction_count()?;
+        if !self.is_running {
+            self.start_lockstep();
+        }
+        for _ in 0..MAX_EDGES_PER_INSTRUCTION {
+            self.step_lockstep()?;
+            if self.cpu_instruction_count()? != count {
+                return Ok(());
+            }
+        }
+        Err(format!(
+            "CPU completed no instruction within {} clock edges",
+            MAX_EDGES_PER_INSTRUCTION
+        ))
+    }
+
+    /// Run a closure on the Intel 4002 holding a RAM register
+    /// Parameters: bank - RAM bank (0-3), register - Register within the bank as addressed
+    ///             by SRC (0-15: chip number in bits 3-2, register in bits 1-0), f - Closure
+    /// Returns: Ok(result of f), or Err(String) if no RAM chip answers that address
+    fn with_ram_chip<T>(
+        &self,
+        bank: u8,
+        register: u8,
+        f: impl FnOnce(&mut crate::components::memory::intel_4002::Intel4002) -> T,
+    ) -> Result<T, String> {
+        use crate::components::memory::intel_4002::Intel4002;
+
+        if bank > 3 || register > 15 {
+            return Err(format!("RAM address {}:{} out of range", bank, register));
+        }
+        let mut names: Vec<&String> = self.components.keys().collect();
+        names.sort();
+        for name in names {
+            let mut guard = self.components[name]
+                .lock()
+                .map_err(|_| format!("Failed to lock RAM component {}", name))?;
+            if let Some(ram) = guard.as_any_mut().downcast_mut::<Intel4002>() {
+                if ram.get_chip_id() == register >> 2 && self.ram_bank_of(name) == bank {
+                    return Ok(f(ram));
+                }
+            }
+        }
+        Err(format!(
+            "No Intel 4002 at bank {} chip {}",
+            bank,
+            register >> 2
+        ))
+    }
+
+    /// Read one RAM character
+    /// Parameters: bank - RAM bank (0-3), register - Register as addressed by SRC (0-15),
+    ///             character - Main character 0-15, or 16-19 for status characters 0-3
+    /// Returns: Ok(4-bit value), Err(String) if the location does not exist
+    pub fn read_ram_character(&self, bank: u8, register: u8, character: u8) -> Result<u8, String> {
+        let address = Self::ram_chip_address(register, character)?;
+        self.with_ram_chip(bank, register, |ram| ram.read_ram(address))?
+            .ok_or_else(|| format!("RAM character {} out of range", character))
+    }
+
+    /// Write one RAM character
+    /// Parameters: bank - RAM bank (0-3), register - Register as addressed by SRC (0-15),
+    ///             character - Main character 0-15, or 16-19 for status characters 0-3,
+    ///             value - 4-bit value to store
+    /// Returns: Ok(()) on success, Err(String) if the location does not exist
+    pub fn write_ram_character(
+        &self,
+        bank: u8,
+        register: u8,
+        character: u8,
+        value: u8,
+    ) -> Result<(), String> {
+        let address = Self::ram_chip_address(register, character)?;
+        self.with_ram_chip(bank, register, |ram| ram.write_ram(address, value))?
+    }
+
+    /// Map a register and character onto the flat address of an Intel 4002
+    /// Parameters: register - Register as addressed by SRC (0-15), character - Character (0-19)
+    /// Returns: Ok(register × 20 + character within the chip), Err(String) if out of range
+    fn ram_chip_address(register: u8, character: u8) -> Result<u8, String> {
+        if character > 19 {
+            return Err(format!("RAM character {} out of range 0-19", character));
+        }
+        Ok((register & 0x03) * 20 + character)
+    }
+
+    /// Get a snapshot of the Intel 4004 or 4040 register file for display
+    /// Returns: CpuRegisters with the current CPU state, or Err(String) if the system has no MCS-4 CPU
+    pub fn get_cpu_registers(&self) -> Result<CpuRegisters, String> {
+        use crate::components::cpu::intel_4040::mcs4_cpu;
+
+        let cpu_name = self.find_cpu_name()?;
+        let guard = self.components[&cpu_name]
+            .lock()
+            .map_err(|_| format!("Failed to lock CPU component {}", cpu_name))?;
+        let cpu = mcs4_cpu(guard.as_ref())
+            .ok_or_else(|| format!("Component {} is not an Intel 4004 or 4040", cpu_name))?;
+
+        let mut index_registers = [0u8; 16];
+        for (index, value) in index_registers.iter_mut().enumerate() {
+            *value = cpu.get_register(index as u8).unwrap_or(0);
+        }
+
+        Ok(CpuRegisters {
+            program_counter: cpu.get_program_counter(),
+            accumulator: cpu.get_accumulator(),
+            carry: cpu.get_carry(),
+            index_registers,
+            stack_pointer: cpu.get_stack_pointer(),
+            stack: cpu.get_stack(),
+            instruction_count: cpu.get_instruction_count(),
+        })
+    }
+
+    /// Find the system's MCS-4 CPU
+    /// Returns: Component name of the first Intel 4004 or 4040 in update order, or Err(String) if none exists
+    fn find_cpu_name(&self) -> Result<String, String> {
+        use crate::components::cpu::intel_4040::mcs4_cpu;
+
+        self.update_order
+            .iter()
+            .find(|name| {
+                self.components[*name]
+                    .lock()
+                    .is_ok_and(|c| mcs4_cpu(c.as_ref()).is_some())
+            })
+            .cloned()
+            .ok_or_else(|| "No Intel 4004 or 4040 CPU component found in system".to_string())
+    }
+
+    /// Get the component update order used by the lockstep scheduler
+    /// Returns: Component names in update order
+    pub fn get_update_order(&self) -> &[String] {
+        &self.update_order
+    }
+
+    /// Get the simulated time elapsed in lockstep mode
+    /// Returns: Simulated time in nanoseconds
+    pub fn get_simulated_time_ns(&self) -> u64 {
+        self.time_base.now().as_nanos()
+    }
+
+    /// Get a handle to the simulation time base shared by all components
+    /// Returns: Cloned TimeBase handle referring to the system's simulated clock
+    pub fn get_time_base(&self) -> TimeBase {
+        self.time_base.clone()
+    }
+
+    /// Get the number of clock edges stepped in lockstep mode
+    /// Returns: Edge count since `start_lockstep`
+    pub fn get_edge_count(&self) -> u64 {
+        self.edge_count
+    }
+
+    /// Capture the complete machine state: every component's internal state, the drivers
+    /// of all pins, simulated time and the lockstep edge count
+    /// Returns: Ok(SystemSnapshot) on success, Err(String) if a component cannot be locked
+    pub fn snapshot(&self) -> Result<SystemSnapshot, String> {
+        let types = Self::component_types(&self.config);
+        let mut components = BTreeMap::new();
+
+        for name in &self.update_order {
+            let component = self.components[name]
+                .lock()
+                .map_err(|_| format!("Failed to lock component {}", name))?;
+
+            let mut pins = BTreeMap::new();
+            for (pin_name, pin) in component.pins() {
+                let pin = pin
+                    .lock()
+                    .map_err(|_| format!("Failed to lock pin {}.{}", name, pin_name))?;
+                let drivers: BTreeMap<String, (PinValue, DriveStrength)> = pin
+                    .get_drivers()
+                    .iter()
+                    .map(|(driver, value)| (driver.clone(), *value))
+                    .collect();
+                pins.insert(pin_name, drivers);
+            }
+
+            components.insert(
+                name.clone(),
+                ComponentSnapshot {
+                    component_type: types.get(name).cloned().unwrap_or_default(),
+                    state: component.snapshot_state(),
+                    pins,
+                },
+            );
+        }
+
+        Ok(SystemSnapshot {
+            system: self.config.name.clone(),
+            sim_time: self.time_base.now(),
+            edge_count: self.edge_count,
+            components,
+        })
+    }
+
+    /// Restore a machine state captured by `snapshot`
+    /// The system is prepared for lockstep execution first, since starting it would reset
+    /// the CPUs; continue with `run_lockstep` or `step_instruction` afterwards.
+    /// Parameters: snapshot - Snapshot of a system built from the same configuration
+    /// Returns: Ok(()) on success, Err(String) if the snapshot does not match this system
+    pub fn restore(&mut self, snapshot: &SystemSnapshot) -> Result<(), String> {
+        if snapshot.system != self.config.name {
+            return Err(format!(
+                "Snapshot of system '{}' cannot be restored into '{}'",
+                snapshot.system, self.config.name
+            ));
+        }
+
+        let types = Self::component_types(&self.config);
+        for name in &self.update_order {
+            let saved = snapshot
+                .components
+                .get(name)
+                .ok_or_else(|| format!("Snapshot has no state for component {}", name))?;
+            let component_type = types.get(name).cloned().unwrap_or_default();
+            if saved.component_type != component_type {
+                return Err(format!(
+                    "Component {} is a {} but the snapshot holds a {}",
+                    name, component_type, saved.component_type
+                ));
+            }
+        }
+        if let Some(extra) = snapshot
+            .components
+            .keys()
+            .find(|name| !self.components.contains_key(*name))
+        {
+            return Err(format!("Snapshot contains unknown component {}", extra));
+        }
+
+        if !self.is_running {
+            self.start_lockstep();
+        }
+
+        for name in &self.update_order {
+            let saved = &snapshot.components[name];
+            let mut component = self.components[name]
+                .lock()
+                .map_err(|_| format!("Failed to lock component {}", name))?;
+
+            if let Some(state) = &saved.state {
+                component.restore_state(state)?;
+            }
+
+            // Pins are restored last, so the exact mid-cycle bus state wins over
+            // anything the component drove while restoring its registers
+            for (pin_name, drivers) in &saved.pins {
+                let pin = component.get_pin(pin_name)?;
+                let mut pin = pin
+                    .lock()
+                    .map_err(|_| format!("Failed to lock pin {}.{}", name, pin_name))?;
+                pin.restore_drivers(
+                    drivers
+                        .iter()
+                        .map(|(driver, value)| (driver.clone(), *value))
+                        .collect(),
+                );
+            }
+        }
+
+        self.time_base.set(snapshot.sim_time);
+        self.edge_count = snapshot.edge_count;
+        Ok(())
+    }
+
+    /// Save the complete machine state to a JSON file
+    /// Parameters: path - File to write
+    /// Returns: Ok(()) on success, Err(String) if the snapshot or the file cannot be written
+    pub fn save_state(&self, path: &str) -> Result<(), String> {
+        let snapshot = self.snapshot()?;
+        let json = serde_json::to_string_pretty(&snapshot)
+            .map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+        std::fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+
+    /// Load a machine state saved by `save_state`
+    /// Parameters: path - File to read
+    /// Returns: Ok(()) on success, Err(String) if the file is unreadable or does not match
+    pub fn load_state(&mut self, path: &str) -> Result<(), String> {
+        let json =
+            std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let snapshot: SystemSnapshot = serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse snapshot {}: {}", path, e))?;
+        self.restore(&snapshot)
+    }
+
     pub fn stop(&mut self) {
         self.is_running = false;
     }
@@ -579,46 +1505,455 @@ impl ConfigurableSystem {
         }
     }
 
+    /// Get the names of all Intel 4001 ROM components in chip number order
+    /// The chip number decides which 256-byte page of program memory a ROM holds
+    /// Returns: Vector of ROM component names, lowest chip number first
+    pub fn get_rom_names(&self) -> Vec<String> {
+        let mut roms: Vec<(u8, String)> = self
+            .components
+            .iter()
+            .filter_map(|(name, component)| {
+                let component = component.lock().ok()?;
+                let rom = component
+                    .as_any()
+                    .downcast_ref::<crate::components::memory::intel_4001::Intel4001>()?;
+                Some((rom.get_chip_id(), name.clone()))
+            })
+            .collect();
+
+        roms.sort();
+        roms.into_iter().map(|(_, name)| name).collect()
+    }
+
+    /// Find the CPU command lines a chip's CM pin is connected to
+    /// Parameters: name - Component name
+    /// Returns: Iterator over the source pin names of the connections driving CM
+    fn cm_lines_of<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.config
+            .connections
+            .values()
+            .filter(move |connection| {
+                connection.targets.iter().any(|target| {
+                    target.pin == "CM"
+                        && SystemFactory::resolve_component_reference(
+                            &self.config,
+                            &target.component,
+                        )
+                        .iter()
+                        .any(|target_name| target_name == name)
+                })
+            })
+            .map(|connection| connection.source.pin.as_str())
+    }
+
+    /// Find the RAM bank an Intel 4002 belongs to
+    /// A RAM answers the CM-RAM line its CM pin is connected to
+    /// Parameters: name - RAM component name
+    /// Returns: Bank number (0-3), 0 if the CM pin is not wired to a CM-RAM line
+    fn ram_bank_of(&self, name: &str) -> u8 {
+        self.cm_lines_of(name)
+            .find_map(|line| {
+                line.strip_prefix("CM_RAM")
+                    .and_then(|bank| bank.parse().ok())
+            })
+            .unwrap_or(0)
+    }
+
+    /// Find the ROM bank program memory belongs to
+    /// A chip answers the 4040's second bank when its CM pin is connected to CM_ROM1;
+    /// chips on CM_ROM and memory without a CM pin belong to bank 0
+    /// Parameters: name - Program memory component name
+    /// Returns: Bank number (0-1)
+    fn rom_bank_of(&self, name: &str) -> u8 {
+        u8::from(self.cm_lines_of(name).any(|line| line == "CM_ROM1"))
+    }
+
+    /// Read program memory the way the CPU fetches it
+    /// Page n comes from the Intel 4001 with chip number n; a system without 4001s reads
+    /// its single generic RAM. Unpopulated addresses read as 0, like a floating bus.
+    /// Parameters: address - First 12-bit program address, length - Number of bytes,
+    ///             wrapping at the end of the 4 KiB program space
+    /// Returns: Program bytes
+    pub fn read_program_memory(&self, address: u16, length: usize) -> Vec<u8> {
+        use crate::components::memory::generic_ram::GenericRam;
+        use crate::components::memory::intel_4001::Intel4001;
+
+        let guards: Vec<_> = self
+            .components
+            .values()
+            .filter_map(|component| component.lock().ok())
+            .collect();
+        let roms: HashMap<u8, &Intel4001> = guards
+            .iter()
+            .filter_map(|guard| guard.as_any().downcast_ref::<Intel4001>())
+            .map(|rom| (rom.get_chip_id(), rom))
+            .collect();
+        let program_ram = guards
+            .iter()
+            .find_map(|guard| guard.as_any().downcast_ref::<GenericRam>())
+            .filter(|_| roms.is_empty());
+
+        (0..length)
+            .map(|offset| {
+                let address = (address as usize + offset) & 0xFFF;
+                match program_ram {
+                    Some(ram) => ram.read_byte(address),
+                    None => roms
+                        .get(&((address >> 8) as u8))
+                        .and_then(|rom| rom.read_rom(address as u8)),
+                }
+                .unwrap_or(0)
+            })
+            .collect()
+    }
+
+    /// Write program memory the way `read_program_memory` reads it
+    /// Used by debuggers to patch or download code; each byte goes to the Intel 4001 of
+    /// its page, or to the single generic RAM of a system without 4001s
+    /// Parameters: address - First 12-bit program address, data - Bytes to store,
+    ///             wrapping at the end of the 4 KiB program space
+    /// Returns: Ok(()) on success, Err(String) naming the first address without memory
+    pub fn write_program_memory(&self, address: u16, data: &[u8]) -> Result<(), String> {
+        use crate::components::memory::generic_ram::GenericRam;
+        use crate::components::memory::intel_4001::Intel4001;
+
+        let mut guards: Vec<_> = self
+            .components
+            .values()
+            .filter_map(|component| component.lock().ok())
+            .collect();
+        let has_roms = guards.iter().any(|guard| guard.as_any().is::<Intel4001>());
+
+        for (offset, &byte) in data.iter().enumerate() {
+            let address = (address as usize + offset) & 0xFFF;
+            let stored = guards.iter_mut().any(|guard| {
+                let any = guard.as_any_mut();
+                if let Some(rom) = any.downcast_mut::<Intel4001>() {
+                    rom.get_chip_id() as usize == address >> 8
+                        && rom.load_rom_data(vec![byte], address & 0xFF).is_ok()
+                } else if let Some(ram) = any.downcast_mut::<GenericRam>() {
+                    !has_roms && ram.write_byte(address, byte).is_ok()
+                } else {
+                    false
+                }
+            });
+            if !stored {
+                return Err(format!("No program memory at 0x{:03X}", address));
+            }
+        }
+        Ok(())
+    }
+
+    /// Read memory on an 8-bit bus
+    /// Each byte comes from the `GenericRam` or `Rom8Bit` whose address window holds it;
+    /// chip selects are not consulted
+    /// Parameters: address - First 16-bit bus address, length - Number of bytes,
+    ///             wrapping at the end of the 64 KiB address space
+    /// Returns: Ok(bytes), Err(String) naming the first address without memory
+    pub fn read_bus_memory(&self, address: u16, length: usize) -> Result<Vec<u8>, String> {
+        let guards: Vec<_> = self
+            .components
+            .values()
+            .filter_map(|component| component.lock().ok())
+            .collect();
+
+        (0..length)
+            .map(|offset| {
+                let address = address.wrapping_add(offset as u16);
+                guards
+                    .iter()
+                    .find_map(|guard| Self::bus_memory_byte(guard.as_any(), address))
+                    .ok_or_else(|| format!("No memory at 0x{:04X}", address))
+            })
+            .collect()
+    }
+
+    /// Write memory on an 8-bit bus
+    /// RAM is written as the CPU would; ROM is reprogrammed, so a debugger can download code
+    /// Parameters: address - First 16-bit bus address, data - Bytes to store,
+    ///             wrapping at the end of the 64 KiB address space
+    /// Returns: Ok(()) on success, Err(String) naming the first address without memory
+    pub fn write_bus_memory(&self, address: u16, data: &[u8]) -> Result<(), String> {
+        use crate::components::memory::generic_ram::GenericRam;
+        use crate::components::memory::rom_8bit::Rom8Bit;
+
+        let mut guards: Vec<_> = self
+            .components
+            .values()
+            .filter_map(|component| component.lock().ok())
+            .collect();
+
+        for (offset, &byte) in data.iter().enumerate() {
+            let address = address.wrapping_add(offset as u16);
+            let stored = guards.iter_mut().any(|guard| {
+                let any = guard.as_any_mut();
+                if let Some(ram) = any.downcast_mut::<GenericRam>() {
+                    let offset = address.wrapping_sub(ram.get_base_address()) as usize;
+                    ram.write_byte(offset, byte).is_ok()
+                } else if let Some(rom) = any.downcast_mut::<Rom8Bit>() {
+                    let offset = address.wrapping_sub(rom.get_base_address()) as usize;
+                    rom.load_data(&[byte], offset).is_ok()
+                } else {
+                    false
+                }
+            });
+            if !stored {
+                return Err(format!("No memory at 0x{:04X}", address));
+            }
+        }
+        Ok(())
+    }
+
+    /// Read one byte from an 8-bit bus memory component
+    /// Parameters: component - Component to read, address - 16-bit bus address
+    /// Returns: Some(byte) if the component is a `GenericRam` or `Rom8Bit` holding the address
+    fn bus_memory_byte(component: &dyn std::any::Any, address: u16) -> Option<u8> {
+        use crate::components::memory::generic_ram::GenericRam;
+        use crate::components::memory::rom_8bit::Rom8Bit;
+
+        if let Some(ram) = component.downcast_ref::<GenericRam>() {
+            ram.read_byte(address.wrapping_sub(ram.get_base_address()) as usize)
+        } else if let Some(rom) = component.downcast_ref::<Rom8Bit>() {
+            rom.read_byte(address.wrapping_sub(rom.get_base_address()) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Get a snapshot of every Intel 4002 RAM for display
+    /// Returns: One entry per RAM chip, ordered by component name
+    pub fn get_ram_contents(&self) -> Vec<RamChipContents> {
+        use crate::components::memory::intel_4002::Intel4002;
+
+        let mut rams: Vec<RamChipContents> = self
+            .components
+            .iter()
+            .filter_map(|(name, component)| {
+                let component = component.lock().ok()?;
+                let ram = component.as_any().downcast_ref::<Intel4002>()?;
+                Some(RamChipContents {
+                    name: name.clone(),
+                    chip_id: ram.get_chip_id(),
+                    registers: *ram.get_registers(),
+                })
+            })
+            .collect();
+
+        rams.sort_by(|a, b| a.name.cmp(&b.name));
+        rams
+    }
+
     /// Load program data into ROM components
+    /// The image is split into 256-byte pages; page n goes to the Intel 4001 with chip number n.
+    /// A system without 4001s runs from standard memory behind a 4008/4009 pair, so the image
+    /// is loaded at address 0 of its single generic RAM instead
     /// Parameters: program_data - Binary program data to load
-    /// Returns: Ok(()) on success, Err(String) on failure
+    /// Returns: Ok(()) on success, Err(String) if a page has no ROM to hold it
     pub fn load_program_data(&mut self, program_data: &[u8]) -> Result<(), String> {
+        use crate::components::memory::intel_4001::Intel4001;
+
+        let rom_names = self.get_rom_names();
+        if rom_names.is_empty() {
+            if !self.get_rom_8bit_names().is_empty() {
+                return self.load_program_into_rom_8bit(program_data);
+            }
+            return self.load_program_into_ram(program_data);
+        }
+
+        // Every page of the image needs a ROM with the matching chip number
+        let chips: Vec<usize> = rom_names
+            .iter()
+            .map(|name| {
+                let guard = self.components[name].lock().unwrap();
+                guard
+                    .as_any()
+                    .downcast_ref::<Intel4001>()
+                    .unwrap()
+                    .get_chip_id() as usize
+            })
+            .collect();
+        let pages = program_data.len().div_ceil(256);
+        if let Some(missing) = (0..pages).find(|page| !chips.contains(page)) {
+            return Err(format!(
+                "Program size {} bytes needs page {}, but no ROM has chip number {}",
+                program_data.len(),
+                missing,
+                missing
+            ));
+        }
+
         println!(
-            "DEBUG: Loading {} bytes of program data into ROM components",
-            program_data.len()
+            "DEBUG: Loading {} bytes of program data into {} ROM components",
+            program_data.len(),
+            rom_names.len()
         );
 
-        // Load program data into ROM_4001_1 (first 256 bytes)
-        if let Some(rom1_component) = self.components.get_mut("ROM_4001_1") {
-            if let Ok(_rom1) = rom1_component.lock() {
-                let rom1_data = &program_data[..program_data.len().min(256)];
-                println!("DEBUG: Loading {} bytes into ROM_4001_1", rom1_data.len());
-                println!("DEBUG: Program data: {:02X?}", rom1_data);
-                // TODO: Load data using Intel4001's load_rom_data method when downcast is working
-                println!("DEBUG: Program data should be loaded into ROM_4001_1");
+        for (name, chip) in rom_names.iter().zip(chips) {
+            let start = (chip * 256).min(program_data.len());
+            let end = ((chip + 1) * 256).min(program_data.len());
+
+            // Pad every ROM to a full page so stale contents never survive a reload
+            let mut page = program_data[start..end].to_vec();
+            page.resize(256, 0);
+
+            let component = &self.components[name];
+            let mut guard = component
+                .lock()
+                .map_err(|_| format!("Failed to lock ROM component {}", name))?;
+            let rom = guard
+                .as_any_mut()
+                .downcast_mut::<Intel4001>()
+                .ok_or_else(|| format!("Component {} is not an Intel 4001", name))?;
+            rom.load_rom_data(page, 0)?;
+
+            if end > start {
+                println!("DEBUG: Loaded {} bytes into {}", end - start, name);
             }
-        } else {
-            println!("DEBUG: Warning - ROM_4001_1 component not found");
         }
 
-        // Load remaining data into ROM_4001_2 if program is larger than 256 bytes
-        if program_data.len() > 256 {
-            if let Some(rom2_component) = self.components.get_mut("ROM_4001_2") {
-                if let Ok(_rom2) = rom2_component.lock() {
-                    let rom2_data = &program_data[256..program_data.len().min(512)];
-                    println!("DEBUG: Loading {} bytes into ROM_4001_2", rom2_data.len());
-                    // TODO: Load data using Intel4001's load_rom_data method when available
-                }
-            } else {
-                println!("DEBUG: Warning - ROM_4001_2 component not found");
+        Ok(())
+    }
+
+    /// Get the names of all 8-bit bus ROM components
+    /// Returns: Vector of ROM component names in name order
+    fn get_rom_8bit_names(&self) -> Vec<String> {
+        use crate::components::memory::rom_8bit::Rom8Bit;
+
+        let mut names: Vec<String> = self
+            .components
+            .iter()
+            .filter(|(_, component)| {
+                component
+                    .lock()
+                    .map(|guard| guard.as_any().is::<Rom8Bit>())
+                    .unwrap_or(false)
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Load program data into the 8-bit ROM that has no image file in the configuration
+    /// ROMs whose `file` property names an image keep it, so a system where every ROM
+    /// has one ignores the program data
+    /// Parameters: program_data - Binary program data to load at the ROM's first byte
+    /// Returns: Ok(()) on success, Err(String) if several ROMs could take it or it does not fit
+    fn load_program_into_rom_8bit(&mut self, program_data: &[u8]) -> Result<(), String> {
+        use crate::components::memory::rom_8bit::Rom8Bit;
+
+        let config = &self.config;
+        let has_image_file = |name: &String| {
+            config.components.values().any(|component_config| {
+                component_config.instance_names().contains(name)
+                    && component_config.properties_for(name).contains_key("file")
+            })
+        };
+        let free: Vec<String> = self
+            .get_rom_8bit_names()
+            .into_iter()
+            .filter(|name| !has_image_file(name))
+            .collect();
+
+        let name = match free.as_slice() {
+            [] => {
+                println!("DEBUG: Every ROM loads its image from the configuration, program data not loaded");
+                return Ok(());
             }
-        }
+            [name] => name.clone(),
+            _ => {
+                return Err(format!(
+                    "{} ROMs without an image file found; cannot choose program memory",
+                    free.len()
+                ))
+            }
+        };
+
+        let mut guard = self.components[&name]
+            .lock()
+            .map_err(|_| format!("Failed to lock ROM component {}", name))?;
+        let rom = guard
+            .as_any_mut()
+            .downcast_mut::<Rom8Bit>()
+            .ok_or_else(|| format!("Component {} is not an 8-bit ROM", name))?;
+        rom.erase();
+        rom.load_data(program_data, 0)?;
+
+        println!(
+            "DEBUG: Loaded {} bytes of program data into {}",
+            program_data.len(),
+            name
+        );
+        Ok(())
+    }
+
+    /// Load program data at address 0 of the system's generic RAM
+    /// Parameters: program_data - Binary program data to load
+    /// Returns: Ok(()) on success, Err(String) unless there is exactly one RAM that fits it
+    fn load_program_into_ram(&mut self, program_data: &[u8]) -> Result<(), String> {
+        use crate::components::memory::generic_ram::GenericRam;
 
-        println!("DEBUG: Program loading completed");
+        let mut ram_names: Vec<&String> = self
+            .components
+            .iter()
+            .filter(|(_, component)| {
+                component
+                    .lock()
+                    .map(|guard| guard.as_any().is::<GenericRam>())
+                    .unwrap_or(false)
+            })
+            .map(|(name, _)| name)
+            .collect();
+        ram_names.sort();
+
+        let name = match ram_names.as_slice() {
+            [] => return Err("No Intel 4001 ROM or generic RAM components found in system".to_string()),
+            [name] => (*name).clone(),
+            _ => {
+                return Err(format!(
+                    "No Intel 4001 ROM components and {} generic RAMs found; cannot choose program memory",
+                    ram_names.len()
+                ))
+            }
+        };
+
+        let mut guard = self.components[&name]
+            .lock()
+            .map_err(|_| format!("Failed to lock RAM component {}", name))?;
+        let ram = guard
+            .as_any_mut()
+            .downcast_mut::<GenericRam>()
+            .ok_or_else(|| format!("Component {} is not a generic RAM", name))?;
+        ram.clear();
+        ram.load_data(program_data, 0)?;
+
+        println!(
+            "DEBUG: Loaded {} bytes of program data into {}",
+            program_data.len(),
+            name
+        );
         Ok(())
     }
 }
 
+/// Complete machine state of a `ConfigurableSystem`, as saved by `save_state`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemSnapshot {
+    pub system: String,    // Name of the system configuration
+    pub sim_time: SimTime, // Simulated time at the snapshot
+    pub edge_count: u64,   // Clock edges stepped in lockstep mode
+    pub components: BTreeMap<String, ComponentSnapshot>, // State of every component by name
+}
+
+/// State of one component within a `SystemSnapshot`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentSnapshot {
+    #[serde(rename = "type")]
+    pub component_type: String, // Registry type, checked on restore
+    pub state: Option<serde_json::Value>, // Internal state from `Component::snapshot_state`
+    pub pins: BTreeMap<String, BTreeMap<String, (PinValue, DriveStrength)>>, // Drivers per pin
+}
+
 #[derive(Debug, Clone)]
 pub struct SystemInfo {
     pub name: String,
@@ -628,3 +1963,23 @@ pub struct SystemInfo {
     pub rom_size: usize,
     pub ram_size: usize,
 }
+
+/// Snapshot of the Intel 4004/4040 registers shown by the console and GUI register views
+#[derive(Debug, Clone)]
+pub struct CpuRegisters {
+    pub program_counter: u16,
+    pub accumulator: u8,
+    pub carry: bool,
+    pub index_registers: [u8; 16], // R0-R15 of the selected bank
+    pub stack_pointer: u8,
+    pub stack: Vec<u16>, // Return addresses, most recently pushed first
+    pub instruction_count: u64,
+}
+
+/// Snapshot of one Intel 4002 shown by the GUI memory view
+#[derive(Debug, Clone)]
+pub struct RamChipContents {
+    pub name: String,
+    pub chip_id: u8, // Chip number within the bank
+    pub registers: [crate::components::memory::intel_4002::RamRegister; 4],
+}
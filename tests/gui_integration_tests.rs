@@ -121,8 +121,9 @@ mod gui_system_integration_tests {
         assert!(app.gui_state.system_loaded);
         assert!(!app.gui_state.system_running); // Should start as not running
 
-        // Test that GUI state has proper structure for synchronization
-        assert_eq!(app.gui_state.memory_state.ram_contents.len(), 4);
+        // Test that GUI state has proper structure for synchronization; the RAM chips
+        // are read from the system on the first update
+        assert!(app.gui_state.memory_state.ram_chips.is_empty());
         assert_eq!(app.gui_state.register_state.index_registers.len(), 16);
     }
 
@@ -221,14 +222,15 @@ mod gui_system_integration_tests {
             // Test that system provides memory information
             let _ = system_guard.get_system_info(); // Access system info
 
-            // Verify GUI state can hold memory data
-            let gui_state = rusty_emu::gui::state::GuiState::new();
-            assert_eq!(gui_state.memory_state.ram_contents.len(), 4);
+            // Verify GUI state can hold memory data: the basic system has one 4002
+            let mut gui_state = rusty_emu::gui::state::GuiState::new();
+            gui_state.memory_state.ram_chips = system_guard.get_ram_contents();
+            assert_eq!(gui_state.memory_state.ram_chips.len(), 1);
 
-            for bank in &gui_state.memory_state.ram_contents {
-                assert_eq!(bank.len(), 4);
+            for chip in &gui_state.memory_state.ram_chips {
+                assert_eq!(chip.registers.len(), 4);
             }
-        }
+        };
     }
 
     #[test]
@@ -293,7 +295,7 @@ mod gui_system_integration_tests {
         assert!(app.get_system().is_some());
 
         // Test that GUI state is properly initialized for the system
-        assert_eq!(app.gui_state.memory_state.ram_contents.len(), 4);
+        assert!(app.gui_state.memory_state.ram_chips.is_empty());
         assert_eq!(app.gui_state.register_state.index_registers.len(), 16);
     }
 
@@ -357,7 +359,7 @@ mod gui_system_integration_tests {
                 // In a real implementation, this would call update_from_system
                 // For now, we verify the state structure remains consistent
 
-                assert_eq!(app.gui_state.memory_state.ram_contents.len(), 4);
+                assert!(app.gui_state.memory_state.ram_chips.len() <= 1);
                 assert_eq!(app.gui_state.register_state.index_registers.len(), 16);
 
                 // Simulate state change
@@ -457,15 +459,16 @@ mod gui_error_handling_tests {
         let mut state = rusty_emu::gui::state::GuiState::new();
 
         // Simulate state corruption and recovery
-        state.memory_state.ram_contents[0][0] = 255; // Invalid value
+        state.memory_state.ram_chips = create_test_system().lock().unwrap().get_ram_contents();
+        state.memory_state.ram_chips[0].registers[0].main[0] = 255; // Invalid value
         state.register_state.accumulator = 255; // Invalid value
 
         // Test recovery by resetting state
-        state.memory_state.ram_contents = vec![[0; 4]; 4];
+        state.memory_state.ram_chips[0].registers = Default::default();
         state.register_state.accumulator = 0;
 
         // Verify recovery
-        assert_eq!(state.memory_state.ram_contents[0][0], 0);
+        assert_eq!(state.memory_state.ram_chips[0].registers[0].main[0], 0);
         assert_eq!(state.register_state.accumulator, 0);
     }
 }
@@ -533,21 +536,24 @@ mod gui_real_time_tests {
         // In a real implementation, this would test actual memory synchronization
         // For now, we test the data structures are compatible
 
-        if let Ok(_system_guard) = system.lock() {
+        if let Ok(system_guard) = system.lock() {
             // Test memory data structure compatibility
-            let gui_state = rusty_emu::gui::state::GuiState::new();
+            let mut gui_state = rusty_emu::gui::state::GuiState::new();
+            gui_state.memory_state.ram_chips = system_guard.get_ram_contents();
 
             // Verify memory layout matches expected Intel 4002 structure
-            assert_eq!(gui_state.memory_state.ram_contents.len(), 4); // 4 banks
+            assert_eq!(gui_state.memory_state.ram_chips.len(), 1); // 1 RAM chip
 
-            for bank in &gui_state.memory_state.ram_contents {
-                assert_eq!(bank.len(), 4); // 4 bytes per bank
+            for chip in &gui_state.memory_state.ram_chips {
+                assert_eq!(chip.registers.len(), 4); // 4 registers per chip
+                assert_eq!(chip.registers[0].main.len(), 16); // 16 main characters
+                assert_eq!(chip.registers[0].status.len(), 4); // 4 status characters
             }
 
             // Test bank selection bounds
             gui_state.memory_state.selected_bank = 0;
             gui_state.memory_state.selected_bank = 3; // Should be valid
-        }
+        };
     }
 
     #[test]
@@ -6,8 +6,10 @@
 #![cfg(test)]
 
 use eframe::egui;
+use rusty_emu::components::memory::intel_4002::RamRegister;
 use rusty_emu::gui::components::*;
 use rusty_emu::gui::state::*;
+use rusty_emu::system_config::RamChipContents;
 use std::sync::{Arc, Mutex};
 
 /// Test utilities for GUI testing
@@ -42,13 +44,19 @@ mod gui_test_utils {
         state.system_running = true;
         state.cycle_count = 12345;
 
-        // Set up test memory data
-        state.memory_state.ram_contents = vec![
-            [0x12, 0x34, 0x56, 0x78],
-            [0x9A, 0xBC, 0xDE, 0xF0],
-            [0x11, 0x22, 0x33, 0x44],
-            [0x55, 0x66, 0x77, 0x88],
-        ];
+        // Set up test memory data: one 4002 per bank
+        state.memory_state.ram_chips = (0..4)
+            .map(|bank| {
+                let mut registers = [RamRegister::default(); 4];
+                registers[0].main[0] = bank as u8;
+                registers[0].status[3] = 0xF;
+                RamChipContents {
+                    name: format!("RAM_BANK_{}", bank),
+                    chip_id: 0,
+                    registers,
+                }
+            })
+            .collect();
 
         // Set up test register data
         state.register_state.accumulator = 0x0F;
@@ -174,9 +182,10 @@ mod gui_components_tests {
         assert!(memory_viewer.show_hex);
 
         // Test that memory data is accessible
-        assert_eq!(state.memory_state.ram_contents.len(), 4);
-        for bank in &state.memory_state.ram_contents {
-            assert_eq!(bank.len(), 4);
+        assert_eq!(state.memory_state.ram_chips.len(), 4);
+        for chip in &state.memory_state.ram_chips {
+            assert_eq!(chip.registers.len(), 4);
+            assert_eq!(chip.registers[0].status[3], 0xF);
         }
     }
 
@@ -270,7 +279,7 @@ mod gui_state_tests {
         assert!(!state.component_states.clock_running);
 
         // Test memory state
-        assert_eq!(state.memory_state.ram_contents.len(), 4);
+        assert!(state.memory_state.ram_chips.is_empty());
         assert_eq!(state.memory_state.selected_bank, 0);
         assert_eq!(state.memory_state.selected_address, 0);
 
@@ -315,17 +324,14 @@ mod gui_state_tests {
     fn test_memory_state_initialization() {
         let state = GuiState::new();
 
-        // Verify memory banks are properly initialized
-        assert_eq!(state.memory_state.ram_contents.len(), 4);
-
-        for (bank_idx, bank) in state.memory_state.ram_contents.iter().enumerate() {
-            assert_eq!(bank.len(), 4, "Bank {} should have 4 bytes", bank_idx);
+        // RAM chips are read from the system, so there are none before one is attached
+        assert!(state.memory_state.ram_chips.is_empty());
+        assert_eq!(state.memory_state.selected_bank, 0);
 
-            // All bytes should be initialized to 0
-            for (addr, &value) in bank.iter().enumerate() {
-                assert_eq!(value, 0, "Bank {}, address {} should be 0", bank_idx, addr);
-            }
-        }
+        // A fresh register has all main and status characters cleared
+        let register = RamRegister::default();
+        assert_eq!(register.main, [0; 16]);
+        assert_eq!(register.status, [0; 4]);
     }
 
     #[test]
@@ -430,7 +436,7 @@ mod gui_integration_tests {
         // For now, we verify the components exist and have valid state
 
         // Test control panel rendering (would panic if component is invalid)
-        components.render_control_panel(&mut create_test_ui(&ctx), &mut state);
+        components.render_control_panel(&mut create_test_ui(&ctx), &mut state, None);
 
         // Test memory viewer rendering
         components.render_memory_viewer(&mut create_test_ui(&ctx), &state);
@@ -82,7 +82,7 @@ mod intel_4001_integration_tests {
         assert_eq!(rom.get_access_time(), 1);
 
         // Test address latch time handling
-        let test_time = std::time::Instant::now();
+        let test_time = rusty_emu::sim_time::SimTime::from_nanos(1_234);
         rom.set_address_latch_time(Some(test_time));
         assert_eq!(rom.get_address_latch_time(), Some(test_time));
     }
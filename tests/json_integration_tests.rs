@@ -52,6 +52,141 @@ mod json_system_tests {
         assert!(!system.is_running());
     }
 
+    #[test]
+    fn test_max_system_wires_ram_banks_to_cm_ram_lines() {
+        use rusty_emu::components::memory::intel_4001::Intel4001;
+        use rusty_emu::components::memory::intel_4002::{Intel4002, RamVariant};
+        use rusty_emu::pin::PinValue;
+
+        let factory = SystemFactory::new();
+        let system = factory.create_from_json("configs/mcs4_max.json").unwrap();
+        let components = system.get_components();
+
+        // CPU, clock, 16 ROMs, 16 RAMs in four banks and 3 shift registers
+        assert_eq!(system.get_system_info().component_count, 37);
+        assert_eq!(system.get_rom_names().len(), 16);
+
+        // Chips in an array are numbered by position; the number picks the 4002 variant
+        let ram = |name: &str| {
+            let ram = components[name].lock().unwrap();
+            let ram = ram.as_any().downcast_ref::<Intel4002>().unwrap();
+            (ram.get_chip_id(), ram.get_variant())
+        };
+        assert_eq!(ram("RAM_4002_05"), (1, RamVariant::Type1));
+        assert_eq!(ram("RAM_4002_07"), (3, RamVariant::Type2));
+        let rom_chip_id = |name: &str| {
+            let rom = components[name].lock().unwrap();
+            rom.as_any()
+                .downcast_ref::<Intel4001>()
+                .unwrap()
+                .get_chip_id()
+        };
+        assert_eq!(rom_chip_id("ROM_4001_15"), 15);
+
+        // CM-RAM2 reaches exactly the four RAMs of bank 2
+        let pin = |component: &str, pin: &str| {
+            components[component].lock().unwrap().get_pin(pin).unwrap()
+        };
+        pin("CPU_4004", "CM_RAM2")
+            .lock()
+            .unwrap()
+            .set_driver(Some("TEST".to_string()), PinValue::High);
+        for index in 0..16 {
+            let cm = pin(&format!("RAM_4002_{:02}", index), "CM")
+                .lock()
+                .unwrap()
+                .read();
+            assert_eq!(
+                cm == PinValue::High,
+                (8..12).contains(&index),
+                "RAM {}",
+                index
+            );
+        }
+    }
+
+    #[test]
+    fn test_max_system_chains_shift_registers() {
+        use rusty_emu::components::memory::intel_4002::Intel4002;
+        use rusty_emu::components::memory::intel_4003::Intel4003;
+
+        let factory = SystemFactory::new();
+        let system = factory.create_from_json("configs/mcs4_max.json").unwrap();
+        let components = system.get_components();
+        let chain = ["SHIFT_4003_RAM3_1", "SHIFT_4003_RAM3_2"];
+        for name in chain {
+            components[name].lock().unwrap().start();
+        }
+
+        // RAM 3 output lines: O0 clocks both 4003s, O1 feeds the first, O2 enables both
+        let set_outputs = |cp: u8, data: u8, enable: u8| {
+            let mut ram = components["RAM_4002_03"].lock().unwrap();
+            let ram = ram.as_any_mut().downcast_mut::<Intel4002>().unwrap();
+            ram.set_output_port(0, cp).unwrap();
+            ram.set_output_port(1, data).unwrap();
+            ram.set_output_port(2, enable).unwrap();
+            drop(ram);
+            for name in chain {
+                components[name].lock().unwrap().update();
+            }
+        };
+
+        // Shift a 1 followed by 11 zeros: after 12 clocks it sits in Q1 of the second part
+        for bit in std::iter::once(1).chain(std::iter::repeat(0).take(11)) {
+            set_outputs(0, bit, 1);
+            set_outputs(1, bit, 1);
+        }
+
+        let shift_register = |name: &str| {
+            let sr = components[name].lock().unwrap();
+            let sr = sr.as_any().downcast_ref::<Intel4003>().unwrap();
+            (sr.get_shift_register(), sr.get_outputs())
+        };
+        assert_eq!(shift_register("SHIFT_4003_RAM3_1"), (0, 0));
+        assert_eq!(shift_register("SHIFT_4003_RAM3_2"), (0b10, 0b10));
+        assert_eq!(
+            components["SHIFT_4003_RAM3_2"]
+                .lock()
+                .unwrap()
+                .get_pin("Q1")
+                .unwrap()
+                .lock()
+                .unwrap()
+                .read(),
+            rusty_emu::pin::PinValue::High
+        );
+    }
+
+    #[test]
+    fn test_io_demo_rom_line_mask_options() {
+        use rusty_emu::components::memory::intel_4001::{
+            Intel4001, IoDirection, IoLineConfig, IoPull,
+        };
+        use rusty_emu::pin::PinValue;
+
+        let factory = SystemFactory::new();
+        let system = factory
+            .create_from_json("configs/mcs4_io_demo.json")
+            .unwrap();
+        let rom = system.get_components()["ROM_4001_0"].lock().unwrap();
+        let rom4001 = rom.as_any().downcast_ref::<Intel4001>().unwrap();
+
+        assert_eq!(
+            rom4001.get_io_line_config(1),
+            Some(IoLineConfig {
+                direction: Some(IoDirection::Output),
+                inverted: true,
+                pull: IoPull::None,
+            })
+        );
+        assert_eq!(rom4001.get_io_line_config(3).unwrap().pull, IoPull::Up);
+        assert_eq!(rom4001.get_io_direction(2), Some(IoDirection::Input));
+        assert_eq!(
+            rom.get_pin("IO2").unwrap().lock().unwrap().read(),
+            PinValue::High
+        );
+    }
+
     #[test]
     fn test_invalid_config_file() {
         let factory = SystemFactory::new();
@@ -71,6 +206,154 @@ mod json_system_tests {
         let _ = fs::remove_file("test_invalid.json");
     }
 
+    #[test]
+    fn test_6502_cpu_types_and_illegal_opcode_property() {
+        use rusty_emu::components::cpu::mos_6502::{Mos6502Variant, MOS6502};
+        use rusty_emu::components::cpu::WDC65C02;
+
+        let config = r#"{
+            "name": "Cpu6502Variants",
+            "description": "6502 CPU types",
+            "version": "1.0",
+            "metadata": {},
+            "components": {
+                "CPU_NMOS": {"single": {"component_type": "mos_6502", "name": "CPU_NMOS",
+                    "properties": {"nmos_illegal": true}}},
+                "CPU_PLAIN": {"single": {"component_type": "mos_6502", "name": "CPU_PLAIN",
+                    "properties": {}}},
+                "CPU_CMOS": {"single": {"component_type": "wdc_65c02", "name": "CPU_CMOS",
+                    "properties": {}}}
+            },
+            "connections": {}
+        }"#;
+        fs::write("test_6502_variants.json", config).unwrap();
+        let result = SystemFactory::new().create_from_json("test_6502_variants.json");
+        let _ = fs::remove_file("test_6502_variants.json");
+        let system = result.unwrap();
+
+        let components = system.get_components();
+        let variant = |name: &str| {
+            let component = components[name].lock().unwrap();
+            component
+                .as_any()
+                .downcast_ref::<MOS6502>()
+                .map(|cpu| cpu.get_variant())
+                .or_else(|| {
+                    component
+                        .as_any()
+                        .downcast_ref::<WDC65C02>()
+                        .map(|cpu| cpu.get_base_cpu().get_variant())
+                })
+        };
+        assert_eq!(variant("CPU_NMOS"), Some(Mos6502Variant::Nmos6502Illegal));
+        assert_eq!(variant("CPU_PLAIN"), Some(Mos6502Variant::Nmos6502));
+        assert_eq!(variant("CPU_CMOS"), Some(Mos6502Variant::Wdc65C02));
+    }
+
+    #[test]
+    fn test_6502_system_runs_rom_program_through_decoder() {
+        use rusty_emu::components::cpu::MOS6502;
+        use rusty_emu::components::memory::generic_ram::GenericRam;
+
+        let factory = SystemFactory::new();
+        let mut system = factory
+            .create_from_json("configs/mos6502_basic.json")
+            .unwrap();
+        assert_eq!(
+            &system.get_update_order()[..3],
+            ["SYSTEM_CLOCK", "CPU_6502", "DECODER"]
+        );
+
+        // The ROM image comes from the configuration, so program data is not loaded
+        system.load_program_data(&[0xEA; 16]).unwrap();
+        system.run_lockstep(2000).unwrap();
+        system.stop_lockstep();
+
+        let components = system.get_components();
+        let ram = components["RAM"].lock().unwrap();
+        let ram = ram.as_any().downcast_ref::<GenericRam>().unwrap();
+        assert_eq!(
+            &ram.get_contents()[0x0200..0x020D],
+            &[0, 1, 1, 2, 3, 5, 8, 13, 21, 34, 55, 89, 144]
+        );
+        let cpu = components["CPU_6502"].lock().unwrap();
+        let cpu = cpu.as_any().downcast_ref::<MOS6502>().unwrap();
+        assert!((0xFF21..=0xFF24).contains(&cpu.get_program_counter()));
+        assert_eq!(cpu.get_stack_pointer(), 0xFF);
+    }
+
+    #[test]
+    fn test_8bit_memory_properties() {
+        use rusty_emu::components::interface::address_decoder::AddressDecoder;
+        use rusty_emu::components::memory::rom_8bit::Rom8Bit;
+
+        let config = r#"{
+            "name": "Memory8Bit",
+            "description": "8-bit memory properties",
+            "version": "1.0",
+            "metadata": {},
+            "components": {
+                "ROM": {"single": {"component_type": "rom_8bit", "name": "ROM",
+                    "properties": {"base_address": "$FF00", "file": "programs/mos6502_fibonacci.bin"}}},
+                "BLANK": {"single": {"component_type": "rom_8bit", "name": "BLANK",
+                    "properties": {"size": 1024, "base_address": 49152}}},
+                "DECODER": {"single": {"component_type": "address_decoder", "name": "DECODER",
+                    "properties": {"patterns": ["11", "0x"]}}}
+            },
+            "connections": {}
+        }"#;
+        fs::write("test_8bit_memory.json", config).unwrap();
+        let result = SystemFactory::new().create_from_json("test_8bit_memory.json");
+        let _ = fs::remove_file("test_8bit_memory.json");
+        let mut system = result.unwrap();
+
+        {
+            let components = system.get_components();
+            let rom = components["ROM"].lock().unwrap();
+            let rom = rom.as_any().downcast_ref::<Rom8Bit>().unwrap();
+            assert_eq!(rom.get_size(), 256);
+            assert_eq!(rom.get_base_address(), 0xFF00);
+            assert_eq!(rom.read_byte(0xFD), Some(0xFF));
+            let decoder = components["DECODER"].lock().unwrap();
+            let decoder = decoder.as_any().downcast_ref::<AddressDecoder>().unwrap();
+            assert_eq!(decoder.decode(0xC000), vec![true, false]);
+        }
+
+        // Program data goes to the only ROM without an image file
+        system.load_program_data(&[0xA9, 0x01]).unwrap();
+        let components = system.get_components();
+        let blank = components["BLANK"].lock().unwrap();
+        let blank = blank.as_any().downcast_ref::<Rom8Bit>().unwrap();
+        assert_eq!(blank.get_base_address(), 0xC000);
+        assert_eq!(&blank.get_contents()[..3], &[0xA9, 0x01, 0xFF]);
+    }
+
+    #[test]
+    fn test_8bit_memory_property_errors() {
+        let invalid = [
+            r#""RAM": {"single": {"component_type": "ram_8bit", "name": "RAM",
+                "properties": {"size": 8192, "base_address": "0xF000"}}}"#,
+            r#""RAM": {"single": {"component_type": "ram_8bit", "name": "RAM",
+                "properties": {"base_address": "F000"}}}"#,
+            r#""ROM": {"single": {"component_type": "rom_8bit", "name": "ROM",
+                "properties": {"size": 16, "file": "programs/mos6502_fibonacci.bin"}}}"#,
+            r#""DECODER": {"single": {"component_type": "address_decoder", "name": "DECODER",
+                "properties": {"patterns": ["1z"]}}}"#,
+        ];
+        for (index, component) in invalid.iter().enumerate() {
+            let config = format!(
+                r#"{{"name": "Invalid", "description": "", "version": "1.0", "metadata": {{}},
+                    "components": {{{}}}, "connections": {{}}}}"#,
+                component
+            );
+            let path = format!("test_8bit_invalid_{}.json", index);
+            fs::write(&path, config).unwrap();
+            let result = SystemFactory::new().create_from_json(&path);
+            let _ = fs::remove_file(&path);
+            assert!(result.is_err(), "config {} should be rejected", index);
+        }
+    }
+
     #[test]
     fn test_system_configuration_metadata() {
         let factory = SystemFactory::new();
@@ -111,6 +394,861 @@ mod system_execution_tests {
         let info = system.get_system_info();
         assert_eq!(info.name, "IntelMcs4");
     }
+
+    /// Run the basic system in lockstep mode and record the CPU-visible bus on every edge
+    fn lockstep_trace(edges: u64) -> (Vec<String>, u64, u16, u64) {
+        use rusty_emu::components::cpu::intel_4004::Intel4004;
+
+        let factory = SystemFactory::new();
+        let mut system = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+        system
+            .load_program_data(&fs::read("programs/fibonacci.bin").unwrap())
+            .unwrap();
+
+        system.start_lockstep();
+        let mut trace = Vec::new();
+        for _ in 0..edges {
+            system.step_lockstep().unwrap();
+
+            let cpu = system.get_components()["CPU_4004"].lock().unwrap();
+            let pins: String = ["PHI1", "PHI2", "SYNC", "D3", "D2", "D1", "D0"]
+                .iter()
+                .map(|pin| cpu.get_pin(pin).unwrap().lock().unwrap().read().to_char())
+                .collect();
+            trace.push(pins);
+        }
+
+        let components = system.get_components();
+        let cpu = components["CPU_4004"].lock().unwrap();
+        let cpu = cpu.as_any().downcast_ref::<Intel4004>().unwrap();
+        (
+            trace,
+            cpu.get_cycle_count(),
+            cpu.get_program_counter(),
+            system.get_simulated_time_ns(),
+        )
+    }
+
+    #[test]
+    fn test_lockstep_update_order() {
+        let factory = SystemFactory::new();
+        let system = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+
+        assert_eq!(
+            system.get_update_order(),
+            &[
+                "SYSTEM_CLOCK",
+                "CPU_4004",
+                "RAM_4002",
+                "ROM_4001_1",
+                "ROM_4001_2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lockstep_execution_is_deterministic() {
+        let (trace_a, cycles_a, pc_a, time_a) = lockstep_trace(400);
+        let (trace_b, cycles_b, pc_b, time_b) = lockstep_trace(400);
+
+        assert_eq!(trace_a, trace_b);
+        assert_eq!(cycles_a, cycles_b);
+        assert_eq!(pc_a, pc_b);
+        assert_eq!(time_a, time_b);
+
+        // Clock edges alternate the two phases in simulated time
+        assert_eq!(&trace_a[0][..2], "01");
+        assert_eq!(&trace_a[1][..2], "10");
+        assert_eq!(cycles_a, 400);
+        assert!(time_a > 0);
+    }
+
+    #[test]
+    fn test_functional_mode_runs_loaded_program() {
+        use rusty_emu::components::cpu::intel_4004::Intel4004;
+
+        let factory = SystemFactory::new();
+        let mut system = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+        system
+            .load_program_data(&fs::read("programs/fibonacci.bin").unwrap())
+            .unwrap();
+
+        // Functional execution must be requested explicitly
+        assert!(system.run_functional(1).is_err());
+
+        system.enable_functional_mode().unwrap();
+        system.run_functional(10_000).unwrap();
+
+        let components = system.get_components();
+        let cpu = components["CPU_4004"].lock().unwrap();
+        assert_eq!(
+            cpu.get_pin("SYNC").unwrap().lock().unwrap().read(),
+            rusty_emu::pin::PinValue::Low
+        );
+        let cpu = cpu.as_any().downcast_ref::<Intel4004>().unwrap();
+        assert_eq!(cpu.get_instruction_count(), 10_000);
+    }
+
+    #[test]
+    fn test_lockstep_matches_functional_across_rom_chips() {
+        // Page 0 lives in ROM chip 0 and page 1 in ROM chip 1
+        let mut program = vec![0u8; 0x110];
+        program[0x000..0x004].copy_from_slice(&[
+            0xD5, // LDM 5
+            0xB0, // XCH R0
+            0x41, 0x00, // JUN 100
+        ]);
+        program[0x100..0x105].copy_from_slice(&[
+            0xD7, // LDM 7
+            0x80, // ADD R0
+            0xB1, // XCH R1
+            0x41, 0x00, // JUN 100
+        ]);
+
+        let factory = SystemFactory::new();
+        let mut functional = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+        functional.load_program_data(&program).unwrap();
+        functional.enable_functional_mode().unwrap();
+        functional.run_functional(9).unwrap();
+
+        let mut lockstep = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+        lockstep.load_program_data(&program).unwrap();
+        lockstep.start_lockstep();
+        while lockstep.get_cpu_registers().unwrap().instruction_count < 9 {
+            lockstep.step_lockstep().unwrap();
+        }
+        lockstep.stop_lockstep();
+
+        let expected = functional.get_cpu_registers().unwrap();
+        let actual = lockstep.get_cpu_registers().unwrap();
+        assert_eq!(expected.program_counter, 0x102);
+        assert_eq!(actual.program_counter, expected.program_counter);
+        assert_eq!(actual.accumulator, expected.accumulator);
+        assert_eq!(actual.index_registers, expected.index_registers);
+        assert_eq!(actual.index_registers[1], 0xC);
+    }
+
+    #[test]
+    fn test_lockstep_matches_functional_with_ram_and_rom_ports() {
+        use rusty_emu::components::memory::intel_4001::Intel4001;
+        use rusty_emu::components::memory::intel_4002::Intel4002;
+
+        let program = [
+            0x20, 0x00, // FIM P0, 00: RAM chip 0, register 0, character 0
+            0x21, // SRC P0
+            0xD7, // LDM 7
+            0xE0, // WRM
+            0xD0, // LDM 0
+            0xE9, // RDM
+            0xE6, // WR2
+            0xD3, // LDM 3
+            0xEB, // ADM
+            0xE1, // WMP
+            0x22, 0x10, // FIM P1, 10: ROM chip 1, RAM register 1
+            0x23, // SRC P1
+            0xD9, // LDM 9
+            0xE2, // WRR
+            0xD0, // LDM 0
+            0xEA, // RDR
+            0xB4, // XCH R4
+            0x21, // SRC P0
+            0xEE, // RD2
+            0xE8, // SBM
+            0xB5, // XCH R5
+            0xD1, // LDM 1
+            0xFD, // DCL: bank 1 has no RAM
+            0xD5, // LDM 5
+            0xE9, // RDM
+            0xB6, // XCH R6
+            0x40, 0x21, // JUN 021
+        ];
+        let instructions = 30;
+
+        let factory = SystemFactory::new();
+        let mut functional = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+        functional.load_program_data(&program).unwrap();
+        functional.enable_functional_mode().unwrap();
+        functional.run_functional(instructions).unwrap();
+
+        let mut lockstep = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+        lockstep.load_program_data(&program).unwrap();
+        lockstep.start_lockstep();
+        while lockstep.get_cpu_registers().unwrap().instruction_count < instructions {
+            lockstep.step_lockstep().unwrap();
+        }
+        lockstep.stop_lockstep();
+
+        let expected = lockstep.get_cpu_registers().unwrap();
+        let actual = functional.get_cpu_registers().unwrap();
+        assert_eq!(expected.index_registers[4], 0x9); // RDR
+        assert_eq!(expected.index_registers[6], 0x0); // RDM with no RAM on the bank
+        assert_eq!(actual.program_counter, expected.program_counter);
+        assert_eq!(actual.accumulator, expected.accumulator);
+        assert_eq!(actual.carry, expected.carry);
+        assert_eq!(actual.index_registers, expected.index_registers);
+
+        let rams = |system: &rusty_emu::system_config::ConfigurableSystem| {
+            let components = system.get_components();
+            let ram = components["RAM_4002"].lock().unwrap();
+            let ram = ram.as_any().downcast_ref::<Intel4002>().unwrap();
+            let ports: Vec<Option<u8>> = (0..4).map(|port| ram.get_output_port(port)).collect();
+            (*ram.get_registers(), ports)
+        };
+        let (registers, ports) = rams(&lockstep);
+        assert_eq!(registers[0].main[0], 0x7);
+        assert_eq!(registers[0].status[2], 0x7);
+        assert_eq!(ports, [Some(0), Some(1), Some(0), Some(1)]); // WMP of 0xA
+        assert_eq!(rams(&functional), (registers, ports));
+
+        let rom_port = |system: &rusty_emu::system_config::ConfigurableSystem| {
+            let components = system.get_components();
+            let rom = components["ROM_4001_2"].lock().unwrap();
+            rom.as_any()
+                .downcast_ref::<Intel4001>()
+                .unwrap()
+                .get_output_latch()
+        };
+        assert_eq!(rom_port(&lockstep), 0x9);
+        assert_eq!(rom_port(&functional), 0x9);
+    }
+
+    #[test]
+    fn test_ram_contents_snapshot_shows_status_characters() {
+        let factory = SystemFactory::new();
+        let mut system = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+
+        let program = [
+            0x20, 0x23, // FIM P0, 23: register 2, character 3
+            0xD9, // LDM 9
+            0x21, // SRC P0
+            0xE5, // WR1
+            0xE0, // WRM
+            0x40, 0x06, // JUN 006
+        ];
+        system.load_program_data(&program).unwrap();
+        system.start_lockstep();
+        while system.get_cpu_registers().unwrap().instruction_count < 6 {
+            system.step_lockstep().unwrap();
+        }
+        system.stop_lockstep();
+
+        let rams = system.get_ram_contents();
+        assert_eq!(rams.len(), 1);
+        assert_eq!(rams[0].chip_id, 0);
+        assert_eq!(rams[0].registers[2].status, [0, 0x9, 0, 0]);
+        assert_eq!(rams[0].registers[2].main[3], 0x9);
+        assert_eq!(rams[0].registers[0].status, [0; 4]);
+    }
+
+    #[test]
+    fn test_assembled_program_runs_in_lockstep() {
+        let assembly = rusty_emu::asm::assemble_file("programs/fibonacci_ram.asm").unwrap();
+        assert!(assembly.warnings.is_empty());
+        assert_eq!(
+            assembly.binary,
+            fs::read("programs/fibonacci_ram.bin").unwrap()
+        );
+        assert_eq!(
+            assembly.symbol_file(),
+            fs::read_to_string("programs/fibonacci_ram.sym").unwrap()
+        );
+
+        let factory = SystemFactory::new();
+        let mut system = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+        system.load_program_data(&assembly.binary).unwrap();
+        system.start_lockstep();
+        // 7 setup instructions, then 9 per number
+        while system.get_cpu_registers().unwrap().instruction_count < 85 {
+            system.step_lockstep().unwrap();
+        }
+        system.stop_lockstep();
+
+        let rams = system.get_ram_contents();
+        assert_eq!(&rams[0].registers[0].main[..8], &[0, 1, 1, 2, 3, 5, 8, 13]);
+        let pc = system.get_cpu_registers().unwrap().program_counter;
+        assert!((assembly.symbols["DONE"]..=assembly.symbols["DONE"] + 1).contains(&pc));
+    }
+
+    #[test]
+    fn test_disassembly_reads_program_memory_across_rom_chips() {
+        use rusty_emu::disasm::{disassemble_from_pc, SymbolTable};
+
+        let factory = SystemFactory::new();
+        let mut system = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+        let mut program = vec![0u8; 0x101];
+        program[0x0FF] = 0x41; // JUN 123, split across ROM chips 0 and 1
+        program[0x100] = 0x23;
+        system.load_program_data(&program).unwrap();
+        assert_eq!(system.read_program_memory(0x0FF, 2), vec![0x41, 0x23]);
+        // Pages without a ROM read as 0 and addresses wrap at 4 KiB
+        assert_eq!(system.read_program_memory(0xFFF, 2), vec![0x00, 0x00]);
+
+        let symbols =
+            SymbolTable::parse(&fs::read_to_string("programs/fibonacci_ram.sym").unwrap()).unwrap();
+        system
+            .load_program_data(&fs::read("programs/fibonacci_ram.bin").unwrap())
+            .unwrap();
+        let instructions = disassemble_from_pc(&system, 3, Some(&symbols)).unwrap();
+        assert_eq!(instructions.len(), 3);
+        assert_eq!(instructions[0].address, 0x000);
+        assert_eq!(instructions[0].text, "FIM P0, 0x00");
+        assert_eq!(instructions[1].address, 0x002);
+        assert_eq!(symbols.name_at(0x012), Some("DONE"));
+
+        let system = factory
+            .create_from_json("configs/mos6502_basic.json")
+            .unwrap();
+        assert!(disassemble_from_pc(&system, 3, None).is_err());
+    }
+
+    #[test]
+    fn test_debugger_breakpoints_watchpoints_and_edits() {
+        use rusty_emu::debugger::Debugger;
+        use rusty_emu::disasm::SymbolTable;
+
+        let factory = SystemFactory::new();
+        let mut system = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+        system
+            .load_program_data(&fs::read("programs/fibonacci_ram.bin").unwrap())
+            .unwrap();
+        let symbols = SymbolTable::load("programs/fibonacci_ram.sym").unwrap();
+        let mut debugger = Debugger::new();
+        let mut run = |system: &mut _, command: &str| {
+            debugger.execute(system, command, Some(&symbols)).unwrap()
+        };
+
+        assert_eq!(
+            run(&mut system, "break LOOP"),
+            vec!["Breakpoint at 0x008 (LOOP)"]
+        );
+        let output = run(&mut system, "continue");
+        assert_eq!(output[0], "Breakpoint at 0x008 (LOOP)");
+        assert_eq!(output[1], "> 008: 21     SRC P0");
+        assert_eq!(system.get_cpu_registers().unwrap().instruction_count, 7);
+
+        // Stepping off a breakpoint executes the instruction there
+        run(&mut system, "delete LOOP");
+        run(&mut system, "step 2");
+        assert_eq!(system.get_cpu_registers().unwrap().program_counter, 0x00A);
+
+        assert_eq!(
+            run(&mut system, "watch ram 0:0:2"),
+            vec!["Watching ram 0:0:2 = 0x0"]
+        );
+        let output = run(&mut system, "continue");
+        assert_eq!(output[0], "Watchpoint ram 0:0:2: 0x0 -> 0x1");
+        assert_eq!(output[1], "> 00B: 61     INC R1");
+
+        run(&mut system, "unwatch");
+        run(&mut system, "break DONE");
+        assert_eq!(run(&mut system, "c")[0], "Breakpoint at 0x012 (DONE)");
+        assert_eq!(
+            run(&mut system, "x ram 0:0"),
+            vec!["ram 0:0  0 1 1 2 3 5 8 D 0 0 0 0 0 0 0 0  status 0 0 0 0"]
+        );
+        assert_eq!(run(&mut system, "x 0 4"), vec!["000: 20 00 D0 B2"]);
+
+        // Register and memory edits
+        run(&mut system, "set ram 0:0:8 0xF");
+        assert_eq!(system.read_ram_character(0, 0, 8), Ok(0xF));
+        run(&mut system, "set acc 9");
+        run(&mut system, "set r15 3");
+        run(&mut system, "set pc START");
+        let registers = system.get_cpu_registers().unwrap();
+        assert_eq!(registers.accumulator, 9);
+        assert_eq!(registers.index_registers[15], 3);
+        assert_eq!(registers.program_counter, 0x000);
+
+        assert!(debugger
+            .execute(&mut system, "set r16 0", Some(&symbols))
+            .is_err());
+        assert!(debugger
+            .execute(&mut system, "watch ram 1:0:0", Some(&symbols))
+            .is_err());
+        assert!(debugger.execute(&mut system, "frobnicate", None).is_err());
+    }
+
+    #[test]
+    fn test_debugger_next_and_finish() {
+        use rusty_emu::debugger::Debugger;
+
+        let assembly = rusty_emu::asm::assemble(
+            "START:  JMS SUB
+                    LDM 3
+            DONE:   JUN DONE
+            SUB:    LDM 7
+                    IAC
+                    BBL 2",
+        )
+        .unwrap();
+        let factory = SystemFactory::new();
+        let mut system = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+        system.load_program_data(&assembly.binary).unwrap();
+        let mut debugger = Debugger::new();
+
+        // next runs the whole subroutine
+        debugger.execute(&mut system, "next", None).unwrap();
+        let registers = system.get_cpu_registers().unwrap();
+        assert_eq!(registers.program_counter, 0x002);
+        assert_eq!(registers.accumulator, 2);
+        assert_eq!(registers.instruction_count, 4);
+
+        // finish returns from inside the subroutine
+        debugger.execute(&mut system, "set pc 0", None).unwrap();
+        debugger.execute(&mut system, "step 2", None).unwrap();
+        assert_eq!(system.get_cpu_registers().unwrap().program_counter, 0x006);
+        debugger.execute(&mut system, "finish", None).unwrap();
+        assert_eq!(system.get_cpu_registers().unwrap().program_counter, 0x002);
+
+        // A loop that never returns hits the run limit
+        debugger.set_run_limit(20);
+        let output = debugger.execute(&mut system, "finish", None).unwrap();
+        assert_eq!(output[0], "Still running after 20 instructions");
+    }
+
+    /// Send one packet to a GDB stub and return its reply data
+    fn gdb_request(
+        stream: &mut std::net::TcpStream,
+        reader: &mut std::io::BufReader<std::net::TcpStream>,
+        packet: &str,
+    ) -> String {
+        use std::io::{BufRead, Read, Write};
+
+        stream
+            .write_all(rusty_emu::gdb::encode_packet(packet).as_bytes())
+            .unwrap();
+        let mut ack = [0u8];
+        reader.read_exact(&mut ack).unwrap();
+        assert_eq!(ack[0], b'+', "packet '{}' not acknowledged", packet);
+
+        let mut reply = Vec::new();
+        reader.read_until(b'#', &mut reply).unwrap();
+        let mut checksum = [0u8; 2];
+        reader.read_exact(&mut checksum).unwrap();
+        stream.write_all(b"+").unwrap();
+        assert_eq!(reply.remove(0), b'$');
+        reply.pop();
+        String::from_utf8(reply).unwrap()
+    }
+
+    /// Serve a system with a GDB stub on a loopback socket and connect to it
+    fn gdb_connect(
+        system: rusty_emu::system_config::ConfigurableSystem,
+    ) -> (
+        std::net::TcpStream,
+        std::io::BufReader<std::net::TcpStream>,
+        std::thread::JoinHandle<()>,
+    ) {
+        use rusty_emu::gdb::GdbStub;
+        use std::sync::{Arc, Mutex};
+
+        let mut stub = GdbStub::new(Arc::new(Mutex::new(system))).unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            stub.serve(stream).unwrap();
+        });
+        let stream = std::net::TcpStream::connect(address).unwrap();
+        let reader = std::io::BufReader::new(stream.try_clone().unwrap());
+        (stream, reader, server)
+    }
+
+    #[test]
+    fn test_gdb_stub_debugs_mcs4_system() {
+        let factory = SystemFactory::new();
+        let mut system = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+        system
+            .load_program_data(&fs::read("programs/fibonacci_ram.bin").unwrap())
+            .unwrap();
+        let (mut stream, mut reader, server) = gdb_connect(system);
+        let mut request = |packet: &str| gdb_request(&mut stream, &mut reader, packet);
+
+        assert!(request("qSupported:xmlRegisters=i386").contains("qXfer:features:read+"));
+        let description = request("qXfer:features:read:target.xml:0,1000");
+        assert!(description.starts_with("l<?xml"));
+        assert!(description.contains("org.rusty_emu.mcs4"));
+        assert_eq!(request("?"), "S05");
+
+        // r0-r15, acc, cy and a little-endian PC, all zero after reset
+        assert_eq!(request("g"), "00".repeat(20));
+        assert_eq!(request("m0,2"), "2000");
+        assert_eq!(request("s"), "S05");
+        assert_eq!(&request("g")[36..], "0200");
+
+        // Continue stops at a stub-side breakpoint without touching program memory
+        assert_eq!(request("Z0,8,1"), "OK");
+        assert_eq!(request("c"), "S05");
+        assert_eq!(&request("g")[36..], "0800");
+        assert_eq!(request("m8,1"), "21");
+        assert_eq!(request("z0,8,1"), "OK");
+
+        // Registers, RAM characters and program memory can be written
+        let registers = format!("{}0501{}", "00".repeat(15) + "0a", "0800");
+        assert_eq!(request(&format!("G{}", registers)), "OK");
+        assert_eq!(request("g"), registers);
+        assert_eq!(request("M10021,2:0304"), "OK");
+        assert_eq!(request("m10021,2"), "0304");
+        assert_eq!(request("M100,1:d7"), "OK");
+        assert_eq!(request("m100,1"), "d7");
+        assert_eq!(request("m10014,1"), "E01");
+        assert_eq!(request("vMustReplyEmpty"), "");
+
+        // Lengths that overflow the address space or exceed a packet are refused
+        assert_eq!(request("m10000,ffffffff"), "E01");
+        assert_eq!(request("m1,ffffffffffffffff"), "E01");
+        assert_eq!(request("mffffffff,2"), "E01");
+        assert_eq!(request("Mffffffff,2:0102"), "E01");
+        assert_eq!(
+            request("qXfer:features:read:target.xml:1,ffffffffffffffff"),
+            "E01"
+        );
+        assert_eq!(request("m100,1"), "d7");
+
+        assert_eq!(request("D"), "OK");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_gdb_stub_debugs_mos6502_system() {
+        let factory = SystemFactory::new();
+        let system = factory
+            .create_from_json("configs/mos6502_basic.json")
+            .unwrap();
+        let (mut stream, mut reader, server) = gdb_connect(system);
+        let mut request = |packet: &str| gdb_request(&mut stream, &mut reader, packet);
+
+        let description = request("qXfer:features:read:target.xml:0,1000");
+        assert!(description.contains("org.rusty_emu.mos6502"));
+
+        // The stub attaches after the reset sequence, at the reset vector
+        assert_eq!(request("mfffc,2"), "00ff");
+        assert_eq!(&request("g")[10..], "00ff");
+
+        // Run to the idle loop, by which time the sequence is in RAM
+        assert_eq!(request("Z0,ff21,1"), "OK");
+        assert_eq!(request("c"), "S05");
+        assert_eq!(&request("g")[10..], "21ff");
+        assert_eq!(request("m200,8"), "000101020305080d");
+
+        assert_eq!(request("M300,2:beef"), "OK");
+        assert_eq!(request("m300,2"), "beef");
+        assert_eq!(request("Gaa0102fd2400ff"), "OK");
+        assert_eq!(request("g"), "aa0102fd2400ff");
+        assert_eq!(request("s"), "S05");
+        assert_eq!(request("m8000,1"), "E01");
+
+        assert_eq!(request("k"), "");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_gdb_stub_debugs_wdc65c02_system() {
+        let config = fs::read_to_string("configs/mos6502_basic.json")
+            .unwrap()
+            .replace("\"mos_6502\"", "\"wdc_65c02\"");
+        fs::write("test_gdb_65c02.json", config).unwrap();
+        let factory = SystemFactory::new();
+        let system = factory.create_from_json("test_gdb_65c02.json");
+        let _ = fs::remove_file("test_gdb_65c02.json");
+
+        let (mut stream, mut reader, server) = gdb_connect(system.unwrap());
+        let mut request = |packet: &str| gdb_request(&mut stream, &mut reader, packet);
+
+        // The 65C02 is debugged through its 6502 core
+        let description = request("qXfer:features:read:target.xml:0,1000");
+        assert!(description.contains("org.rusty_emu.mos6502"));
+        assert_eq!(&request("g")[10..], "00ff");
+
+        assert_eq!(request("Z0,ff21,1"), "OK");
+        assert_eq!(request("c"), "S05");
+        assert_eq!(&request("g")[10..], "21ff");
+        assert_eq!(request("m200,8"), "000101020305080d");
+
+        // Non-ASCII packets and hex data are refused instead of ending the session
+        assert_eq!(request("\u{FFFD}"), "");
+        assert_eq!(request("M300,2:\u{FFFD}0"), "E01");
+        assert_eq!(request("Gaa\u{FFFD}"), "E01");
+        assert_eq!(request("m300,1"), "00");
+
+        assert_eq!(request("k"), "");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_standard_memory_system_writes_program_ram_with_wpm() {
+        use rusty_emu::components::memory::generic_ram::GenericRam;
+
+        let factory = SystemFactory::new();
+        let mut system = factory
+            .create_from_json("configs/mcs4_4008_4009.json")
+            .unwrap();
+
+        let program = [
+            0x20, 0x10, // FIM P0, 10: WPM address 0x10
+            0x21, // SRC P0
+            0xD2, // LDM 2
+            0xE1, // WMP: RAM output port selects write page 2
+            0xDA, // LDM A
+            0xE3, // WPM: high nibble
+            0xD5, // LDM 5
+            0xE3, // WPM: low nibble, writes the byte
+            0x40, 0x09, // JUN 009
+        ];
+        system.load_program_data(&program).unwrap();
+        system.start_lockstep();
+        while system.get_cpu_registers().unwrap().instruction_count < 9 {
+            system.step_lockstep().unwrap();
+        }
+        system.stop_lockstep();
+
+        let components = system.get_components();
+        let ram = components["PROGRAM_RAM"].lock().unwrap();
+        let ram = ram.as_any().downcast_ref::<GenericRam>().unwrap();
+        assert_eq!(ram.read_byte(0x210), Some(0xA5));
+        assert_eq!(ram.read_byte(0x010), Some(0x00));
+        assert_eq!(ram.read_byte(0x002), Some(0x21));
+    }
+
+    #[test]
+    fn test_4040_standard_memory_system_reads_back_wpm_with_rpm() {
+        use rusty_emu::components::memory::generic_ram::GenericRam;
+
+        let config = fs::read_to_string("configs/mcs4_4008_4009.json")
+            .unwrap()
+            .replace("\"intel_4004\"", "\"intel_4040\"");
+        fs::write("test_rpm_4040.json", config).unwrap();
+        let factory = SystemFactory::new();
+        let lockstep = factory.create_from_json("test_rpm_4040.json");
+        let functional = factory.create_from_json("test_rpm_4040.json");
+        let _ = fs::remove_file("test_rpm_4040.json");
+        let (mut lockstep, mut functional) = (lockstep.unwrap(), functional.unwrap());
+
+        let program = [
+            0x20, 0x10, // FIM P0, 10: WPM/RPM address 0x10
+            0x21, // SRC P0
+            0xD2, // LDM 2
+            0xE1, // WMP: RAM output port selects page 2
+            0xDA, // LDM A
+            0xE3, // WPM: high nibble
+            0xD5, // LDM 5
+            0xE3, // WPM: low nibble, writes the byte
+            0xD0, // LDM 0
+            0x0E, // RPM: high nibble
+            0xB2, // XCH R2
+            0x0E, // RPM: low nibble
+            0x40, 0x0D, // JUN 00D
+        ];
+        let instructions = 12;
+
+        lockstep.load_program_data(&program).unwrap();
+        lockstep.start_lockstep();
+        while lockstep.get_cpu_registers().unwrap().instruction_count < instructions {
+            lockstep.step_lockstep().unwrap();
+        }
+        lockstep.stop_lockstep();
+
+        functional.load_program_data(&program).unwrap();
+        functional.enable_functional_mode().unwrap();
+        functional.run_functional(instructions).unwrap();
+
+        for system in [&lockstep, &functional] {
+            let registers = system.get_cpu_registers().unwrap();
+            assert_eq!(registers.index_registers[2], 0xA);
+            assert_eq!(registers.accumulator, 0x5);
+
+            let components = system.get_components();
+            let ram = components["PROGRAM_RAM"].lock().unwrap();
+            let ram = ram.as_any().downcast_ref::<GenericRam>().unwrap();
+            assert_eq!(ram.read_byte(0x210), Some(0xA5));
+        }
+    }
+
+    #[test]
+    fn test_4040_system_runs_with_second_register_bank() {
+        let factory = SystemFactory::new();
+        let mut system = factory.create_from_json("configs/mcs4_4040.json").unwrap();
+        assert_eq!(system.get_update_order()[1], "CPU_4040");
+
+        let program = [
+            0x0B, // SB1
+            0x20, 0x25, // FIM P0, 25 into bank 1: register 2, character 5
+            0x21, // SRC P0
+            0xD9, // LDM 9
+            0xE0, // WRM
+            0x0A, // SB0
+            0x40, 0x07, // JUN 007
+        ];
+        system.load_program_data(&program).unwrap();
+        system.start_lockstep();
+        while system.get_cpu_registers().unwrap().instruction_count < 7 {
+            system.step_lockstep().unwrap();
+        }
+        system.stop_lockstep();
+
+        let registers = system.get_cpu_registers().unwrap();
+        assert_eq!(registers.index_registers[0..2], [0, 0]);
+        assert_eq!(registers.stack.len(), 7);
+        assert_eq!(system.get_ram_contents()[0].registers[2].main[5], 0x9);
+    }
+
+    #[test]
+    fn test_4040_interrupt_wakes_halted_cpu_in_lockstep() {
+        use rusty_emu::pin::PinValue;
+
+        let factory = SystemFactory::new();
+        let mut system = factory.create_from_json("configs/mcs4_4040.json").unwrap();
+
+        let program = [
+            0x0C, // EIN
+            0x01, // HLT
+            0x00, // NOP
+            0xD9, // 003: LDM 9
+            0x40, 0x04, // JUN 004
+        ];
+        system.load_program_data(&program).unwrap();
+        system.start_lockstep();
+        for _ in 0..200 {
+            system.step_lockstep().unwrap();
+        }
+        let halted = system.get_cpu_registers().unwrap();
+        assert_eq!(halted.instruction_count, 2);
+        assert_eq!(halted.program_counter, 0x002);
+
+        let int_pin = system.get_components()["CPU_4040"]
+            .lock()
+            .unwrap()
+            .get_pin("INT")
+            .unwrap();
+        int_pin
+            .lock()
+            .unwrap()
+            .set_driver(Some("TEST".to_string()), PinValue::High);
+        while system.get_cpu_registers().unwrap().instruction_count < 3 {
+            system.step_lockstep().unwrap();
+        }
+        system.stop_lockstep();
+
+        let registers = system.get_cpu_registers().unwrap();
+        assert_eq!(registers.accumulator, 0x9);
+        assert_eq!(registers.stack[0], 0x002);
+    }
+
+    #[test]
+    fn test_cpu_registers_snapshot_includes_stack() {
+        let factory = SystemFactory::new();
+        let mut system = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+
+        // JMS 010 / 010: BBL 0 - the call leaves its return address on the stack
+        let mut program = vec![0u8; 0x20];
+        program[0x00..0x02].copy_from_slice(&[0x50, 0x10]);
+        program[0x10] = 0xC0;
+        system.load_program_data(&program).unwrap();
+        system.enable_functional_mode().unwrap();
+        system.run_functional(1).unwrap();
+
+        let registers = system.get_cpu_registers().unwrap();
+        assert_eq!(registers.program_counter, 0x010);
+        assert_eq!(registers.stack_pointer, 1);
+        assert_eq!(registers.stack[0], 0x002);
+        assert_eq!(registers.instruction_count, 1);
+    }
+
+    #[test]
+    fn test_simulated_time_follows_clock_edges() {
+        use rusty_emu::components::clock::two_phase_clock::TwoPhaseClock;
+
+        let factory = SystemFactory::new();
+        let mut system = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+        let phase_ns = {
+            let components = system.get_components();
+            let clock = components["SYSTEM_CLOCK"].lock().unwrap();
+            clock
+                .as_any()
+                .downcast_ref::<TwoPhaseClock>()
+                .unwrap()
+                .get_phase_time_ns()
+        };
+
+        system.start_lockstep();
+        assert_eq!(system.get_simulated_time_ns(), 0);
+
+        // Wall-clock delays must not move simulated time
+        system.run_lockstep(10).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert_eq!(system.get_simulated_time_ns(), 10 * phase_ns);
+
+        system.run_lockstep(5).unwrap();
+        assert_eq!(system.get_simulated_time_ns(), 15 * phase_ns);
+    }
+
+    #[test]
+    fn test_machine_snapshot_resumes_mid_instruction() {
+        let factory = SystemFactory::new();
+        let mut original = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+        original
+            .load_program_data(&fs::read("programs/fibonacci_ram.bin").unwrap())
+            .unwrap();
+
+        // An odd edge count stops between clock phases, in the middle of a bus cycle
+        original.run_lockstep(3001).unwrap();
+        let snapshot = original.snapshot().unwrap();
+        assert_eq!(snapshot.edge_count, 3001);
+
+        // The ROM contents are part of the snapshot, so no program is loaded here
+        let mut restored = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+        restored.restore(&snapshot).unwrap();
+        assert_eq!(restored.snapshot().unwrap(), snapshot);
+        assert_eq!(
+            restored.get_simulated_time_ns(),
+            original.get_simulated_time_ns()
+        );
+
+        original.run_lockstep(4000).unwrap();
+        restored.run_lockstep(4000).unwrap();
+        assert_eq!(restored.snapshot().unwrap(), original.snapshot().unwrap());
+
+        let expected = original.get_cpu_registers().unwrap();
+        let actual = restored.get_cpu_registers().unwrap();
+        assert!(expected.instruction_count > 0);
+        assert_eq!(actual.program_counter, expected.program_counter);
+        assert_eq!(actual.instruction_count, expected.instruction_count);
+        assert_eq!(
+            restored.get_ram_contents()[0].registers,
+            original.get_ram_contents()[0].registers
+        );
+    }
+
+    #[test]
+    fn test_machine_state_files_round_trip_and_check_the_system() {
+        let factory = SystemFactory::new();
+        let mut system = factory
+            .create_from_json("configs/mos6502_basic.json")
+            .unwrap();
+        system.run_lockstep(1000).unwrap();
+
+        let path = "test_machine_state.json";
+        system.save_state(path).unwrap();
+        let mut restored = factory
+            .create_from_json("configs/mos6502_basic.json")
+            .unwrap();
+        let loaded = restored.load_state(path);
+        let mut other = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+        let mismatch = other.load_state(path);
+        let _ = fs::remove_file(path);
+
+        loaded.unwrap();
+        assert!(mismatch.is_err());
+        assert!(!other.is_running());
+
+        system.run_lockstep(1000).unwrap();
+        restored.run_lockstep(1000).unwrap();
+        assert_eq!(
+            restored.read_bus_memory(0x0200, 13).unwrap(),
+            system.read_bus_memory(0x0200, 13).unwrap()
+        );
+        assert_eq!(restored.snapshot().unwrap(), system.snapshot().unwrap());
+    }
 }
 
 #[cfg(test)]
@@ -128,6 +1266,40 @@ mod fibonacci_program_tests {
         assert_eq!(info.name, "IntelMcs4");
     }
 
+    #[test]
+    fn test_program_data_spans_roms_in_bank_order() {
+        use rusty_emu::components::memory::intel_4001::Intel4001;
+
+        let factory = SystemFactory::new();
+        let mut system = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+
+        let program: Vec<u8> = (0..300).map(|i| (i % 251) as u8).collect();
+        system.load_program_data(&program).unwrap();
+
+        assert_eq!(system.get_rom_names(), vec!["ROM_4001_1", "ROM_4001_2"]);
+
+        let components = system.get_components();
+        let rom1 = components["ROM_4001_1"].lock().unwrap();
+        let rom1 = rom1.as_any().downcast_ref::<Intel4001>().unwrap();
+        assert_eq!(rom1.read_rom(0x00), Some(0));
+        assert_eq!(rom1.read_rom(0xFF), Some(255 % 251));
+
+        let rom2 = components["ROM_4001_2"].lock().unwrap();
+        let rom2 = rom2.as_any().downcast_ref::<Intel4001>().unwrap();
+        assert_eq!(rom2.read_rom(0x00), Some((256 % 251) as u8));
+        assert_eq!(rom2.read_rom(0x2B), Some((299 % 251) as u8));
+        assert_eq!(rom2.read_rom(0x2C), Some(0));
+    }
+
+    #[test]
+    fn test_program_data_exceeding_rom_capacity() {
+        let factory = SystemFactory::new();
+        let mut system = factory.create_from_json("configs/mcs4_basic.json").unwrap();
+
+        let program = vec![0u8; 513];
+        assert!(system.load_program_data(&program).is_err());
+    }
+
     #[test]
     fn test_program_file_existence() {
         // Test that fibonacci program files exist
@@ -137,6 +1309,10 @@ mod fibonacci_program_tests {
         // Test that config files exist
         assert!(std::path::Path::new("configs/mcs4_basic.json").exists());
         assert!(std::path::Path::new("configs/mcs4_max.json").exists());
+        assert!(std::path::Path::new("configs/mcs4_4040.json").exists());
+        assert!(std::path::Path::new("configs/mos6502_basic.json").exists());
+        assert!(std::path::Path::new("programs/mos6502_fibonacci.bin").exists());
+        assert!(std::path::Path::new("programs/fibonacci_ram.asm").exists());
     }
 
     #[test]
@@ -0,0 +1,367 @@
+//! MOS 6502 tests
+//!
+//! Runs the 6502 through its pins against a bench memory, and hosts the harness
+//! for Klaus Dormann's 6502 functional test.
+
+use rusty_emu::component::{BaseComponent, Component};
+use rusty_emu::components::cpu::mos_6502::{self, MOS6502};
+use rusty_emu::connection::connect_pins;
+use rusty_emu::pin::{Pin, PinValue};
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Functional test binary used when MOS6502_FUNCTIONAL_TEST is not set
+const FUNCTIONAL_TEST_PATH: &str = "tests/data/6502_functional_test.bin";
+
+/// 64 KB memory that answers the 6502 bus: drives D0-D7 while RW is high and
+/// stores D0-D7 while RW is low
+struct BenchMemory {
+    base: BaseComponent,
+    memory: Vec<u8>,
+}
+
+impl BenchMemory {
+    fn new() -> Self {
+        let mut pin_names: Vec<String> = (0..16).map(|i| format!("A{}", i)).collect();
+        pin_names.extend((0..8).map(|i| format!("D{}", i)));
+        pin_names.push("RW".to_string());
+        let pin_refs: Vec<&str> = pin_names.iter().map(String::as_str).collect();
+        let pins = BaseComponent::create_pin_map(&pin_refs, "MEMORY");
+
+        BenchMemory {
+            base: BaseComponent::new("MEMORY".to_string(), pins),
+            memory: vec![0; 0x10000],
+        }
+    }
+
+    fn read_lines(&self, prefix: &str, width: usize) -> usize {
+        (0..width)
+            .filter(|line| {
+                self.base
+                    .get_pin(&format!("{}{}", prefix, line))
+                    .unwrap()
+                    .lock()
+                    .unwrap()
+                    .read()
+                    == PinValue::High
+            })
+            .fold(0, |value, line| value | (1 << line))
+    }
+
+    fn drive_data(&self, data: Option<u8>) {
+        for line in 0..8 {
+            let value = data.map_or(PinValue::HighZ, |d| {
+                PinValue::from_bool((d >> line) & 1 == 1)
+            });
+            self.base
+                .get_pin(&format!("D{}", line))
+                .unwrap()
+                .lock()
+                .unwrap()
+                .set_driver(Some("MEMORY_DATA".to_string()), value);
+        }
+    }
+}
+
+impl Component for BenchMemory {
+    fn name(&self) -> String {
+        self.base.name()
+    }
+
+    fn pins(&self) -> HashMap<String, Arc<Mutex<Pin>>> {
+        self.base.pins()
+    }
+
+    fn get_pin(&self, name: &str) -> Result<Arc<Mutex<Pin>>, String> {
+        self.base.get_pin(name)
+    }
+
+    fn update(&mut self) {
+        let address = self.read_lines("A", 16);
+        let read = self.base.get_pin("RW").unwrap().lock().unwrap().read() != PinValue::Low;
+        if read {
+            self.drive_data(Some(self.memory[address]));
+        } else {
+            self.drive_data(None);
+            self.memory[address] = self.read_lines("D", 8) as u8;
+        }
+    }
+
+    fn start(&mut self) {}
+
+    fn run(&mut self) {}
+
+    fn stop(&mut self) {}
+
+    fn is_running(&self) -> bool {
+        true
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Peripheral that raises IRQ through its pin: a write to its register asserts the
+/// open-drain IRQ output, a read acknowledges and releases it
+struct BenchInterruptSource {
+    base: BaseComponent,
+    register: usize,
+    asserted: bool,
+}
+
+impl BenchInterruptSource {
+    fn new(register: usize) -> Self {
+        let mut pin_names: Vec<String> = (0..16).map(|i| format!("A{}", i)).collect();
+        pin_names.push("RW".to_string());
+        pin_names.push("IRQ".to_string());
+        let pin_refs: Vec<&str> = pin_names.iter().map(String::as_str).collect();
+        let pins = BaseComponent::create_pin_map(&pin_refs, "TIMER");
+
+        BenchInterruptSource {
+            base: BaseComponent::new("TIMER".to_string(), pins),
+            register,
+            asserted: false,
+        }
+    }
+
+    fn read_pin(&self, name: &str) -> PinValue {
+        self.base.get_pin(name).unwrap().lock().unwrap().read()
+    }
+}
+
+impl Component for BenchInterruptSource {
+    fn name(&self) -> String {
+        self.base.name()
+    }
+
+    fn pins(&self) -> HashMap<String, Arc<Mutex<Pin>>> {
+        self.base.pins()
+    }
+
+    fn get_pin(&self, name: &str) -> Result<Arc<Mutex<Pin>>, String> {
+        self.base.get_pin(name)
+    }
+
+    fn update(&mut self) {
+        let address = (0..16)
+            .filter(|line| self.read_pin(&format!("A{}", line)) == PinValue::High)
+            .fold(0, |value, line| value | (1 << line));
+        if address == self.register {
+            self.asserted = self.read_pin("RW") == PinValue::Low;
+        }
+
+        // Open drain: pull IRQ low or let it float
+        let level = if self.asserted {
+            PinValue::Low
+        } else {
+            PinValue::HighZ
+        };
+        self.base
+            .get_pin("IRQ")
+            .unwrap()
+            .lock()
+            .unwrap()
+            .set_driver(Some("TIMER_IRQ".to_string()), level);
+    }
+
+    fn start(&mut self) {}
+
+    fn run(&mut self) {}
+
+    fn stop(&mut self) {}
+
+    fn is_running(&self) -> bool {
+        true
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Wire a 6502 to a bench memory over A0-A15, D0-D7 and RW
+fn connect_bench(cpu: &MOS6502, memory: &BenchMemory) {
+    let names = (0..16)
+        .map(|i| format!("A{}", i))
+        .chain((0..8).map(|i| format!("D{}", i)))
+        .chain(std::iter::once("RW".to_string()));
+    for name in names {
+        connect_pins(cpu.get_pin(&name).unwrap(), memory.get_pin(&name).unwrap()).unwrap();
+    }
+}
+
+/// Run one full CLK period: high, then the falling edge that completes a bus cycle
+fn clock_cycle(cpu: &mut MOS6502, memory: &mut BenchMemory) {
+    let clock = cpu.get_pin("CLK").unwrap();
+    for level in [PinValue::High, PinValue::Low] {
+        clock
+            .lock()
+            .unwrap()
+            .set_driver(Some("CLOCK".to_string()), level);
+        cpu.update();
+        memory.update();
+    }
+}
+
+#[test]
+fn test_6502_runs_program_over_pins() {
+    let mut cpu = MOS6502::new("CPU_6502".to_string());
+    let mut memory = BenchMemory::new();
+    connect_bench(&cpu, &memory);
+
+    // Reset vector -> $0200: LDA #$42; STA $0300; JMP $0205
+    memory.memory[0xFFFC] = 0x00;
+    memory.memory[0xFFFD] = 0x02;
+    memory.memory[0x0200..0x0208]
+        .copy_from_slice(&[0xA9, 0x42, 0x8D, 0x00, 0x03, 0x4C, 0x05, 0x02]);
+
+    cpu.start();
+    memory.update();
+
+    // Reset sequence (7), then LDA (2) and STA (4)
+    for _ in 0..13 {
+        clock_cycle(&mut cpu, &mut memory);
+    }
+    assert_eq!(cpu.get_accumulator(), 0x42);
+    assert_eq!(memory.memory[0x0300], 0x42);
+    assert_eq!(cpu.get_program_counter(), 0x0205);
+    assert_eq!(cpu.get_cycle_count(), 13);
+
+    // SYNC marks the JMP opcode fetch now on the bus
+    assert_eq!(
+        cpu.get_pin("SYNC").unwrap().lock().unwrap().read(),
+        PinValue::High
+    );
+
+    for _ in 0..9 {
+        clock_cycle(&mut cpu, &mut memory);
+    }
+    assert_eq!(cpu.get_program_counter(), 0x0205);
+    assert_eq!(cpu.get_instruction_count(), 5);
+}
+
+#[test]
+fn test_6502_reset_pin_holds_cpu() {
+    let mut cpu = MOS6502::new("CPU_6502".to_string());
+    let mut memory = BenchMemory::new();
+    connect_bench(&cpu, &memory);
+    memory.memory[0xFFFC] = 0x00;
+    memory.memory[0xFFFD] = 0x10;
+
+    cpu.start();
+    memory.update();
+    cpu.get_pin("RES")
+        .unwrap()
+        .lock()
+        .unwrap()
+        .set_driver(Some("TEST".to_string()), PinValue::Low);
+    for _ in 0..4 {
+        clock_cycle(&mut cpu, &mut memory);
+    }
+    assert_eq!(cpu.get_cycle_count(), 0);
+
+    cpu.get_pin("RES")
+        .unwrap()
+        .lock()
+        .unwrap()
+        .set_driver(Some("TEST".to_string()), PinValue::High);
+    for _ in 0..7 {
+        clock_cycle(&mut cpu, &mut memory);
+    }
+    assert_eq!(cpu.get_program_counter(), 0x1000);
+    assert_eq!(cpu.get_stack_pointer(), 0xFD);
+}
+
+#[test]
+fn test_6502_peripheral_raises_irq_through_pin() {
+    let mut cpu = MOS6502::new("CPU_6502".to_string());
+    let mut memory = BenchMemory::new();
+    let mut timer = BenchInterruptSource::new(0xD000);
+    connect_bench(&cpu, &memory);
+    for line in (0..16).map(|i| format!("A{}", i)).chain(["RW".to_string()]) {
+        connect_pins(cpu.get_pin(&line).unwrap(), timer.get_pin(&line).unwrap()).unwrap();
+    }
+    connect_pins(cpu.get_pin("IRQ").unwrap(), timer.get_pin("IRQ").unwrap()).unwrap();
+
+    // CLI; LDA #$01; STA $D000; JMP $0206 - the handler acknowledges, counts in Y and returns
+    memory.memory[0x0200..0x0209]
+        .copy_from_slice(&[0x58, 0xA9, 0x01, 0x8D, 0x00, 0xD0, 0x4C, 0x06, 0x02]);
+    memory.memory[0x0300..0x0305].copy_from_slice(&[0xAD, 0x00, 0xD0, 0xC8, 0x40]);
+    memory.memory[0xFFFC..0x10000].copy_from_slice(&[0x00, 0x02, 0x00, 0x03]);
+
+    cpu.start();
+    memory.update();
+    for _ in 0..80 {
+        clock_cycle(&mut cpu, &mut memory);
+        timer.update();
+    }
+
+    assert_eq!(cpu.get_y_register(), 1);
+    assert!(!timer.asserted);
+    assert_ne!(
+        cpu.get_pin("IRQ").unwrap().lock().unwrap().read(),
+        PinValue::Low
+    );
+    assert!((0x0206..=0x0209).contains(&cpu.get_program_counter()));
+}
+
+#[test]
+fn test_6502_rdy_inserts_wait_states() {
+    let mut cpu = MOS6502::new("CPU_6502".to_string());
+    let mut memory = BenchMemory::new();
+    connect_bench(&cpu, &memory);
+    memory.memory[0xFFFC..0xFFFE].copy_from_slice(&[0x00, 0x02]);
+    memory.memory[0x0200..0x0202].copy_from_slice(&[0xA9, 0x42]); // LDA #$42
+
+    cpu.start();
+    memory.update();
+    for _ in 0..8 {
+        clock_cycle(&mut cpu, &mut memory);
+    }
+    assert_eq!(memory.read_lines("A", 16), 0x0201);
+
+    // The operand read stays on the bus while RDY is low
+    let rdy = cpu.get_pin("RDY").unwrap();
+    rdy.lock()
+        .unwrap()
+        .set_driver(Some("TEST".to_string()), PinValue::Low);
+    for _ in 0..3 {
+        clock_cycle(&mut cpu, &mut memory);
+    }
+    assert_eq!(cpu.get_accumulator(), 0);
+    assert_eq!(memory.read_lines("A", 16), 0x0201);
+    assert_eq!(cpu.get_cycle_count(), 11);
+
+    rdy.lock()
+        .unwrap()
+        .set_driver(Some("TEST".to_string()), PinValue::High);
+    clock_cycle(&mut cpu, &mut memory);
+    assert_eq!(cpu.get_accumulator(), 0x42);
+    assert_eq!(cpu.get_instruction_count(), 1);
+}
+
+/// Klaus Dormann's 6502_functional_test, built with the default options
+/// The binary is not part of the repository: build it from
+/// https://github.com/Klaus2m5/6502_65C02_functional_tests and run
+/// `MOS6502_FUNCTIONAL_TEST=path/to/6502_functional_test.bin cargo test -- --ignored`
+#[test]
+#[ignore]
+fn test_6502_klaus_dormann_functional_test() {
+    let path = std::env::var("MOS6502_FUNCTIONAL_TEST")
+        .unwrap_or_else(|_| FUNCTIONAL_TEST_PATH.to_string());
+
+    match mos_6502::run_functional_test(&path, 200_000_000) {
+        Ok(cycles) => println!("Functional test passed in {} cycles", cycles),
+        Err(e) => panic!("{}", e),
+    }
+}